@@ -0,0 +1,63 @@
+"""トリミング範囲（--crop）の解釈を dispatcher.py / ocr_chanked.py / ui_preview.py で共通にする。
+
+座標の約束:
+- `left,top,width,height` は回転・傾き補正を適用した後の画像に対する割合（0〜1）。
+  --auto-orient はページごとに向きが変わり、プレビューで選んだ範囲と合わなくなるので併用できない。
+  PDF はページ全体、画像は EXIF の向きを反映した全体が 0〜1 になる。
+- 範囲は画像の内側に切り詰める（left/top を 0〜1 に、width/height をはみ出さない長さに）。
+  面積が 0 になる指定は「トリミングなし」として扱う。
+- ピクセルへの変換は各辺を `round(割合 × 画像サイズ)` で丸める（右端・下端は含まない）。
+  同じ割合でも描画 DPI が違えば境界が 1px ずれうるので、見た目を揃えるには同じ DPI で描く。
+"""
+
+from __future__ import annotations
+
+CropRect = tuple[float, float, float, float]
+CropBox = tuple[int, int, int, int]
+
+
+def parse_crop(value: str | None) -> CropRect | None:
+    """`left,top,width,height` を切り詰めた CropRect にする。形式が不正なら ValueError。"""
+
+    if not value:
+        return None
+    parts = [p.strip() for p in value.split(",")]
+    if len(parts) != 4:
+        raise ValueError("crop は left,top,width,height の4要素が必要です")
+    try:
+        left, top, width, height = (float(p) for p in parts)
+    except ValueError as exc:
+        raise ValueError("crop の値は数値で指定してください") from exc
+    left = max(0.0, min(1.0, left))
+    top = max(0.0, min(1.0, top))
+    width = max(0.0, min(1.0 - left, width))
+    height = max(0.0, min(1.0 - top, height))
+    if width <= 0 or height <= 0:
+        return None
+    return (left, top, width, height)
+
+
+def crop_box(size: tuple[int, int], crop: CropRect | None) -> CropBox | None:
+    """画像サイズ (w, h) に対する切り出し範囲（PIL の box）。切り出さない場合は None。"""
+
+    if not crop:
+        return None
+    left, top, width, height = crop
+    w, h = size
+    box = (
+        int(round(left * w)),
+        int(round(top * h)),
+        int(round((left + width) * w)),
+        int(round((top + height) * h)),
+    )
+    if box[2] <= box[0] or box[3] <= box[1]:
+        return None
+    return box
+
+
+def apply_crop(img, crop: CropRect | None):
+    box = crop_box(img.size, crop)
+    return img if box is None else img.crop(box)
+
+
+__all__ = ["CropBox", "CropRect", "apply_crop", "crop_box", "parse_crop"]
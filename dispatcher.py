@@ -3,15 +3,23 @@
 from __future__ import annotations
 
 import argparse
+import json
+import os
 import subprocess
 import sys
+import tempfile
 from pathlib import Path
 
-from ingest import InputKind, IngestError, inspect
-from image_normalizer import ImageConversionError, ensure_png_image
-from ocr import OcrOptions, run_ocr, export_csv
+from ingest import PDF_PASSWORD_ENV, InputKind, IngestError, inspect
+from page_spec import PageSpecError, format_page_spec, page_spec_label, parse_page_spec
+from ocr_languages import LanguageSpecError, needs_tesseract, parse_languages, supported_languages
+from image_normalizer import ImageConversionError, ensure_png_image, tiff_frame_count, tiff_frames_to_pdf
+from ocr import FIGURE_FORMATS, OcrOptions, build_command, run_ocr, export_csv
 from export_docx import convert_file
-from export_excel_poc import main as export_excel_main, parse_args as parse_excel_args
+from export_excel_poc import CSV_ENCODINGS, main as export_excel_main, parse_args as parse_excel_args
+from crop_rect import apply_crop, parse_crop
+from font_check import font_warnings
+from result_metadata import OUTPUT_DIR_MARKER, RESULT_MARKER, collect_result_metadata, write_auto_rotations
 
 DEFAULT_OUTPUT_ROOT = Path("result")
 CONVERTED_DIR_NAME = "converted"
@@ -47,11 +55,22 @@ def _parse_cli_int(args: list[str] | None, name: str) -> int | None:
 def _infer_pdf_output_dir(pdf_path: Path, *, output_root: Path, extra_args: list[str] | None) -> Path:
     """ocr_chanked.py の出力ディレクトリ名ルールに合わせて output_dir を推定する。"""
 
+    output_name = _parse_cli_value(extra_args, "--output-name")
+    if output_name:
+        return output_root / output_name
+
     stem = pdf_path.stem
     label = _parse_cli_value(extra_args, "--label")
     if label:
         return output_root / f"{stem}_{label}"
 
+    pages = _parse_cli_value(extra_args, "--pages")
+    if pages:
+        try:
+            return output_root / f"{stem}_{page_spec_label(parse_page_spec(pages))}"
+        except PageSpecError:
+            pass
+
     start = _parse_cli_int(extra_args, "--start")
     end = _parse_cli_int(extra_args, "--end")
 
@@ -87,7 +106,7 @@ def _infer_pdf_output_dir(pdf_path: Path, *, output_root: Path, extra_args: list
 
 def parse_args() -> argparse.Namespace:
     parser = argparse.ArgumentParser(description="入力ファイル自動判定 + OCR 実行")
-    parser.add_argument("input_path", help="PDF / 画像ファイル")
+    parser.add_argument("input_path", nargs="?", help="PDF / 画像ファイル")
     parser.add_argument(
         "--mode",
         choices=["lite", "full"],
@@ -127,6 +146,12 @@ def parse_args() -> argparse.Namespace:
         default=True,
         help="YomiToku の --figure オプションを有効/無効",
     )
+    parser.add_argument(
+        "--figure-format",
+        choices=FIGURE_FORMATS,
+        default=None,
+        help="抽出した図版の保存形式（未指定は png）",
+    )
     parser.add_argument(
         "--image-as-pdf",
         action=argparse.BooleanOptionalAction,
@@ -175,6 +200,17 @@ def parse_args() -> argparse.Namespace:
         default="layout",
         help="表出力モード（xlsx/csv）。layout=レイアウト優先、table=結合解除してテーブル化 (default: layout)",
     )
+    parser.add_argument(
+        "--csv-delimiter",
+        default=",",
+        help="csv 出力の区切り文字（1 文字。タブは実際のタブ文字で渡す） (default: ,)",
+    )
+    parser.add_argument(
+        "--csv-encoding",
+        choices=list(CSV_ENCODINGS),
+        default="utf-8",
+        help="csv 出力の文字コード。shift_jis は cp932 で書く (default: utf-8)",
+    )
     parser.add_argument(
         "--excel-meta",
         dest="excel_meta_sheet",
@@ -182,10 +218,83 @@ def parse_args() -> argparse.Namespace:
         default=True,
         help="xlsx 出力時にメタ情報シートを付与する",
     )
+    parser.add_argument(
+        "--export-only",
+        action="store_true",
+        help="input_path を既存の結果ディレクトリとみなし、OCR せず --formats の書き出しだけ行う",
+    )
     parser.add_argument(
         "--crop",
         help="正規化トリミング範囲（left,top,width,height / 0〜1）。PDF/画像どちらにも適用されます。",
     )
+    parser.add_argument(
+        "--rotate",
+        type=int,
+        choices=[0, 90, 180, 270],
+        default=0,
+        help="OCR 前に時計回りに回転する角度。PDF/画像どちらにも適用され、--crop より先に効きます",
+    )
+    parser.add_argument(
+        "--deskew",
+        action=argparse.BooleanOptionalAction,
+        default=False,
+        help="OCR 前に小さな傾き（±5度程度）を自動補正する",
+    )
+    parser.add_argument(
+        "--auto-orient",
+        action=argparse.BooleanOptionalAction,
+        default=False,
+        help="横倒しのページを見つけてページごとに回す（--rotate の後、--deskew の前に効く）。回したページは @RESULT の rotated_pages に出る",
+    )
+    parser.add_argument(
+        "--lang",
+        default=None,
+        help='OCR 言語 (例: "ja,en,ko")。pytesseract のフォールバック / 追記に使う。未指定は従来どおり日英',
+    )
+    parser.add_argument(
+        "--merge-sep",
+        choices=["hr", "heading", "none", "page"],
+        default=None,
+        help="結合 Markdown のページ区切り (hr / heading=## Page N / none / page=改ページ)。PDF 経路で ocr_chanked.py に渡されます",
+    )
+    parser.add_argument(
+        "--list-languages",
+        action="store_true",
+        help="この環境で --lang に指定できる言語コードを JSON で出力して終了する",
+    )
+    parser.add_argument(
+        "--warm-models",
+        action="store_true",
+        help="--mode / --device のモデルを取得・ロードして終了する（初回ジョブのダウンロード待ちを避ける）",
+    )
+    parser.add_argument(
+        "--password",
+        default=None,
+        help=f"パスワード保護された PDF を開くためのパスワード（未指定なら環境変数 {PDF_PASSWORD_ENV}。ocr_chanked.py へは環境変数で渡し、ログには出しません）",
+    )
+    parser.add_argument(
+        "--temp-dir",
+        default=None,
+        help="中間ファイル（ページ画像など）の作業ディレクトリ。PDF 経路では ocr_chanked.py にも渡されます",
+    )
+    parser.add_argument(
+        "--dpi",
+        type=int,
+        default=None,
+        help="PDF→画像変換の DPI。PDF 経路（--image-as-pdf 含む）で ocr_chanked.py に渡されます",
+    )
+    parser.add_argument(
+        "--output-name",
+        default=None,
+        help="出力ディレクトリ名 (<output-root>/<output-name>)。省略時は入力ファイル名（PDF はページ範囲などの suffix 付き）",
+    )
+    parser.add_argument("--start", type=int, default=None, help="PDF の開始ページ (1 起点)")
+    parser.add_argument("--end", type=int, default=None, help="PDF の終了ページ (指定なしは最終ページ)")
+    parser.add_argument(
+        "--pages",
+        default=None,
+        help='PDF の複数範囲ページ指定 (例: "1-3,7,10-12")。指定時は --start/--end より優先',
+    )
     # `dispatcher.py <input> -- <ocr_chanked.py args...>` の形式で PDF 向け引数を透過させる。
     # argparse の parse_known_args だと区切り `--` 自体も extra に混ざり、
     # そのまま ocr_chanked.py に渡すと argparse がオプション解析を停止してしまうため、
@@ -200,10 +309,81 @@ def parse_args() -> argparse.Namespace:
         known_argv = argv
 
     args = parser.parse_args(known_argv)
+    if args.input_path is None and not (args.list_languages or args.warm_models):
+        parser.error("input_path を指定してください")
+    if args.lang:
+        try:
+            args.lang = parse_languages(args.lang)
+        except LanguageSpecError as exc:
+            parser.error(f"--lang: {exc}")
+    if args.output_name is not None and (
+        not args.output_name.strip() or any(c in args.output_name for c in "/\\") or ".." in args.output_name
+    ):
+        parser.error(f"--output-name にはパス区切りや .. を含まない名前を指定してください: {args.output_name!r}")
+    if len(args.csv_delimiter) != 1 or args.csv_delimiter in "\"\r\n":
+        parser.error(f"--csv-delimiter は改行・引用符以外の 1 文字で指定してください: {args.csv_delimiter!r}")
+    if args.pages:
+        try:
+            args.pages = format_page_spec(parse_page_spec(args.pages))
+        except PageSpecError as exc:
+            parser.error(f"--pages: {exc}")
+    if args.crop and args.auto_orient:
+        # 範囲は回転前のプレビューで選ぶので、ページごとに回すと別の場所を切り出してしまう
+        parser.error("--crop と --auto-orient は同時に指定できません")
+    args.password = args.password or os.environ.get(PDF_PASSWORD_ENV) or None
     args.extra = passthrough
     return args
 
 
+def redact_password_args(args: list[str]) -> list[str]:
+    """ログ出力用に `--password` の値を伏せたコピーを返す。"""
+
+    redacted = list(args)
+    for i, arg in enumerate(redacted):
+        if arg == "--password" and i + 1 < len(redacted):
+            redacted[i + 1] = "***"
+        elif arg.startswith("--password="):
+            redacted[i] = "--password=***"
+    return redacted
+
+
+def _merge_pdf_args(
+    extra: list[str] | None,
+    *,
+    dpi: int | None,
+    start: int | None,
+    end: int | None,
+    pages: str | None = None,
+    temp_dir: str | None = None,
+    lang: list[str] | None = None,
+    merge_sep: str | None = None,
+    output_name: str | None = None,
+    figure_format: str | None = None,
+) -> list[str]:
+    """dispatcher で受けた DPI / ページ範囲 / 作業ディレクトリ / 出力名 / 図版形式を ocr_chanked.py 向けの引数に合流させる。
+
+    `--` 以降で同じ引数が明示されている場合はそちらを優先する。
+    """
+
+    extra = list(extra) if extra else []
+    head: list[str] = []
+    for name, value in (
+        ("--dpi", dpi),
+        ("--start", start),
+        ("--end", end),
+        ("--pages", pages),
+        ("--temp-dir", temp_dir),
+        ("--lang", ",".join(lang) if lang else None),
+        ("--merge-sep", merge_sep),
+        ("--output-name", output_name),
+        ("--figure-format", figure_format),
+    ):
+        if value is None or _parse_cli_value(extra, name) is not None:
+            continue
+        head.extend([name, str(value)])
+    return head + extra
+
+
 def _append_force_flags(extra: list[str] | None, fallback: bool, force: bool) -> list[str]:
     extra = list(extra) if extra else []
     if fallback and "--fallback-tesseract" not in extra:
@@ -231,8 +411,16 @@ def run(
     formats: list[str] | None = None,
     docx_math: str = "text",
     crop: str | None = None,
+    rotate: int = 0,
+    deskew: bool = False,
+    auto_orient: bool = False,
     excel_mode: str = "layout",
     excel_meta_sheet: bool = True,
+    languages: list[str] | None = None,
+    csv_delimiter: str = ",",
+    csv_encoding: str = "utf-8",
+    output_name: str | None = None,
+    figure_format: str = "png",
 ) -> Path:
     formats = formats or ["md"]
     meta = inspect(path)
@@ -250,8 +438,37 @@ def run(
             emit_csv=False,  # CSV is no longer needed for Excel, assuming user didn't ask explicitly for CSV
             emit_json=needs_json,
             crop=crop,
+            rotate=rotate,
+            deskew=deskew,
+            auto_orient=auto_orient,
         )
         output_dir = _infer_pdf_output_dir(meta.path, output_root=output_root, extra_args=extra_pdf_args)
+    elif meta.is_image and _is_multipage_tiff(meta.path):
+        # マルチページ TIFF は各フレームを 1 ページとした PDF にまとめ、PDF 経路に流す。
+        # これで --start/--end/--pages のページ範囲指定がそのまま効く。
+        with tempfile.TemporaryDirectory(prefix="ocr_to_doc_tiff_") as tmp:
+            pdf_path = Path(tmp) / f"{meta.path.stem}.pdf"
+            try:
+                tiff_frames_to_pdf(meta.path, pdf_path, dpi=image_dpi)
+            except ImageConversionError as exc:
+                raise IngestError(str(exc)) from exc
+            print(f"[dispatcher] マルチページ TIFF を PDF 化してから OCR: {pdf_path} (dpi={image_dpi})")
+            _run_pdf(
+                pdf_path,
+                mode=mode,
+                device=device,
+                use_math_refiner=use_math_refiner,
+                output_root=output_root,
+                extra_args=_append_force_flags(extra_pdf_args, fallback_tesseract, force_tesseract_merge),
+                force_tesseract_merge=force_tesseract_merge,
+                emit_csv=False,
+                emit_json=needs_json,
+                crop=crop,
+                rotate=rotate,
+                deskew=deskew,
+                auto_orient=auto_orient,
+            )
+            output_dir = _infer_pdf_output_dir(pdf_path, output_root=output_root, extra_args=extra_pdf_args)
     elif meta.is_image:
         output_dir = _run_image(
             meta.path,
@@ -269,12 +486,47 @@ def run(
             emit_csv=False,
             emit_json=needs_json,
             crop=crop,
+            rotate=rotate,
+            deskew=deskew,
+            auto_orient=auto_orient,
+            languages=languages,
+            output_name=output_name,
+            figure_format=figure_format,
         )
     else:
         raise IngestError(f"未対応の入力種別です: {path}")
 
-    # Post-processing for formats
-    if "docx" in formats and output_dir:
+    if output_dir:
+        _export_formats(
+            output_dir,
+            source_stem=path.stem,
+            output_root=output_root,
+            formats=formats,
+            docx_math=docx_math,
+            excel_mode=excel_mode,
+            excel_meta_sheet=excel_meta_sheet,
+            csv_delimiter=csv_delimiter,
+            csv_encoding=csv_encoding,
+        )
+
+    return output_dir
+
+
+def _export_formats(
+    output_dir: Path,
+    *,
+    source_stem: str,
+    output_root: Path,
+    formats: list[str],
+    docx_math: str = "text",
+    excel_mode: str = "layout",
+    excel_meta_sheet: bool = True,
+    csv_delimiter: str = ",",
+    csv_encoding: str = "utf-8",
+) -> None:
+    """OCR 済みの output_dir から md 以外のフォーマットを書き出す（OCR は走らせない）。"""
+
+    if "docx" in formats:
         # Try to find the markdown file to convert
         # 1. Merged markdown (PDF or image-as-pdf)
         merged_md = output_dir / f"{output_dir.name}_merged.md"
@@ -290,7 +542,7 @@ def run(
             
             # If we have page_001.md, let's rename it to {stem}.md if it doesn't exist
             page_md = output_dir / "page_001.md"
-            target_md = output_dir / f"{path.stem}.md"
+            target_md = output_dir / f"{source_stem}.md"
             
             if page_md.exists():
                 # Rename for consistency if it's the only file
@@ -306,16 +558,46 @@ def run(
                 convert_file(md_to_convert, math_mode=docx_math)
                 print(f"[dispatcher] Converting to docx: {md_to_convert}")
 
-    if "xlsx" in formats and output_dir:
+    if "xlsx" in formats:
         # json -> xlsx
         print("[dispatcher] processing excel_via=json")
         _convert_to_excel(output_dir, output_root, excel_mode=excel_mode, excel_meta_sheet=excel_meta_sheet)
 
-    if "csv" in formats and output_dir:
+    if "csv" in formats:
         print("[dispatcher] processing csv_via=json")
-        _convert_to_csv(output_dir, excel_mode=excel_mode)
+        _convert_to_csv(output_dir, excel_mode=excel_mode, delimiter=csv_delimiter, encoding=csv_encoding)
 
-    return output_dir
+
+def export_existing(
+    result_dir: Path,
+    *,
+    formats: list[str],
+    docx_math: str = "text",
+    excel_mode: str = "layout",
+    excel_meta_sheet: bool = True,
+    csv_delimiter: str = ",",
+    csv_encoding: str = "utf-8",
+) -> Path:
+    """既存の結果ディレクトリを別フォーマットに書き出し直す（--export-only）。"""
+
+    result_dir = Path(result_dir)
+    if not result_dir.is_dir():
+        raise IngestError(f"結果ディレクトリが見つかりません: {result_dir}")
+    has_md = (result_dir / f"{result_dir.name}_merged.md").exists() or any(result_dir.glob("*.md"))
+    if not has_md:
+        raise IngestError(f"変換元の Markdown が見つかりません: {result_dir}")
+    _export_formats(
+        result_dir,
+        source_stem=result_dir.name,
+        output_root=result_dir.parent,
+        formats=formats,
+        docx_math=docx_math,
+        excel_mode=excel_mode,
+        excel_meta_sheet=excel_meta_sheet,
+        csv_delimiter=csv_delimiter,
+        csv_encoding=csv_encoding,
+    )
+    return result_dir
 
 
 def _run_pdf(
@@ -330,6 +612,9 @@ def _run_pdf(
     emit_csv: bool = False,
     emit_json: bool = False,
     crop: str | None = None,
+    rotate: int = 0,
+    deskew: bool = False,
+    auto_orient: bool = False,
 ) -> None:
     script = Path(__file__).resolve().parent / "ocr_chanked.py"
     cmd = [
@@ -353,11 +638,17 @@ def _run_pdf(
         cmd.extend(["--emit-json", "on"])
     if crop:
         cmd.extend(["--crop", crop])
+    if rotate:
+        cmd.extend(["--rotate", str(rotate)])
+    if deskew:
+        cmd.append("--deskew")
+    if auto_orient:
+        cmd.append("--auto-orient")
     if force_tesseract_merge and "--force-tesseract-merge" not in (extra_args or []):
         extra_args = (extra_args or []) + ["--force-tesseract-merge"]
     if extra_args:
         cmd.extend(extra_args)
-    print(f"[dispatcher] PDF を OCR ルートへ委譲: {' '.join(cmd)}")
+    print(f"[dispatcher] PDF を OCR ルートへ委譲: {' '.join(redact_password_args(cmd))}")
     subprocess.run(cmd, check=True)
 
 
@@ -378,21 +669,48 @@ def _run_image(
     emit_csv: bool = False,
     emit_json: bool = False,
     crop: str | None = None,
+    rotate: int = 0,
+    deskew: bool = False,
+    auto_orient: bool = False,
+    languages: list[str] | None = None,
+    output_name: str | None = None,
+    figure_format: str = "png",
 ) -> Path:
     # 画像処理に必要なモジュールはここで遅延インポートして、PDF 経路では Pillow 未インストールでも動くようにする
     from image_preprocessor import (
         PROFILE_REGISTRY,
         get_profile,
+        orient_image,
         preprocess_image_variants,
     )
 
-    output_dir = _ensure_output_dir(image_path, output_root)
+    output_dir = _ensure_output_dir(image_path, output_root, name=output_name)
+    print(f"{OUTPUT_DIR_MARKER} {output_dir.resolve()}", flush=True)
     convert_dir = output_dir / CONVERTED_DIR_NAME
     try:
         conversion = ensure_png_image(image_path, convert_dir=convert_dir, svg_dpi=svg_dpi)
     except ImageConversionError as exc:
         raise IngestError(str(exc)) from exc
 
+    # 回転・傾き補正はトリミング範囲（プレビュー上で指定）と座標系を揃えるため先に行う
+    auto_rotation = 0
+    if rotate or deskew or auto_orient:
+        from PIL import Image, ImageOps
+        from image_normalizer import ImageConversionResult
+
+        oriented_path = convert_dir / f"{image_path.stem}_oriented.png"
+        with Image.open(conversion.converted) as img:
+            img = ImageOps.exif_transpose(img)
+            oriented, auto_rotation = orient_image(img, rotate=rotate, deskew=deskew, auto_orient=auto_orient)
+            oriented.save(oriented_path, format="PNG", optimize=True)
+        if auto_rotation:
+            print(f"[dispatcher] 自動回転: 時計回りに {auto_rotation} 度回転しました")
+        conversion = ImageConversionResult(
+            source=conversion.source,
+            converted=oriented_path,
+            performed=True,
+        )
+
     if crop:
         try:
             from PIL import Image, ImageOps
@@ -400,32 +718,17 @@ def _run_image(
             raise IngestError("Pillow がインストールされていません（トリミングに必要）") from exc
         from image_normalizer import ImageConversionResult
 
-        def _parse_crop(value: str) -> tuple[float, float, float, float]:
-            parts = [p.strip() for p in value.split(",")]
-            if len(parts) != 4:
-                raise ValueError("crop は left,top,width,height の4要素が必要です")
-            left, top, width, height = (float(p) for p in parts)
-            return left, top, width, height
-
         try:
-            left, top, width, height = _parse_crop(crop)
+            crop_rect = parse_crop(crop)
         except ValueError as exc:
             raise IngestError(f"--crop の指定が不正です: {exc}") from exc
-        left = max(0.0, min(1.0, left))
-        top = max(0.0, min(1.0, top))
-        width = max(0.0, min(1.0 - left, width))
-        height = max(0.0, min(1.0 - top, height))
-        if width > 0 and height > 0:
+        if crop_rect is not None:
             cropped_path = convert_dir / f"{image_path.stem}_cropped.png"
             with Image.open(conversion.converted) as img:
                 img = ImageOps.exif_transpose(img)
-                w, h = img.size
-                lpx = int(round(left * w))
-                tpx = int(round(top * h))
-                rpx = int(round((left + width) * w))
-                bpx = int(round((top + height) * h))
-                if rpx > lpx and bpx > tpx:
-                    img.crop((lpx, tpx, rpx, bpx)).save(cropped_path, format="PNG", optimize=True)
+                cropped = apply_crop(img, crop_rect)
+                if cropped is not img:
+                    cropped.save(cropped_path, format="PNG", optimize=True)
                     conversion = ImageConversionResult(
                         source=conversion.source,
                         converted=cropped_path,
@@ -446,8 +749,11 @@ def _run_image(
             extra_args=_append_force_flags(extra_pdf_args, fallback_tesseract, force_tesseract_merge),
             force_tesseract_merge=force_tesseract_merge,
             emit_json=emit_json,  # PDF経由もJSONを出す
-            crop=crop,
+            # 回転・傾き補正とトリミングは PDF 化前の画像に適用済み（ここで渡すと二重に切り抜かれる）
         )
+        # ocr_chanked.py は --auto-orient なしだと前回の記録を消すので、その後に書く
+        if auto_orient:
+            write_auto_rotations(output_dir, {1: auto_rotation})
         return output_dir
 
     ocr_profile_obj = get_profile(ocr_profile)
@@ -463,8 +769,10 @@ def _run_image(
         mode=mode,
         device=device,
         enable_figure=enable_figure,
+        figure_format=figure_format,
         fallback_tesseract=fallback_tesseract,
         force_tesseract_merge=force_tesseract_merge,
+        languages=languages,
     )
     print(f"[dispatcher] 画像を OCR ルートへ委譲: {ocr_source}")
     run_ocr(ocr_source, output_dir, page_number=1, options=options)
@@ -474,6 +782,8 @@ def _run_image(
         # ocr.py の export_json は便利関数として使える
         from ocr import export_json
         export_json(ocr_source, output_dir, options)
+    if auto_orient:
+        write_auto_rotations(output_dir, {1: auto_rotation})
     return output_dir
 
 
@@ -572,7 +882,13 @@ def _convert_to_excel(
     print(f"[dispatcher] Saved Excel: {xlsx_path}")
 
 
-def _convert_to_csv(output_dir: Path, *, excel_mode: str) -> None:
+def _convert_to_csv(
+    output_dir: Path,
+    *,
+    excel_mode: str,
+    delimiter: str = ",",
+    encoding: str = "utf-8",
+) -> None:
     """yomi_formats/json 内の JSON を集めて CSV（結合解除＋分割）に変換する。"""
 
     import csv
@@ -586,6 +902,7 @@ def _convert_to_csv(output_dir: Path, *, excel_mode: str) -> None:
         print(f"[dispatcher] JSON dir not found: {json_dir}")
 
     from export_excel_poc import (
+        csv_codec,
         load_tables_from_json,
         split_text_to_paragraphs,
         write_tables_to_csv_files,
@@ -622,6 +939,8 @@ def _convert_to_csv(output_dir: Path, *, excel_mode: str) -> None:
             output_dir=output_dir,
             base_name=output_dir.name,
             excel_mode=excel_mode,
+            delimiter=delimiter,
+            encoding=encoding,
         )
         if outputs:
             print(f"[dispatcher] Saved CSVs: {len(outputs)}")
@@ -644,22 +963,29 @@ def _convert_to_csv(output_dir: Path, *, excel_mode: str) -> None:
             print(f"[dispatcher] Failed to read markdown: {md_path.name}: {exc}")
     paragraphs = split_text_to_paragraphs("\n\n".join(text_parts))
     csv_path = output_dir / f"{output_dir.name}.csv"
-    with csv_path.open("w", encoding="utf-8", newline="") as fp:
-        writer = csv.writer(fp)
+    with csv_path.open("w", encoding=csv_codec(encoding), errors="replace", newline="") as fp:
+        writer = csv.writer(fp, delimiter=delimiter)
         writer.writerow(["本文"])
         for para in paragraphs:
             writer.writerow([para])
     print(f"[dispatcher] Saved CSV: {csv_path}")
 
 
-def _ensure_output_dir(source: Path, output_root: Path) -> Path:
+def _ensure_output_dir(source: Path, output_root: Path, *, name: str | None = None) -> Path:
     output_root = Path(output_root)
     output_root.mkdir(parents=True, exist_ok=True)
-    target = output_root / source.stem
+    target = output_root / (name or source.stem)
     target.mkdir(parents=True, exist_ok=True)
     return target
 
 
+def _is_multipage_tiff(path: Path) -> bool:
+    try:
+        return tiff_frame_count(path) > 1
+    except ImageConversionError as exc:
+        raise IngestError(str(exc)) from exc
+
+
 def _convert_image_to_pdf(image_path: Path, pdf_path: Path, *, dpi: int) -> None:
     try:
         from PIL import Image
@@ -674,6 +1000,22 @@ def _convert_image_to_pdf(image_path: Path, pdf_path: Path, *, dpi: int) -> None
 
 def main() -> None:
     args = parse_args()
+    if args.password:
+        # ocr_chanked.py / ui_preview.py へは引数ではなく環境変数で引き継ぐ（プロセス一覧に出さない）
+        os.environ[PDF_PASSWORD_ENV] = args.password
+    if args.list_languages:
+        print(json.dumps(supported_languages()))
+        return
+    if args.warm_models:
+        try:
+            warm_models(mode=args.mode, device=args.device)
+        except (ImportError, subprocess.CalledProcessError) as exc:
+            print(f"[dispatcher] モデルの準備に失敗しました: {exc}")
+            sys.exit(1)
+        return
+    if args.temp_dir:
+        Path(args.temp_dir).mkdir(parents=True, exist_ok=True)
+        tempfile.tempdir = args.temp_dir
     print(
         "[dispatcher] parsed args:",
         {
@@ -683,19 +1025,51 @@ def main() -> None:
             "image_dpi": args.image_dpi,
             "ocr_profile": args.ocr_profile,
             "enable_figure": args.enable_figure,
+            "figure_format": args.figure_format,
             "math_refiner": args.math_refiner,
             "fallback_tesseract": args.fallback_tesseract,
             "force_tesseract_merge": args.force_tesseract_merge,
             "formats": args.formats,
+            "export_only": args.export_only,
             "excel_mode": args.excel_mode,
             "excel_meta_sheet": args.excel_meta_sheet,
+            "csv_delimiter": args.csv_delimiter,
+            "csv_encoding": args.csv_encoding,
             "docx_math": args.docx_math,
             "crop": args.crop,
-            "extra": args.extra,
+            "rotate": args.rotate,
+            "deskew": args.deskew,
+            "auto_orient": args.auto_orient,
+            "dpi": args.dpi,
+            "start": args.start,
+            "end": args.end,
+            "pages": args.pages,
+            "temp_dir": args.temp_dir,
+            "password": "***" if args.password else None,
+            "lang": args.lang,
+            "merge_sep": args.merge_sep,
+            "output_name": args.output_name,
+            "extra": redact_password_args(args.extra),
         },
     )
+    force_tesseract_merge = args.force_tesseract_merge
+    if needs_tesseract(args.lang) and not force_tesseract_merge:
+        # YomiToku は日英以外を読めないので、pytesseract の結果を追記して補う
+        print(f"[dispatcher] --lang={','.join(args.lang)} のため pytesseract の追記を有効化します")
+        force_tesseract_merge = True
     try:
-        run(
+        if args.export_only:
+            export_existing(
+                Path(args.input_path),
+                formats=args.formats,
+                docx_math=args.docx_math,
+                excel_mode=args.excel_mode,
+                excel_meta_sheet=args.excel_meta_sheet,
+                csv_delimiter=args.csv_delimiter,
+                csv_encoding=args.csv_encoding,
+            )
+            return
+        output_dir = run(
             Path(args.input_path),
             mode=args.mode,
             device=args.device,
@@ -703,21 +1077,81 @@ def main() -> None:
             svg_dpi=args.svg_dpi,
             enable_figure=args.enable_figure,
             use_math_refiner=args.math_refiner,
-            extra_pdf_args=args.extra or None,
+            extra_pdf_args=_merge_pdf_args(
+                args.extra,
+                dpi=args.dpi,
+                start=args.start,
+                end=args.end,
+                pages=args.pages,
+                temp_dir=args.temp_dir,
+                lang=args.lang,
+                merge_sep=args.merge_sep,
+                output_name=args.output_name,
+                figure_format=args.figure_format,
+            )
+            or None,
             ocr_profile=args.ocr_profile,
             image_as_pdf=args.image_as_pdf,
             image_dpi=args.image_dpi,
             fallback_tesseract=args.fallback_tesseract,
-            force_tesseract_merge=args.force_tesseract_merge,
+            force_tesseract_merge=force_tesseract_merge,
             formats=args.formats,
             docx_math=args.docx_math,
             crop=args.crop,
+            rotate=args.rotate,
+            deskew=args.deskew,
+            auto_orient=args.auto_orient,
             excel_mode=args.excel_mode,
             excel_meta_sheet=args.excel_meta_sheet,
+            languages=args.lang,
+            csv_delimiter=args.csv_delimiter,
+            csv_encoding=args.csv_encoding,
+            output_name=args.output_name,
+            figure_format=args.figure_format or "png",
         )
     except (IngestError, ImageConversionError, subprocess.CalledProcessError) as exc:
         print(f"[dispatcher] エラー: {exc}")
         sys.exit(1)
+    if output_dir:
+        _emit_result_metadata(Path(output_dir))
+
+
+# モデルのロード確認に使う白紙画像の一辺（px）
+WARMUP_IMAGE_SIZE = 64
+
+
+def warm_models(*, mode: str, device: str) -> None:
+    """白紙画像を実ジョブと同じ YomiToku コマンドで OCR し、モデルを取得・ロードさせる。
+
+    出力は捨てる。YomiToku のダウンロード進捗はそのまま標準出力・標準エラーに流す。
+    """
+
+    from PIL import Image
+
+    options = OcrOptions(mode=mode, device=device, enable_figure=False)
+    with tempfile.TemporaryDirectory(prefix="ocr_to_doc_warm_") as tmp:
+        image_path = Path(tmp) / "warmup.png"
+        Image.new("RGB", (WARMUP_IMAGE_SIZE, WARMUP_IMAGE_SIZE), "white").save(image_path)
+        cmd = build_command(image_path, Path(tmp) / "out", options)
+        print(f"[dispatcher] モデルを準備しています (mode={mode}, device={device})", flush=True)
+        subprocess.run(cmd, check=True)
+    print("[dispatcher] モデルの準備が完了しました", flush=True)
+
+
+def _emit_result_metadata(output_dir: Path) -> None:
+    """UI が JobInfo.metadata に取り込む `@RESULT {json}` 行を出す（集計に失敗しても処理結果には影響させない）。"""
+
+    try:
+        meta = collect_result_metadata(output_dir)
+    except Exception as exc:  # pragma: no cover - best effort
+        print(f"[dispatcher] 結果メタ情報の集計に失敗しました: {exc}")
+        return
+    # docx で代替表示されるフォント（UI が警告として出す）。調べられなくても結果は出す
+    try:
+        meta["font_warnings"] = font_warnings(output_dir)
+    except Exception as exc:  # pragma: no cover - best effort
+        print(f"[dispatcher] フォントの確認に失敗しました: {exc}")
+    print(f"{RESULT_MARKER} {json.dumps(meta, ensure_ascii=False)}", flush=True)
 
 
 if __name__ == "__main__":
@@ -22,6 +22,8 @@ IMG_HTML_PATTERN = re.compile(r"<img[^>]*src=\"([^\"]+)\"[^>]*>")
 IMG_MD_PATTERN = re.compile(r"!\[[^\]]*\]\(([^)]+)\)")
 WIDTH_PATTERN = re.compile(r"width\s*=\s*\"?([0-9]+(?:\.[0-9]+)?)(px|cm|mm)?\"?")
 PAGE_HEADING_PATTERN = re.compile(r"^#\s+Page\s+(?P<page>\d+)\s*$")
+# postprocess.py の `PAGE_BREAK`（と pandoc の同義語 \pagebreak）。Word の改ページにする
+PAGE_BREAK_PATTERN = re.compile(r"^\\(?:newpage|pagebreak)$")
 
 
 def read_markdown(path: Path) -> list[str]:
@@ -381,6 +383,12 @@ def convert_markdown(
             i += 1
             continue
 
+        if PAGE_BREAK_PATTERN.match(normalized):
+            flush_paragraph(document, paragraph_buffer, base_dir)
+            document.add_page_break()
+            i += 1
+            continue
+
         page_heading = PAGE_HEADING_PATTERN.match(normalized)
         if page_heading:
             flush_paragraph(document, paragraph_buffer, base_dir)
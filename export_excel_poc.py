@@ -30,6 +30,22 @@ class TableCell:
     box: list[int] | None = None
 
 
+# CSV の文字コード指定 → Python の codec。Shift_JIS は Excel と同じく機種依存文字を含む cp932 で書く
+CSV_ENCODINGS: dict[str, str] = {
+    "utf-8": "utf-8",
+    "utf-8-sig": "utf-8-sig",
+    "shift_jis": "cp932",
+}
+
+
+def csv_codec(encoding: str) -> str:
+    try:
+        return CSV_ENCODINGS[encoding]
+    except KeyError:
+        known = ", ".join(CSV_ENCODINGS)
+        raise ValueError(f"未対応の CSV 文字コードです: {encoding!r}（対応: {known}）") from None
+
+
 SYMBOL_WHITELIST = "○〇◯◎△▲▼×✕xX□■●"
 SYMBOL_NORMALIZE = {
     "✕": "×",
@@ -947,13 +963,18 @@ def write_tables_to_csv_files(
     output_dir: Path,
     base_name: str,
     excel_mode: str = "table",
+    delimiter: str = ",",
+    encoding: str = "utf-8",
 ) -> list[Path]:
     """JSON tables から CSV を出力する。
 
     - table: 結合解除＋構造変化で分割（実務向け）
     - layout: JSON の表グリッドをそのまま CSV 化（結合は表現できないため値は埋める）
+
+    encoding は CSV_ENCODINGS のキー。その文字コードで表せない文字は "?" に置き換える。
     """
 
+    codec = csv_codec(encoding)
     output_dir.mkdir(parents=True, exist_ok=True)
     used: set[str] = set()
     outputs: list[Path] = []
@@ -982,8 +1003,8 @@ def write_tables_to_csv_files(
             base = f"{base_name}__table_{segment_index:02d}"
             unique = _make_unique_name(base, used, max_len=200)
             out_path = output_dir / f"{unique}.csv"
-            with out_path.open("w", encoding="utf-8", newline="") as fp:
-                writer = csv.writer(fp)
+            with out_path.open("w", encoding=codec, errors="replace", newline="") as fp:
+                writer = csv.writer(fp, delimiter=delimiter)
                 for r in range(1, last_row + 1):
                     row = [
                         to_plain_text(values[r - 1][c - 1])
@@ -1016,8 +1037,8 @@ def write_tables_to_csv_files(
             unique = _make_unique_name(base, used, max_len=200)
             out_path = output_dir / f"{unique}.csv"
 
-            with out_path.open("w", encoding="utf-8", newline="") as fp:
-                writer = csv.writer(fp)
+            with out_path.open("w", encoding=codec, errors="replace", newline="") as fp:
+                writer = csv.writer(fp, delimiter=delimiter)
                 writer.writerow([to_plain_text(h) for h in header])
                 for r in data_rows:
                     row = [
@@ -0,0 +1,207 @@
+"""docx が参照するフォントのうち、この PC に入っていないものを調べる。
+
+Word は無いフォントを別のフォントに置き換えて表示するため、レイアウト崩れや豆腐（□）の原因になる。
+dispatcher.py が `@RESULT` の `font_warnings` に載せ、UI が警告として表示する。
+
+テーマのフォント（python-docx の既定テンプレートの Calibri / ＭＳ 明朝 など）は Windows 以外では
+Office が環境に合わせて置き換えるため、Windows でだけ調べる（他の OS では誤検知になる）。
+"""
+
+from __future__ import annotations
+
+import functools
+import os
+import re
+import struct
+import sys
+import unicodedata
+import zipfile
+from pathlib import Path
+from typing import BinaryIO, Iterable
+
+# フォント指定を読む docx 内のパーツ（本文・スタイル）とテーマ
+DOCX_FONT_PARTS = ("word/document.xml", "word/styles.xml")
+DOCX_THEME_PART = "word/theme/theme1.xml"
+# <w:rFonts w:ascii="..." w:eastAsia="..."/> の明示指定
+RFONTS_ATTR_PATTERN = re.compile(r'w:(?:ascii|hAnsi|eastAsia|cs)="([^"]+)"')
+# テーマの欧文フォントと日本語（Jpan）フォント
+THEME_FONT_PATTERN = re.compile(r'<a:(?:latin|font script="Jpan") typeface="([^"]+)"')
+
+FONT_EXTENSIONS = {".ttf", ".otf", ".ttc", ".otc"}
+# name テーブルで見る ID（1: ファミリ名, 4: フルネーム, 16: 優先ファミリ名）
+FONT_NAME_IDS = {1, 4, 16}
+
+
+def normalize_font_name(name: str) -> str:
+    """全角/半角・大小文字・空白の違いを無視して比べる（"ＭＳ 明朝" と "MS明朝" を同じにする）。"""
+
+    return re.sub(r"\s+", "", unicodedata.normalize("NFKC", name)).casefold()
+
+
+def docx_font_names(docx_path: Path, include_theme: bool = True) -> list[str]:
+    """docx が指定しているフォント名（出現順、重複なし）。`include_theme` が偽ならテーマのフォントは除く。"""
+
+    parts = (*DOCX_FONT_PARTS, DOCX_THEME_PART) if include_theme else DOCX_FONT_PARTS
+    found: dict[str, str] = {}
+    with zipfile.ZipFile(docx_path) as archive:
+        names = set(archive.namelist())
+        for part in parts:
+            if part not in names:
+                continue
+            xml = archive.read(part).decode("utf-8", errors="replace")
+            for match in (*RFONTS_ATTR_PATTERN.finditer(xml), *THEME_FONT_PATTERN.finditer(xml)):
+                name = match.group(1).strip()
+                if name:
+                    found.setdefault(normalize_font_name(name), name)
+    return list(found.values())
+
+
+def font_dirs() -> list[Path]:
+    if sys.platform.startswith("win"):
+        dirs = [Path(os.environ.get("WINDIR", r"C:\Windows")) / "Fonts"]
+        local = os.environ.get("LOCALAPPDATA")
+        if local:
+            dirs.append(Path(local) / "Microsoft" / "Windows" / "Fonts")
+    elif sys.platform == "darwin":
+        dirs = [
+            Path("/System/Library/Fonts"),
+            Path("/Library/Fonts"),
+            Path.home() / "Library" / "Fonts",
+            # Word 同梱のフォント
+            Path("/Applications/Microsoft Word.app/Contents/Resources/DFonts"),
+        ]
+    else:
+        dirs = [
+            Path("/usr/share/fonts"),
+            Path("/usr/local/share/fonts"),
+            Path.home() / ".fonts",
+            Path.home() / ".local" / "share" / "fonts",
+        ]
+    return [d for d in dirs if d.is_dir()]
+
+
+def _read_at(fp: BinaryIO, offset: int, size: int) -> bytes:
+    fp.seek(offset)
+    data = fp.read(size)
+    if len(data) != size:
+        raise struct.error("truncated font file")
+    return data
+
+
+def _sfnt_names(fp: BinaryIO, offset: int) -> set[str]:
+    (num_tables,) = struct.unpack(">H", _read_at(fp, offset + 4, 2))
+    directory = _read_at(fp, offset + 12, num_tables * 16)
+    for i in range(num_tables):
+        tag, _, table_offset, _ = struct.unpack_from(">4sIII", directory, i * 16)
+        if tag == b"name":
+            break
+    else:
+        return set()
+
+    _, count, string_offset = struct.unpack(">HHH", _read_at(fp, table_offset, 6))
+    records = _read_at(fp, table_offset + 6, count * 12)
+    names: set[str] = set()
+    for i in range(count):
+        platform, encoding, _, name_id, length, rel = struct.unpack_from(">HHHHHH", records, i * 12)
+        if name_id not in FONT_NAME_IDS:
+            continue
+        raw = _read_at(fp, table_offset + string_offset + rel, length)
+        if platform in (0, 3):
+            text = raw.decode("utf-16-be", errors="ignore")
+        elif platform == 1 and encoding == 0:
+            text = raw.decode("latin-1")
+        else:
+            continue
+        if text.strip():
+            names.add(text.strip())
+    return names
+
+
+def read_font_names(path: Path) -> set[str]:
+    """TrueType/OpenType（コレクション含む）の name テーブルからファミリ名・フルネームを読む。
+
+    フォント全体は読まず、必要な部分だけを seek して読む（CJK フォントは数十 MB あるため）。
+    """
+
+    with path.open("rb") as fp:
+        header = _read_at(fp, 0, 12)
+        if header[:4] == b"ttcf":
+            (num_fonts,) = struct.unpack_from(">I", header, 8)
+            offsets = struct.unpack(f">{num_fonts}I", _read_at(fp, 12, num_fonts * 4))
+        else:
+            offsets = (0,)
+        names: set[str] = set()
+        for offset in offsets:
+            names |= _sfnt_names(fp, offset)
+        return names
+
+
+def installed_font_names(dirs: Iterable[Path] | None = None) -> set[str]:
+    """インストール済みフォントの名前（normalize_font_name 済み）。読めないファイルは飛ばす。"""
+
+    names: set[str] = set()
+    for font_dir in font_dirs() if dirs is None else dirs:
+        for path in font_dir.rglob("*"):
+            if path.suffix.lower() not in FONT_EXTENSIONS:
+                continue
+            try:
+                names |= {normalize_font_name(n) for n in read_font_names(path)}
+            except (OSError, struct.error):
+                continue
+    return names
+
+
+@functools.lru_cache(maxsize=None)
+def system_font_names() -> frozenset[str]:
+    """この PC のフォント名。フォントディレクトリ全体を読むので、プロセスごとに 1 回だけ調べる。"""
+
+    return frozenset(installed_font_names())
+
+
+def missing_fonts(docx_path: Path, installed: set[str], include_theme: bool = True) -> list[str]:
+    return [
+        name
+        for name in docx_font_names(docx_path, include_theme=include_theme)
+        if normalize_font_name(name) not in installed
+    ]
+
+
+def font_warnings(
+    output_dir: Path,
+    installed: set[str] | None = None,
+    include_theme: bool | None = None,
+) -> list[str]:
+    """出力ディレクトリの docx ごとに、見つからないフォントの警告文を返す。
+
+    フォント一覧が取れない環境（フォントディレクトリが無いなど）では誤検知を避けて何も返さない。
+    `include_theme` 未指定なら Windows でだけテーマのフォントも調べる。
+    """
+
+    docx_files = sorted(output_dir.glob("*.docx"))
+    if not docx_files:
+        return []
+    if installed is None:
+        installed = system_font_names()
+    if not installed:
+        return []
+    if include_theme is None:
+        include_theme = sys.platform.startswith("win")
+    warnings = []
+    for docx_path in docx_files:
+        missing = missing_fonts(docx_path, installed, include_theme=include_theme)
+        if missing:
+            warnings.append(
+                f"{docx_path.name}: フォント {', '.join(missing)} が見つからないため、Word では別のフォントで表示されます"
+            )
+    return warnings
+
+
+__all__ = [
+    "docx_font_names",
+    "font_warnings",
+    "installed_font_names",
+    "missing_fonts",
+    "normalize_font_name",
+    "read_font_names",
+    "system_font_names",
+]
@@ -13,6 +13,7 @@ from pathlib import Path
 HEIC_EXTENSIONS = {".heic", ".heif"}
 SVG_EXTENSIONS = {".svg"}
 CONVERTIBLE_EXTENSIONS = HEIC_EXTENSIONS | SVG_EXTENSIONS
+TIFF_EXTENSIONS = {".tif", ".tiff"}
 
 
 class ImageConversionError(RuntimeError):
@@ -97,12 +98,52 @@ def _convert_svg_to_png(source: Path, target: Path, *, dpi: int = 300) -> None:
         raise ImageConversionError(f"SVG 変換に失敗しました: {exc}") from exc
 
 
+def tiff_frame_count(source: Path) -> int:
+    """Return the number of frames in a TIFF (1 for every other image type)."""
+
+    if source.suffix.lower() not in TIFF_EXTENSIONS:
+        return 1
+    try:
+        from PIL import Image
+    except ImportError as exc:  # pragma: no cover - Pillow is a hard dependency in practice
+        raise ImageConversionError("Pillow がインストールされていません（TIFF の読み込みに必要）") from exc
+
+    try:
+        with Image.open(source) as img:
+            return max(1, int(getattr(img, "n_frames", 1)))
+    except Exception as exc:
+        raise ImageConversionError(f"TIFF の読み込みに失敗しました: {exc}") from exc
+
+
+def tiff_frames_to_pdf(source: Path, target: Path, *, dpi: int = 300) -> Path:
+    """Write every frame of a multi-page TIFF into one PDF (one frame per page)."""
+
+    try:
+        from PIL import Image, ImageSequence
+    except ImportError as exc:  # pragma: no cover - Pillow is a hard dependency in practice
+        raise ImageConversionError("Pillow がインストールされていません（TIFF の変換に必要）") from exc
+
+    target.parent.mkdir(parents=True, exist_ok=True)
+    try:
+        with Image.open(source) as img:
+            frames = [frame.convert("RGB") for frame in ImageSequence.Iterator(img)]
+    except Exception as exc:
+        raise ImageConversionError(f"TIFF の読み込みに失敗しました: {exc}") from exc
+    if not frames:
+        raise ImageConversionError(f"TIFF にフレームがありません: {source}")
+    frames[0].save(target, format="PDF", resolution=dpi, save_all=True, append_images=frames[1:])
+    return target
+
+
 __all__ = [
     "HEIC_EXTENSIONS",
     "SVG_EXTENSIONS",
     "CONVERTIBLE_EXTENSIONS",
+    "TIFF_EXTENSIONS",
     "ImageConversionError",
     "ImageConversionResult",
     "requires_conversion",
     "ensure_png_image",
+    "tiff_frame_count",
+    "tiff_frames_to_pdf",
 ]
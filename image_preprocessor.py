@@ -200,6 +200,122 @@ def get_profile(key: str) -> ImagePreprocessProfile:
     return PROFILE_REGISTRY[key]
 
 
+ALLOWED_ROTATIONS = (0, 90, 180, 270)
+
+
+def rotate_image(image: Image.Image, degrees: int) -> Image.Image:
+    """時計回りに 90 度単位で回転する（スキャン向きの補正用）。"""
+
+    if degrees not in ALLOWED_ROTATIONS:
+        raise ValueError(f"rotate は {ALLOWED_ROTATIONS} のいずれかで指定してください: {degrees}")
+    if degrees == 0:
+        return image
+    # PIL の rotate は反時計回り
+    return image.rotate(-degrees, expand=True)
+
+
+def estimate_skew_angle(image: Image.Image, *, max_angle: float = 5.0, step: float = 0.5) -> float:
+    """文字行を水平に揃えるために回すべき角度（度、反時計回りが正）を推定する。
+
+    文字行が水平になると行/行間の明暗差が最大になる、という単純な方法なので
+    ±max_angle の小さな傾きだけを対象にする。
+    """
+
+    gray = image.convert("L")
+    if max(gray.size) > 800:
+        # 角度の推定だけなので縮小して十分
+        gray = resize_long_edge(gray, 800)
+    ink = np.asarray(gray, dtype=np.float32) < 128
+    if not ink.any():
+        return 0.0
+    mask = Image.fromarray((ink * 255).astype(np.uint8))
+
+    best_angle, best_score = 0.0, -1.0
+    steps = int(round(max_angle / step))
+    for i in range(-steps, steps + 1):
+        angle = i * step
+        rotated = np.asarray(mask.rotate(angle, expand=True, fillcolor=0), dtype=np.float32)
+        score = float(np.var(rotated.sum(axis=1)))
+        if score > best_score:
+            best_angle, best_score = angle, score
+    return best_angle
+
+
+def deskew_image(image: Image.Image, *, max_angle: float = 5.0) -> Image.Image:
+    """estimate_skew_angle の角度だけ回して傾きを打ち消す。余白は白で埋める。"""
+
+    angle = estimate_skew_angle(image, max_angle=max_angle)
+    if angle == 0.0:
+        return image
+    fill = 255 if image.mode == "L" else (255,) * len(image.getbands())
+    return image.rotate(angle, expand=True, resample=Image.BICUBIC, fillcolor=fill)
+
+
+# 列方向の投影の濃淡が行方向のこの倍を超えたら横倒しとみなす（表や図だけのページで誤判定しないよう余裕を持たせる）
+ORIENTATION_CONTRAST_RATIO = 1.5
+# 暗い画素がこの割合に満たないページ（白紙に近い）は向きを判定しない
+MIN_INK_FRACTION = 0.002
+
+
+def _profile_contrast(profile: np.ndarray) -> float:
+    """投影（行または列ごとの黒画素数）の濃淡。画像の縦横比に左右されないよう平均の 2 乗で割る。"""
+
+    mean = float(profile.mean())
+    return float(profile.var()) / (mean * mean) if mean > 0 else 0.0
+
+
+def _left_aligned(ink: np.ndarray) -> bool:
+    """文字のある行の左端のばらつきが右端以下か（横書きは行頭が揃い、行末は不揃いになる）。"""
+
+    lines = ink[ink.any(axis=1)]
+    starts = lines.argmax(axis=1)
+    ends = lines.shape[1] - 1 - lines[:, ::-1].argmax(axis=1)
+    return float(np.std(starts)) <= float(np.std(ends))
+
+
+def detect_orientation(image: Image.Image) -> int:
+    """横書きの文字行が水平になるよう時計回りに回すべき角度（0・90・270）を推定する。
+
+    estimate_skew_angle と同じく、文字行が水平なら行方向の投影の濃淡が列方向より大きくなる
+    ことを使う。横倒しなら行頭の揃い方で 90 と 270 を選ぶ。上下逆さ（180 度）は投影では
+    区別できないので対象外。縦書きのページは横倒しの横書きと見分けられない。
+    """
+
+    gray = image.convert("L")
+    if max(gray.size) > 800:
+        gray = resize_long_edge(gray, 800)
+    ink = np.asarray(gray, dtype=np.float32) < 128
+    if ink.mean() < MIN_INK_FRACTION:
+        return 0
+    rows = _profile_contrast(ink.sum(axis=1))
+    cols = _profile_contrast(ink.sum(axis=0))
+    if cols <= rows * ORIENTATION_CONTRAST_RATIO:
+        return 0
+    # np.rot90 の k=-1 は rotate_image(…, 90) と同じ時計回り
+    return 90 if _left_aligned(np.rot90(ink, k=-1)) else 270
+
+
+def orient_image(
+    image: Image.Image, *, rotate: int = 0, deskew: bool = False, auto_orient: bool = False
+) -> tuple[Image.Image, int]:
+    """回転 → 自動の向き補正 → 傾き補正の順で適用し、(画像, 自動で足した回転角) を返す。"""
+
+    image = rotate_image(image, rotate)
+    auto_rotation = detect_orientation(image) if auto_orient else 0
+    image = rotate_image(image, auto_rotation)
+    if deskew:
+        image = deskew_image(image)
+    return image, auto_rotation
+
+
+def apply_orientation(
+    image: Image.Image, *, rotate: int = 0, deskew: bool = False, auto_orient: bool = False
+) -> Image.Image:
+    """回転 → 自動の向き補正 → 傾き補正の順で適用する（トリミングより前に呼ぶ）。"""
+
+    return orient_image(image, rotate=rotate, deskew=deskew, auto_orient=auto_orient)[0]
+
+
 __all__ = [
     "ImagePreprocessProfile",
     "PROFILE_REGISTRY",
@@ -207,6 +323,13 @@ __all__ = [
     "PRESENTATION_COLOR_PROFILE",
     "preprocess_image_variants",
     "get_profile",
+    "ALLOWED_ROTATIONS",
+    "rotate_image",
+    "estimate_skew_angle",
+    "deskew_image",
+    "detect_orientation",
+    "orient_image",
+    "apply_orientation",
 ]
 
 
@@ -87,6 +87,17 @@ def inspect(path: Path) -> InputMeta:
     return InputMeta(path=path, kind=kind, pages=pages, note=note)
 
 
+# パスワード保護 PDF のパスワードを子プロセスへ渡す環境変数（コマンドラインやプロセス一覧に残さないため）。
+# 各スクリプトの `--password` が指定されていればそちらを優先する。
+PDF_PASSWORD_ENV = "OCR_TO_DOC_PDF_PASSWORD"
+
+
+def is_pdf_password_error(exc: BaseException) -> bool:
+    """poppler（pdfinfo / pdftoppm）がパスワード保護された PDF で失敗したかどうか。"""
+
+    return "password" in str(exc).lower()
+
+
 def prepare_workdirs(page_dir: Path = DEFAULT_PAGE_DIR, figure_dir: Path = DEFAULT_FIGURE_DIR) -> None:
     page_dir.mkdir(parents=True, exist_ok=True)
     figure_dir.mkdir(parents=True, exist_ok=True)
@@ -96,6 +107,8 @@ __all__ = [
     "InputKind",
     "InputMeta",
     "inspect",
+    "is_pdf_password_error",
+    "PDF_PASSWORD_ENV",
     "prepare_workdirs",
     "IngestError",
 ]
@@ -21,12 +21,17 @@ from typing import Iterable, Sequence, Dict, Any
 from PIL import Image, ImageStat
 
 from markdown_cleanup import clean_file
+from ocr_languages import tesseract_lang
 
 RAW_MD_PATTERN = re.compile(r"page_(\d+)_p(\d+)\.md")
 ALT_MD_PATTERN = re.compile(r"(?:.*_)?page_?(\d+)(?:_p(\d+))?\.md")
 TARGET_MD_PATTERN = re.compile(r"page_(\d+)(?:_p(\d+))?\.md")
 RAW_FIG_PATTERN = re.compile(r"(?:.*_)?page_(\d+)(?:_p(\d+))?_figure_(\d+)(\.[A-Za-z0-9]+)$")
 MATH_PATTERN = re.compile(r"(\\\(|\\\[)(.*?)(\\\)|\\\])", re.DOTALL)
+# 抽出した図版の保存形式（--figure-format）。YomiToku は PNG で書き出す
+FIGURE_FORMATS = ("png", "jpeg")
+FIGURE_SUFFIXES = {"png": (".png",), "jpeg": (".jpg", ".jpeg")}
+JPEG_FIGURE_QUALITY = 90
 IMG_TAG_PATTERN = re.compile(
     r"<img[^>]*?src=\"(?P<src>[^\"]+)\"[^>]*?(?:alt=\"(?P<alt>[^\"]*)\")?[^>]*?>",
     re.IGNORECASE,
@@ -89,9 +94,13 @@ class OcrOptions:
     mode: str = "lite"  # "lite" or "full"
     device: str = "cpu"
     enable_figure: bool = True
+    # 図版の保存形式（FIGURE_FORMATS のいずれか）
+    figure_format: str = "png"
     extra_args: Sequence[str] | None = None
     fallback_tesseract: bool = False
     force_tesseract_merge: bool = False
+    # pytesseract に渡す言語（ocr_languages のコード）。None なら日英
+    languages: Sequence[str] | None = None
 
     def to_cli_args(self) -> list[str]:
         args: list[str] = []
@@ -192,7 +201,10 @@ def rename_figure_assets(
     page_number: int,
     icon_config: IconFilterConfig | None = None,
     page_metrics: dict[str, Any] | None = None,
+    figure_format: str = "png",
 ) -> None:
+    if figure_format not in FIGURE_FORMATS:
+        raise ValueError(f"unsupported figure format: {figure_format}")
     figure_dir = output_dir / "figures"
     if not figure_dir.exists():
         return
@@ -224,20 +236,41 @@ def rename_figure_assets(
     mapping: Dict[str, str] = {}
     for new_idx, (_, _, fig_path) in enumerate(entries, start=1):
         old_name = fig_path.name
-        new_name = f"fig_page{page_number:03d}_{new_idx:02d}{fig_path.suffix.lower()}"
+        suffix = fig_path.suffix.lower()
+        convert = suffix not in FIGURE_SUFFIXES[figure_format]
+        if convert:
+            suffix = FIGURE_SUFFIXES[figure_format][0]
+        new_name = f"fig_page{page_number:03d}_{new_idx:02d}{suffix}"
         new_path = figure_dir / new_name
         if new_path.exists():
             try:
                 new_path.unlink()
             except FileNotFoundError:
                 pass
-        fig_path.rename(new_path)
+        if convert:
+            _convert_figure(fig_path, new_path, figure_format)
+        else:
+            fig_path.rename(new_path)
         mapping[old_name] = new_name
 
     _update_markdown_figure_links(output_dir, page_number, mapping)
     remove_icon_figures(output_dir, page_number, icon_config, page_metrics)
 
 
+def _convert_figure(src: Path, dest: Path, figure_format: str) -> None:
+    """図版を figure_format で保存し直し、元のファイルを消す。"""
+    with Image.open(src) as img:
+        if figure_format == "jpeg":
+            # JPEG は透過を持てないので白背景に合成する
+            rgba = img.convert("RGBA")
+            flat = Image.new("RGB", rgba.size, "white")
+            flat.paste(rgba, mask=rgba.getchannel("A"))
+            flat.save(dest, format="JPEG", quality=JPEG_FIGURE_QUALITY)
+        else:
+            img.save(dest, format="PNG", optimize=True)
+    src.unlink()
+
+
 def _update_markdown_figure_links(output_dir: Path, page_number: int, mapping: Dict[str, str]) -> None:
     if not mapping:
         return
@@ -482,7 +515,9 @@ def _load_page_metrics(image_path: Path) -> dict[str, Any] | None:
     return {"width": width, "height": height, "area": width * height}
 
 
-def _maybe_fallback_tesseract(image_path: Path, output_dir: Path, page_number: int) -> None:
+def _maybe_fallback_tesseract(
+    image_path: Path, output_dir: Path, page_number: int, lang: str
+) -> None:
     """If OCR output is too sparse, try pytesseract as a fallback."""
 
     md_path = output_dir / f"page_{page_number:03d}.md"
@@ -513,7 +548,7 @@ def _maybe_fallback_tesseract(image_path: Path, output_dir: Path, page_number: i
             gray = ImageEnhance.Contrast(gray).enhance(1.6)
             text = pytesseract.image_to_string(
                 gray,
-                lang="jpn+eng",
+                lang=lang,
                 config="--psm 6",
             )
             log_lines.append("fallback=tesseract applied (contrast x1.6)")
@@ -529,7 +564,7 @@ def _maybe_fallback_tesseract(image_path: Path, output_dir: Path, page_number: i
         (output_dir / "fallback.log").write_text("\n".join(log_lines), encoding="utf-8")
 
 
-def _force_tesseract_merge(image_path: Path, output_dir: Path, page_number: int) -> None:
+def _force_tesseract_merge(image_path: Path, output_dir: Path, page_number: int, lang: str) -> None:
     """Always run pytesseract and append its text to the page markdown."""
 
     md_path = output_dir / f"page_{page_number:03d}.md"
@@ -551,7 +586,7 @@ def _force_tesseract_merge(image_path: Path, output_dir: Path, page_number: int)
             gray = ImageEnhance.Contrast(gray).enhance(1.6)
             text = pytesseract.image_to_string(
                 gray,
-                lang="jpn+eng",
+                lang=lang,
                 config="--psm 6",
             )
     except Exception as exc:  # pragma: no cover
@@ -624,11 +659,11 @@ def run_ocr(
         )
     normalize_markdown_files(output_dir, target_page=page_number)
     page_metrics = _load_page_metrics(image_path)
-    rename_figure_assets(output_dir, page_number, icon_config, page_metrics)
+    rename_figure_assets(output_dir, page_number, icon_config, page_metrics, figure_format=options.figure_format)
     if options.fallback_tesseract:
-        _maybe_fallback_tesseract(image_path, output_dir, page_number)
+        _maybe_fallback_tesseract(image_path, output_dir, page_number, tesseract_lang(options.languages))
     if options.force_tesseract_merge:
-        _force_tesseract_merge(image_path, output_dir, page_number)
+        _force_tesseract_merge(image_path, output_dir, page_number, tesseract_lang(options.languages))
 
 
 def export_json(
@@ -721,6 +756,7 @@ def export_csv(
 
 __all__ = [
     "OcrOptions",
+    "FIGURE_FORMATS",
     "IconFilterConfig",
     "build_command",
     "normalize_markdown_files",
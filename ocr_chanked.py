@@ -4,14 +4,23 @@ import os
 import sys
 import time
 import platform
+import shutil
 import subprocess
+import tempfile
 from pathlib import Path
 from typing import Any
 
 from pdf2image import convert_from_path, pdfinfo_from_path
 
+from crop_rect import apply_crop, parse_crop as parse_crop_rect
+from ingest import PDF_PASSWORD_ENV, is_pdf_password_error
 from math_refiner import MathRefiner
+from page_spec import PageSpecError, expand_pages, page_spec_label, parse_page_spec
+from page_weights import WEIGHT_DPI, page_weight, progress_line
+from ocr_languages import LanguageSpecError, parse_languages
+from result_metadata import AUTO_ORIENT_FILE, OUTPUT_DIR_MARKER, write_auto_rotations
 from ocr import (
+    FIGURE_FORMATS,
     IconFilterConfig,
     OcrOptions,
     build_command,
@@ -26,6 +35,7 @@ from ocr import (
 
 例:
     poetry run python ocr_chanked.py input.pdf --start 11 --end 20
+    poetry run python ocr_chanked.py input.pdf --pages 1-3,7,10-12
 """
 
 
@@ -34,6 +44,11 @@ def parse_args() -> argparse.Namespace:
     parser.add_argument("pdf_path", help="入力 PDF ファイル")
     parser.add_argument("--start", type=int, default=1, help="開始ページ (1 起点)")
     parser.add_argument("--end", type=int, default=None, help="終了ページ (指定なしは最終ページ)")
+    parser.add_argument(
+        "--pages",
+        default=None,
+        help='複数範囲のページ指定 (例: "1-3,7,10-12")。指定時は --start/--end より優先',
+    )
     parser.add_argument(
         "--dpi",
         type=int,
@@ -72,6 +87,16 @@ def parse_args() -> argparse.Namespace:
         "--label",
         help="追加ラベル。出力ディレクトリ名 (<output-root>/<PDF名>_<label>) に付与されます",
     )
+    parser.add_argument(
+        "--output-name",
+        help="出力ディレクトリ名 (<output-root>/<output-name>)。指定時は --label やページ範囲の suffix を付けません",
+    )
+    parser.add_argument(
+        "--figure-format",
+        choices=FIGURE_FORMATS,
+        default="png",
+        help="figures/ に保存する図版の形式 (default: png)",
+    )
     parser.add_argument(
         "--output-root",
         type=Path,
@@ -108,6 +133,17 @@ def parse_args() -> argparse.Namespace:
         default=True,
         help="ページ画像 (page_images/*.png) を保存しない",
     )
+    parser.add_argument(
+        "--password",
+        default=None,
+        help=f"パスワード保護された PDF のパスワード（未指定なら環境変数 {PDF_PASSWORD_ENV}）",
+    )
+    parser.add_argument(
+        "--temp-dir",
+        type=Path,
+        default=None,
+        help="ページ画像などの中間ファイルを書く作業ディレクトリ（未指定なら出力ディレクトリ内）",
+    )
     parser.add_argument(
         "--icon-profile",
         choices=["default", "strict", "lenient"],
@@ -161,15 +197,57 @@ def parse_args() -> argparse.Namespace:
         default=False,
         help="YomiToku 結果に関わらず pytesseract の結果を追記する",
     )
+    parser.add_argument(
+        "--lang",
+        default=None,
+        help='pytesseract で使う言語 (例: "ja,en,ko")。未指定は日英',
+    )
+    parser.add_argument(
+        "--merge-sep",
+        choices=["hr", "heading", "none", "page"],
+        default=None,
+        help="結合 Markdown のページ区切り（merged_md.py に渡す）。未指定は # Page N 見出し",
+    )
     parser.add_argument(
         "--crop",
         help="正規化トリミング範囲（left,top,width,height / 0〜1）。全ページに適用されます。",
     )
-    return parser.parse_args()
+    parser.add_argument(
+        "--rotate",
+        type=int,
+        choices=[0, 90, 180, 270],
+        default=0,
+        help="各ページを時計回りに回転する角度（--crop より先に適用）",
+    )
+    parser.add_argument(
+        "--deskew",
+        action=argparse.BooleanOptionalAction,
+        default=False,
+        help="各ページの小さな傾きを自動補正する",
+    )
+    parser.add_argument(
+        "--auto-orient",
+        action=argparse.BooleanOptionalAction,
+        default=False,
+        help="横倒しのページを見つけてページごとに 90/270 度回す（横書き前提。回したページは auto_orient.json に記録）",
+    )
+    args = parser.parse_args()
+    args.password = args.password or os.environ.get(PDF_PASSWORD_ENV) or None
+    if args.lang:
+        try:
+            args.lang = parse_languages(args.lang)
+        except LanguageSpecError as exc:
+            parser.error(f"--lang: {exc}")
+    return args
 
 
 args = parse_args()
 
+if args.temp_dir:
+    args.temp_dir.mkdir(parents=True, exist_ok=True)
+    # pdf2image など tempfile を使うライブラリの一時ファイルも同じ場所に寄せる
+    tempfile.tempdir = str(args.temp_dir)
+
 PDF_PATH = Path(args.pdf_path)
 
 if not PDF_PATH.exists():
@@ -211,40 +289,13 @@ def resolve_poppler_path(base_dir: Path) -> Path:
 POPPLER_PATH = resolve_poppler_path(BASE_DIR)
 os.environ["PATH"] = str(POPPLER_PATH) + os.pathsep + os.environ.get("PATH", "")
 
-CropRect = tuple[float, float, float, float]
-
 
-def parse_crop(value: str | None) -> CropRect | None:
-    if not value:
-        return None
-    parts = [p.strip() for p in value.split(",")]
-    if len(parts) != 4:
-        raise SystemExit("エラー: --crop は left,top,width,height の4要素が必要です")
+def parse_crop(value: str | None):
+    # 解釈は crop_rect.py に揃える（UI のプレビューも同じ関数で切り抜く）
     try:
-        left, top, width, height = (float(p) for p in parts)
-    except ValueError:
-        raise SystemExit("エラー: --crop の値は数値で指定してください")
-    left = max(0.0, min(1.0, left))
-    top = max(0.0, min(1.0, top))
-    width = max(0.0, min(1.0 - left, width))
-    height = max(0.0, min(1.0 - top, height))
-    if width <= 0 or height <= 0:
-        return None
-    return (left, top, width, height)
-
-
-def apply_crop(img, crop: CropRect | None):
-    if not crop:
-        return img
-    left, top, width, height = crop
-    w, h = img.size
-    lpx = int(round(left * w))
-    tpx = int(round(top * h))
-    rpx = int(round((left + width) * w))
-    bpx = int(round((top + height) * h))
-    if rpx <= lpx or bpx <= tpx:
-        return img
-    return img.crop((lpx, tpx, rpx, bpx))
+        return parse_crop_rect(value)
+    except ValueError as exc:
+        raise SystemExit(f"エラー: --{exc}")
 
 
 CROP = parse_crop(args.crop)
@@ -282,8 +333,10 @@ OPTIONS = OcrOptions(
     mode=args.mode,
     device=args.device,
     enable_figure=True,
+    figure_format=args.figure_format,
     fallback_tesseract=args.fallback_tesseract,
     force_tesseract_merge=args.force_tesseract_merge,
+    languages=args.lang,
 )
 
 
@@ -362,6 +415,37 @@ if args.math_refiner:
         MATH_REFINER = None
 
 
+def emit_page_weights(pages: list[int], *, ordinal: bool) -> None:
+    """対象ページの重みを `@PROGRESS` 行で先にまとめて出す（見積もりに失敗しても OCR は続ける）。"""
+
+    weights: list[float] = []
+    run_start = 0
+    try:
+        # 連続するページはまとめて 1 回で描画する
+        for i in range(1, len(pages) + 1):
+            if i < len(pages) and pages[i] == pages[i - 1] + 1:
+                continue
+            images = convert_from_path(
+                str(PDF_PATH),
+                dpi=WEIGHT_DPI,
+                first_page=pages[run_start],
+                last_page=pages[i - 1],
+                grayscale=True,
+                poppler_path=str(POPPLER_PATH),
+                userpw=args.password,
+            )
+            weights.extend(page_weight(apply_crop(img, CROP)) for img in images)
+            run_start = i
+    except Exception as exc:
+        print(f"ページの重みの見積もりに失敗しました（進捗はページ数で表示します）: {exc}")
+        return
+    if len(weights) != len(pages):
+        return
+    for position, (page, weight) in enumerate(zip(pages, weights), start=1):
+        print(progress_line(position if ordinal else page, weight))
+    sys.stdout.flush()
+
+
 def run_merger(base_name: str):
     merger = BASE_DIR / "poppler" / "merged_md.py"
     if not merger.exists():
@@ -376,57 +460,94 @@ def run_merger(base_name: str):
         "--base-name",
         base_name,
     ]
+    if args.merge_sep:
+        cmd.extend(["--merge-sep", args.merge_sep])
     print("\n--- merged_md.py を実行 ---")
     subprocess.run(cmd, check=True)
 
 DPI = max(72, int(args.dpi))
 
-info = pdfinfo_from_path(str(PDF_PATH), poppler_path=str(POPPLER_PATH))
+try:
+    info = pdfinfo_from_path(str(PDF_PATH), poppler_path=str(POPPLER_PATH), userpw=args.password)
+except Exception as exc:
+    if is_pdf_password_error(exc):
+        print("エラー: PDF にパスワードが設定されています。正しいパスワードを指定してください")
+        sys.exit(1)
+    raise
 num_pages = int(info["Pages"])
 
-start_page_limit = max(1, args.start)
-end_page_limit = args.end if args.end is not None else num_pages
-end_page_limit = min(end_page_limit, num_pages)
-
-if start_page_limit > end_page_limit:
-    raise SystemExit(
-        f"開始ページ ({start_page_limit}) が終了ページ ({end_page_limit}) より後です。"
-    )
-
 label_suffix = args.label
-if not label_suffix and (start_page_limit != 1 or end_page_limit != num_pages):
-    label_suffix = f"p{start_page_limit}-{end_page_limit}"
+if args.pages:
+    try:
+        page_ranges = parse_page_spec(args.pages)
+    except PageSpecError as exc:
+        raise SystemExit(f"エラー: --pages {exc}")
+    target_pages = expand_pages(page_ranges, max_page=num_pages)
+    if not target_pages:
+        raise SystemExit(f"--pages {args.pages} に該当するページがありません (総ページ数 {num_pages})")
+    # 進捗マーカーは「何ページ目/対象ページ数」で出す（飛び飛びでも UI の進捗計算が崩れないように）
+    start_page_limit, end_page_limit = 1, len(target_pages)
+    use_ordinal_markers = True
+    if not label_suffix:
+        label_suffix = page_spec_label(page_ranges)
+else:
+    start_page_limit = max(1, args.start)
+    end_page_limit = args.end if args.end is not None else num_pages
+    end_page_limit = min(end_page_limit, num_pages)
+
+    if start_page_limit > end_page_limit:
+        raise SystemExit(
+            f"開始ページ ({start_page_limit}) が終了ページ ({end_page_limit}) より後です。"
+        )
+    target_pages = list(range(start_page_limit, end_page_limit + 1))
+    use_ordinal_markers = False
+    if not label_suffix and (start_page_limit != 1 or end_page_limit != num_pages):
+        label_suffix = f"p{start_page_limit}-{end_page_limit}"
 
 RESULT_ROOT = args.output_root
-output_dir_name = PDF_PATH.stem if not label_suffix else f"{PDF_PATH.stem}_{label_suffix}"
+if args.output_name:
+    output_dir_name = args.output_name
+else:
+    output_dir_name = PDF_PATH.stem if not label_suffix else f"{PDF_PATH.stem}_{label_suffix}"
 OUT_DIR = RESULT_ROOT / output_dir_name
 OUT_DIR.mkdir(parents=True, exist_ok=True)
 (OUT_DIR / "figures").mkdir(exist_ok=True)
-PAGE_IMAGE_DIR = OUT_DIR / "page_images"
-PAGE_IMAGE_DIR.mkdir(exist_ok=True)
+# 保存するページ画像の置き場所。--temp-dir 指定時は作業ディレクトリで描画・OCR してから移す
+KEPT_PAGE_IMAGE_DIR = OUT_DIR / "page_images"
+PAGE_IMAGE_DIR = args.temp_dir / output_dir_name / "page_images" if args.temp_dir else KEPT_PAGE_IMAGE_DIR
+PAGE_IMAGE_DIR.mkdir(parents=True, exist_ok=True)
+# 自動回転したページ（ページ番号 → 角度）。前回の実行の記録は残さない
+AUTO_ROTATIONS: dict[int, int] = {}
+if args.auto_orient:
+    write_auto_rotations(OUT_DIR, AUTO_ROTATIONS)
+else:
+    (OUT_DIR / AUTO_ORIENT_FILE).unlink(missing_ok=True)
 
 print(f"PDF: {PDF_PATH}")
 print(f"出力ディレクトリ: {OUT_DIR}")
+print(f"{OUTPUT_DIR_MARKER} {OUT_DIR.resolve()}", flush=True)
 print(f"総ページ数: {num_pages}")
 print(f"処理範囲: {start_page_limit}〜{end_page_limit}")
+if use_ordinal_markers:
+    print(f"対象ページ: {args.pages} ({len(target_pages)} ページ)")
 print(f"チャンクサイズ: {CHUNK_SIZE}")
 if REST_SECONDS > 0:
     print(f"チャンク休憩: {REST_SECONDS} 秒 (有効)")
 else:
     print("チャンク休憩: 無効 ( --enable-rest を指定で有効化 )")
 print(f"poppler path: {POPPLER_PATH}")
+emit_page_weights(target_pages, ordinal=use_ordinal_markers)
 
-current = start_page_limit
 chunk_index = 1
 
-while current <= end_page_limit:
-    chunk_start = current
-    chunk_end = min(current + CHUNK_SIZE - 1, end_page_limit)
+for chunk_offset in range(0, len(target_pages), CHUNK_SIZE):
+    chunk_pages = target_pages[chunk_offset : chunk_offset + CHUNK_SIZE]
 
-    print(f"\n=== Chunk {chunk_index}: {chunk_start}〜{chunk_end} ===")
+    print(f"\n=== Chunk {chunk_index}: {chunk_pages[0]}〜{chunk_pages[-1]} ===")
 
-    for page in range(chunk_start, chunk_end + 1):
-        print(f"\n--- Page {page}/{end_page_limit} (abs {page}/{num_pages}) ---")
+    for position, page in enumerate(chunk_pages, start=chunk_offset + 1):
+        marker = position if use_ordinal_markers else page
+        print(f"\n--- Page {marker}/{end_page_limit} (abs {page}/{num_pages}) ---")
 
         images = convert_from_path(
             str(PDF_PATH),
@@ -435,8 +556,20 @@ while current <= end_page_limit:
             last_page=page,
             fmt="png",
             poppler_path=str(POPPLER_PATH),
+            userpw=args.password,
         )
-        img = apply_crop(images[0], CROP)
+        img = images[0]
+        if args.rotate or args.deskew or args.auto_orient:
+            from image_preprocessor import orient_image
+
+            img, auto_rotation = orient_image(
+                img, rotate=args.rotate, deskew=args.deskew, auto_orient=args.auto_orient
+            )
+            if auto_rotation:
+                print(f"自動回転: page {page} を時計回りに {auto_rotation} 度回転しました")
+                AUTO_ROTATIONS[page] = auto_rotation
+                write_auto_rotations(OUT_DIR, AUTO_ROTATIONS)
+        img = apply_crop(img, CROP)
 
         img_path = PAGE_IMAGE_DIR / f"page_{page:03}.png"
         img.save(img_path)
@@ -486,8 +619,11 @@ while current <= end_page_limit:
                 img_path.unlink()
             except FileNotFoundError:
                 pass
+        elif PAGE_IMAGE_DIR != KEPT_PAGE_IMAGE_DIR:
+            KEPT_PAGE_IMAGE_DIR.mkdir(exist_ok=True)
+            shutil.move(str(img_path), KEPT_PAGE_IMAGE_DIR / img_path.name)
 
-        print(f"--- Done {page}/{end_page_limit} ---")
+        print(f"--- Done {marker}/{end_page_limit} ---")
         time.sleep(1.0)  # ページごとの軽い休憩
 
     if REST_SECONDS > 0:
@@ -496,7 +632,6 @@ while current <= end_page_limit:
     else:
         print(f"\n=== Chunk {chunk_index} 完了 → 休憩なし ===")
 
-    current += CHUNK_SIZE
     chunk_index += 1
 
 run_merger(output_dir_name)
@@ -0,0 +1,98 @@
+"""OCR 言語指定（例: "ja,en,ko"）の解析。dispatcher.py / ocr_chanked.py / ocr.py で共有する。
+
+YomiToku は日本語・英語を前提としたモデルで言語指定を持たないため、
+指定言語は pytesseract（フォールバック / 追記）の `lang`（例: "jpn+eng+kor"）に反映する。
+"""
+
+from __future__ import annotations
+
+from typing import Sequence
+
+# YomiToku 単体で読める言語。これ以外を指定した場合は pytesseract の追記が必要になる
+YOMITOKU_LANGUAGES = ("ja", "en")
+
+# UI / CLI で使う言語コード → Tesseract の言語データ名
+TESSERACT_LANG_CODES: dict[str, str] = {
+    "ja": "jpn",
+    "en": "eng",
+    "zh-Hans": "chi_sim",
+    "zh-Hant": "chi_tra",
+    "ko": "kor",
+    "de": "deu",
+    "fr": "fra",
+    "es": "spa",
+    "it": "ita",
+    "pt": "por",
+    "ru": "rus",
+}
+
+# 言語未指定時に pytesseract へ渡す既定値（従来の挙動）
+DEFAULT_TESSERACT_LANG = "jpn+eng"
+
+
+class LanguageSpecError(ValueError):
+    pass
+
+
+def parse_languages(spec: str) -> list[str]:
+    """"ja,en" を重複なし・指定順の言語コードのリストにする。未知のコードはエラー。"""
+
+    languages: list[str] = []
+    for raw in spec.split(","):
+        code = raw.strip()
+        if not code:
+            raise LanguageSpecError(f"言語指定に空の要素があります: {spec!r}")
+        if code not in TESSERACT_LANG_CODES:
+            known = ", ".join(TESSERACT_LANG_CODES)
+            raise LanguageSpecError(f"未対応の言語コードです: {code!r}（対応: {known}）")
+        if code not in languages:
+            languages.append(code)
+    return languages
+
+
+def tesseract_lang(languages: Sequence[str] | None) -> str:
+    """pytesseract の `lang` 引数。未指定なら従来どおり日英。"""
+
+    if not languages:
+        return DEFAULT_TESSERACT_LANG
+    return "+".join(TESSERACT_LANG_CODES[code] for code in languages)
+
+
+def needs_tesseract(languages: Sequence[str] | None) -> bool:
+    return any(code not in YOMITOKU_LANGUAGES for code in languages or ())
+
+
+def installed_tesseract_languages() -> set[str] | None:
+    """インストール済みの Tesseract 言語データ。pytesseract / tesseract が無ければ None。"""
+
+    try:
+        import pytesseract  # type: ignore
+
+        return set(pytesseract.get_languages(config=""))
+    except Exception:
+        return None
+
+
+def supported_languages(installed: set[str] | None = None) -> list[str]:
+    """この環境で指定できる言語コード。YomiToku の言語は常に、それ以外は言語データがある場合のみ。"""
+
+    if installed is None:
+        installed = installed_tesseract_languages() or set()
+    return [
+        code
+        for code, tess in TESSERACT_LANG_CODES.items()
+        if code in YOMITOKU_LANGUAGES or tess in installed
+    ]
+
+
+__all__ = [
+    "YOMITOKU_LANGUAGES",
+    "TESSERACT_LANG_CODES",
+    "DEFAULT_TESSERACT_LANG",
+    "LanguageSpecError",
+    "parse_languages",
+    "tesseract_lang",
+    "needs_tesseract",
+    "installed_tesseract_languages",
+    "supported_languages",
+]
@@ -0,0 +1,74 @@
+"""ページ指定文字列（例: "1-3,7,10-12"）の解析。dispatcher.py / ocr_chanked.py で共有する。"""
+
+from __future__ import annotations
+
+PageRanges = list[tuple[int, int]]
+
+
+class PageSpecError(ValueError):
+    pass
+
+
+def parse_page_spec(spec: str) -> PageRanges:
+    """"1-3,7,10-12" を昇順・重複なしの (開始, 終了) のリストにする。
+
+    重なり・隣接する範囲はまとめる（"1-5,3-7" → [(1, 7)]）。
+    0 以下のページや "5-3" のような逆順の範囲はエラー。
+    """
+
+    ranges: PageRanges = []
+    for raw in spec.split(","):
+        part = raw.strip()
+        if not part:
+            raise PageSpecError(f"ページ指定に空の要素があります: {spec!r}")
+        head, sep, tail = part.partition("-")
+        try:
+            start = int(head.strip())
+            end = int(tail.strip()) if sep else start
+        except ValueError as exc:
+            raise PageSpecError(f"ページ番号は整数で指定してください: {part!r}") from exc
+        if start < 1 or end < 1:
+            raise PageSpecError(f"ページ番号は 1 以上で指定してください: {part!r}")
+        if start > end:
+            raise PageSpecError(f"範囲は昇順で指定してください: {part!r}")
+        ranges.append((start, end))
+
+    ranges.sort()
+    merged: PageRanges = []
+    for start, end in ranges:
+        if merged and start <= merged[-1][1] + 1:
+            merged[-1] = (merged[-1][0], max(merged[-1][1], end))
+        else:
+            merged.append((start, end))
+    return merged
+
+
+def expand_pages(ranges: PageRanges, *, max_page: int | None = None) -> list[int]:
+    """範囲を個々のページ番号に展開する（max_page を超えるページは捨てる）。"""
+
+    pages: list[int] = []
+    for start, end in ranges:
+        if max_page is not None:
+            end = min(end, max_page)
+        pages.extend(range(start, end + 1))
+    return pages
+
+
+def format_page_spec(ranges: PageRanges) -> str:
+    return ",".join(str(s) if s == e else f"{s}-{e}" for s, e in ranges)
+
+
+def page_spec_label(ranges: PageRanges) -> str:
+    """出力ディレクトリ名の suffix（例: [(1, 3), (7, 7)] → "p1-3_7"）。"""
+
+    return "p" + "_".join(str(s) if s == e else f"{s}-{e}" for s, e in ranges)
+
+
+__all__ = [
+    "PageRanges",
+    "PageSpecError",
+    "parse_page_spec",
+    "expand_pages",
+    "format_page_spec",
+    "page_spec_label",
+]
@@ -0,0 +1,37 @@
+"""ページごとの処理コストの目安（重み）を見積もる。
+
+OCR の所要時間はページの中身（密な表か白紙か）で大きく変わるため、低解像度で描画したときの
+インク量を重みとして `@PROGRESS {json}` 行で出す。UI は完了したページの重みの合計で進捗を計算する。
+"""
+
+from __future__ import annotations
+
+import json
+
+PROGRESS_MARKER = "@PROGRESS"
+
+# 重みの見積もり用に描画する解像度（A4 で 150x210px 程度。全ページ分でも数秒で済む）
+WEIGHT_DPI = 18
+# これより暗い画素（0-255 のグレースケール）をインクとみなす
+INK_THRESHOLD = 200
+# 白紙でもモデル呼び出しなどの固定コストがかかる分
+BASE_PAGE_WEIGHT = 1.0
+INK_WEIGHT_SCALE = 20.0
+
+
+def ink_ratio(img) -> float:
+    hist = img.convert("L").histogram()
+    total = sum(hist)
+    if not total:
+        return 0.0
+    return sum(hist[:INK_THRESHOLD]) / total
+
+
+def page_weight(img) -> float:
+    return round(BASE_PAGE_WEIGHT + INK_WEIGHT_SCALE * ink_ratio(img), 3)
+
+
+def progress_line(page: int, weight: float) -> str:
+    """`page` は `--- Done N/M ---` の N と同じ番号（--pages 指定時は対象ページ内の順番）。"""
+
+    return f"{PROGRESS_MARKER} {json.dumps({'page': page, 'weight': weight})}"
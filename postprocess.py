@@ -16,6 +16,10 @@ DEFAULT_OUTPUT = Path("merged.md")
 PATTERN = re.compile(r"(?:.*_)?page_?(\d+)(?:_p(\d+))?\.md$")
 FRACTION_KEYWORDS = ("比率", "割合", "分数", "比率", "率", "比")
 FRACTION_SYMBOLS = ("/", "÷", "×", "%", "％")
+# ページ間の区切り（--merge-sep）。未指定時は従来どおり各ページ先頭に `# Page N`
+MERGE_SEPARATORS = ("hr", "heading", "none", "page")
+# "page" でページ間に入れる改ページの行（pandoc の \newpage。export_docx.py は Word の改ページにする）
+PAGE_BREAK = "\\newpage"
 
 
 @dataclass(order=True)
@@ -50,7 +54,14 @@ def write_merged_md(
     files: Iterable[PageFile],
     output_path: Path,
     add_page_heading: bool = True,
+    separator: str | None = None,
 ) -> List[MathIssue]:
+    """ページ順に結合する。`separator` は "hr"（水平線）/ "heading"（`## Page N`）/ "none" /
+    "page"（改ページ `\\newpage`）。
+
+    `separator` 未指定なら従来どおり `add_page_heading` で `# Page N` を入れるかを決める。
+    """
+
     output_path.parent.mkdir(parents=True, exist_ok=True)
     issues: List[MathIssue] = []
     with output_path.open("w", encoding="utf-8") as out:
@@ -66,11 +77,19 @@ def write_merged_md(
             page_chunks.clear()
             if not page_text.strip():
                 return
-            if add_page_heading:
+            if separator == "heading":
+                out.write(f"## Page {page}\n\n")
+            elif separator == "hr":
+                if not first_section:
+                    out.write("---\n\n")
+            elif separator == "page":
+                if not first_section:
+                    out.write(PAGE_BREAK + "\n\n")
+            elif separator is None and add_page_heading:
                 if not first_section:
                     out.write("\n")
                 out.write(f"# Page {page}\n\n")
-                first_section = False
+            first_section = False
             out.write(page_text + "\n\n")
             detected = detect_math_issues(page_text, page)
             if detected:
@@ -150,6 +169,12 @@ def parse_args(argv: list[str] | None = None) -> argparse.Namespace:
     parser.add_argument("--output", default=None, help="出力ファイルパス (省略時は <base-name>_merged.md)")
     parser.add_argument("--base-name", default="merged", help="出力ベース名。--output 指定時は無視")
     parser.add_argument("--no-heading", action="store_true", help="ページ見出しを挿入しない")
+    parser.add_argument(
+        "--merge-sep",
+        choices=MERGE_SEPARATORS,
+        default=None,
+        help="ページ間の区切り (hr=水平線, heading=## Page N, none=なし, page=改ページ)。未指定は従来の # Page N",
+    )
     return parser.parse_args(argv)
 
 
@@ -170,6 +195,7 @@ def main(argv: list[str] | None = None) -> None:
         files,
         output_path,
         add_page_heading=not args.no_heading,
+        separator=args.merge_sep,
     )
     write_math_review_log(input_dir / "math_review.csv", issues)
     clean_file(output_path, inplace=True)
@@ -0,0 +1,102 @@
+"""OCR 結果ディレクトリから UI 向けのメタ情報（ページ数・言語・表/図の数）を集める。
+
+dispatcher.py が処理の最後に `@RESULT {json}` として出力し、UI 側が JobInfo.metadata に保存する。
+"""
+
+from __future__ import annotations
+
+import json
+import re
+from pathlib import Path
+
+RESULT_MARKER = "@RESULT"
+# 出力ディレクトリを作った直後に出す行。UI はキャンセル時に書きかけの出力を消すのに使う
+OUTPUT_DIR_MARKER = "@OUTPUT_DIR"
+# --auto-orient で自動回転したページの記録（ocr_chanked.py / dispatcher.py が結果ディレクトリに書く）
+AUTO_ORIENT_FILE = "auto_orient.json"
+
+PAGE_MD_PATTERN = re.compile(r"(?:.*_)?page_?(\d+)(?:_p\d+)?\.md$")
+PAGE_HEADING_PATTERN = re.compile(r"^# Page (\d+)\s*$", re.MULTILINE)
+# md の表の区切り行（| --- | :---: |）
+TABLE_SEPARATOR_PATTERN = re.compile(r"^\s*\|?\s*:?-{3,}:?\s*(?:\|\s*:?-{3,}:?\s*)+\|?\s*$", re.MULTILINE)
+HTML_TABLE_PATTERN = re.compile(r"<table\b", re.IGNORECASE)
+IMAGE_PATTERN = re.compile(r"!\[[^\]]*\]\([^)]*\)|<img\b", re.IGNORECASE)
+# 言語判定の前に落とす記法（タグ・リンク先・"# Page N" 見出し）。英字として数えないため
+MARKUP_PATTERN = re.compile(r"<[^>]+>|\]\([^)]*\)|^# Page \d+\s*$", re.MULTILINE)
+
+# 文字種で判定する言語（出現文字数の多い順に返す）
+LANGUAGE_PATTERNS = {
+    "ja": re.compile(r"[\u3040-\u30ff\u4e00-\u9fff]"),
+    "en": re.compile(r"[A-Za-z]"),
+}
+# これ未満の文字数しか無い言語は、ページ番号や記号まじりのノイズとみなして数えない
+MIN_LANGUAGE_CHARS = 20
+
+
+def detect_languages(text: str) -> list[str]:
+    counts = {lang: len(pattern.findall(text)) for lang, pattern in LANGUAGE_PATTERNS.items()}
+    found = [lang for lang, count in counts.items() if count >= MIN_LANGUAGE_CHARS]
+    return sorted(found, key=lambda lang: counts[lang], reverse=True)
+
+
+def _markdown_sources(output_dir: Path) -> tuple[list[Path], list[Path]]:
+    """(本文として読む md, ページ単位の md) を返す。merged があればそちらを本文にする。"""
+
+    page_mds = sorted(p for p in output_dir.glob("*.md") if PAGE_MD_PATTERN.match(p.name))
+    merged = output_dir / f"{output_dir.name}_merged.md"
+    if merged.exists():
+        return [merged], page_mds
+    if page_mds:
+        return page_mds, page_mds
+    return sorted(output_dir.glob("*.md")), page_mds
+
+
+def write_auto_rotations(output_dir: Path, rotations: dict[int, int]) -> None:
+    """自動回転したページ（ページ番号 → 時計回りの角度）を記録する。回転しなかったページは含めない。"""
+
+    entries = [{"page": page, "degrees": degrees} for page, degrees in sorted(rotations.items()) if degrees]
+    (output_dir / AUTO_ORIENT_FILE).write_text(json.dumps({"rotated_pages": entries}), encoding="utf-8")
+
+
+def read_auto_rotations(output_dir: Path) -> list[dict] | None:
+    """write_auto_rotations の記録。--auto-orient を使わなかった（記録が無い・読めない）ときは None。"""
+
+    try:
+        data = json.loads((output_dir / AUTO_ORIENT_FILE).read_text(encoding="utf-8"))
+    except (OSError, ValueError):
+        return None
+    return [
+        {"dir_name": output_dir.name, "page": int(e["page"]), "degrees": int(e["degrees"])}
+        for e in data.get("rotated_pages", [])
+    ]
+
+
+def collect_result_metadata(output_dir: Path) -> dict:
+    sources, page_mds = _markdown_sources(output_dir)
+    text = "\n\n".join(p.read_text(encoding="utf-8", errors="replace") for p in sources)
+
+    pages = {int(m.group(1)) for p in page_mds if (m := PAGE_MD_PATTERN.match(p.name))}
+    if not pages:
+        pages = {int(n) for n in PAGE_HEADING_PATTERN.findall(text)}
+    page_count = len(pages) or (1 if sources else 0)
+
+    meta = {
+        "pages": page_count,
+        "languages": detect_languages(MARKUP_PATTERN.sub(" ", text)),
+        "tables": len(TABLE_SEPARATOR_PATTERN.findall(text)) + len(HTML_TABLE_PATTERN.findall(text)),
+        "figures": len(IMAGE_PATTERN.findall(text)),
+    }
+    rotated = read_auto_rotations(output_dir)
+    if rotated is not None:
+        meta["rotated_pages"] = rotated
+    return meta
+
+
+__all__ = [
+    "AUTO_ORIENT_FILE",
+    "RESULT_MARKER",
+    "collect_result_metadata",
+    "detect_languages",
+    "read_auto_rotations",
+    "write_auto_rotations",
+]
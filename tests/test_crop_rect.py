@@ -0,0 +1,23 @@
+import pytest
+
+from crop_rect import crop_box, parse_crop
+
+
+def test_parse_crop_clamps_to_the_image():
+    assert parse_crop("0.5,-0.2,0.8,0.5") == (0.5, 0.0, 0.5, 0.5)
+    assert parse_crop("0.2,0.2,0,0.5") is None
+    assert parse_crop(None) is None
+
+
+@pytest.mark.parametrize("value", ["0.1,0.2,0.3", "a,b,c,d"])
+def test_parse_crop_rejects_malformed(value):
+    with pytest.raises(ValueError):
+        parse_crop(value)
+
+
+def test_crop_box_rounds_each_edge_against_the_rendered_size():
+    crop = parse_crop("0.100000,0.333333,0.500000,0.250000")
+    assert crop_box((2480, 3508), crop) == (248, 1169, 1488, 2046)
+    # 同じ割合でも描画サイズ（DPI）が違えば px 境界は変わる
+    assert crop_box((1240, 1754), crop) == (124, 585, 744, 1023)
+    assert crop_box((10, 10), parse_crop("0.5,0.5,0.01,0.01")) is None
@@ -1,5 +1,8 @@
+import re
 import sys
 
+import pytest
+
 
 def test_dispatcher_splits_passthrough_args(monkeypatch):
     import dispatcher
@@ -63,3 +66,259 @@ def test_dispatcher_infers_pdf_output_dir_with_start_end(tmp_path):
         extra_args=["--start", "9", "--end", "9"],
     )
     assert inferred == expected
+
+
+def test_dispatcher_accepts_dpi_and_page_range(monkeypatch):
+    import dispatcher
+
+    monkeypatch.setattr(
+        sys,
+        "argv",
+        [
+            "dispatcher.py",
+            "sample.pdf",
+            "--dpi",
+            "200",
+            "--start",
+            "2",
+            "--end",
+            "5",
+            "--",
+            "--chunk-size",
+            "4",
+        ],
+    )
+    args = dispatcher.parse_args()
+    assert args.dpi == 200
+    assert (args.start, args.end) == (2, 5)
+    assert args.extra == ["--chunk-size", "4"]
+
+
+def test_dispatcher_merges_pdf_args_into_passthrough():
+    import dispatcher
+
+    merged = dispatcher._merge_pdf_args(["--chunk-size", "4"], dpi=200, start=2, end=5)
+    assert merged == ["--dpi", "200", "--start", "2", "--end", "5", "--chunk-size", "4"]
+
+
+def test_dispatcher_forwards_temp_dir_to_pdf_args():
+    import dispatcher
+
+    merged = dispatcher._merge_pdf_args([], dpi=None, start=None, end=None, temp_dir="D:/scratch")
+    assert merged == ["--temp-dir", "D:/scratch"]
+
+
+def test_dispatcher_merge_prefers_explicit_passthrough():
+    import dispatcher
+
+    merged = dispatcher._merge_pdf_args(["--dpi", "150"], dpi=200, start=None, end=None)
+    assert merged == ["--dpi", "150"]
+
+
+def test_dispatcher_forwards_figure_format(monkeypatch):
+    import dispatcher
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "doc.pdf", "--figure-format", "jpeg"])
+    args = dispatcher.parse_args()
+    merged = dispatcher._merge_pdf_args(args.extra, dpi=None, start=None, end=None, figure_format=args.figure_format)
+    assert merged == ["--figure-format", "jpeg"]
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "doc.pdf"])
+    assert dispatcher.parse_args().figure_format is None
+
+
+def test_dispatcher_parses_rotate_and_deskew(monkeypatch):
+    import dispatcher
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "scan.png", "--rotate", "90", "--deskew"])
+    args = dispatcher.parse_args()
+    assert args.rotate == 90
+    assert args.deskew is True
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "scan.png"])
+    args = dispatcher.parse_args()
+    assert args.rotate == 0
+    assert args.deskew is False
+    assert args.auto_orient is False
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "scan.pdf", "--auto-orient"])
+    assert dispatcher.parse_args().auto_orient is True
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "scan.pdf", "--auto-orient", "--crop", "0,0,0.5,0.5"])
+    with pytest.raises(SystemExit):
+        dispatcher.parse_args()
+
+
+def test_dispatcher_normalizes_pages_and_forwards_them(monkeypatch):
+    import dispatcher
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "sample.pdf", "--pages", "7, 1-3,2"])
+    args = dispatcher.parse_args()
+    assert args.pages == "1-3,7"
+    merged = dispatcher._merge_pdf_args(args.extra, dpi=None, start=None, end=None, pages=args.pages)
+    assert merged == ["--pages", "1-3,7"]
+
+
+def test_dispatcher_infers_pdf_output_dir_from_pages(tmp_path):
+    import dispatcher
+
+    output_root = tmp_path / "result"
+    inferred = dispatcher._infer_pdf_output_dir(
+        tmp_path / "doc.pdf", output_root=output_root, extra_args=["--pages", "1-3,7"]
+    )
+    assert inferred == output_root / "doc_p1-3_7"
+
+
+def test_dispatcher_forwards_output_name_and_uses_it_as_output_dir(monkeypatch, tmp_path):
+    import dispatcher
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "doc.pdf", "--output-name", "doc_20261016_300dpi", "--pages", "1-3"])
+    args = dispatcher.parse_args()
+    merged = dispatcher._merge_pdf_args(
+        args.extra, dpi=None, start=None, end=None, pages=args.pages, output_name=args.output_name
+    )
+    assert merged == ["--pages", "1-3", "--output-name", "doc_20261016_300dpi"]
+
+    output_root = tmp_path / "result"
+    inferred = dispatcher._infer_pdf_output_dir(tmp_path / "doc.pdf", output_root=output_root, extra_args=merged)
+    assert inferred == output_root / "doc_20261016_300dpi"
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "doc.pdf", "--output-name", "../doc"])
+    with pytest.raises(SystemExit):
+        dispatcher.parse_args()
+
+
+def test_dispatcher_export_existing_skips_ocr(monkeypatch, tmp_path):
+    import dispatcher
+
+    result_dir = tmp_path / "result" / "sample"
+    result_dir.mkdir(parents=True)
+    (result_dir / "sample_merged.md").write_text("# hi", encoding="utf-8")
+    calls = []
+    monkeypatch.setattr(dispatcher, "_export_formats", lambda out, **kw: calls.append((out, kw)))
+    monkeypatch.setattr(dispatcher, "run", lambda *a, **kw: pytest.fail("OCR must not run"))
+
+    dispatcher.export_existing(result_dir, formats=["docx"])
+
+    assert calls[0][0] == result_dir
+    assert calls[0][1]["formats"] == ["docx"]
+    assert calls[0][1]["source_stem"] == "sample"
+
+
+def test_dispatcher_export_existing_requires_markdown(tmp_path):
+    import dispatcher
+
+    result_dir = tmp_path / "empty"
+    result_dir.mkdir()
+    with pytest.raises(dispatcher.IngestError):
+        dispatcher.export_existing(result_dir, formats=["docx"])
+
+
+def test_dispatcher_reads_password_from_env_and_keeps_it_off_the_command_line(monkeypatch):
+    import dispatcher
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "locked.pdf"])
+    monkeypatch.setenv(dispatcher.PDF_PASSWORD_ENV, "s3cret")
+    args = dispatcher.parse_args()
+    assert args.password == "s3cret"
+    merged = dispatcher._merge_pdf_args(args.extra, dpi=None, start=None, end=None, temp_dir=None)
+    assert "s3cret" not in merged
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "locked.pdf", "--password", "cli"])
+    assert dispatcher.parse_args().password == "cli"
+    monkeypatch.delenv(dispatcher.PDF_PASSWORD_ENV)
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "locked.pdf"])
+    assert dispatcher.parse_args().password is None
+
+    # `--` で ocr_chanked.py に直接渡された --password はログで伏せる
+    assert dispatcher.redact_password_args(["a.pdf", "--password", "x", "--password=y"]) == [
+        "a.pdf",
+        "--password",
+        "***",
+        "--password=***",
+    ]
+
+
+def test_dispatcher_routes_multipage_tiff_through_pdf_with_page_range(monkeypatch, tmp_path):
+    Image = pytest.importorskip("PIL.Image")
+    import dispatcher
+
+    tiff_path = tmp_path / "scan.tiff"
+    frames = [Image.new("L", (20, 20), color) for color in (0, 128, 255)]
+    frames[0].save(tiff_path, format="TIFF", save_all=True, append_images=frames[1:])
+    calls = []
+
+    def fake_run_pdf(pdf_path, **kw):
+        pages = len(re.findall(rb"/Type\s*/Page\b", pdf_path.read_bytes()))
+        calls.append((pdf_path.name, pages, kw["extra_args"]))
+
+    monkeypatch.setattr(dispatcher, "_run_pdf", fake_run_pdf)
+    monkeypatch.setattr(dispatcher, "_run_image", lambda *a, **kw: pytest.fail("TIFF must use the PDF path"))
+    monkeypatch.setattr(dispatcher, "_export_formats", lambda *a, **kw: None)
+
+    output_root = tmp_path / "result"
+    output_dir = dispatcher.run(tiff_path, output_root=output_root, extra_pdf_args=["--start", "2", "--end", "3"])
+
+    assert calls == [("scan.pdf", 3, ["--start", "2", "--end", "3"])]
+    assert output_dir == output_root / "scan_p2-3"
+
+
+def test_dispatcher_crops_image_as_pdf_only_once(monkeypatch, tmp_path):
+    # 以前は PDF 化前に切り抜いた画像を ocr_chanked.py でも --crop で切り抜いていた
+    Image = pytest.importorskip("PIL.Image")
+    import dispatcher
+
+    image_path = tmp_path / "scan.png"
+    Image.new("L", (100, 40), 255).save(image_path)
+    pdf_sources = []
+    pdf_calls = []
+
+    def fake_convert(src, pdf_path, dpi):
+        with Image.open(src) as img:
+            pdf_sources.append(img.size)
+
+    monkeypatch.setattr(dispatcher, "_convert_image_to_pdf", fake_convert)
+    monkeypatch.setattr(dispatcher, "_run_pdf", lambda pdf_path, **kw: pdf_calls.append(kw))
+
+    dispatcher._run_image(
+        image_path,
+        mode="lite",
+        device="cpu",
+        output_root=tmp_path / "result",
+        svg_dpi=300,
+        enable_figure=False,
+        ocr_profile="ocr_default",
+        image_as_pdf=True,
+        image_dpi=300,
+        extra_pdf_args=None,
+        fallback_tesseract=False,
+        force_tesseract_merge=False,
+        crop="0.5,0,0.5,1",
+    )
+
+    assert pdf_sources == [(50, 40)]
+    assert len(pdf_calls) == 1
+    assert pdf_calls[0].get("crop") is None
+    assert "--crop" not in (pdf_calls[0]["extra_args"] or [])
+
+
+def test_dispatcher_warm_models_runs_yomitoku_on_blank_image(monkeypatch):
+    pytest.importorskip("PIL.Image")
+    import dispatcher
+
+    monkeypatch.setattr(sys, "argv", ["dispatcher.py", "--warm-models", "--device", "cuda"])
+    args = dispatcher.parse_args()
+    assert args.input_path is None and args.warm_models
+
+    calls = []
+
+    def fake_run(cmd, check):
+        assert check and cmd[cmd.index("-f") - 1].endswith("warmup.png")
+        calls.append(cmd)
+
+    monkeypatch.setattr(dispatcher.subprocess, "run", fake_run)
+    dispatcher.warm_models(mode=args.mode, device=args.device)
+
+    assert len(calls) == 1
+    assert calls[0][2] == "yomitoku.cli.main"
+    assert calls[0][-3:] == ["--lite", "-d", "cuda"]
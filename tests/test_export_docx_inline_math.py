@@ -23,6 +23,13 @@ class ExportDocxMathTextModeTests(unittest.TestCase):
         self.assertIn("x+y", full_xml)
         self.assertNotIn("$$", full_xml)
 
+    def test_newpage_line_becomes_a_page_break(self) -> None:
+        doc = Document()
+        convert_markdown(doc, ["one", "", "\\newpage", "", "two"], base_dir=Path("."))
+        full_xml = "\n".join(p._p.xml for p in doc.paragraphs)
+        self.assertIn('w:type="page"', full_xml)
+        self.assertNotIn("newpage", full_xml)
+
 
 if __name__ == "__main__":
     unittest.main()
@@ -1,4 +1,6 @@
-from export_excel_poc import TableCell, write_tables_to_workbook
+import pytest
+
+from export_excel_poc import TableCell, write_tables_to_csv_files, write_tables_to_workbook
 
 
 def test_excel_table_mode_splits_by_structure_and_names_sheets() -> None:
@@ -48,3 +50,26 @@ def test_excel_table_mode_splits_by_structure_and_names_sheets() -> None:
     assert ws2["B2"].value == "C校"
     assert len(ws2.tables) == 1
 
+
+def test_csv_layout_mode_uses_delimiter_and_encoding(tmp_path) -> None:
+    cells = [
+        TableCell(row=1, col=1, text="学校名"),
+        TableCell(row=1, col=2, text="募集"),
+        TableCell(row=2, col=1, text="A校"),
+        TableCell(row=2, col=2, text="○"),
+    ]
+
+    outputs = write_tables_to_csv_files(
+        [cells],
+        output_dir=tmp_path,
+        base_name="doc",
+        excel_mode="layout",
+        delimiter=";",
+        encoding="shift_jis",
+    )
+
+    assert [p.name for p in outputs] == ["doc__table_01.csv"]
+    assert outputs[0].read_bytes().decode("cp932").splitlines() == ["学校名;募集", "A校;○"]
+
+    with pytest.raises(ValueError):
+        write_tables_to_csv_files([cells], output_dir=tmp_path, base_name="doc", encoding="latin-1")
@@ -0,0 +1,74 @@
+import struct
+import zipfile
+
+import font_check
+from font_check import docx_font_names, font_warnings, normalize_font_name, read_font_names
+
+
+def _font_bytes(names: list[str]) -> bytes:
+    """name テーブルだけを持つ最小の sfnt（Windows / UTF-16BE のファミリ名）。"""
+
+    strings = b"".join(n.encode("utf-16-be") for n in names)
+    records = b""
+    offset = 0
+    for name in names:
+        length = len(name.encode("utf-16-be"))
+        records += struct.pack(">HHHHHH", 3, 1, 0x409, 1, length, offset)
+        offset += length
+    name_table = struct.pack(">HHH", 0, len(names), 6 + len(records)) + records + strings
+    header = struct.pack(">IHHHH", 0x00010000, 1, 16, 0, 0)
+    directory = struct.pack(">4sIII", b"name", 0, 12 + 16, len(name_table))
+    return header + directory + name_table
+
+
+def _docx(path, styles: str, theme: str = "") -> None:
+    with zipfile.ZipFile(path, "w") as archive:
+        archive.writestr("[Content_Types].xml", "<Types/>")
+        archive.writestr("word/styles.xml", styles)
+        if theme:
+            archive.writestr("word/theme/theme1.xml", theme)
+
+
+def test_read_font_names_parses_name_table(tmp_path):
+    font = tmp_path / "test.ttf"
+    font.write_bytes(_font_bytes(["Noto Sans JP", "ＭＳ 明朝"]))
+    assert read_font_names(font) == {"Noto Sans JP", "ＭＳ 明朝"}
+
+
+def test_docx_font_names_reads_rfonts_and_theme(tmp_path):
+    docx = tmp_path / "out.docx"
+    _docx(
+        docx,
+        '<w:rFonts w:ascii="Century" w:hAnsi="Century" w:eastAsia="ＭＳ 明朝"/>',
+        '<a:latin typeface="Calibri"/><a:ea typeface=""/><a:font script="Jpan" typeface="游明朝"/>',
+    )
+    assert docx_font_names(docx) == ["Century", "ＭＳ 明朝", "Calibri", "游明朝"]
+    assert docx_font_names(docx, include_theme=False) == ["Century", "ＭＳ 明朝"]
+
+
+def test_font_warnings_lists_missing_fonts_per_docx(tmp_path):
+    _docx(tmp_path / "a.docx", '<w:rFonts w:ascii="Century" w:eastAsia="MS明朝"/>')
+    installed = {normalize_font_name("ＭＳ 明朝")}
+
+    warnings = font_warnings(tmp_path, installed)
+    assert len(warnings) == 1
+    assert warnings[0].startswith("a.docx: ")
+    assert "Century" in warnings[0] and "MS明朝" not in warnings[0]
+
+    # フォント一覧が取れないときは判定しない
+    assert font_warnings(tmp_path, set()) == []
+
+
+def test_font_warnings_skip_theme_fonts_off_windows_and_scan_fonts_once(monkeypatch, tmp_path):
+    _docx(tmp_path / "a.docx", '<w:rFonts w:ascii="Century"/>', '<a:latin typeface="Calibri"/>')
+    installed = {normalize_font_name("Century")}
+    assert font_warnings(tmp_path, installed, include_theme=False) == []
+    assert "Calibri" in font_warnings(tmp_path, installed, include_theme=True)[0]
+
+    scans = []
+    monkeypatch.setattr(font_check, "installed_font_names", lambda: scans.append(1) or installed)
+    font_check.system_font_names.cache_clear()
+    for _ in range(3):
+        font_warnings(tmp_path)
+    font_check.system_font_names.cache_clear()
+    assert scans == [1]
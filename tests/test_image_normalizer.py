@@ -1,5 +1,6 @@
 from __future__ import annotations
 
+import re
 import tempfile
 import unittest
 from pathlib import Path
@@ -14,6 +15,13 @@ except ImportError:  # pragma: no cover - handled via skip decorator
 else:  # pragma: no cover - marker for readability only
     CAIRO_AVAILABLE = True
 
+try:  # pragma: no cover - only for optional dependency detection
+    from PIL import Image
+except ImportError:  # pragma: no cover - handled via skip decorator
+    PIL_AVAILABLE = False
+else:  # pragma: no cover - marker for readability only
+    PIL_AVAILABLE = True
+
 
 class ImageNormalizerTests(unittest.TestCase):
     def test_requires_conversion_flags_known_extensions(self) -> None:
@@ -73,6 +81,23 @@ class ImageNormalizerTests(unittest.TestCase):
             self.assertEqual(result.converted.read_bytes(), b"png")
             self.assertTrue(result.performed)
 
+    def test_tiff_frame_count_is_one_for_other_images(self) -> None:
+        self.assertEqual(normalizer.tiff_frame_count(Path("note.png")), 1)
+
+    @unittest.skipUnless(PIL_AVAILABLE, "Pillow がインストールされていません")
+    def test_tiff_frames_to_pdf_keeps_every_frame(self) -> None:
+        with tempfile.TemporaryDirectory() as tmpdir:
+            tiff_path = Path(tmpdir) / "scan.tiff"
+            frames = [Image.new("L", (20, 20), color) for color in (0, 128, 255)]
+            frames[0].save(tiff_path, format="TIFF", save_all=True, append_images=frames[1:])
+
+            self.assertEqual(normalizer.tiff_frame_count(tiff_path), 3)
+
+            pdf_path = normalizer.tiff_frames_to_pdf(tiff_path, Path(tmpdir) / "out" / "scan.pdf", dpi=72)
+
+            self.assertTrue(pdf_path.exists())
+            self.assertEqual(len(re.findall(rb"/Type\s*/Page\b", pdf_path.read_bytes())), 3)
+
 
 if __name__ == "__main__":  # pragma: no cover
     unittest.main()
@@ -9,11 +9,21 @@ except ImportError:  # pragma: no cover - Pillow not installed in minimal env
     Image = None
 
 try:  # pragma: no cover - guard for environments without Pillow
-    from image_preprocessor import PROFILE_REGISTRY, preprocess_image_variants
+    from image_preprocessor import (
+        PROFILE_REGISTRY,
+        apply_orientation,
+        detect_orientation,
+        estimate_skew_angle,
+        orient_image,
+        preprocess_image_variants,
+        rotate_image,
+    )
     PREPROCESS_IMPORT_ERROR = None
 except ModuleNotFoundError as exc:  # pragma: no cover
     PROFILE_REGISTRY = {}
     preprocess_image_variants = None  # type: ignore
+    apply_orientation = estimate_skew_angle = rotate_image = None  # type: ignore
+    detect_orientation = orient_image = None  # type: ignore
     PREPROCESS_IMPORT_ERROR = exc
 
 
@@ -48,3 +58,46 @@ class ImagePreprocessorTests(unittest.TestCase):
             with Image.open(pres_path) as img:
                 self.assertEqual(img.mode, "RGB")
                 self.assertIn("page_005", pres_path.name)
+
+    def test_rotate_image_turns_clockwise_and_rejects_odd_angles(self) -> None:
+        img = Image.new("L", (40, 20), color=255)
+        img.putpixel((0, 0), 0)  # 左上の黒点
+
+        rotated = rotate_image(img, 90)
+        self.assertEqual(rotated.size, (20, 40))
+        # 時計回りに 90 度回すと左上は右上に来る
+        self.assertEqual(rotated.getpixel((19, 0)), 0)
+        self.assertIs(rotate_image(img, 0), img)
+        with self.assertRaises(ValueError):
+            rotate_image(img, 45)
+
+    def test_estimate_skew_angle_recovers_small_tilt(self) -> None:
+        img = Image.new("L", (600, 400), color=255)
+        for y in range(40, 360, 40):
+            for x in range(60, 540):
+                for dy in range(6):
+                    img.putpixel((x, y + dy), 0)
+        tilted = img.rotate(3, expand=True, fillcolor=255)
+
+        self.assertAlmostEqual(estimate_skew_angle(img), 0.0, delta=0.5)
+        self.assertAlmostEqual(estimate_skew_angle(tilted), -3.0, delta=0.5)
+        self.assertEqual(apply_orientation(img, rotate=180).size, img.size)
+
+    def test_detect_orientation_finds_sideways_pages(self) -> None:
+        # 左揃え・行末不揃いの横書きの行
+        img = Image.new("L", (600, 400), color=255)
+        for i, y in enumerate(range(40, 360, 40)):
+            for x in range(60, 60 + (480, 430, 470, 300, 480, 410, 200, 460)[i]):
+                for dy in range(6):
+                    img.putpixel((x, y + dy), 0)
+
+        self.assertEqual(detect_orientation(img), 0)
+        # 反時計回りに倒れたページは時計回りに 90 度、その逆は 270 度で戻る
+        self.assertEqual(detect_orientation(rotate_image(img, 270)), 90)
+        self.assertEqual(detect_orientation(rotate_image(img, 90)), 270)
+        self.assertEqual(detect_orientation(Image.new("L", (600, 400), color=255)), 0)
+
+        oriented, auto_rotation = orient_image(rotate_image(img, 270), auto_orient=True)
+        self.assertEqual(auto_rotation, 90)
+        self.assertEqual(oriented.size, img.size)
+        self.assertEqual(orient_image(rotate_image(img, 270))[1], 0)
@@ -26,3 +26,29 @@ def test_rename_figure_assets_overwrites_existing(tmp_path, monkeypatch):
     assert (figure_dir / "fig_page001_01.png").read_text(encoding="utf-8") == "new"
     assert "./figures/fig_page001_01.png" in md_path.read_text(encoding="utf-8")
 
+
+
+def test_rename_figure_assets_converts_to_jpeg(tmp_path, monkeypatch):
+    import ocr
+    from PIL import Image
+
+    output_dir = tmp_path / "result"
+    figure_dir = output_dir / "figures"
+    figure_dir.mkdir(parents=True)
+
+    raw = figure_dir / "page_images_page_002_figure_0.png"
+    Image.new("RGBA", (8, 8), (255, 0, 0, 128)).save(raw)
+
+    md_path = output_dir / "page_002.md"
+    md_path.write_text("![](figures/page_images_page_002_figure_0.png)", encoding="utf-8")
+
+    monkeypatch.setattr(ocr, "remove_icon_figures", lambda *args, **kwargs: None)
+
+    ocr.rename_figure_assets(output_dir, 2, figure_format="jpeg")
+
+    converted = figure_dir / "fig_page002_01.jpg"
+    assert not raw.exists()
+    with Image.open(converted) as img:
+        assert img.format == "JPEG"
+        assert img.mode == "RGB"
+    assert "./figures/fig_page002_01.jpg" in md_path.read_text(encoding="utf-8")
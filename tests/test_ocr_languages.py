@@ -0,0 +1,33 @@
+import pytest
+
+from ocr_languages import (
+    DEFAULT_TESSERACT_LANG,
+    LanguageSpecError,
+    needs_tesseract,
+    parse_languages,
+    supported_languages,
+    tesseract_lang,
+)
+
+
+def test_parse_languages_keeps_order_and_drops_duplicates():
+    assert parse_languages(" ko, ja ,ko") == ["ko", "ja"]
+
+
+@pytest.mark.parametrize("spec", ["", "ja,,en", "xx", "jpn"])
+def test_parse_languages_rejects_invalid(spec):
+    with pytest.raises(LanguageSpecError):
+        parse_languages(spec)
+
+
+def test_tesseract_lang_defaults_to_japanese_and_english():
+    assert tesseract_lang(None) == DEFAULT_TESSERACT_LANG
+    assert tesseract_lang([]) == DEFAULT_TESSERACT_LANG
+    assert tesseract_lang(["ko", "en"]) == "kor+eng"
+    assert not needs_tesseract(["ja", "en"])
+    assert needs_tesseract(["ja", "ko"])
+
+
+def test_supported_languages_depend_on_installed_tesseract_data():
+    assert supported_languages(set()) == ["ja", "en"]
+    assert supported_languages({"eng", "kor", "osd"}) == ["ja", "en", "ko"]
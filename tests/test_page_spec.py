@@ -0,0 +1,22 @@
+import pytest
+
+from page_spec import PageSpecError, expand_pages, format_page_spec, page_spec_label, parse_page_spec
+
+
+def test_parse_page_spec_sorts_and_merges_overlaps():
+    assert parse_page_spec("10-12, 1-3,7") == [(1, 3), (7, 7), (10, 12)]
+    assert parse_page_spec("1-5,3-7,8") == [(1, 8)]
+    assert parse_page_spec("4,4") == [(4, 4)]
+
+
+@pytest.mark.parametrize("spec", ["5-3", "0-2", "1,,2", "a-b", "-3", ""])
+def test_parse_page_spec_rejects_invalid(spec):
+    with pytest.raises(PageSpecError):
+        parse_page_spec(spec)
+
+
+def test_expand_and_format_page_spec():
+    ranges = parse_page_spec("1-3,7,10-12")
+    assert expand_pages(ranges, max_page=11) == [1, 2, 3, 7, 10, 11]
+    assert format_page_spec(ranges) == "1-3,7,10-12"
+    assert page_spec_label(ranges) == "p1-3_7_10-12"
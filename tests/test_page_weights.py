@@ -0,0 +1,22 @@
+import json
+
+from PIL import Image
+
+from page_weights import BASE_PAGE_WEIGHT, PROGRESS_MARKER, ink_ratio, page_weight, progress_line
+
+
+def test_page_weight_grows_with_ink():
+    blank = Image.new("RGB", (10, 10), "white")
+    half = Image.new("RGB", (10, 10), "white")
+    half.paste((0, 0, 0), (0, 0, 10, 5))
+
+    assert ink_ratio(blank) == 0.0
+    assert ink_ratio(half) == 0.5
+    assert page_weight(blank) == BASE_PAGE_WEIGHT
+    assert page_weight(half) > page_weight(blank)
+
+
+def test_progress_line_is_marker_and_json():
+    marker, payload = progress_line(3, 2.5).split(" ", 1)
+    assert marker == PROGRESS_MARKER
+    assert json.loads(payload) == {"page": 3, "weight": 2.5}
@@ -0,0 +1,27 @@
+from postprocess import collect_md_files, write_merged_md
+
+
+def _pages(tmp_path):
+    (tmp_path / "page_001.md").write_text("one", encoding="utf-8")
+    (tmp_path / "page_002.md").write_text("two", encoding="utf-8")
+    return collect_md_files(tmp_path)
+
+
+def test_merge_default_keeps_page_headings(tmp_path):
+    out = tmp_path / "merged.md"
+    write_merged_md(_pages(tmp_path), out)
+    assert out.read_text(encoding="utf-8") == "# Page 1\n\none\n\n\n# Page 2\n\ntwo\n\n"
+
+
+def test_merge_separators(tmp_path):
+    files = _pages(tmp_path)
+    expected = {
+        "hr": "one\n\n---\n\ntwo\n\n",
+        "heading": "## Page 1\n\none\n\n## Page 2\n\ntwo\n\n",
+        "none": "one\n\ntwo\n\n",
+        "page": "one\n\n\\newpage\n\ntwo\n\n",
+    }
+    for sep, text in expected.items():
+        out = tmp_path / f"merged_{sep}.md"
+        write_merged_md(files, out, separator=sep)
+        assert out.read_text(encoding="utf-8") == text
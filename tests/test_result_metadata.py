@@ -0,0 +1,46 @@
+from result_metadata import collect_result_metadata, detect_languages, write_auto_rotations
+
+
+def test_detect_languages_orders_by_character_count():
+    text = "これは日本語の文章です。表と図を含む報告書の本文になります。" + " Summary of results."
+    assert detect_languages(text) == ["ja"]
+    # 英字のほうが多ければ en が先に来る
+    assert detect_languages(text + " The quick brown fox jumps over the lazy dog again and again.") == [
+        "en",
+        "ja",
+    ]
+    assert detect_languages("p.1") == []
+
+
+def test_collect_result_metadata_counts_pages_tables_and_figures(tmp_path):
+    out = tmp_path / "report"
+    out.mkdir()
+    (out / "page_001.md").write_text("# タイトル\n\n| a | b |\n| --- | --- |\n| 1 | 2 |\n", encoding="utf-8")
+    (out / "page_002.md").write_text("![fig](figures/p2.png)\n", encoding="utf-8")
+    (out / "report_merged.md").write_text(
+        "# Page 1\n\n# タイトル\n\nこれは日本語で書かれた報告書の本文です。ここに表があります。\n\n"
+        "| a | b |\n| --- | --- |\n| 1 | 2 |\n\n# Page 2\n\n![fig](figures/p2.png)\n<table><tr><td>x</td></tr></table>\n",
+        encoding="utf-8",
+    )
+
+    meta = collect_result_metadata(out)
+
+    assert meta == {"pages": 2, "languages": ["ja"], "tables": 2, "figures": 1}
+
+
+def test_collect_result_metadata_handles_empty_dir(tmp_path):
+    assert collect_result_metadata(tmp_path) == {"pages": 0, "languages": [], "tables": 0, "figures": 0}
+
+
+def test_collect_result_metadata_reports_auto_rotated_pages(tmp_path):
+    out = tmp_path / "scan"
+    out.mkdir()
+    (out / "page_001.md").write_text("# a\n", encoding="utf-8")
+    assert "rotated_pages" not in collect_result_metadata(out)
+
+    write_auto_rotations(out, {3: 90, 1: 0, 2: 270})
+
+    assert collect_result_metadata(out)["rotated_pages"] == [
+        {"dir_name": "scan", "page": 2, "degrees": 270},
+        {"dir_name": "scan", "page": 3, "degrees": 90},
+    ]
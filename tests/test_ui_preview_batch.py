@@ -0,0 +1,32 @@
+from ui_preview import render_batch
+
+
+def test_render_batch_keeps_order_and_labels_each_response(tmp_path):
+    first = tmp_path / "a.png"
+    second = tmp_path / "b.png"
+    first.write_bytes(b"")
+    second.write_bytes(b"")
+
+    responses = render_batch([str(first), str(second)], lambda path: {"data": path.name})
+
+    assert responses == [
+        {"data": "a.png", "path": str(first)},
+        {"data": "b.png", "path": str(second)},
+    ]
+
+
+def test_render_batch_reports_failures_per_file(tmp_path):
+    broken = tmp_path / "broken.pdf"
+    broken.write_bytes(b"")
+    missing = tmp_path / "missing.pdf"
+
+    def render(path):
+        raise RuntimeError("bad page")
+
+    responses = render_batch([str(broken), str(missing)], render)
+
+    assert responses[0]["errorKind"] == "render_failed"
+    assert "bad page" in responses[0]["error"]
+    assert responses[0]["path"] == str(broken)
+    assert responses[1]["errorKind"] == "not_found"
+    assert responses[1]["path"] == str(missing)
@@ -0,0 +1,51 @@
+import os
+from types import SimpleNamespace
+
+import pytest
+
+from ui_preview import prune_tile_cache, tile_box, tile_cache_dir
+
+
+def test_tile_box_clips_edge_tiles():
+    assert tile_box(1000, 600, 0, 0, 512) == ((0, 0, 512, 512), 2, 2)
+    assert tile_box(1000, 600, 1, 1, 512) == ((512, 512, 1000, 600), 2, 2)
+
+
+def test_tile_box_single_tile_for_small_images():
+    assert tile_box(100, 80, 0, 0, 512) == ((0, 0, 100, 80), 1, 1)
+
+
+@pytest.mark.parametrize("tile_x, tile_y, tile_size", [(2, 0, 512), (0, -1, 512), (0, 0, 0)])
+def test_tile_box_rejects_out_of_range(tile_x, tile_y, tile_size):
+    with pytest.raises(ValueError):
+        tile_box(1000, 600, tile_x, tile_y, tile_size)
+
+
+def _tile_args(**overrides):
+    args = dict(page=1, zoom=1.0, tile_size=512, rotate=0, deskew=False, auto_orient=False, password=None)
+    args.update(overrides)
+    return SimpleNamespace(**args)
+
+
+def test_tile_cache_dir_is_per_page_and_zoom(tmp_path):
+    pdf = tmp_path / "a.pdf"
+    pdf.write_bytes(b"%PDF")
+
+    base = tile_cache_dir(pdf, _tile_args(), root=tmp_path)
+    assert base == tile_cache_dir(pdf, _tile_args(), root=tmp_path)
+    assert base != tile_cache_dir(pdf, _tile_args(page=2), root=tmp_path)
+    assert base != tile_cache_dir(pdf, _tile_args(zoom=2.0), root=tmp_path)
+    # 復号したページはディスクに残さない
+    assert tile_cache_dir(pdf, _tile_args(password="secret"), root=tmp_path) is None
+
+
+def test_prune_tile_cache_keeps_recently_used_pages(tmp_path):
+    for i in range(4):
+        entry = tmp_path / f"page{i}"
+        entry.mkdir()
+        os.utime(entry, (1000 + i, 1000 + i))
+    (tmp_path / ".staging_x").mkdir()
+
+    prune_tile_cache(tmp_path, keep=2)
+
+    assert sorted(p.name for p in tmp_path.iterdir()) == [".staging_x", "page2", "page3"]
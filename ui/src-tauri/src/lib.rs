@@ -1,16 +1,22 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
     path::PathBuf,
-    process::Command,
-    sync::{Arc, Mutex},
+    process::{Child, Command, ExitStatus},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, OnceLock, RwLock,
+    },
     thread,
-    time::{Instant, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
-use tauri::{Manager, State};
-use tauri_plugin_dialog;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_notification::NotificationExt;
 use uuid::Uuid;
 
 fn apply_python_env(cmd: &mut Command) {
@@ -19,6 +25,57 @@ fn apply_python_env(cmd: &mut Command) {
     cmd.env("PYTHONIOENCODING", "utf-8");
 }
 
+/// パスワード保護 PDF のパスワードを Python 側へ渡す環境変数（ingest.py の `PDF_PASSWORD_ENV`）。
+/// `--password` で渡すとプロセス一覧や spawn ログに残るため、引数には載せない
+const PDF_PASSWORD_ENV: &str = "OCR_TO_DOC_PDF_PASSWORD";
+
+fn apply_pdf_password(cmd: &mut Command, password: Option<&str>) {
+    if let Some(pw) = password.filter(|pw| !pw.is_empty()) {
+        cmd.env(PDF_PASSWORD_ENV, pw);
+    }
+}
+
+/// アプリが設定するため、settings.json の `env` では上書きさせない（job.log にも出さない）変数
+const PROTECTED_ENV_VARS: &[&str] = &["PYTHONUTF8", "PYTHONIOENCODING", PDF_PASSWORD_ENV];
+
+fn is_protected_env_var(key: &str) -> bool {
+    PROTECTED_ENV_VARS
+        .iter()
+        .any(|p| p.eq_ignore_ascii_case(key))
+}
+
+/// Add the user's extra env vars (settings.json `env`) to a python command, skipping protected ones.
+fn apply_extra_env(cmd: &mut Command, env: &HashMap<String, String>) {
+    cmd.envs(
+        env.iter()
+            .filter(|(k, _)| !k.is_empty() && !is_protected_env_var(k)),
+    );
+}
+
+/// settings.json の `env`（読めなければ空）
+fn configured_extra_env(project_root: &std::path::Path) -> HashMap<String, String> {
+    load_settings_from_disk(project_root)
+        .ok()
+        .and_then(|s| s.env)
+        .unwrap_or_default()
+}
+
+/// spawn ログに値を出さない環境変数名（部分一致・大文字小文字無視）
+const SECRET_ENV_MARKERS: &[&str] = &[
+    "KEY",
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+fn is_secret_env_var(key: &str) -> bool {
+    let upper = key.to_ascii_uppercase();
+    SECRET_ENV_MARKERS.iter().any(|m| upper.contains(m))
+}
+
 pub fn run_cli_if_requested() -> Option<i32> {
     let args: Vec<String> = std::env::args().collect();
     let is_self_test = args.iter().any(|a| a == "--self-test");
@@ -148,6 +205,14 @@ pub fn run_cli_if_requested() -> Option<i32> {
         return Some(2);
     }
     let output_root_arg = output_root.to_string_lossy().to_string();
+    let mut temp_dir_args = Vec::new();
+    match configured_temp_dir(&project_root) {
+        Ok(dir) => push_temp_dir_arg(&mut temp_dir_args, dir.as_deref()),
+        Err(e) => {
+            eprintln!("[self-test] {e}");
+            return Some(2);
+        }
+    }
 
     let fixture_stem = fixture
         .file_stem()
@@ -172,9 +237,17 @@ pub fn run_cli_if_requested() -> Option<i32> {
             .args(["--device", "cpu"])
             .args(["--no-figure"])
             .args(["--output-root", &output_root_arg])
+            .args(&temp_dir_args)
             .current_dir(&project_root);
-        match cmd.output() {
-            Ok(o) => o,
+        match output_with_timeout(&mut cmd, SELF_TEST_TIMEOUT) {
+            Ok(Some(o)) => o,
+            Ok(None) => {
+                eprintln!(
+                    "[self-test] dispatcher timed out after {} s",
+                    SELF_TEST_TIMEOUT.as_secs()
+                );
+                return Some(1);
+            }
             Err(e) => {
                 eprintln!("[self-test] failed to spawn dispatcher: {e}");
                 return Some(1);
@@ -235,14 +308,142 @@ pub fn run_cli_if_requested() -> Option<i32> {
 
 #[derive(Default)]
 struct AppState {
-    jobs: Mutex<HashMap<String, JobInfo>>,
+    jobs: RwLock<HashMap<String, JobInfo>>,
+    watch: Mutex<Option<WatchHandle>>,
+    /// 起動時に一度だけ解決したプロジェクトルート（コマンド毎の ancestors 探索を避ける）
+    project_root: OnceLock<PathBuf>,
+    /// 実行中の dispatcher プロセス（job_id ごと）。アプリ終了時にまとめて kill する。
+    children: Mutex<HashMap<String, Child>>,
+    /// `dispatcher.py --list-languages` の結果（Python の起動が重いので一度だけ問い合わせる）
+    supported_languages: OnceLock<Vec<String>>,
+    /// Python 実行ファイルごとの `query_env_fingerprint` の結果（pip freeze をジョブ毎に走らせない）
+    env_fingerprints: Mutex<HashMap<String, EnvFingerprint>>,
+    /// 出力ファイルごとの `check_output_file` の結果（一覧を開くたびに docx/xlsx を読み直さない）
+    output_checks: Mutex<HashMap<PathBuf, CachedOutputCheck>>,
+}
+
+/// ポーリングが jobs の読み取りロックをこれ以上待ったら warn で残す（書き込み側との競合の目安）
+const SLOW_JOBS_READ: Duration = Duration::from_millis(50);
+
+impl AppState {
+    /// Read access to the job table for pollers. Logs how long the lock took (debug, or warn
+    /// past `SLOW_JOBS_READ`) so contention with running jobs' log writes can be measured.
+    fn read_jobs(
+        &self,
+        caller: &str,
+    ) -> Result<std::sync::RwLockReadGuard<'_, HashMap<String, JobInfo>>, String> {
+        let started = Instant::now();
+        let jobs = self
+            .jobs
+            .read()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        let waited = started.elapsed();
+        if waited >= SLOW_JOBS_READ {
+            log::warn!(
+                "{caller}: waited {} ms for the job table",
+                waited.as_millis()
+            );
+        } else {
+            log::debug!("{caller}: job table read in {} us", waited.as_micros());
+        }
+        Ok(jobs)
+    }
+
+    /// Cached project root; walks up from the exe via `resolve_project_root` only if not yet set.
+    fn project_root(&self) -> Option<PathBuf> {
+        if let Some(root) = self.project_root.get() {
+            return Some(root.clone());
+        }
+        let exe_path = std::env::current_exe().ok()?;
+        let root = resolve_project_root(&exe_path)?;
+        Some(self.project_root.get_or_init(|| root).clone())
+    }
+
+    /// Cached `list_supported_languages`; a failed query is not cached so it can be retried.
+    fn supported_languages(&self, project_root: &std::path::Path) -> Result<Vec<String>, String> {
+        if let Some(langs) = self.supported_languages.get() {
+            return Ok(langs.clone());
+        }
+        let langs = query_supported_languages(project_root)?;
+        Ok(self.supported_languages.get_or_init(|| langs).clone())
+    }
+
+    /// Cached env fingerprint for the configured Python; `refresh` re-queries (e.g. after a
+    /// pip install). Failures are not cached.
+    fn env_fingerprint(
+        &self,
+        project_root: &std::path::Path,
+        refresh: bool,
+    ) -> Result<EnvFingerprint, String> {
+        let python_bin = resolve_python_bin(project_root);
+        if !refresh {
+            if let Some(fp) = self
+                .env_fingerprints
+                .lock()
+                .ok()
+                .and_then(|cache| cache.get(&python_bin).cloned())
+            {
+                return Ok(fp);
+            }
+        }
+        let fp = query_env_fingerprint(project_root)?;
+        if let Ok(mut cache) = self.env_fingerprints.lock() {
+            cache.insert(python_bin, fp.clone());
+        }
+        Ok(fp)
+    }
+
+    /// Cached `check_output_file`; re-checked when the file's mtime or size changes.
+    fn check_output_file(
+        &self,
+        path: &std::path::Path,
+        csv_encoding: Option<CsvEncoding>,
+    ) -> OutputVerification {
+        let stamp = fs::metadata(path)
+            .ok()
+            .map(|m| (m.modified().ok(), m.len()));
+        if let Some((modified, len)) = stamp {
+            let hit = self.output_checks.lock().ok().and_then(|cache| {
+                cache
+                    .get(path)
+                    .filter(|c| {
+                        c.modified == modified && c.len == len && c.csv_encoding == csv_encoding
+                    })
+                    .map(|c| c.verification.clone())
+            });
+            if let Some(verification) = hit {
+                return verification;
+            }
+        }
+        let verification = check_output_file(path, csv_encoding);
+        if let (Some((modified, len)), Ok(mut cache)) = (stamp, self.output_checks.lock()) {
+            cache.insert(
+                path.to_path_buf(),
+                CachedOutputCheck {
+                    modified,
+                    len,
+                    csv_encoding,
+                    verification: verification.clone(),
+                },
+            );
+        }
+        verification
+    }
+}
+
+/// 監視フォルダモードの実行中ハンドル。drop すると notify の監視も止まる。
+struct WatchHandle {
+    folder: PathBuf,
+    _watcher: notify::RecommendedWatcher,
+    stop: Arc<AtomicBool>,
+    recent: Arc<Mutex<Vec<WatchFileEvent>>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct JobInfo {
     status: JobStatus,
     progress: f32,
-    log: Vec<String>,
+    log: Vec<LogEntry>,
     outputs: Vec<String>,
     preview: Option<String>,
     error: Option<String>,
@@ -250,1431 +451,11420 @@ struct JobInfo {
     page_current: Option<u32>,
     page_total: Option<u32>,
     eta_seconds: Option<u32>,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-enum JobStatus {
-    Idle,
-    Running,
-    Done,
-    Error,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RunOptions {
-    #[serde(default)]
-    formats: Vec<String>,
+    /// ジョブ開始時刻（UNIX ms）。一覧表示の並び順に使う。
     #[serde(default)]
-    image_as_pdf: bool,
+    started_at_ms: u64,
+    /// ジョブ終了時刻（UNIX ms）。`notify_job_finished` で記録し、実行中は None
     #[serde(default)]
-    enable_figure: bool,
+    finished_at_ms: Option<u64>,
+    /// `request_stop` で立てる。ワーカーは次のファイルに進む前にこれを見て止まる。
     #[serde(default)]
-    use_gpu: bool,
+    stop_requested: bool,
+    /// バッチ内の各入力ファイルの結果（`paths` と同じ順）
     #[serde(default)]
-    mode: String,
+    files: Vec<FileProgress>,
+    /// dispatcher の `@RESULT` 行から集めた結果の概要。古い dispatcher では None のまま
     #[serde(default)]
-    chunk_size: Option<u32>,
+    metadata: Option<ResultMetadata>,
+    /// dispatcher の `@OUTPUT_DIR` 行から集めた結果ディレクトリ名（`cancel_and_discard` で消す対象）
     #[serde(default)]
-    enable_rest: bool,
+    output_dirs: Vec<String>,
+    /// `output_dirs` のうち、このジョブが新しく作ったもの（起動前には無かった）。
+    /// `cancel_and_discard` はこれだけを消し、再実行で上書きしていた既存の結果は残す
     #[serde(default)]
-    rest_seconds: Option<u32>,
+    created_dirs: Vec<String>,
+    /// `set_job_tags` で付けた整理用のタグ（結果の run.json にも書く）
     #[serde(default)]
-    pdf_dpi: Option<u32>,
+    tags: Vec<String>,
+    /// バッチ全体の ETA 用に、完了したファイルの実績を積み上げる
     #[serde(default)]
-    excel_mode: Option<String>,
+    batch_stats: BatchStats,
+    /// `skip_unchanged` で処理しなかった（結果が入力より新しい）ファイルの数
     #[serde(default)]
-    excel_meta_sheet: Option<bool>,
+    skipped_up_to_date: usize,
+    /// post_hook の実行中。一時停止・再開はフックのプロセスには効かないので受け付けない
     #[serde(default)]
-    file_options: Option<HashMap<String, FileSpecificOptions>>,
+    finalizing: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct CropRect {
-    left: f64,
-    top: f64,
-    width: f64,
-    height: f64,
+/// 完了したファイルのページ数と、最初のページマーカーからの処理時間（バッチ全体の残り時間の見積もりに使う）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct BatchStats {
+    files_done: u32,
+    pages_done: u32,
+    seconds: f32,
+    /// 最初のファイルが終わるまで使う過去の実績（`metrics.json`）
+    seed_secs_per_page: f32,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct FileSpecificOptions {
-    start: Option<u32>,
-    end: Option<u32>,
-    crop: Option<CropRect>,
-}
+impl BatchStats {
+    fn record_file(&mut self, pages: u32, seconds: f32) {
+        self.files_done += 1;
+        self.pages_done += pages;
+        if seconds.is_finite() && seconds > 0.0 {
+            self.seconds += seconds;
+        }
+    }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct RunJobResponse {
-    job_id: String,
-}
+    /// バッチ内のファイルをまたいだ 1 ページあたりの平均秒数
+    fn secs_per_page(&self) -> Option<f32> {
+        if self.pages_done > 0 && self.seconds > 0.0 {
+            return Some(self.seconds / self.pages_done as f32);
+        }
+        (self.seed_secs_per_page.is_finite() && self.seed_secs_per_page > 0.0)
+            .then_some(self.seed_secs_per_page)
+    }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ProgressResponse {
-    status: JobStatus,
-    progress: f32,
-    log: Vec<String>,
-    error: Option<String>,
-    current_message: Option<String>,
-    page_current: Option<u32>,
-    page_total: Option<u32>,
-    eta_seconds: Option<u32>,
+    /// 未着手ファイルのページ数の見込み。完了したファイルの平均、無ければ処理中のファイルのページ数
+    fn pages_per_file(&self, current_total: Option<u32>) -> Option<f32> {
+        if self.files_done > 0 {
+            return Some(self.pages_done as f32 / self.files_done as f32);
+        }
+        current_total.map(|t| t.max(1) as f32)
+    }
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ResultResponse {
-    outputs: Vec<String>,
-    preview: Option<String>,
+/// `@RESULT {json}` 行（dispatcher.py / result_metadata.py）の中身
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct ResultMetadata {
+    pages: u32,
+    languages: Vec<String>,
+    tables: u32,
+    figures: u32,
+    /// docx が指定したフォントのうちこの PC に無いもの（font_check.py）。Word では別のフォントで表示される
+    #[serde(alias = "font_warnings")]
+    font_warnings: Vec<String>,
+    /// `autoOrient` で自動回転したページ（result_metadata.py `auto_orient.json`）
+    #[serde(alias = "rotated_pages")]
+    rotated_pages: Vec<RotatedPage>,
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct RecentResultEntry {
+/// 自動回転した 1 ページ。バッチでは結果ディレクトリごとにページ番号が重なるので `dir_name` で区別する
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct RotatedPage {
+    #[serde(alias = "dir_name")]
     dir_name: String,
-    updated_at_ms: u64,
-    page_range: Option<String>,
-    best_file: Option<String>,
+    page: u32,
+    /// 時計回りの角度（90 / 270）
+    degrees: u32,
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct EnvironmentStatus {
-    project_root: String,
-    dispatcher_found: bool,
-    result_dir_found: bool,
-    python_bin: String,
+/// dispatcher が最後に出す結果メタ情報行の接頭辞
+const RESULT_LINE_PREFIX: &str = "@RESULT ";
+
+/// ページの重み行（page_weights.py `PROGRESS_MARKER`）の接頭辞
+const PROGRESS_LINE_PREFIX: &str = "@PROGRESS ";
+
+/// `@PROGRESS {"page": N, "weight": w}`。N は `--- Done N/M ---` と同じ番号
+#[derive(Debug, Deserialize, PartialEq)]
+struct PageWeight {
+    page: u32,
+    weight: f32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PreviewResponse {
-    data_url: String,
-    page_count: Option<u32>,
-    page: Option<u32>,
+impl PageWeight {
+    /// 壊れた行や 0 以下の重みは None（そのページは既知の重みの平均として扱う）。
+    fn parse_line(line: &str) -> Option<Self> {
+        let json = line.trim_end().strip_prefix(PROGRESS_LINE_PREFIX)?;
+        let w: PageWeight = serde_json::from_str(json).ok()?;
+        (w.weight.is_finite() && w.weight > 0.0).then_some(w)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct AppSettings {
-    #[serde(default)]
-    formats: Vec<String>,
-    #[serde(default)]
-    image_as_pdf: bool,
-    #[serde(default)]
-    enable_figure: bool,
-    #[serde(default)]
-    use_gpu: bool,
-    #[serde(default)]
-    output_root: Option<String>,
-    #[serde(default = "default_excel_meta_sheet")]
-    excel_meta_sheet: bool,
-    #[serde(default)]
-    chunk_size: Option<u32>,
-    #[serde(default)]
-    enable_rest: bool,
-    #[serde(default)]
-    rest_seconds: Option<u32>,
-    #[serde(default)]
-    pdf_dpi: Option<u32>,
-    #[serde(default)]
-    window_width: Option<u32>,
-    #[serde(default)]
-    window_height: Option<u32>,
+/// Fraction of the pages `start..=end` that is finished once page `cur` is done, weighted
+/// by the `@PROGRESS` weights. Pages without a weight count as the mean of the known ones.
+/// Returns None when no weights were sent, so the caller falls back to page counts.
+fn weighted_page_ratio(weights: &HashMap<u32, f32>, start: u32, cur: u32, end: u32) -> Option<f32> {
+    if weights.is_empty() || end < start {
+        return None;
+    }
+    let mean = weights.values().sum::<f32>() / weights.len() as f32;
+    let (mut done, mut total) = (0.0f32, 0.0f32);
+    for page in start..=end {
+        let w = weights.get(&page).copied().unwrap_or(mean);
+        total += w;
+        if page <= cur {
+            done += w;
+        }
+    }
+    (total > 0.0).then(|| (done / total).min(1.0))
 }
 
-fn default_excel_meta_sheet() -> bool {
-    true
+/// 出力ディレクトリを作った直後に出る行の接頭辞（result_metadata.py `OUTPUT_DIR_MARKER`）
+const OUTPUT_DIR_LINE_PREFIX: &str = "@OUTPUT_DIR ";
+
+/// `@OUTPUT_DIR <絶対パス>` 行から結果ディレクトリ名（最後の要素）を取り出す。
+fn parse_output_dir_line(line: &str) -> Option<String> {
+    let path = line.trim_end().strip_prefix(OUTPUT_DIR_LINE_PREFIX)?.trim();
+    let name = std::path::Path::new(path).file_name()?.to_str()?;
+    validate_result_dir_name(name).ok()?;
+    Some(name.to_string())
 }
 
-fn load_settings_from_disk(project_root: &std::path::Path) -> Result<AppSettings, String> {
-    // Ensure configs directory exists
-    let config_dir = project_root.join("configs");
-    if !config_dir.exists() {
-        let _ = fs::create_dir_all(&config_dir);
+impl ResultMetadata {
+    /// `@RESULT` 行を読む。接頭辞が無い行や壊れた JSON は None（ジョブ自体は失敗させない）。
+    fn parse_line(line: &str) -> Option<Self> {
+        let json = line.trim_end().strip_prefix(RESULT_LINE_PREFIX)?;
+        serde_json::from_str(json).ok()
     }
 
-    let settings_path = config_dir.join("settings.json");
-    if settings_path.exists() {
-        let content = fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
-        let settings: AppSettings = serde_json::from_str(&content).map_err(|e| e.to_string())?;
-        Ok(settings)
-    } else {
-        // Return defaults
-        Ok(AppSettings {
-            formats: vec!["md".into()],
-            image_as_pdf: false,
-            enable_figure: true,
-            use_gpu: false,
-            output_root: None,
-            excel_meta_sheet: true,
-            chunk_size: Some(10),
-            enable_rest: false,
-            rest_seconds: Some(10),
-            pdf_dpi: Some(300),
-            window_width: Some(1200),
-            window_height: Some(760),
-        })
+    /// Fold another file's metadata into a batch total (counts add up, languages are unioned).
+    fn merge(&mut self, other: ResultMetadata) {
+        self.pages = self.pages.saturating_add(other.pages);
+        self.tables = self.tables.saturating_add(other.tables);
+        self.figures = self.figures.saturating_add(other.figures);
+        for lang in other.languages {
+            if !self.languages.contains(&lang) {
+                self.languages.push(lang);
+            }
+        }
+        self.font_warnings.extend(other.font_warnings);
+        self.rotated_pages.extend(other.rotated_pages);
     }
 }
 
-fn default_gpu_device() -> &'static str {
-    #[cfg(target_os = "macos")]
-    {
-        "mps"
-    }
-    #[cfg(not(target_os = "macos"))]
-    {
-        "cuda"
-    }
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum FileStatus {
+    Pending,
+    Running,
+    Done,
+    Error,
+    /// 停止要求やエラー中断、`skip_file` で処理しなかったファイル
+    Skipped,
+    /// 処理中に `skip_file` で止めたファイル（バッチは次のファイルへ進む）
+    Cancelled,
 }
 
-fn apply_window_settings(app: &tauri::AppHandle, project_root: &std::path::Path) {
-    let settings = load_settings_from_disk(project_root).ok();
-    let width = settings
-        .as_ref()
-        .and_then(|s| s.window_width)
-        .unwrap_or(1200)
-        .max(720);
-    let height = settings
-        .as_ref()
-        .and_then(|s| s.window_height)
-        .unwrap_or(760)
-        .max(540);
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileProgress {
+    path: String,
+    status: FileStatus,
+    outputs: Vec<String>,
+    error: Option<String>,
+}
 
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.set_min_size(Some(tauri::Size::Logical(tauri::LogicalSize::new(
-            720.0, 540.0,
-        ))));
-        let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(
-            width as f64,
-            height as f64,
-        )));
+impl FileProgress {
+    fn pending(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            status: FileStatus::Pending,
+            outputs: vec![],
+            error: None,
+        }
     }
 }
 
-#[tauri::command]
-fn run_job(
-    paths: Vec<String>,
-    options: Option<RunOptions>,
-    state: State<Arc<AppState>>,
-) -> Result<RunJobResponse, String> {
-    if paths.is_empty() {
-        return Err("no input files".into());
+/// 進捗はこの関数だけで動かす。ファイル開始時の基準値や再試行で小さい値が来ても
+/// バーが巻き戻らないよう、今より小さい値は無視する
+fn set_progress(job: &mut JobInfo, value: f32) {
+    if value.is_finite() {
+        job.progress = value.max(job.progress);
     }
+}
 
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
-    let dispatcher = resolve_python_entry(&project_root, "dispatcher.py");
-    if !dispatcher.exists() {
-        return Err(format!(
-            "dispatcher.py not found at {}",
-            dispatcher.display()
-        ));
+impl JobInfo {
+    fn new_running(paths: &[String]) -> Self {
+        let started_at_ms = now_ms();
+        Self {
+            status: JobStatus::Running,
+            progress: 0.0,
+            // 外部のログと突き合わせられるよう UTC の時刻を付ける
+            log: vec![LogEntry::app(format!(
+                "job started at {}",
+                format_utc_ms(started_at_ms)
+            ))],
+            outputs: vec![],
+            preview: None,
+            error: None,
+            current_message: None,
+            page_current: None,
+            page_total: None,
+            eta_seconds: None,
+            started_at_ms,
+            finished_at_ms: None,
+            stop_requested: false,
+            files: paths.iter().map(|p| FileProgress::pending(p)).collect(),
+            metadata: None,
+            output_dirs: vec![],
+            created_dirs: vec![],
+            tags: vec![],
+            batch_stats: BatchStats::default(),
+            skipped_up_to_date: 0,
+            finalizing: false,
+        }
     }
 
-    let python_bin = resolve_python_bin(&project_root);
+    /// 処理中のファイルの残り時間（`eta_seconds`）に、未着手のファイルの見込みを足したもの
+    fn batch_eta_seconds(&self) -> Option<u32> {
+        if !self.status.is_active() {
+            return None;
+        }
+        let pending = self
+            .files
+            .iter()
+            .filter(|f| f.status == FileStatus::Pending)
+            .count();
+        if pending == 0 {
+            return self.eta_seconds;
+        }
+        let per_file = self.batch_stats.pages_per_file(self.page_total)?;
+        let per_page = self.batch_stats.secs_per_page()?;
+        let rest = (per_file * pending as f32 * per_page).round() as u32;
+        Some(self.eta_seconds.unwrap_or(0).saturating_add(rest))
+    }
 
-    let job_id = Uuid::new_v4().to_string();
-    {
-        let mut jobs = state
-            .jobs
-            .lock()
-            .map_err(|e| format!("lock poisoned: {e}"))?;
-        jobs.insert(
-            job_id.clone(),
-            JobInfo {
-                status: JobStatus::Running,
-                progress: 0.0,
-                log: vec!["job started".into()],
-                outputs: vec![],
-                preview: None,
-                error: None,
-                current_message: None,
-                page_current: None,
-                page_total: None,
-                eta_seconds: None,
-            },
-        );
+    /// Set the final status once the per-file loop ran to the end (or was soft-stopped).
+    /// With `continue_on_error`, a batch is `Error` only if every processed file failed;
+    /// otherwise it is `Done` with the failure count in `error`.
+    fn finish_batch(&mut self) {
+        skip_pending_files(self);
+        // 最後のファイルの直後にキャンセルされた場合もキャンセル扱いのまま残す
+        if self.status == JobStatus::Cancelled {
+            return;
+        }
+        let failed = self
+            .files
+            .iter()
+            .filter(|f| f.status == FileStatus::Error)
+            .count();
+        let succeeded = self
+            .files
+            .iter()
+            .filter(|f| f.status == FileStatus::Done)
+            .count();
+        self.status = if failed > 0 && succeeded == 0 {
+            JobStatus::Error
+        } else {
+            JobStatus::Done
+        };
+        let total = self.files.len();
+        let up_to_date = self.skipped_up_to_date;
+        if failed > 0 {
+            let mut summary = format!("{failed}/{total} files failed");
+            if up_to_date > 0 {
+                summary.push_str(&format!(", {up_to_date} skipped (up to date)"));
+            }
+            self.log.push(LogEntry::new(
+                LogSource::App,
+                LogLevel::Warn,
+                summary.clone(),
+            ));
+            self.error = Some(summary);
+        } else if up_to_date > 0 {
+            let summary = format!("{up_to_date}/{total} files skipped (up to date)");
+            self.log.push(LogEntry::app(summary.clone()));
+            self.current_message = Some(summary);
+        }
     }
 
-    let state_arc: Arc<AppState> = state.inner().clone();
-    let dispatcher_path = dispatcher.clone();
-    let (
-        formats,
-        image_as_pdf,
-        enable_figure,
-        use_gpu,
-        mode,
-        chunk_size,
-        enable_rest,
-        rest_seconds,
-        pdf_dpi,
-        excel_mode,
-        excel_meta_sheet,
-        file_opts_map,
-    ) = match options {
-        Some(o) => (
-            o.formats,
-            o.image_as_pdf,
-            o.enable_figure,
-            o.use_gpu,
-            Some(o.mode),
-            o.chunk_size,
-            o.enable_rest,
-            o.rest_seconds,
-            o.pdf_dpi,
-            o.excel_mode,
-            o.excel_meta_sheet,
-            o.file_options,
-        ),
-        None => (
-            vec!["md".into()],
-            false,
-            true,
-            false,
-            None,
-            None,
-            false,
-            None,
-            None,
-            None,
-            None,
-            None,
-        ),
-    };
-    let python_bin_cloned = python_bin.clone();
-    let project_root_cloned = project_root.clone();
-    let paths_cloned = paths.clone();
-    let job_id_cloned = job_id.clone();
+    /// `skip_unchanged` で `idx` のファイルを処理済み扱いで飛ばす（既存の結果を `outputs` に載せる）
+    fn skip_up_to_date(&mut self, idx: usize, outputs: Vec<String>) {
+        let Some(f) = self.files.get_mut(idx) else {
+            return;
+        };
+        f.status = FileStatus::Skipped;
+        f.outputs = outputs;
+        let note = format!("{}: skipped (up to date)", f.path);
+        self.log.push(LogEntry::app(note));
+        self.skipped_up_to_date += 1;
+    }
 
-    thread::spawn(move || {
-        let mut outputs = Vec::new();
-        let paths_len = paths_cloned.len();
-        for (idx, p) in paths_cloned.iter().enumerate() {
-            let mut cmd = Command::new(&python_bin_cloned);
-            apply_python_env(&mut cmd);
-            // Force unbuffered output for Python
-            cmd.arg("-u");
-
-            cmd.arg(&dispatcher_path).arg(p);
-
-            // Global args
-            if !formats.is_empty() {
-                cmd.arg("--formats");
-                for fmt in &formats {
-                    cmd.arg(fmt);
-                }
-            }
-            if let Some(em) = &excel_mode {
-                if !em.is_empty() {
-                    cmd.arg("--excel-mode").arg(em);
-                }
-            }
-            if let Some(v) = excel_meta_sheet {
-                if v {
-                    cmd.arg("--excel-meta");
-                } else {
-                    cmd.arg("--no-excel-meta");
-                }
-            }
-            if image_as_pdf {
-                cmd.arg("--image-as-pdf");
-            }
-            if enable_figure {
-                cmd.arg("--figure");
-            } else {
-                cmd.arg("--no-figure");
+    /// `rename_result_dir` の後、結果ディレクトリ `old_name` を指していた記録を `new_name` に付け替える
+    fn rename_output_dir(&mut self, old_name: &str, new_name: &str) {
+        if !self.output_dirs.iter().any(|d| d == old_name) {
+            return;
+        }
+        // ディレクトリ名の付かない出力は、結果ディレクトリが 1 つのときしか持ち主が分からない
+        let single_dir = self.output_dirs.len() == 1;
+        for dir in self
+            .output_dirs
+            .iter_mut()
+            .chain(self.created_dirs.iter_mut())
+        {
+            if dir == old_name {
+                *dir = new_name.to_string();
             }
-            cmd.arg("--device")
-                .arg(if use_gpu { default_gpu_device() } else { "cpu" });
-            if let Some(m) = &mode {
-                cmd.arg("--mode").arg(m);
+        }
+        let figure_prefix = format!("{old_name}/");
+        let rename = |name: &mut String| {
+            let renamed = match name.strip_prefix(&figure_prefix) {
+                Some(rest) => Some(format!("{new_name}/{rest}")),
+                None if single_dir => renamed_output(name, old_name, new_name),
+                None => None,
+            };
+            if let Some(renamed) = renamed {
+                *name = renamed;
             }
+        };
+        self.outputs.iter_mut().for_each(&rename);
+        for f in self.files.iter_mut() {
+            f.outputs.iter_mut().for_each(&rename);
+        }
+    }
 
-            // File specific options (Crop) - dispatcher の通常引数として渡す
-            if let Some(opts_map) = &file_opts_map {
-                if let Some(f_opts) = opts_map.get(p) {
-                    if let Some(crop) = &f_opts.crop {
-                        cmd.arg("--crop").arg(format!(
-                            "{:.6},{:.6},{:.6},{:.6}",
-                            crop.left, crop.top, crop.width, crop.height
-                        ));
-                    }
-                }
+    /// Mark a running job as cancelled: unfinished files become `Skipped`.
+    fn cancel(&mut self) {
+        self.status = JobStatus::Cancelled;
+        self.stop_requested = true;
+        for f in self.files.iter_mut() {
+            if matches!(f.status, FileStatus::Pending | FileStatus::Running) {
+                f.status = FileStatus::Skipped;
             }
+        }
+        self.eta_seconds = None;
+        self.current_message = Some("キャンセルしました".into());
+        self.log.push(LogEntry::app("job cancelled"));
+    }
 
-            // Extra args (passed to ocr_chanked.py via --)
-            // Collect all extra args first
-            let mut extra_args = Vec::new();
-
-            // Stability settings
-            if let Some(cs) = chunk_size {
-                extra_args.push(format!("--chunk-size"));
-                extra_args.push(cs.to_string());
-            }
-            if let Some(dpi) = pdf_dpi {
-                extra_args.push("--dpi".into());
-                extra_args.push(dpi.to_string());
-            }
-            if enable_rest {
-                extra_args.push("--enable-rest".into());
-            }
-            if let Some(rs) = rest_seconds {
-                if enable_rest {
-                    extra_args.push(format!("--rest-seconds"));
-                    extra_args.push(rs.to_string());
-                }
-            }
+    /// バッチから 1 ファイルだけ外す。未着手なら `Skipped` にして飛ばし、処理中なら `Cancelled` にする。
+    /// 処理中だった（呼び出し側で dispatcher を止める必要がある）ときは true
+    fn skip_file(&mut self, path: &str) -> Result<bool, String> {
+        let file = self
+            .files
+            .iter_mut()
+            .find(|f| {
+                f.path == path && matches!(f.status, FileStatus::Pending | FileStatus::Running)
+            })
+            .ok_or_else(|| format!("no pending or running file in this job: {path}"))?;
+        let running = file.status == FileStatus::Running;
+        file.status = if running {
+            FileStatus::Cancelled
+        } else {
+            FileStatus::Skipped
+        };
+        self.log.push(LogEntry::app(if running {
+            format!("{path}: skipped while running")
+        } else {
+            format!("{path}: removed from the queue")
+        }));
+        if running {
+            self.eta_seconds = None;
+        }
+        Ok(running)
+    }
 
-            // File specific options (Page range)
-            if let Some(opts_map) = &file_opts_map {
-                if let Some(f_opts) = opts_map.get(p) {
-                    // Match by full path string
-                    if let Some(s) = f_opts.start {
-                        extra_args.push("--start".into());
-                        extra_args.push(s.to_string());
-                    }
-                    if let Some(e) = f_opts.end {
-                        extra_args.push("--end".into());
-                        extra_args.push(e.to_string());
-                    }
-                }
-            }
+    /// 未着手（`Pending`）のファイルだけを `new_order` の順に並べ替える。処理済み・処理中のファイルの
+    /// 位置は変えない。`new_order` は未着手のファイルの並べ替え（過不足・重複なし）でなければならない
+    fn reorder_files(&mut self, new_order: &[String]) -> Result<(), String> {
+        let slots: Vec<usize> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.status == FileStatus::Pending)
+            .map(|(i, _)| i)
+            .collect();
+        let mut remaining: Vec<&str> = slots.iter().map(|&i| self.files[i].path.as_str()).collect();
+        let mut requested: Vec<&str> = new_order.iter().map(String::as_str).collect();
+        remaining.sort_unstable();
+        requested.sort_unstable();
+        if remaining != requested {
+            return Err(format!(
+                "newOrder must list the {} pending files of this job exactly once",
+                slots.len()
+            ));
+        }
+        for (&slot, path) in slots.iter().zip(new_order) {
+            self.files[slot] = FileProgress::pending(path);
+        }
+        self.log.push(LogEntry::app(format!(
+            "pending files reordered: {}",
+            new_order.join(", ")
+        )));
+        Ok(())
+    }
+}
 
-            if !extra_args.is_empty() {
-                cmd.arg("--");
-                for arg in extra_args {
-                    cmd.arg(arg);
-                }
-            }
+/// stdout/stderr のリーダースレッドから集約スレッドへ送る更新
+enum JobUpdate {
+    Log(LogEntry),
+    Progress(ProgressPatch),
+}
 
-            cmd.current_dir(&project_root_cloned);
+/// 1 行分（または複数行をまとめた）進捗の差分。`None` のフィールドは変更しない。
+#[derive(Debug, Default, PartialEq)]
+struct ProgressPatch {
+    progress: Option<f32>,
+    current_message: Option<String>,
+    page_current: Option<u32>,
+    page_total: Option<u32>,
+    /// `Some(None)` は ETA のクリア
+    eta_seconds: Option<Option<u32>>,
+    metadata: Option<ResultMetadata>,
+    /// 新しく作られた結果ディレクトリ名（バッチでは複数届くので積み上げる）
+    output_dirs: Vec<String>,
+    /// `output_dirs` のうち dispatcher の起動前には無かったもの
+    created_dirs: Vec<String>,
+}
 
-            // Pipe output to read in real-time
-            cmd.stdout(std::process::Stdio::piped());
-            cmd.stderr(std::process::Stdio::piped());
+impl ProgressPatch {
+    fn is_empty(&self) -> bool {
+        *self == ProgressPatch::default()
+    }
 
-            let log_line = format!("spawn: {:?}", cmd);
-            if let Ok(mut jobs) = state_arc.jobs.lock() {
-                if let Some(job) = jobs.get_mut(&job_id_cloned) {
-                    job.log.push(log_line.clone());
-                    // Start of this file processing
-                    let base_progress = (idx as f32) / paths_len as f32 * 100.0;
-                    job.progress = base_progress.min(99.0);
-                }
+    /// Fold a newer patch into this one (latest value wins; progress never goes backwards).
+    fn merge(&mut self, newer: ProgressPatch) {
+        if let Some(p) = newer.progress {
+            self.progress = Some(self.progress.map_or(p, |cur| cur.max(p)));
+        }
+        if newer.current_message.is_some() {
+            self.current_message = newer.current_message;
+        }
+        if newer.page_current.is_some() {
+            self.page_current = newer.page_current;
+        }
+        if newer.page_total.is_some() {
+            self.page_total = newer.page_total;
+        }
+        if newer.eta_seconds.is_some() {
+            self.eta_seconds = newer.eta_seconds;
+        }
+        if let Some(meta) = newer.metadata {
+            match &mut self.metadata {
+                Some(cur) => cur.merge(meta),
+                None => self.metadata = Some(meta),
             }
+        }
+        self.output_dirs.extend(newer.output_dirs);
+        self.created_dirs.extend(newer.created_dirs);
+    }
 
-            match cmd.spawn() {
-                Ok(mut child) => {
-                    let stdout = child.stdout.take().expect("failed to get stdout");
-                    let stderr = child.stderr.take().expect("failed to get stderr");
-
-                    // Clone state for threads
-                    let state_out = state_arc.clone();
-                    let job_id_out = job_id_cloned.clone();
-
-                    // Stdout reader thread
-                    let stdout_handle = thread::spawn(move || {
-                        use std::collections::VecDeque;
-                        use std::io::{BufRead, BufReader};
-                        let reader = BufReader::new(stdout);
-                        let mut range_start: Option<u32> = None;
-                        let mut range_end: Option<u32> = None;
-                        let mut page_started_at: Option<Instant> = None;
-                        let mut recent_secs: VecDeque<f32> = VecDeque::new();
-                        const ETA_WINDOW: usize = 5;
-
-                        let parse_range = |line: &str| -> Option<(u32, u32)> {
-                            let prefix = "処理範囲:";
-                            let rest = line.strip_prefix(prefix)?.trim();
-                            let mut parts = rest.split('〜');
-                            let start = parts.next()?.trim().parse::<u32>().ok()?;
-                            let end = parts.next()?.trim().parse::<u32>().ok()?;
-                            Some((start, end))
-                        };
-
-                        let parse_page_marker = |line: &str, marker: &str| -> Option<(u32, u32)> {
-                            // e.g. "--- Page 3/9 (abs 3/12) ---" / "--- Done 3/9 ---"
-                            let start = format!("--- {marker} ");
-                            let rest = line.strip_prefix(&start)?;
-                            let head = rest.split_whitespace().next()?; // "3/9"
-                            let mut parts = head.split('/');
-                            let cur = parts.next()?.parse::<u32>().ok()?;
-                            let total = parts.next()?.parse::<u32>().ok()?;
-                            Some((cur, total))
-                        };
-
-                        for line in reader.lines() {
-                            if let Ok(l) = line {
-                                if let Ok(mut jobs) = state_out.jobs.lock() {
-                                    if let Some(job) = jobs.get_mut(&job_id_out) {
-                                        job.log.push(l.clone());
-
-                                        let file_start = (idx as f32) / paths_len as f32 * 100.0;
-                                        let file_end =
-                                            ((idx as f32) + 1.0) / paths_len as f32 * 100.0;
-                                        let file_span = (file_end - file_start).max(1.0);
-
-                                        if let Some((s, e)) = parse_range(&l) {
-                                            range_start = Some(s);
-                                            range_end = Some(e);
-                                            let total = e.saturating_sub(s).saturating_add(1);
-                                            job.page_total = Some(total);
-                                            job.eta_seconds = None;
-                                        }
-
-                                        if let Some((cur, total_in_run)) =
-                                            parse_page_marker(&l, "Page")
-                                        {
-                                            job.page_current = Some(cur);
-                                            job.page_total = Some(total_in_run);
-                                            job.current_message = Some(format!(
-                                                "PDF変換中: {cur}/{total_in_run}ページ"
-                                            ));
-                                            job.eta_seconds = None;
-                                            page_started_at = Some(Instant::now());
-                                        }
-
-                                        if let Some((cur, total_in_run)) =
-                                            parse_page_marker(&l, "Done")
-                                        {
-                                            if let Some(started) = page_started_at.take() {
-                                                let secs = started.elapsed().as_secs_f32();
-                                                if secs.is_finite() && secs > 0.0 {
-                                                    recent_secs.push_back(secs);
-                                                    while recent_secs.len() > ETA_WINDOW {
-                                                        recent_secs.pop_front();
-                                                    }
-                                                }
-                                            }
-
-                                            job.page_current = Some(cur);
-                                            job.page_total = Some(total_in_run);
-
-                                            let (start_page, end_page) =
-                                                match (range_start, range_end) {
-                                                    (Some(s), Some(e)) => (s, e),
-                                                    _ => (1, total_in_run),
-                                                };
-                                            let total_pages = end_page
-                                                .saturating_sub(start_page)
-                                                .saturating_add(1)
-                                                .max(1);
-                                            let done_pages = cur
-                                                .saturating_sub(start_page)
-                                                .saturating_add(1)
-                                                .min(total_pages);
-                                            let remaining_pages = end_page.saturating_sub(cur);
-
-                                            let ocr_ratio = done_pages as f32 / total_pages as f32;
-                                            let target_progress =
-                                                file_start + file_span * (0.90 * ocr_ratio);
-                                            if target_progress.is_finite()
-                                                && target_progress > job.progress
-                                            {
-                                                job.progress = target_progress.min(99.0);
-                                            }
-
-                                            if !recent_secs.is_empty() && remaining_pages > 0 {
-                                                let avg = recent_secs.iter().copied().sum::<f32>()
-                                                    / recent_secs.len() as f32;
-                                                if avg.is_finite() && avg > 0.0 {
-                                                    job.eta_seconds = Some(
-                                                        (avg * remaining_pages as f32).round()
-                                                            as u32,
-                                                    );
-                                                }
-                                            } else {
-                                                job.eta_seconds = None;
-                                            }
+    fn apply(self, job: &mut JobInfo) {
+        // キャンセル直前に作られたディレクトリも後で消せるよう、状態に関係なく記録する
+        for dir in &self.output_dirs {
+            if !job.output_dirs.contains(dir) {
+                job.output_dirs.push(dir.clone());
+            }
+        }
+        for dir in &self.created_dirs {
+            if !job.created_dirs.contains(dir) {
+                job.created_dirs.push(dir.clone());
+            }
+        }
+        // キャンセル後に届いた出力で表示を巻き戻さない
+        if job.status == JobStatus::Cancelled {
+            return;
+        }
+        if let Some(p) = self.progress {
+            set_progress(job, p.min(99.0));
+        }
+        if let Some(msg) = self.current_message {
+            job.current_message = Some(if job.stop_requested {
+                format!("{msg}（{STOP_PENDING_SUFFIX}）")
+            } else {
+                msg
+            });
+        }
+        if self.page_current.is_some() {
+            job.page_current = self.page_current;
+        }
+        if self.page_total.is_some() {
+            job.page_total = self.page_total;
+        }
+        if let Some(eta) = self.eta_seconds {
+            job.eta_seconds = eta;
+        }
+        if let Some(meta) = self.metadata {
+            // 処理は成功しているので失敗にはせず、ログに警告として残す
+            for warning in &meta.font_warnings {
+                job.log.push(LogEntry::new(
+                    LogSource::App,
+                    LogLevel::Warn,
+                    warning.clone(),
+                ));
+            }
+            // バッチでは入力ファイルごとに届くので合算する
+            match &mut job.metadata {
+                Some(cur) => cur.merge(meta),
+                None => job.metadata = Some(meta),
+            }
+        }
+    }
+}
 
-                                            job.current_message = Some(format!(
-                                                "PDF変換中: {cur}/{total_in_run}ページ"
-                                            ));
-                                        }
+/// 停止予約中に `current_message` へ添える文言
+const STOP_PENDING_SUFFIX: &str = "このファイルの処理後に停止";
 
-                                        if l.contains("--- merged_md.py を実行 ---") {
-                                            job.current_message =
-                                                Some("後処理: Markdown結合中".into());
-                                            job.eta_seconds = None;
-                                            let target = file_start + file_span * 0.92;
-                                            if target > job.progress {
-                                                job.progress = target.min(99.0);
-                                            }
-                                        }
-                                        if l.contains("[dispatcher] Converting to docx") {
-                                            job.current_message = Some("後処理: Word変換中".into());
-                                            job.eta_seconds = None;
-                                            let target = file_start + file_span * 0.96;
-                                            if target > job.progress {
-                                                job.progress = target.min(99.0);
-                                            }
-                                        }
-                                        if l.contains("[dispatcher] processing excel_via=json") {
-                                            job.current_message =
-                                                Some("後処理: Excel変換中".into());
-                                            job.eta_seconds = None;
-                                            let target = file_start + file_span * 0.99;
-                                            if target > job.progress {
-                                                job.progress = target.min(99.0);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    });
+/// 集約スレッドが `JobInfo` に書き込む頻度（UI のポーリングとのロック競合を抑える）。
+/// settings.json の `progressUpdateHz` で上書きでき、範囲外の値は丸める。
+const DEFAULT_PROGRESS_UPDATE_HZ: u32 = 10;
+const PROGRESS_UPDATE_HZ_RANGE: std::ops::RangeInclusive<u32> = 1..=30;
 
-                    // Stderr reader thread
-                    let state_err = state_arc.clone();
-                    let job_id_err = job_id_cloned.clone();
-                    let stderr_handle = thread::spawn(move || {
-                        use std::io::{BufRead, BufReader};
-                        let reader = BufReader::new(stderr);
-                        for line in reader.lines() {
-                            if let Ok(l) = line {
-                                if let Ok(mut jobs) = state_err.jobs.lock() {
-                                    if let Some(job) = jobs.get_mut(&job_id_err) {
-                                        job.log.push(format!("[err] {}", l));
-                                    }
-                                }
-                            }
-                        }
-                    });
+fn progress_flush_interval(hz: Option<u32>) -> Duration {
+    let hz = hz.unwrap_or(DEFAULT_PROGRESS_UPDATE_HZ).clamp(
+        *PROGRESS_UPDATE_HZ_RANGE.start(),
+        *PROGRESS_UPDATE_HZ_RANGE.end(),
+    );
+    Duration::from_millis(1000 / u64::from(hz))
+}
 
-                    // Wait for finish
-                    let status = child.wait();
-                    stdout_handle.join().unwrap_or(());
-                    stderr_handle.join().unwrap_or(());
-
-                    match status {
-                        Ok(s) if s.success() => {
-                            if let Ok(mut jobs) = state_arc.jobs.lock() {
-                                if let Some(job) = jobs.get_mut(&job_id_cloned) {
-                                    job.progress =
-                                        ((idx as f32 + 1.0) / paths_len as f32 * 100.0).min(100.0);
-                                }
-                            }
-                            outputs.push(p.clone());
-                        }
-                        Ok(_) => {
-                            if let Ok(mut jobs) = state_arc.jobs.lock() {
-                                if let Some(job) = jobs.get_mut(&job_id_cloned) {
-                                    job.status = JobStatus::Error;
-                                    job.error =
-                                        Some("dispatcher failed (non-zero exit code)".into());
-                                }
-                            }
-                            return;
-                        }
-                        Err(e) => {
-                            if let Ok(mut jobs) = state_arc.jobs.lock() {
-                                if let Some(job) = jobs.get_mut(&job_id_cloned) {
-                                    job.status = JobStatus::Error;
-                                    job.error = Some(format!("failed to spawn python: {e}"));
-                                }
-                            }
-                            return;
-                        }
+/// Collect reader updates and apply them to the job at most once per `flush_interval`.
+/// Returns once every sender is dropped, after a final flush.
+fn spawn_job_aggregator(
+    state: Arc<AppState>,
+    job_id: String,
+    rx: mpsc::Receiver<JobUpdate>,
+    flush_interval: Duration,
+    log_file: Option<JobLogFile>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut logs: Vec<LogEntry> = Vec::new();
+        let mut patch = ProgressPatch::default();
+        let mut last_flush = Instant::now();
+        loop {
+            let wait = flush_interval.saturating_sub(last_flush.elapsed());
+            let disconnected = match rx.recv_timeout(wait) {
+                Ok(JobUpdate::Log(entry)) => {
+                    logs.push(entry);
+                    false
+                }
+                Ok(JobUpdate::Progress(p)) => {
+                    patch.merge(p);
+                    false
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => false,
+                Err(mpsc::RecvTimeoutError::Disconnected) => true,
+            };
+
+            if disconnected || last_flush.elapsed() >= flush_interval {
+                let mut batch = std::mem::take(&mut logs);
+                let pending = std::mem::take(&mut patch);
+                if let Some(file) = &log_file {
+                    if let Err(e) = file.append(&batch) {
+                        log::warn!("failed to write job log {}: {e}", file.path.display());
                     }
                 }
-                Err(e) => {
-                    if let Ok(mut jobs) = state_arc.jobs.lock() {
-                        if let Some(job) = jobs.get_mut(&job_id_cloned) {
-                            job.status = JobStatus::Error;
-                            job.error = Some(format!("failed to spawn python: {e}"));
+                if !batch.is_empty() || !pending.is_empty() {
+                    if let Ok(mut jobs) = state.jobs.write() {
+                        if let Some(job) = jobs.get_mut(&job_id) {
+                            job.log.append(&mut batch);
+                            pending.apply(job);
                         }
                     }
-                    return;
                 }
+                last_flush = Instant::now();
+            }
+            if disconnected {
+                break;
             }
         }
+    })
+}
 
-        // set done
-        if let Ok(mut jobs) = state_arc.jobs.lock() {
-            if let Some(job) = jobs.get_mut(&job_id_cloned) {
-                job.status = JobStatus::Done;
-                job.progress = 100.0;
-                let output_files =
-                    collect_output_files(&project_root_cloned, &paths_cloned, &formats);
-                job.outputs = output_files
-                    .iter()
-                    .map(|p| {
-                        p.file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string()
-                    })
-                    .collect();
+/// `logToFile` 有効時のジョブごとのログファイル（`logs/<job_id>.log`）。
+/// 書き込むのは集約スレッドだけ（stdout/stderr のリーダーはチャネル経由）なので、
+/// ローテーションと追記が別スレッドと競合することはない。
+struct JobLogFile {
+    path: PathBuf,
+    max_bytes: u64,
+}
 
-                // Markdownプレビュー: 最初に見つかった md を読む
-                if let Some(md_path) = output_files
-                    .iter()
-                    .find(|p| p.extension().map(|e| e == "md").unwrap_or(false))
-                {
-                    if let Ok(content) = fs::read_to_string(md_path) {
-                        job.preview = Some(content);
-                    } else {
-                        job.preview = Some(format!(
-                            "failed to read markdown preview: {}",
-                            md_path.display()
-                        ));
-                    }
-                } else {
-                    job.preview = Some(format!(
-                        "Converted markdown for: {} (md preview not found)",
-                        outputs.join(", ")
-                    ));
-                }
-            }
+/// `maxLogFileBytes` 未指定時のローテーション閾値
+const DEFAULT_MAX_LOG_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+impl JobLogFile {
+    fn new(project_root: &std::path::Path, job_id: &str, max_bytes: Option<u64>) -> Self {
+        Self {
+            path: project_root.join("logs").join(format!("{job_id}.log")),
+            max_bytes: max_bytes.unwrap_or(DEFAULT_MAX_LOG_FILE_BYTES),
         }
-    });
+    }
 
-    Ok(RunJobResponse { job_id })
+    /// Append entries, first moving the file to `<name>.1` (replacing an older one)
+    /// if the write would take it past `max_bytes`.
+    fn append(&self, entries: &[LogEntry]) -> std::io::Result<()> {
+        use std::io::Write;
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let text: String = entries.iter().map(format_log_line).collect();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let current = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if current > 0 && current + text.len() as u64 > self.max_bytes {
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            fs::rename(&self.path, rotated)?;
+        }
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(text.as_bytes())
+    }
 }
 
-#[tauri::command]
-fn render_preview(
-    path: String,
-    page: Option<u32>,
-    crop: Option<CropRect>,
-    max_long_edge: Option<u32>,
-) -> Result<PreviewResponse, String> {
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
-    let python_bin = resolve_python_bin(&project_root);
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
 
-    let helper = resolve_python_entry(&project_root, "ui_preview.py");
-    if !helper.exists() {
-        return Err(format!("ui_preview.py not found at {}", helper.display()));
-    }
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum LogSource {
+    /// アプリ自身が出すログ（job started / spawn など）
+    App,
+    Stdout,
+    Stderr,
+}
 
-    let mut cmd = Command::new(&python_bin);
-    apply_python_env(&mut cmd);
-    cmd.arg("-u")
-        .arg(helper)
-        .arg("--input")
-        .arg(&path)
-        .arg("--page")
-        .arg(page.unwrap_or(1).to_string());
+/// `JobInfo.log` の 1 行。`to_string()` で従来の文字列形式（stderr は `[err] ` 付き）になる。
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogEntry {
+    ts_ms: u64,
+    level: LogLevel,
+    source: LogSource,
+    text: String,
+}
 
-    if let Some(c) = crop {
-        cmd.arg("--crop").arg(format!(
-            "{:.6},{:.6},{:.6},{:.6}",
-            c.left, c.top, c.width, c.height
-        ));
+impl LogEntry {
+    fn new(source: LogSource, level: LogLevel, text: impl Into<String>) -> Self {
+        Self {
+            ts_ms: now_ms(),
+            level,
+            source,
+            text: text.into(),
+        }
     }
-    if let Some(max_le) = max_long_edge {
-        cmd.arg("--max-long-edge").arg(max_le.to_string());
+
+    fn app(text: impl Into<String>) -> Self {
+        Self::new(LogSource::App, LogLevel::Info, text)
     }
 
-    cmd.current_dir(&project_root);
+    fn stdout(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let level = infer_stdout_level(&text);
+        Self::new(LogSource::Stdout, level, text)
+    }
 
-    let output = cmd
-        .output()
-        .map_err(|e| format!("failed to run preview helper: {e}"))?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        return Err(format!("preview helper failed: {}", stderr.trim()));
+    fn stderr(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let level = infer_stderr_level(&text);
+        Self::new(LogSource::Stderr, level, text)
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    serde_json::from_str::<PreviewResponse>(&stdout)
-        .map_err(|e| format!("failed to parse preview helper output: {e}"))
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source {
+            LogSource::Stderr => write!(f, "[err] {}", self.text),
+            _ => f.write_str(&self.text),
+        }
+    }
 }
 
-/// Resolve python entry script path with priority:
-/// 1) project_root/resources/py/<filename>
-/// 2) project_root/<filename> (legacy)
-fn resolve_python_entry(project_root: &std::path::Path, filename: &str) -> PathBuf {
-    let res = project_root.join("resources").join("py").join(filename);
-    if res.exists() {
-        return res;
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// 進捗マーカー（ocr_chanked / dispatcher が出す定型行）は info、それ以外の stdout は debug
+fn infer_stdout_level(line: &str) -> LogLevel {
+    const MARKERS: &[&str] = &[
+        "--- Page ",
+        "--- Done ",
+        "--- merged_md.py",
+        "=== Chunk",
+        "処理範囲:",
+        "[dispatcher]",
+    ];
+    let trimmed = line.trim_start();
+    if trimmed.contains("エラー") || trimmed.starts_with("Traceback") {
+        return LogLevel::Error;
+    }
+    if MARKERS.iter().any(|m| trimmed.starts_with(m)) {
+        LogLevel::Info
+    } else {
+        LogLevel::Debug
     }
-    project_root.join(filename)
 }
 
-/// Resolve python binary path with priority:
-/// 1) env PYTHON_BIN
-/// 2) project_root/resources/python/python(.exe) (portable runtime)
-/// 3) project_root/resources/.venv/(Scripts|bin)/python(.exe) (legacy)
-/// 4) project_root/.venv/(Scripts|bin)/python(.exe)
-/// 5) "python"
-fn resolve_python_bin(project_root: &std::path::Path) -> String {
-    if let Ok(bin) = std::env::var("PYTHON_BIN") {
-        if !bin.is_empty() {
-            return bin;
-        }
+/// stderr は Python の警告/進捗バーも流れてくるので基本は warn、例外らしき行だけ error
+fn infer_stderr_level(line: &str) -> LogLevel {
+    let trimmed = line.trim_start();
+    let looks_fatal = trimmed.starts_with("Traceback")
+        || trimmed.contains("Error:")
+        || trimmed.contains("Exception:")
+        || trimmed.contains("エラー");
+    if looks_fatal {
+        LogLevel::Error
+    } else {
+        LogLevel::Warn
     }
+}
 
-    // resources/python (portable runtime)
-    #[cfg(target_os = "windows")]
-    let res_python = project_root
-        .join("resources")
-        .join("python")
-        .join("python.exe");
-    #[cfg(not(target_os = "windows"))]
-    let res_python = project_root
-        .join("resources")
-        .join("python")
-        .join("bin")
-        .join("python");
-    if res_python.exists() {
-        return res_python.to_string_lossy().to_string();
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum JobStatus {
+    Idle,
+    Running,
+    Done,
+    Error,
+    /// `cancel_all_jobs` で打ち切ったジョブ（実行中の dispatcher は kill 済み）
+    Cancelled,
+    /// `pause_job` で OS プロセスごと一時停止中（チェックポイントではないので、アプリ終了で失われる）
+    Paused,
+}
+
+impl JobStatus {
+    /// 実行中または一時停止中（まだ終わっていない）
+    fn is_active(&self) -> bool {
+        matches!(self, JobStatus::Running | JobStatus::Paused)
     }
+}
 
-    // resources/.venv (配布用に同梱する場合)
-    #[cfg(target_os = "windows")]
-    let res_venv = project_root
-        .join("resources")
-        .join(".venv")
-        .join("Scripts")
-        .join("python.exe");
-    #[cfg(not(target_os = "windows"))]
-    let res_venv = project_root
-        .join("resources")
-        .join(".venv")
-        .join("bin")
-        .join("python");
-    if res_venv.exists() {
-        return res_venv.to_string_lossy().to_string();
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RunOptions {
+    #[serde(default)]
+    formats: Vec<String>,
+    #[serde(default)]
+    image_as_pdf: bool,
+    #[serde(default)]
+    enable_figure: bool,
+    #[serde(default)]
+    use_gpu: bool,
+    #[serde(default, deserialize_with = "deserialize_option_value")]
+    mode: Option<Mode>,
+    #[serde(default)]
+    chunk_size: Option<u32>,
+    #[serde(default)]
+    enable_rest: bool,
+    #[serde(default)]
+    rest_seconds: Option<u32>,
+    #[serde(default)]
+    pdf_dpi: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_option_value")]
+    excel_mode: Option<ExcelMode>,
+    #[serde(default)]
+    excel_meta_sheet: Option<bool>,
+    #[serde(default)]
+    file_options: Option<HashMap<String, FileSpecificOptions>>,
+    /// UI 未対応の dispatcher.py 引数（そのまま通常引数の末尾に付ける）
+    #[serde(default)]
+    extra_dispatcher_args: Vec<String>,
+    /// UI 未対応の ocr_chanked.py 引数（`--` の後ろに付ける）
+    #[serde(default)]
+    extra_ocr_args: Vec<String>,
+    /// true なら失敗したファイルを Error にしてバッチを続ける（既定はジョブごと中断）
+    #[serde(default)]
+    continue_on_error: bool,
+    /// OCR 前に時計回りに回転する角度（0/90/180/270）
+    #[serde(default)]
+    rotate: Option<i32>,
+    /// OCR 前に小さな傾きを自動補正する
+    #[serde(default)]
+    auto_deskew: bool,
+    /// 横倒しのページを見つけてページごとに回す（`rotate` の後・傾き補正の前。回したページは
+    /// `ResultMetadata.rotated_pages` に出る）
+    #[serde(default)]
+    auto_orient: bool,
+    /// true なら同じ入力を同じ出力設定で処理し終えていて、入力が変わっていない（run.json の入力の
+    /// ハッシュが同じ、ハッシュの無い古い結果では結果の方が新しい）ファイルを処理せずに飛ばす
+    #[serde(default)]
+    skip_unchanged: bool,
+    /// パスワード保護された PDF のパスワード（settings.json の監視設定には保存しない）
+    #[serde(default, skip_serializing)]
+    password: Option<String>,
+    /// OCR 言語（`list_supported_languages` のコード）。空なら従来どおり自動（日英）
+    #[serde(default)]
+    languages: Vec<String>,
+    /// true なら `estimate_disk_needed` の見積もりが空き容量を超えるときに開始しない
+    #[serde(default)]
+    require_free_space: bool,
+    /// モデル取得のタイムアウトなど一時的な失敗のとき、同じファイルを再実行する回数
+    #[serde(default)]
+    max_retries: u32,
+    /// 結合 Markdown のページ区切り。None なら従来どおり `# Page N` 見出し
+    #[serde(default, deserialize_with = "deserialize_option_value")]
+    merge_separator: Option<MergeSeparator>,
+    /// true なら各ファイルの成功後に `cleanup_intermediates` と同じ中間生成物を消す
+    #[serde(default)]
+    auto_cleanup: bool,
+    /// csv 出力の区切り文字（1 文字）。None なら `,`
+    #[serde(default)]
+    csv_delimiter: Option<String>,
+    /// csv 出力の文字コード。None なら UTF-8（BOM なし）
+    #[serde(default, deserialize_with = "deserialize_option_value")]
+    csv_encoding: Option<CsvEncoding>,
+    /// true なら settings.json の `maxPages` を超えるジョブも実行する
+    #[serde(default)]
+    confirm_large: bool,
+    /// `figures/` に保存する図版の形式。None なら dispatcher の既定（png）
+    #[serde(default, deserialize_with = "deserialize_option_value")]
+    figure_format: Option<FigureFormat>,
+}
+
+/// dispatcher.py の選択肢を取る引数に対応する `RunOptions` の値
+trait OptionValue: Copy + 'static {
+    /// エラーメッセージと `describe_run_options` に出すフィールド名
+    const FIELD: &'static str;
+    const ALL: &'static [Self];
+
+    fn as_str(self) -> &'static str;
+
+    /// Parse a value from the frontend. An empty string means "dispatcher default"
+    /// (the flag is not passed and the dispatcher default applies).
+    fn parse_optional(value: &str) -> Result<Option<Self>, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|v| v.as_str() == value)
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "unknown {}: {value} (valid: {})",
+                    Self::FIELD,
+                    allowed_option_values::<Self>().join(", ")
+                )
+            })
     }
+}
 
-    #[cfg(target_os = "windows")]
-    let venv = project_root
-        .join(".venv")
-        .join("Scripts")
-        .join("python.exe");
-    #[cfg(not(target_os = "windows"))]
-    let venv = project_root.join(".venv").join("bin").join("python");
-    if venv.exists() {
-        return venv.to_string_lossy().to_string();
+fn allowed_option_values<T: OptionValue>() -> Vec<&'static str> {
+    T::ALL.iter().map(|v| v.as_str()).collect()
+}
+
+/// `#[serde(deserialize_with)]` 用。null と空文字は None（dispatcher の既定）
+fn deserialize_option_value<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: OptionValue,
+{
+    let raw: Option<String> = Option::deserialize(deserializer)?;
+    T::parse_optional(raw.as_deref().unwrap_or("")).map_err(serde::de::Error::custom)
+}
+
+/// dispatcher.py `--mode` が受け付ける値
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    Lite,
+    Full,
+}
+
+impl OptionValue for Mode {
+    const FIELD: &'static str = "mode";
+    const ALL: &'static [Mode] = &[Mode::Lite, Mode::Full];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Mode::Lite => "lite",
+            Mode::Full => "full",
+        }
     }
-    "python".into()
 }
 
-#[tauri::command]
-fn get_progress(job_id: String, state: State<Arc<AppState>>) -> Result<ProgressResponse, String> {
-    let jobs = state
-        .jobs
-        .lock()
-        .map_err(|e| format!("lock poisoned: {e}"))?;
-    if let Some(job) = jobs.get(&job_id) {
-        return Ok(ProgressResponse {
-            status: job.status.clone(),
-            progress: job.progress,
-            log: job.log.clone(),
-            error: job.error.clone(),
-            current_message: job.current_message.clone(),
-            page_current: job.page_current,
-            page_total: job.page_total,
-            eta_seconds: job.eta_seconds,
-        });
+/// dispatcher.py `--excel-mode` が受け付ける値（xlsx/csv の表出力）
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ExcelMode {
+    /// レイアウト優先（結合セルをそのまま再現）
+    Layout,
+    /// 結合を解除して 1 セル 1 値のテーブルにする
+    Table,
+}
+
+impl OptionValue for ExcelMode {
+    const FIELD: &'static str = "excelMode";
+    const ALL: &'static [ExcelMode] = &[ExcelMode::Layout, ExcelMode::Table];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ExcelMode::Layout => "layout",
+            ExcelMode::Table => "table",
+        }
     }
-    Err("job not found".into())
 }
 
-/// 入力パスに応じて出力候補を探す
-fn collect_output_files(
-    project_root: &std::path::Path,
-    inputs: &[String],
-    formats: &[String],
-) -> Vec<PathBuf> {
-    fn push_unique(found: &mut Vec<PathBuf>, path: PathBuf) {
-        if path.exists() && !found.contains(&path) {
-            found.push(path);
+/// dispatcher.py `--merge-sep`（結合 Markdown のページ区切り）が受け付ける値
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum MergeSeparator {
+    /// 水平線（`---`）
+    Hr,
+    /// `## Page N` 見出し
+    Heading,
+    /// 区切りなし（空行のみ）
+    None,
+    /// 改ページ（`\newpage`。export_docx.py は Word の改ページ `w:br w:type="page"` にする）
+    Page,
+}
+
+impl OptionValue for MergeSeparator {
+    const FIELD: &'static str = "mergeSeparator";
+    const ALL: &'static [MergeSeparator] = &[
+        MergeSeparator::Hr,
+        MergeSeparator::Heading,
+        MergeSeparator::None,
+        MergeSeparator::Page,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            MergeSeparator::Hr => "hr",
+            MergeSeparator::Heading => "heading",
+            MergeSeparator::None => "none",
+            MergeSeparator::Page => "page",
         }
     }
+}
 
-    fn pick_latest_result_dir(result_root: &std::path::Path, stem: &str) -> Option<PathBuf> {
-        if !result_root.exists() {
-            return None;
+/// dispatcher.py `--csv-encoding` が受け付ける値
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+enum CsvEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// BOM 付き UTF-8（Excel でそのまま開ける）
+    #[serde(rename = "utf-8-sig")]
+    Utf8Sig,
+    /// Shift_JIS（dispatcher は機種依存文字も書ける cp932 で出力する）
+    #[serde(rename = "shift_jis")]
+    ShiftJis,
+}
+
+impl OptionValue for CsvEncoding {
+    const FIELD: &'static str = "csvEncoding";
+    const ALL: &'static [CsvEncoding] = &[
+        CsvEncoding::Utf8,
+        CsvEncoding::Utf8Sig,
+        CsvEncoding::ShiftJis,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CsvEncoding::Utf8 => "utf-8",
+            CsvEncoding::Utf8Sig => "utf-8-sig",
+            CsvEncoding::ShiftJis => "shift_jis",
         }
+    }
+}
 
-        let mut candidates: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
+/// dispatcher.py `--figure-format` が受け付ける値
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum FigureFormat {
+    Png,
+    Jpeg,
+}
 
-        let direct = result_root.join(stem);
-        if direct.is_dir() {
-            let modified = direct
-                .metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-            candidates.push((modified, direct));
+impl OptionValue for FigureFormat {
+    const FIELD: &'static str = "figureFormat";
+    const ALL: &'static [FigureFormat] = &[FigureFormat::Png, FigureFormat::Jpeg];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            FigureFormat::Png => "png",
+            FigureFormat::Jpeg => "jpeg",
         }
+    }
+}
 
-        if let Ok(entries) = fs::read_dir(result_root) {
-            let prefix = format!("{stem}_");
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if !path.is_dir() {
-                    continue;
-                }
-                let name = path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-                if !name.starts_with(&prefix) {
-                    continue;
-                }
-                let modified = path
-                    .metadata()
-                    .and_then(|m| m.modified())
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-                candidates.push((modified, path));
+/// `csvDelimiter` は改行・引用符以外の 1 文字（dispatcher.py の `--csv-delimiter` と同じ条件）
+fn validate_csv_delimiter(delimiter: Option<&str>) -> Result<(), String> {
+    let Some(d) = delimiter else {
+        return Ok(());
+    };
+    let mut chars = d.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !matches!(c, '"' | '\r' | '\n') => Ok(()),
+        _ => Err(format!(
+            "csvDelimiter must be a single character other than a quote or newline: {d:?}"
+        )),
+    }
+}
+
+/// 正規化トリミング範囲（0〜1）。回転・傾き補正後の画像に対する割合で、
+/// ピクセルへの変換や範囲外の切り詰めは crop_rect.py が行う
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CropRect {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+impl CropRect {
+    /// dispatcher.py / ui_preview.py の `--crop` に渡す文字列（プレビューと実行で同じ値にする）
+    fn to_arg(&self) -> String {
+        format!(
+            "{:.6},{:.6},{:.6},{:.6}",
+            self.left, self.top, self.width, self.height
+        )
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FileSpecificOptions {
+    start: Option<u32>,
+    end: Option<u32>,
+    crop: Option<CropRect>,
+    /// 複数範囲のページ指定（例: "1-3,7,10-12"）。指定時は start/end より優先
+    #[serde(default)]
+    pages: Option<String>,
+    /// 指定があれば `RunOptions.rotate` / `auto_deskew` より優先
+    #[serde(default)]
+    rotate: Option<i32>,
+    #[serde(default)]
+    auto_deskew: Option<bool>,
+    /// 指定があれば `RunOptions.languages` より優先（空配列はこのファイルだけ自動に戻す）
+    #[serde(default)]
+    languages: Option<Vec<String>>,
+}
+
+/// Comparable form of a path: canonicalized when the file exists, `\\` turned into `/`,
+/// and lowercased on Windows where the filesystem ignores case.
+fn normalize_path_key(path: &str) -> String {
+    let resolved = fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string());
+    let key = resolved.replace('\\', "/");
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+/// Parse a page spec like "1-3,7,10-12" into sorted, non-overlapping inclusive ranges.
+/// Overlapping or adjacent ranges are merged; zero, non-numeric and reversed ranges are errors.
+fn parse_page_spec(spec: &str) -> Result<Vec<(u32, u32)>, String> {
+    let mut ranges = Vec::new();
+    for raw in spec.split(',') {
+        let part = raw.trim();
+        if part.is_empty() {
+            return Err(format!("empty element in page spec: {spec:?}"));
+        }
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid page number in {part:?}"))
+        };
+        let (start, end) = match part.split_once('-') {
+            Some((a, b)) => (parse(a)?, parse(b)?),
+            None => {
+                let p = parse(part)?;
+                (p, p)
+            }
+        };
+        if start == 0 || end == 0 {
+            return Err(format!("page numbers start at 1: {part:?}"));
+        }
+        if start > end {
+            return Err(format!("range must be ascending: {part:?}"));
+        }
+        ranges.push((start, end));
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Ok(merged)
+}
+
+/// Inverse of `parse_page_spec` ("1-3,7").
+fn format_page_spec(ranges: &[(u32, u32)]) -> String {
+    ranges
+        .iter()
+        .map(|&(s, e)| {
+            if s == e {
+                s.to_string()
+            } else {
+                format!("{s}-{e}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// dispatcher.py `--rotate` が受け付ける角度（時計回り）
+const ALLOWED_ROTATIONS: [i32; 4] = [0, 90, 180, 270];
+
+fn validate_languages<'a>(
+    requested: impl IntoIterator<Item = &'a str>,
+    supported: &[String],
+) -> Result<(), String> {
+    for lang in requested {
+        if !supported.iter().any(|s| s == lang) {
+            return Err(format!(
+                "unsupported language: {lang} (supported: {})",
+                supported.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_rotate(rotate: Option<i32>) -> Result<(), String> {
+    match rotate {
+        Some(r) if !ALLOWED_ROTATIONS.contains(&r) => {
+            Err(format!("invalid rotate: {r} (valid: 0, 90, 180, 270)"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `describe_run_options` の 1 項目
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunOptionField {
+    name: &'static str,
+    #[serde(rename = "type")]
+    ty: &'static str,
+    /// null を渡せる（省略時は dispatcher 側の既定）
+    optional: bool,
+    default: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_values: Option<Vec<serde_json::Value>>,
+}
+
+/// `RunOptions` の各フィールドの (camelCase 名, 型, null 可)。名前と既定値の整合はテストで
+/// `RunOptions::default()` のシリアライズ結果と突き合わせる。
+const RUN_OPTION_TYPES: &[(&str, &str, bool)] = &[
+    ("formats", "string[]", false),
+    ("imageAsPdf", "boolean", false),
+    ("enableFigure", "boolean", false),
+    ("useGpu", "boolean", false),
+    ("mode", "string", true),
+    ("chunkSize", "integer", true),
+    ("enableRest", "boolean", false),
+    ("restSeconds", "integer", true),
+    ("pdfDpi", "integer", true),
+    ("excelMode", "string", true),
+    ("excelMetaSheet", "boolean", true),
+    ("fileOptions", "Record<string, FileSpecificOptions>", true),
+    ("extraDispatcherArgs", "string[]", false),
+    ("extraOcrArgs", "string[]", false),
+    ("continueOnError", "boolean", false),
+    ("rotate", "integer", true),
+    ("autoDeskew", "boolean", false),
+    ("autoOrient", "boolean", false),
+    ("skipUnchanged", "boolean", false),
+    ("password", "string", true),
+    ("languages", "string[]", false),
+    ("requireFreeSpace", "boolean", false),
+    ("maxRetries", "integer", false),
+    ("mergeSeparator", "string", true),
+    ("autoCleanup", "boolean", false),
+    ("csvDelimiter", "string", true),
+    ("csvEncoding", "string", true),
+    ("confirmLarge", "boolean", false),
+    ("figureFormat", "string", true),
+];
+
+/// 代替フロントエンド向けに、`RunOptions` の項目・型・既定値・取りうる値を返す。
+#[tauri::command]
+fn describe_run_options() -> Vec<RunOptionField> {
+    let defaults = serde_json::to_value(RunOptions::default()).unwrap_or_default();
+    let strings = |values: &[&str]| -> Option<Vec<serde_json::Value>> {
+        Some(values.iter().map(|v| serde_json::json!(v)).collect())
+    };
+    let choices = [
+        (Mode::FIELD, allowed_option_values::<Mode>()),
+        (ExcelMode::FIELD, allowed_option_values::<ExcelMode>()),
+        (
+            MergeSeparator::FIELD,
+            allowed_option_values::<MergeSeparator>(),
+        ),
+        (CsvEncoding::FIELD, allowed_option_values::<CsvEncoding>()),
+        (FigureFormat::FIELD, allowed_option_values::<FigureFormat>()),
+    ];
+    RUN_OPTION_TYPES
+        .iter()
+        .map(|&(name, ty, optional)| RunOptionField {
+            name,
+            ty,
+            optional,
+            default: defaults
+                .get(name)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+            allowed_values: match name {
+                "formats" => strings(SUPPORTED_FORMATS),
+                "rotate" => Some(
+                    ALLOWED_ROTATIONS
+                        .iter()
+                        .map(|r| serde_json::json!(r))
+                        .collect(),
+                ),
+                _ => choices
+                    .iter()
+                    .find(|(field, _)| *field == name)
+                    .and_then(|(_, values)| strings(values)),
+            },
+        })
+        .collect()
+}
+
+impl Default for RunOptions {
+    /// `run_job` に options が渡されなかった場合の既定値
+    fn default() -> Self {
+        Self {
+            formats: vec!["md".into()],
+            image_as_pdf: false,
+            enable_figure: true,
+            use_gpu: false,
+            mode: None,
+            chunk_size: None,
+            enable_rest: false,
+            rest_seconds: None,
+            pdf_dpi: None,
+            excel_mode: None,
+            excel_meta_sheet: None,
+            file_options: None,
+            extra_dispatcher_args: vec![],
+            extra_ocr_args: vec![],
+            continue_on_error: false,
+            rotate: None,
+            auto_deskew: false,
+            auto_orient: false,
+            skip_unchanged: false,
+            password: None,
+            languages: vec![],
+            require_free_space: false,
+            max_retries: 0,
+            merge_separator: None,
+            auto_cleanup: false,
+            csv_delimiter: None,
+            csv_encoding: None,
+            confirm_large: false,
+            figure_format: None,
+        }
+    }
+}
+
+impl RunOptions {
+    /// Per-file options for `path`. Exact key first, then a key that names the same file
+    /// once both sides go through `normalize_path_key` (dialog と D&D で区切り文字・大文字小文字が違うことがある).
+    fn file_options_for(&self, path: &str) -> Option<&FileSpecificOptions> {
+        let map = self.file_options.as_ref()?;
+        if let Some(found) = map.get(path) {
+            return Some(found);
+        }
+        let wanted = normalize_path_key(path);
+        map.iter()
+            .find(|(key, _)| normalize_path_key(key) == wanted)
+            .map(|(_, opts)| opts)
+    }
+
+    /// Every language code requested, globally or per file (checked against the dispatcher's list).
+    fn requested_languages(&self) -> impl Iterator<Item = &str> {
+        self.languages
+            .iter()
+            .chain(
+                self.file_options
+                    .iter()
+                    .flat_map(|m| m.values())
+                    .flat_map(|f| f.languages.iter().flatten()),
+            )
+            .map(String::as_str)
+    }
+
+    /// Validate everything `start_job` can't pass through blindly.
+    fn validate(&self) -> Result<(), String> {
+        self.validate_extra_args()?;
+        validate_rotate(self.rotate)?;
+        validate_csv_delimiter(self.csv_delimiter.as_deref())?;
+        if self.max_retries > MAX_RETRIES_LIMIT {
+            return Err(format!(
+                "maxRetries must be at most {MAX_RETRIES_LIMIT} (got {})",
+                self.max_retries
+            ));
+        }
+        for f in self.file_options.iter().flat_map(|m| m.values()) {
+            validate_rotate(f.rotate)?;
+            if let Some(pages) = &f.pages {
+                parse_page_spec(pages)?;
+            }
+            // トリミング範囲は回転前のプレビューで選ぶので、ページごとに回すと別の場所を切り出す
+            if self.auto_orient && f.crop.is_some() {
+                return Err("crop cannot be combined with autoOrient".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Light sanity check for the pass-through args. `Command` never goes through a shell,
+    /// so only empty strings and a stray `--` (which would break the dispatcher/ocr split) are rejected.
+    fn validate_extra_args(&self) -> Result<(), String> {
+        if self
+            .extra_dispatcher_args
+            .iter()
+            .any(|a| a.trim().is_empty())
+            || self.extra_ocr_args.iter().any(|a| a.trim().is_empty())
+        {
+            return Err("extra args must not contain empty strings".into());
+        }
+        if self.extra_dispatcher_args.iter().any(|a| a == "--") {
+            return Err("extraDispatcherArgs must not contain \"--\"".into());
+        }
+        if self
+            .extra_dispatcher_args
+            .iter()
+            .chain(&self.extra_ocr_args)
+            .any(|a| a == "--password" || a.starts_with("--password="))
+        {
+            return Err("pass the PDF password with the password option, not extra args".into());
+        }
+        Ok(())
+    }
+}
+
+/// `Command` の Debug 表記と同じ形のコマンド行（job.log 用）。
+/// settings.json の `env` で足した変数は先頭に `KEY="value"` で並べ、秘密っぽい名前の値は伏せる。
+fn describe_command(cmd: &Command) -> String {
+    let mut parts: Vec<String> = cmd
+        .get_envs()
+        .filter_map(|(k, v)| Some((k.to_string_lossy(), v?)))
+        .filter(|(k, _)| !is_protected_env_var(k))
+        .map(|(k, v)| {
+            if is_secret_env_var(&k) {
+                format!("{k}=\"***\"")
+            } else {
+                format!("{k}={v:?}")
             }
+        })
+        .collect();
+    parts.sort();
+    parts.push(format!("{:?}", cmd.get_program()));
+    parts.extend(cmd.get_args().map(|arg| format!("{arg:?}")));
+    parts.join(" ")
+}
+
+/// Build the dispatcher argv for one input (everything after `dispatcher.py`).
+///
+/// Arguments are split by consumer:
+/// - dispatcher.py の通常引数: formats / excel / figure / device / mode / crop と、
+///   DPI・ページ範囲 (`--dpi` / `--start` / `--end`)。DPI とページ範囲は dispatcher が
+///   PDF 経路（--image-as-pdf 含む）へ確実に渡し、出力ディレクトリ名の推定にも使うため `--` の前に置く。
+/// - `--` 以降 (ocr_chanked.py へ透過): チャンク処理の安定化設定
+///   (`--chunk-size` / `--enable-rest` / `--rest-seconds`) のみ。
+///
+/// `extra_dispatcher_args` / `extra_ocr_args` はそれぞれの末尾にそのまま足す。
+fn build_dispatcher_args(
+    input: &str,
+    opts: &RunOptions,
+    file_opts: Option<&FileSpecificOptions>,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![input.to_string()];
+
+    if !opts.formats.is_empty() {
+        args.push("--formats".into());
+        args.extend(opts.formats.iter().cloned());
+    }
+    if let Some(em) = opts.excel_mode {
+        args.push("--excel-mode".into());
+        args.push(em.as_str().into());
+    }
+    if let Some(v) = opts.excel_meta_sheet {
+        args.push(if v { "--excel-meta" } else { "--no-excel-meta" }.into());
+    }
+    if let Some(sep) = opts.merge_separator {
+        args.push("--merge-sep".into());
+        args.push(sep.as_str().into());
+    }
+    if let Some(d) = &opts.csv_delimiter {
+        args.push("--csv-delimiter".into());
+        args.push(d.clone());
+    }
+    if let Some(enc) = opts.csv_encoding {
+        args.push("--csv-encoding".into());
+        args.push(enc.as_str().into());
+    }
+    if opts.image_as_pdf {
+        args.push("--image-as-pdf".into());
+    }
+    args.push(
+        if opts.enable_figure {
+            "--figure"
+        } else {
+            "--no-figure"
         }
+        .into(),
+    );
+    if let Some(ff) = opts.figure_format.filter(|_| opts.enable_figure) {
+        args.push("--figure-format".into());
+        args.push(ff.as_str().into());
+    }
+    args.push("--device".into());
+    args.push(
+        if opts.use_gpu {
+            default_gpu_device()
+        } else {
+            "cpu"
+        }
+        .into(),
+    );
+    if let Some(mode) = opts.mode {
+        args.push("--mode".into());
+        args.push(mode.as_str().into());
+    }
+    if let Some(dpi) = opts.pdf_dpi {
+        args.push("--dpi".into());
+        args.push(dpi.to_string());
+    }
+
+    let rotate = file_opts.and_then(|f| f.rotate).or(opts.rotate);
+    if let Some(r) = rotate.filter(|r| *r != 0) {
+        args.push("--rotate".into());
+        args.push(r.to_string());
+    }
+    if file_opts
+        .and_then(|f| f.auto_deskew)
+        .unwrap_or(opts.auto_deskew)
+    {
+        args.push("--deskew".into());
+    }
+    if opts.auto_orient {
+        args.push("--auto-orient".into());
+    }
+    let languages = file_opts
+        .and_then(|f| f.languages.as_ref())
+        .unwrap_or(&opts.languages);
+    if !languages.is_empty() {
+        args.push("--lang".into());
+        args.push(languages.join(","));
+    }
+
+    // File specific options (Crop / Page range)
+    if let Some(f_opts) = file_opts {
+        if let Some(crop) = &f_opts.crop {
+            args.push("--crop".into());
+            args.push(crop.to_arg());
+        }
+        // pages があれば単一範囲の start/end は使わない（validate 済みなので不正値はここでは捨てる）
+        let pages = f_opts
+            .pages
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+            .and_then(|p| parse_page_spec(p).ok());
+        if let Some(ranges) = pages {
+            args.push("--pages".into());
+            args.push(format_page_spec(&ranges));
+        } else {
+            if let Some(s) = f_opts.start {
+                args.push("--start".into());
+                args.push(s.to_string());
+            }
+            if let Some(e) = f_opts.end {
+                args.push("--end".into());
+                args.push(e.to_string());
+            }
+        }
+    }
+    args.extend(opts.extra_dispatcher_args.iter().cloned());
+
+    // Stability settings (passed to ocr_chanked.py via --)
+    let mut extra_args = Vec::new();
+    if let Some(cs) = opts.chunk_size {
+        extra_args.push("--chunk-size".to_string());
+        extra_args.push(cs.to_string());
+    }
+    if opts.enable_rest {
+        extra_args.push("--enable-rest".into());
+        if let Some(rs) = opts.rest_seconds {
+            extra_args.push("--rest-seconds".into());
+            extra_args.push(rs.to_string());
+        }
+    }
+    extra_args.extend(opts.extra_ocr_args.iter().cloned());
+
+    if !extra_args.is_empty() {
+        args.push("--".into());
+        args.extend(extra_args);
+    }
+    args
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunJobResponse {
+    job_id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressResponse {
+    status: JobStatus,
+    progress: f32,
+    log: Vec<LogEntry>,
+    error: Option<String>,
+    current_message: Option<String>,
+    page_current: Option<u32>,
+    page_total: Option<u32>,
+    /// `batch_eta_seconds` と同じ値（互換用）
+    eta_seconds: Option<u32>,
+    /// 処理中のファイルの残り時間
+    file_eta_seconds: Option<u32>,
+    /// 未着手のファイルも含めたバッチ全体の残り時間
+    batch_eta_seconds: Option<u32>,
+    files: Vec<FileProgress>,
+}
+
+/// `get_all_progress` 用の軽量スナップショット（ログは含めない）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobProgressSummary {
+    job_id: String,
+    status: JobStatus,
+    progress: f32,
+    error: Option<String>,
+    current_message: Option<String>,
+    page_current: Option<u32>,
+    page_total: Option<u32>,
+    eta_seconds: Option<u32>,
+    started_at_ms: u64,
+}
+
+/// `job://done` イベントの payload（Done / Error になった時点で1回だけ送る）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobDoneEvent {
+    job_id: String,
+    status: JobStatus,
+    output_count: usize,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultResponse {
+    outputs: Vec<String>,
+    preview: Option<String>,
+    metadata: Option<ResultMetadata>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecentResultEntry {
+    dir_name: String,
+    updated_at_ms: u64,
+    page_range: Option<String>,
+    best_file: Option<String>,
+    /// 先頭ページの縮小画像（data URL）。まだ作っていない・作れなかったときは None
+    /// （一覧では作らないので、無ければ `get_result_thumbnail` で作る）
+    thumbnail: Option<String>,
+    /// この結果を作った実行の入力と設定（`run.json`。保存前の結果は None）
+    run: Option<RunConfig>,
+    /// `best_file` の `verify_output` 結果（開けない代表ファイルを一覧で示すため）
+    best_file_status: Option<OutputIntegrity>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One differing line. Line numbers are 1-based in the respective file.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct DiffLine {
+    kind: DiffKind,
+    line_a: Option<u32>,
+    line_b: Option<u32>,
+    text_a: Option<String>,
+    text_b: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultDiff {
+    file_a: String,
+    file_b: String,
+    lines: Vec<DiffLine>,
+    added: u32,
+    removed: u32,
+    changed: u32,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct SearchHit {
+    dir_name: String,
+    file_name: String,
+    line_number: u32,
+    snippet: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvironmentStatus {
+    project_root: String,
+    dispatcher_found: bool,
+    /// 実際に起動する dispatcher.py（resources/py 優先で解決したもの）
+    dispatcher_path: String,
+    preview_helper_found: bool,
+    result_dir_found: bool,
+    python_bin: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviewResponse {
+    #[serde(default)]
+    data_url: String,
+    #[serde(default)]
+    page_count: Option<u32>,
+    #[serde(default)]
+    page: Option<u32>,
+    /// `data_url` の画像形式（image/png・image/jpeg・image/webp）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+    /// 画像を作れなかった理由の種別。`"encrypted"`（パスワード付き PDF）のほか、
+    /// `preview_batch_first_pages` では 1 ファイルの失敗を `"not_found"` / `"render_failed"` で返す
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error_kind: Option<String>,
+    /// `error_kind` の詳細（ui_preview.py のメッセージ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// `preview_batch_first_pages` のとき、この応答の入力パス（渡した文字列のまま）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// `render_tile` のとき、返したタイルの位置とページ全体のタイル数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tile: Option<TileInfo>,
+    /// `preview_effective_crop` のとき、実行時の解像度で実際に切り出した範囲
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    effective_crop: Option<EffectiveCrop>,
+    /// `auto_orient` のとき、自動で足した時計回りの回転角（0 は回していない）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auto_rotation: Option<u32>,
+}
+
+/// ui_preview.py `--exact-crop` の応答: 回転・傾き補正後の画像（`image_width` x `image_height` px）上で
+/// 切り出した範囲。右端・下端は含まない
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EffectiveCrop {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+    image_width: u32,
+    image_height: u32,
+}
+
+/// ui_preview.py `--tile-size` の応答に付くタイル情報
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TileInfo {
+    x: u32,
+    y: u32,
+    size: u32,
+    zoom: f32,
+    columns: u32,
+    rows: u32,
+    /// zoom をかけたページ全体の大きさ（px）
+    image_width: u32,
+    image_height: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AppSettings {
+    /// settings.json の構造バージョン（旧ファイルは 0 として読み込み、`migrate_settings` で更新）
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(default)]
+    formats: Vec<String>,
+    #[serde(default)]
+    image_as_pdf: bool,
+    #[serde(default)]
+    enable_figure: bool,
+    #[serde(default)]
+    use_gpu: bool,
+    #[serde(default)]
+    output_root: Option<String>,
+    #[serde(default = "default_excel_meta_sheet")]
+    excel_meta_sheet: bool,
+    #[serde(default)]
+    chunk_size: Option<u32>,
+    #[serde(default)]
+    enable_rest: bool,
+    #[serde(default)]
+    rest_seconds: Option<u32>,
+    #[serde(default)]
+    pdf_dpi: Option<u32>,
+    #[serde(default)]
+    window_width: Option<u32>,
+    #[serde(default)]
+    window_height: Option<u32>,
+    #[serde(default)]
+    notify_on_complete: bool,
+    #[serde(default)]
+    watch: Option<WatchConfig>,
+    /// 中間ファイル（ページ画像など）の作業ディレクトリ。未指定なら出力ディレクトリ内
+    #[serde(default)]
+    temp_dir: Option<String>,
+    /// 進捗を JobInfo に反映する回数（毎秒, 1〜30）。未指定なら 10
+    #[serde(default)]
+    progress_update_hz: Option<u32>,
+    /// ジョブのログを `logs/<job_id>.log` にも書き出す
+    #[serde(default)]
+    log_to_file: bool,
+    /// ジョブのログファイルがこのサイズを超えたら `.log.1` に回す。未指定なら 10MB
+    #[serde(default)]
+    max_log_file_bytes: Option<u64>,
+    /// dispatcher / プレビュー用 python に足す環境変数（PYTHONPATH など）。
+    /// `PROTECTED_ENV_VARS` は無視する
+    #[serde(default)]
+    env: Option<HashMap<String, String>>,
+    /// 拡張子（"docx" など）→ 出力を開くアプリのパス。未設定の拡張子は OS の既定アプリで開く
+    #[serde(default)]
+    open_with: Option<HashMap<String, String>>,
+    /// プレビュー作成（ui_preview.py）の制限時間（秒, 1〜600）。未指定なら 30
+    #[serde(default)]
+    preview_timeout_seconds: Option<u32>,
+    /// 1 ジョブで処理するページ数の上限。超えるジョブは `RunOptions.confirmLarge` が無いと開始しない
+    #[serde(default)]
+    max_pages: Option<u32>,
+    /// `resolve_python_bin` が最優先で使う python（`set_python_bin_override` / `save_settings` で起動を確かめてから保存する）
+    #[serde(default)]
+    python_bin_override: Option<String>,
+    /// `list_python_candidates` に追加で並べる python のパス
+    #[serde(default)]
+    python_candidates: Option<Vec<String>>,
+    /// 結果ディレクトリ名のテンプレート（例: "{stem}_{date}_{dpi}dpi"）。`OUTPUT_NAME_PLACEHOLDERS` を使える。
+    /// 未指定なら従来どおり入力ファイル名（+ページ範囲などの suffix）
+    #[serde(default)]
+    output_name_template: Option<String>,
+    /// ジョブが Done になった後に結果ディレクトリごとに実行するプログラム（スクリプト）のパス。
+    /// 引数は結果ディレクトリの絶対パス 1 つ。出力はジョブのログに残す
+    #[serde(default)]
+    post_hook: Option<String>,
+    /// true なら post_hook の失敗（起動失敗・0 以外の終了コード・タイムアウト）でジョブを Error にする。
+    /// false なら警告としてログに残すだけ
+    #[serde(default)]
+    post_hook_required: bool,
+}
+
+/// 監視フォルダ設定（settings.json に保存し、起動時に再開する）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatchConfig {
+    folder: String,
+    #[serde(default)]
+    options: Option<RunOptions>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatchStatus {
+    active: bool,
+    folder: Option<String>,
+    recent: Vec<WatchFileEvent>,
+}
+
+/// `watch://file` イベントの payload（自動処理したファイル 1 件）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatchFileEvent {
+    path: String,
+    job_id: Option<String>,
+    error: Option<String>,
+    detected_at_ms: u64,
+}
+
+fn default_excel_meta_sheet() -> bool {
+    true
+}
+
+/// 出力形式として受け付ける値（dispatcher `--formats`）
+const SUPPORTED_FORMATS: &[&str] = &["md", "docx", "xlsx", "csv"];
+
+impl AppSettings {
+    /// settings.json が無いとき・リセット時に使う初期値
+    fn default_values() -> Self {
+        AppSettings {
+            schema_version: SETTINGS_SCHEMA_VERSION,
+            formats: vec!["md".into()],
+            image_as_pdf: false,
+            enable_figure: true,
+            use_gpu: false,
+            output_root: None,
+            excel_meta_sheet: true,
+            chunk_size: Some(10),
+            enable_rest: false,
+            rest_seconds: Some(10),
+            pdf_dpi: Some(300),
+            window_width: Some(1200),
+            window_height: Some(760),
+            notify_on_complete: false,
+            watch: None,
+            temp_dir: None,
+            progress_update_hz: None,
+            log_to_file: false,
+            max_log_file_bytes: None,
+            env: None,
+            open_with: None,
+            preview_timeout_seconds: None,
+            max_pages: None,
+            python_bin_override: None,
+            python_candidates: None,
+            output_name_template: None,
+            post_hook: None,
+            post_hook_required: false,
+        }
+    }
+
+    /// Reject values the UI would never produce (used before persisting imported settings).
+    fn validate(&self) -> Result<(), String> {
+        if let Some(f) = self
+            .formats
+            .iter()
+            .find(|f| !SUPPORTED_FORMATS.contains(&f.as_str()))
+        {
+            return Err(format!(
+                "unsupported format: {f} (valid: {})",
+                SUPPORTED_FORMATS.join(", ")
+            ));
+        }
+        if let Some(dpi) = self.pdf_dpi {
+            if !(72..=600).contains(&dpi) {
+                return Err(format!("pdfDpi must be between 72 and 600: {dpi}"));
+            }
+        }
+        if let Some(cs) = self.chunk_size {
+            if !(1..=100).contains(&cs) {
+                return Err(format!("chunkSize must be between 1 and 100: {cs}"));
+            }
+        }
+        if let Some(rs) = self.rest_seconds {
+            if rs > 300 {
+                return Err(format!("restSeconds must be 300 or less: {rs}"));
+            }
+        }
+        if self.max_pages == Some(0) {
+            return Err("maxPages must be at least 1".into());
+        }
+        if let Some(secs) = self.preview_timeout_seconds {
+            if !(1..=600).contains(&secs) {
+                return Err(format!(
+                    "previewTimeoutSeconds must be between 1 and 600: {secs}"
+                ));
+            }
+        }
+        if let Some(max) = self.max_log_file_bytes {
+            if max < 1024 {
+                return Err(format!("maxLogFileBytes must be at least 1024: {max}"));
+            }
+        }
+        for key in self.env.iter().flat_map(|env| env.keys()) {
+            if key.is_empty() || key.contains('=') || key.contains('\0') {
+                return Err(format!("invalid env var name: {key:?}"));
+            }
+        }
+        if let Some(w) = &self.watch {
+            if w.folder.trim().is_empty() {
+                return Err("watch.folder is empty".into());
+            }
+        }
+        if let Some(open_with) = &self.open_with {
+            validate_open_with(open_with)?;
+        }
+        if self
+            .python_bin_override
+            .as_deref()
+            .is_some_and(|bin| bin.trim().is_empty())
+        {
+            return Err("pythonBinOverride is empty".into());
+        }
+        if self
+            .python_candidates
+            .iter()
+            .flatten()
+            .any(|bin| bin.trim().is_empty())
+        {
+            return Err("pythonCandidates has an empty path".into());
+        }
+        if let Some(template) = &self.output_name_template {
+            validate_output_name_template(template)?;
+        }
+        if self
+            .post_hook
+            .as_deref()
+            .is_some_and(|hook| hook.trim().is_empty())
+        {
+            return Err("postHook is empty".into());
+        }
+        Ok(())
+    }
+}
+
+/// `outputNameTemplate` で使える `{...}`。date / time はジョブ開始時刻（UTC）の YYYYMMDD / HHMMSS、
+/// index はバッチ内の 1 始まりの番号
+const OUTPUT_NAME_PLACEHOLDERS: &[&str] = &["stem", "date", "time", "dpi", "mode", "index"];
+
+/// Values substituted into `outputNameTemplate` for one input file.
+struct OutputNameVars<'a> {
+    stem: &'a str,
+    started_at_ms: u64,
+    dpi: u32,
+    mode: Mode,
+    index: usize,
+}
+
+/// Expand `template`; unknown or unclosed placeholders are errors, and so is a result that
+/// `validate_result_dir_name` would reject.
+fn render_output_name(template: &str, vars: &OutputNameVars) -> Result<String, String> {
+    let stamp = format_utc_ms(vars.started_at_ms);
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        name.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| format!("outputNameTemplate has an unclosed '{{': {template:?}"))?;
+        let key = &rest[open + 1..open + close];
+        match key {
+            "stem" => name.push_str(vars.stem),
+            "date" => name.push_str(&stamp[..10].replace('-', "")),
+            "time" => name.push_str(&stamp[11..19].replace(':', "")),
+            "dpi" => name.push_str(&vars.dpi.to_string()),
+            "mode" => name.push_str(vars.mode.as_str()),
+            "index" => name.push_str(&vars.index.to_string()),
+            _ => {
+                return Err(format!(
+                    "unknown placeholder {{{key}}} in outputNameTemplate (valid: {})",
+                    OUTPUT_NAME_PLACEHOLDERS.join(", ")
+                ))
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    name.push_str(rest);
+    let name = name.trim().to_string();
+    validate_result_dir_name(&name)
+        .map_err(|e| format!("outputNameTemplate produces an invalid name {name:?}: {e}"))?;
+    Ok(name)
+}
+
+/// Check a template once with sample values (used when saving settings).
+fn validate_output_name_template(template: &str) -> Result<(), String> {
+    render_output_name(
+        template,
+        &OutputNameVars {
+            stem: "sample",
+            started_at_ms: 0,
+            dpi: DEFAULT_PDF_DPI,
+            mode: Mode::Lite,
+            index: 1,
+        },
+    )
+    .map(|_| ())
+}
+
+/// 入力パス → `--output-name` に渡す結果ディレクトリ名。テンプレートが無いときや、
+/// `--label` / `--output-name` を明示したジョブ（`reprocess_result` など）は空にして従来の名前に任せる。
+fn render_output_names(
+    template: Option<&str>,
+    paths: &[String],
+    opts: &RunOptions,
+    started_at_ms: u64,
+) -> Result<HashMap<String, String>, String> {
+    let template = template.map(str::trim).filter(|t| !t.is_empty());
+    let explicit = opts.extra_ocr_args.iter().any(|a| a == "--label")
+        || opts
+            .extra_dispatcher_args
+            .iter()
+            .any(|a| a == "--output-name");
+    let Some(template) = template.filter(|_| !explicit) else {
+        return Ok(HashMap::new());
+    };
+    paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let stem = std::path::Path::new(path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("output");
+            let vars = OutputNameVars {
+                stem,
+                started_at_ms,
+                dpi: opts.pdf_dpi.unwrap_or(DEFAULT_PDF_DPI),
+                mode: opts.mode.unwrap_or(Mode::Lite),
+                index: i + 1,
+            };
+            Ok((path.clone(), render_output_name(template, &vars)?))
+        })
+        .collect()
+}
+
+/// dispatcher の通常引数（`--` より前）に `--output-name` を足す。
+fn push_output_name_arg(args: &mut Vec<String>, output_name: Option<&String>) {
+    if let Some(name) = output_name {
+        let at = args.iter().position(|a| a == "--").unwrap_or(args.len());
+        args.splice(at..at, ["--output-name".to_string(), name.clone()]);
+    }
+}
+
+/// `openWith` の拡張子は "docx" / ".DOCX" のどちらでも同じ扱いにする
+fn normalize_open_with_ext(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_ascii_lowercase()
+}
+
+/// Every `openWith` entry needs a non-empty extension and an application path that exists.
+fn validate_open_with(open_with: &HashMap<String, String>) -> Result<(), String> {
+    for (ext, app) in open_with {
+        if normalize_open_with_ext(ext).is_empty() {
+            return Err(format!("openWith has an empty extension: {ext:?}"));
+        }
+        if !std::path::Path::new(app.trim()).exists() {
+            return Err(format!("openWith.{ext}: application not found: {app}"));
+        }
+    }
+    Ok(())
+}
+
+/// The application configured in `openWith` for `path`'s extension, if any.
+fn open_with_app<'a>(
+    path: &std::path::Path,
+    open_with: Option<&'a HashMap<String, String>>,
+) -> Option<&'a str> {
+    let ext = normalize_open_with_ext(path.extension()?.to_str()?);
+    open_with?
+        .iter()
+        .find(|(k, _)| normalize_open_with_ext(k) == ext)
+        .map(|(_, app)| app.trim())
+        .filter(|app| !app.is_empty())
+}
+
+/// settings.json の `openWith`（読めなければ未設定扱い）
+fn configured_open_with(project_root: &std::path::Path) -> Option<HashMap<String, String>> {
+    load_settings_from_disk(project_root).ok()?.open_with
+}
+
+/// Check that `dir` exists (creating it if needed) and is writable by writing a probe file.
+fn validate_temp_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(dir.trim());
+    fs::create_dir_all(&path)
+        .map_err(|e| format!("作業フォルダを作成できません: {} ({e})", path.display()))?;
+    let probe = path.join(format!(".ocr_to_doc_probe_{}", Uuid::new_v4()));
+    fs::write(&probe, b"")
+        .map_err(|e| format!("作業フォルダに書き込めません: {} ({e})", path.display()))?;
+    let _ = fs::remove_file(&probe);
+    Ok(path)
+}
+
+/// settings.json の `tempDir`（空なら未指定扱い）を検証して返す。
+fn configured_temp_dir(project_root: &std::path::Path) -> Result<Option<PathBuf>, String> {
+    let settings =
+        load_settings_from_disk(project_root).unwrap_or_else(|_| AppSettings::default_values());
+    match settings.temp_dir.as_deref().map(str::trim) {
+        Some(dir) if !dir.is_empty() => validate_temp_dir(dir).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// 結果の出力先ルート。settings の `outputRoot` があればそれ、無ければ `<project_root>/result`。
+fn result_root(project_root: &std::path::Path, settings: &AppSettings) -> PathBuf {
+    match settings.output_root.as_deref().map(str::trim) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => project_root.join("result"),
+    }
+}
+
+/// settings.json を読んで `result_root` を返す。読めなければ既定の `result/`
+fn configured_result_root(project_root: &std::path::Path) -> PathBuf {
+    let settings =
+        load_settings_from_disk(project_root).unwrap_or_else(|_| AppSettings::default_values());
+    result_root(project_root, &settings)
+}
+
+/// 既定以外の出力先なら dispatcher の通常引数に `--output-root` を足す。
+fn push_output_root_arg(
+    args: &mut Vec<String>,
+    project_root: &std::path::Path,
+    root: &std::path::Path,
+) {
+    if root != project_root.join("result") {
+        args.push("--output-root".into());
+        args.push(root.to_string_lossy().to_string());
+    }
+}
+
+/// dispatcher の通常引数（`--` より前）に `--temp-dir` を足す。
+fn push_temp_dir_arg(args: &mut Vec<String>, temp_dir: Option<&std::path::Path>) {
+    if let Some(dir) = temp_dir {
+        args.push("--temp-dir".into());
+        args.push(dir.to_string_lossy().to_string());
+    }
+}
+
+/// 現行の settings.json 構造バージョン。フィールドの改名・削除をしたら上げて
+/// `SETTINGS_MIGRATIONS` に変換を追加する。
+const SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+type SettingsMigration = fn(&mut serde_json::Map<String, serde_json::Value>);
+
+/// (移行後のバージョン, 名前, 変換) の一覧。古い順に適用する。
+const SETTINGS_MIGRATIONS: &[(u32, &str, SettingsMigration)] =
+    &[(1, "v0->v1: rename dpi to pdfDpi", migrate_v0_to_v1)];
+
+fn migrate_v0_to_v1(obj: &mut serde_json::Map<String, serde_json::Value>) {
+    if let Some(dpi) = obj.remove("dpi") {
+        obj.entry("pdfDpi").or_insert(dpi);
+    }
+}
+
+/// Upgrade a raw settings object in place; returns the names of migrations that ran.
+fn migrate_settings(value: &mut serde_json::Value) -> Vec<&'static str> {
+    let Some(obj) = value.as_object_mut() else {
+        return vec![];
+    };
+    let version = obj
+        .get("schemaVersion")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+    if version > SETTINGS_SCHEMA_VERSION {
+        log::warn!(
+            "settings schemaVersion {version} is newer than supported {SETTINGS_SCHEMA_VERSION}"
+        );
+        return vec![];
+    }
+
+    let mut applied = Vec::new();
+    for (to_version, name, migrate) in SETTINGS_MIGRATIONS {
+        if version < *to_version {
+            migrate(obj);
+            applied.push(*name);
+        }
+    }
+    obj.insert(
+        "schemaVersion".into(),
+        serde_json::Value::from(SETTINGS_SCHEMA_VERSION.max(version)),
+    );
+    applied
+}
+
+/// Parse settings JSON, applying migrations first.
+fn parse_settings(content: &str) -> Result<(AppSettings, Vec<&'static str>), String> {
+    let mut value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let migrated = migrate_settings(&mut value);
+    let settings: AppSettings = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok((settings, migrated))
+}
+
+fn load_settings_from_disk(project_root: &std::path::Path) -> Result<AppSettings, String> {
+    // Ensure configs directory exists
+    let config_dir = project_root.join("configs");
+    if !config_dir.exists() {
+        let _ = fs::create_dir_all(&config_dir);
+    }
+
+    let settings_path = config_dir.join("settings.json");
+    if settings_path.exists() {
+        let content = fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
+        let (settings, migrated) = parse_settings(&content)?;
+        if !migrated.is_empty() {
+            for name in &migrated {
+                log::info!("settings migration applied: {name}");
+            }
+            save_settings_to_disk(project_root, &settings)?;
+        }
+        Ok(settings)
+    } else {
+        Ok(AppSettings::default_values())
+    }
+}
+
+fn default_gpu_device() -> &'static str {
+    #[cfg(target_os = "macos")]
+    {
+        "mps"
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        "cuda"
+    }
+}
+
+fn apply_window_settings(app: &tauri::AppHandle, project_root: &std::path::Path) {
+    let settings = load_settings_from_disk(project_root).ok();
+    let width = settings
+        .as_ref()
+        .and_then(|s| s.window_width)
+        .unwrap_or(1200)
+        .max(720);
+    let height = settings
+        .as_ref()
+        .and_then(|s| s.window_height)
+        .unwrap_or(760)
+        .max(540);
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_min_size(Some(tauri::Size::Logical(tauri::LogicalSize::new(
+            720.0, 540.0,
+        ))));
+        let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(
+            width as f64,
+            height as f64,
+        )));
+    }
+}
+
+#[tauri::command(async)]
+fn run_job(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    options: Option<RunOptions>,
+    state: State<Arc<AppState>>,
+) -> Result<RunJobResponse, String> {
+    let job_id = start_job(&app, state.inner(), paths, options)?;
+    Ok(RunJobResponse { job_id })
+}
+
+/// Register a new job and spawn its worker thread; returns the job id.
+/// Shared by `run_job` and the watch-folder mode.
+fn start_job(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    paths: Vec<String>,
+    options: Option<RunOptions>,
+) -> Result<String, String> {
+    if paths.is_empty() {
+        return Err("no input files".into());
+    }
+    if let Some(opts) = &options {
+        opts.validate()?;
+    }
+
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dispatcher = resolve_python_entry(&project_root, "dispatcher.py");
+    if !dispatcher.exists() {
+        return Err(format!(
+            "dispatcher.py not found at {}",
+            dispatcher.display()
+        ));
+    }
+    let settings = load_settings_from_disk(&project_root).ok();
+    if let Some(opts) = &options {
+        // 言語指定があるときだけ問い合わせる（初回のみ Python を起動）
+        if opts.requested_languages().next().is_some() {
+            let supported = state.supported_languages(&project_root)?;
+            validate_languages(opts.requested_languages(), &supported)?;
+        }
+    }
+    // ページ数は入力ごとに Python を起動して数えるので、上限とディスク見積もりで 1 回だけ数える
+    let check_opts = options.clone().unwrap_or_default();
+    let max_pages = settings
+        .as_ref()
+        .and_then(|s| s.max_pages)
+        .filter(|_| !check_opts.confirm_large);
+    if max_pages.is_some() || check_opts.require_free_space {
+        let total_pages = count_selected_pages(&project_root, &paths, &check_opts)?;
+        if let Some(max_pages) = max_pages {
+            check_page_limit(total_pages, max_pages)?;
+        }
+        if check_opts.require_free_space {
+            let estimate = disk_estimate_for_pages(&project_root, total_pages, &check_opts);
+            if estimate.insufficient {
+                return Err(estimate
+                    .warning
+                    .unwrap_or_else(|| "not enough free disk space".into()));
+            }
+        }
+    }
+
+    let python_bin = resolve_python_bin(&project_root);
+    let temp_dir = configured_temp_dir(&project_root)?;
+    let output_root = settings
+        .as_ref()
+        .map(|s| result_root(&project_root, s))
+        .unwrap_or_else(|| project_root.join("result"));
+    if output_root != project_root.join("result") {
+        validate_output_root(&output_root)?;
+    }
+    let flush_interval =
+        progress_flush_interval(settings.as_ref().and_then(|s| s.progress_update_hz));
+    let log_file_limit = settings
+        .as_ref()
+        .filter(|s| s.log_to_file)
+        .map(|s| s.max_log_file_bytes);
+    let output_names = render_output_names(
+        settings
+            .as_ref()
+            .and_then(|s| s.output_name_template.as_deref()),
+        &paths,
+        &options.clone().unwrap_or_default(),
+        now_ms(),
+    )?;
+    let post_hook = settings.as_ref().and_then(|s| {
+        let hook = s.post_hook.as_deref()?.trim();
+        (!hook.is_empty()).then(|| PostHook {
+            program: hook.to_string(),
+            required: s.post_hook_required,
+        })
+    });
+    let extra_env = settings.and_then(|s| s.env).unwrap_or_default();
+
+    let job_id = Uuid::new_v4().to_string();
+    {
+        let mut jobs = state
+            .jobs
+            .write()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        jobs.insert(job_id.clone(), JobInfo::new_running(&paths));
+    }
+
+    let app = app.clone();
+    let state_arc: Arc<AppState> = state.clone();
+    let dispatcher_path = dispatcher.clone();
+    let mut opts = options.unwrap_or_default();
+    push_temp_dir_arg(&mut opts.extra_dispatcher_args, temp_dir.as_deref());
+    push_output_root_arg(&mut opts.extra_dispatcher_args, &project_root, &output_root);
+    let temp_dir_cloned = temp_dir.clone();
+    let python_bin_cloned = python_bin.clone();
+    let project_root_cloned = project_root.clone();
+    let paths_cloned = paths.clone();
+    let job_id_cloned = job_id.clone();
+    let dispatcher_version = read_dispatcher_version(&project_root);
+
+    thread::spawn(move || {
+        // run.json に残す実行環境（初回の pip freeze は少し時間がかかるのでワーカー側で取る）
+        let env_fingerprint = match state_arc.env_fingerprint(&project_root_cloned, false) {
+            Ok(fp) => Some(fp),
+            Err(e) => {
+                log::warn!("env fingerprint unavailable: {e}");
+                None
+            }
+        };
+        // 最初のページが終わるまでの ETA は過去の実績から出す
+        let seed_secs_per_page =
+            load_metrics(&project_root_cloned).seconds_per_page(opts.use_gpu) as f32;
+        let mut pages_processed: u32 = 0;
+        // 最初のページマーカーから数えたページ処理の秒数（起動・モデル読み込みを 1 ページあたりに混ぜない）
+        let mut page_secs: f32 = 0.0;
+        if let Ok(mut jobs) = state_arc.jobs.write() {
+            if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                job.batch_stats.seed_secs_per_page = seed_secs_per_page;
+            }
+        }
+        let mut outputs = Vec::new();
+        let mut stopped_early = false;
+        // 入力ごとのハッシュ（再試行・並べ替えで同じ入力を何度も読まない）
+        let mut input_hashes: HashMap<String, Option<String>> = HashMap::new();
+        let paths_len = paths_cloned.len();
+        for (idx, queued_path) in paths_cloned.iter().enumerate() {
+            wait_while_paused(&state_arc, &job_id_cloned);
+            if is_cancelled(&state_arc, &job_id_cloned) {
+                return notify_job_finished(&app, &state_arc, &project_root_cloned, &job_id_cloned);
+            }
+            if is_stop_requested(&state_arc, &job_id_cloned) {
+                stopped_early = true;
+                break;
+            }
+
+            let mut attempt: u32 = 0;
+            loop {
+                let file_started = Instant::now();
+                // 未着手のファイルは reorder_job_files で入れ替わるので、起動の直前に JobInfo から読む
+                let p = &file_path(&state_arc, &job_id_cloned, idx)
+                    .unwrap_or_else(|| queued_path.clone());
+                // skip_unchanged のときは処理を始める前の内容で比べ、その値を run.json にも残す
+                let input_hash = opts
+                    .skip_unchanged
+                    .then(|| cached_input_hash(&mut input_hashes, p))
+                    .flatten();
+                // skip_unchanged で飛ばすときは既存の結果をファイルの outputs に載せる
+                let up_to_date = opts
+                    .skip_unchanged
+                    .then(|| {
+                        up_to_date_result_dir(
+                            &output_root,
+                            p,
+                            output_names.get(p),
+                            input_hash.as_deref(),
+                            &opts,
+                        )
+                    })
+                    .flatten()
+                    .map(|_| {
+                        output_file_names(&collect_output_files(
+                            &project_root_cloned,
+                            std::slice::from_ref(p),
+                            &opts.formats,
+                            &output_names,
+                            opts.enable_figure,
+                        ))
+                    });
+                let mut cmd = Command::new(&python_bin_cloned);
+                apply_extra_env(&mut cmd, &extra_env);
+                apply_python_env(&mut cmd);
+                apply_pdf_password(&mut cmd, opts.password.as_deref());
+                // Force unbuffered output for Python
+                cmd.arg("-u");
+
+                let file_opts = opts.file_options_for(p);
+                let mut dispatcher_args = build_dispatcher_args(p, &opts, file_opts);
+                push_output_name_arg(&mut dispatcher_args, output_names.get(p));
+                cmd.arg(&dispatcher_path).args(&dispatcher_args);
+
+                cmd.current_dir(&project_root_cloned);
+                // pause_job がグループ単位で SIGSTOP/SIGCONT を送れるよう、dispatcher をグループリーダーにする
+                #[cfg(unix)]
+                {
+                    use std::os::unix::process::CommandExt;
+                    cmd.process_group(0);
+                }
+
+                // Pipe output to read in real-time
+                cmd.stdout(std::process::Stdio::piped());
+                cmd.stderr(std::process::Stdio::piped());
+
+                let log_line = spawn_log_line(&cmd);
+                let mut skipped = false;
+                let mut reordered = false;
+                if let Ok(mut jobs) = state_arc.jobs.write() {
+                    if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                        // skip_file で外されたファイル（再試行待ちの間に外されたものも）は起動しない
+                        skipped = job.files.get(idx).is_some_and(|f| {
+                            matches!(f.status, FileStatus::Skipped | FileStatus::Cancelled)
+                        });
+                        // p を読んだ後に並べ替えられたら、入れ替わったファイルで組み立て直す
+                        reordered = job.files.get(idx).is_some_and(|f| f.path != *p);
+                        if let Some(existing) =
+                            up_to_date.clone().filter(|_| !skipped && !reordered)
+                        {
+                            job.skip_up_to_date(idx, existing);
+                            set_progress(
+                                job,
+                                ((idx as f32 + 1.0) / paths_len as f32 * 100.0).min(100.0),
+                            );
+                        } else if !skipped && !reordered {
+                            job.log.push(LogEntry::app(log_line.clone()));
+                            // Start of this file processing
+                            let base_progress = (idx as f32) / paths_len as f32 * 100.0;
+                            set_progress(job, base_progress.min(99.0));
+                            if let Some(f) = job.files.get_mut(idx) {
+                                f.status = FileStatus::Running;
+                            }
+                        }
+                    }
+                }
+                if skipped || (!reordered && up_to_date.is_some()) {
+                    break;
+                }
+                if reordered {
+                    continue;
+                }
+
+                // cancel_and_discard で既存の結果を消さないよう、起動前からある結果ディレクトリを覚えておく
+                let existing_dirs = result_dir_names(&output_root);
+                match cmd.spawn() {
+                    Ok(mut child) => {
+                        let stdout = child.stdout.take().expect("failed to get stdout");
+                        let stderr = child.stderr.take().expect("failed to get stderr");
+
+                        // リーダーは行を解析して送るだけにし、JobInfo への反映は集約スレッドでまとめて行う
+                        let (tx, rx) = mpsc::channel::<JobUpdate>();
+                        let log_file = log_file_limit
+                            .map(|max| JobLogFile::new(&project_root_cloned, &job_id_cloned, max));
+                        let aggregator = spawn_job_aggregator(
+                            state_arc.clone(),
+                            job_id_cloned.clone(),
+                            rx,
+                            flush_interval,
+                            log_file,
+                        );
+                        let tx_out = tx.clone();
+                        // このファイルでページマーカーが出たか（出ないバックエンドだけバイト数ベースの推定で進める）
+                        let marker_seen = Arc::new(AtomicBool::new(false));
+                        let marker_seen_out = marker_seen.clone();
+                        let byte_watcher = ByteProgressWatcher::spawn(
+                            tx.clone(),
+                            output_root.clone(),
+                            p,
+                            output_names.get(p),
+                            (idx as f32) / paths_len as f32 * 100.0,
+                            100.0 / paths_len as f32,
+                            marker_seen,
+                        );
+
+                        // Stdout reader thread
+                        let stdout_handle = thread::spawn(move || {
+                            use std::collections::VecDeque;
+                            use std::io::{BufRead, BufReader};
+                            let reader = BufReader::new(stdout);
+                            let mut range_start: Option<u32> = None;
+                            let mut range_end: Option<u32> = None;
+                            let mut page_started_at: Option<Instant> = None;
+                            let mut first_page_at: Option<Instant> = None;
+                            let mut recent_secs: VecDeque<f32> = VecDeque::new();
+                            const ETA_WINDOW: usize = 5;
+                            let mut pages_done: u32 = 0;
+                            let mut page_weights: HashMap<u32, f32> = HashMap::new();
+
+                            let parse_range = |line: &str| -> Option<(u32, u32)> {
+                                let prefix = "処理範囲:";
+                                let rest = line.strip_prefix(prefix)?.trim();
+                                let mut parts = rest.split('〜');
+                                let start = parts.next()?.trim().parse::<u32>().ok()?;
+                                let end = parts.next()?.trim().parse::<u32>().ok()?;
+                                Some((start, end))
+                            };
+
+                            let parse_page_marker =
+                                |line: &str, marker: &str| -> Option<(u32, u32)> {
+                                    // e.g. "--- Page 3/9 (abs 3/12) ---" / "--- Done 3/9 ---"
+                                    let start = format!("--- {marker} ");
+                                    let rest = line.strip_prefix(&start)?;
+                                    let head = rest.split_whitespace().next()?; // "3/9"
+                                    let mut parts = head.split('/');
+                                    let cur = parts.next()?.parse::<u32>().ok()?;
+                                    let total = parts.next()?.parse::<u32>().ok()?;
+                                    Some((cur, total))
+                                };
+
+                            for l in reader.lines().map_while(Result::ok) {
+                                let _ = tx_out.send(JobUpdate::Log(LogEntry::stdout(l.clone())));
+                                let mut patch = ProgressPatch::default();
+
+                                let file_start = (idx as f32) / paths_len as f32 * 100.0;
+                                let file_end = ((idx as f32) + 1.0) / paths_len as f32 * 100.0;
+                                let file_span = (file_end - file_start).max(1.0);
+
+                                if let Some((s, e)) = parse_range(&l) {
+                                    range_start = Some(s);
+                                    range_end = Some(e);
+                                    let total = e.saturating_sub(s).saturating_add(1);
+                                    patch.page_total = Some(total);
+                                    patch.eta_seconds = Some(None);
+                                }
+
+                                if let Some(w) = PageWeight::parse_line(&l) {
+                                    page_weights.insert(w.page, w.weight);
+                                }
+
+                                if let Some((cur, total_in_run)) = parse_page_marker(&l, "Page") {
+                                    patch.page_current = Some(cur);
+                                    patch.page_total = Some(total_in_run);
+                                    patch.current_message =
+                                        Some(format!("PDF変換中: {cur}/{total_in_run}ページ"));
+                                    patch.eta_seconds = Some(None);
+                                    if recent_secs.is_empty() {
+                                        let end_page = range_end.unwrap_or(total_in_run);
+                                        let remaining =
+                                            end_page.saturating_sub(cur).saturating_add(1);
+                                        patch.eta_seconds = Some(Some(
+                                            (seed_secs_per_page * remaining as f32).round() as u32,
+                                        ));
+                                    }
+                                    page_started_at = Some(Instant::now());
+                                    first_page_at.get_or_insert_with(Instant::now);
+                                }
+
+                                if let Some((cur, total_in_run)) = parse_page_marker(&l, "Done") {
+                                    pages_done += 1;
+                                    if let Some(started) = page_started_at.take() {
+                                        let secs = started.elapsed().as_secs_f32();
+                                        if secs.is_finite() && secs > 0.0 {
+                                            recent_secs.push_back(secs);
+                                            while recent_secs.len() > ETA_WINDOW {
+                                                recent_secs.pop_front();
+                                            }
+                                        }
+                                    }
+
+                                    patch.page_current = Some(cur);
+                                    patch.page_total = Some(total_in_run);
+
+                                    let (start_page, end_page) = match (range_start, range_end) {
+                                        (Some(s), Some(e)) => (s, e),
+                                        _ => (1, total_in_run),
+                                    };
+                                    let total_pages = end_page
+                                        .saturating_sub(start_page)
+                                        .saturating_add(1)
+                                        .max(1);
+                                    let done_pages = cur
+                                        .saturating_sub(start_page)
+                                        .saturating_add(1)
+                                        .min(total_pages);
+                                    let remaining_pages = end_page.saturating_sub(cur);
+
+                                    // 重みが届いていればページの中身の重さで、無ければページ数で按分する
+                                    let ocr_ratio = weighted_page_ratio(
+                                        &page_weights,
+                                        start_page,
+                                        cur,
+                                        end_page,
+                                    )
+                                    .unwrap_or(done_pages as f32 / total_pages as f32);
+                                    let target_progress =
+                                        file_start + file_span * (0.90 * ocr_ratio);
+                                    patch.progress = Some(target_progress);
+
+                                    if !recent_secs.is_empty() && remaining_pages > 0 {
+                                        let avg = recent_secs.iter().copied().sum::<f32>()
+                                            / recent_secs.len() as f32;
+                                        if avg.is_finite() && avg > 0.0 {
+                                            patch.eta_seconds = Some(Some(
+                                                (avg * remaining_pages as f32).round() as u32,
+                                            ));
+                                        }
+                                    } else {
+                                        patch.eta_seconds = Some(None);
+                                    }
+
+                                    patch.current_message =
+                                        Some(format!("PDF変換中: {cur}/{total_in_run}ページ"));
+                                }
+
+                                if l.contains("--- merged_md.py を実行 ---") {
+                                    patch.current_message = Some("後処理: Markdown結合中".into());
+                                    patch.eta_seconds = Some(None);
+                                    patch.progress = Some(file_start + file_span * 0.92);
+                                }
+                                if l.contains("[dispatcher] Converting to docx") {
+                                    patch.current_message = Some("後処理: Word変換中".into());
+                                    patch.eta_seconds = Some(None);
+                                    patch.progress = Some(file_start + file_span * 0.96);
+                                }
+                                if l.contains("[dispatcher] processing excel_via=json") {
+                                    patch.current_message = Some("後処理: Excel変換中".into());
+                                    patch.eta_seconds = Some(None);
+                                    patch.progress = Some(file_start + file_span * 0.99);
+                                }
+                                if let Some(meta) = ResultMetadata::parse_line(&l) {
+                                    patch.metadata = Some(meta);
+                                }
+                                if let Some(dir) = parse_output_dir_line(&l) {
+                                    if !existing_dirs.contains(&dir) {
+                                        patch.created_dirs.push(dir.clone());
+                                    }
+                                    patch.output_dirs.push(dir);
+                                }
+                                if patch.page_current.is_some() {
+                                    marker_seen_out.store(true, Ordering::Relaxed);
+                                }
+                                if !patch.is_empty() {
+                                    let _ = tx_out.send(JobUpdate::Progress(patch));
+                                }
+                            }
+                            (pages_done, first_page_at)
+                        });
+
+                        // Stderr reader thread
+                        let tx_err = tx;
+                        let stderr_handle = thread::spawn(move || {
+                            use std::io::{BufRead, BufReader};
+                            let reader = BufReader::new(stderr);
+                            // 再試行するかどうかの判定用（ネットワーク・ダウンロード系の失敗が出たか）
+                            let mut saw_transient = false;
+                            for l in reader.lines().map_while(Result::ok) {
+                                saw_transient |= is_transient_failure_line(&l);
+                                let _ = tx_err.send(JobUpdate::Log(LogEntry::stderr(l)));
+                            }
+                            saw_transient
+                        });
+
+                        // Wait for finish（終了時に kill できるよう、ハンドルは AppState に預ける）
+                        if let Ok(mut children) = state_arc.children.lock() {
+                            // Running にしてから預けるまでの間の skip_file・キャンセルは子プロセスが
+                            // 見つからず kill されていないので、ここで終了させる
+                            let dropped = is_cancelled(&state_arc, &job_id_cloned)
+                                || matches!(
+                                    file_status(&state_arc, &job_id_cloned, idx),
+                                    Some(FileStatus::Cancelled | FileStatus::Skipped)
+                                );
+                            if dropped {
+                                let _ = kill_child_tree(&mut child);
+                            } else if is_paused(&state_arc, &job_id_cloned) {
+                                // spawn 直後に pause_job が来ていたら、ここで止めてから預ける
+                                let _ = suspend_process_tree(child.id(), SuspendAction::Suspend);
+                            }
+                            children.insert(job_id_cloned.clone(), child);
+                        }
+                        let status = wait_job_child(&state_arc, &job_id_cloned);
+                        byte_watcher.stop();
+                        let (file_pages, first_page_at) = stdout_handle.join().unwrap_or((0, None));
+                        let saw_transient = stderr_handle.join().unwrap_or(false);
+                        // 送信側がすべて閉じたので、残りを書き込んで終了するのを待つ
+                        aggregator.join().unwrap_or(());
+                        // kill されて終わった場合は失敗扱いにせず、キャンセル済みの状態を残す
+                        if is_cancelled(&state_arc, &job_id_cloned) {
+                            return notify_job_finished(
+                                &app,
+                                &state_arc,
+                                &project_root_cloned,
+                                &job_id_cloned,
+                            );
+                        }
+                        // skip_file で止めたファイルも失敗にはせず、次のファイルへ進む
+                        if file_status(&state_arc, &job_id_cloned, idx)
+                            == Some(FileStatus::Cancelled)
+                        {
+                            break;
+                        }
+
+                        match status {
+                            Ok(s) if s.success() => {
+                                let file_output_paths = collect_output_files(
+                                    &project_root_cloned,
+                                    std::slice::from_ref(p),
+                                    &opts.formats,
+                                    &output_names,
+                                    opts.enable_figure,
+                                );
+                                let input_hash =
+                                    input_hash.or_else(|| cached_input_hash(&mut input_hashes, p));
+                                let run_config_notes = write_run_configs(
+                                    &project_root_cloned,
+                                    &file_output_paths,
+                                    p,
+                                    input_hash.as_deref(),
+                                    &opts,
+                                    &dispatcher_args,
+                                    dispatcher_version.as_deref(),
+                                    env_fingerprint.as_ref(),
+                                );
+                                let cleanup_notes = if opts.auto_cleanup {
+                                    auto_cleanup_notes(
+                                        &project_root_cloned,
+                                        &file_output_paths,
+                                        temp_dir_cloned.as_deref(),
+                                    )
+                                } else {
+                                    vec![]
+                                };
+                                // 実行中に付けたタグも run.json に残す
+                                let tag_notes = write_run_config_tags(
+                                    &project_root_cloned,
+                                    &output_dir_names(&file_output_paths),
+                                    &job_tags(&state_arc, &job_id_cloned),
+                                );
+                                let file_outputs = output_file_names(&file_output_paths);
+                                // ページマーカーが出ない入力（画像など）はファイル全体の時間で代用する
+                                let file_secs = first_page_at
+                                    .unwrap_or(file_started)
+                                    .elapsed()
+                                    .as_secs_f32();
+                                page_secs += file_secs;
+                                if let Ok(mut jobs) = state_arc.jobs.write() {
+                                    if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                                        job.log.extend(run_config_notes);
+                                        job.log.extend(tag_notes);
+                                        job.log.extend(cleanup_notes);
+                                        job.batch_stats.record_file(file_pages.max(1), file_secs);
+                                        set_progress(
+                                            job,
+                                            ((idx as f32 + 1.0) / paths_len as f32 * 100.0)
+                                                .min(100.0),
+                                        );
+                                        if let Some(f) = job.files.get_mut(idx) {
+                                            f.status = FileStatus::Done;
+                                            f.outputs = file_outputs;
+                                        }
+                                    }
+                                }
+                                outputs.push(p.clone());
+                                // 画像はページマーカーが出ないので 1 ページとして数える
+                                pages_processed += file_pages.max(1);
+                            }
+                            Ok(_) if saw_transient && attempt < opts.max_retries => {
+                                attempt += 1;
+                                let delay = retry_backoff(attempt);
+                                if let Ok(mut jobs) = state_arc.jobs.write() {
+                                    if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                                        let note = format!(
+                                        "{p}: 一時的なエラーのため再試行します（{attempt}/{} 回目、{} 秒後）",
+                                        opts.max_retries,
+                                        delay.as_secs()
+                                    );
+                                        job.log.push(LogEntry::new(
+                                            LogSource::App,
+                                            LogLevel::Warn,
+                                            note.clone(),
+                                        ));
+                                        job.current_message = Some(note);
+                                    }
+                                }
+                                if !sleep_unless_cancelled(&state_arc, &job_id_cloned, delay) {
+                                    return notify_job_finished(
+                                        &app,
+                                        &state_arc,
+                                        &project_root_cloned,
+                                        &job_id_cloned,
+                                    );
+                                }
+                                continue;
+                            }
+                            Ok(_) => {
+                                if mark_file_failed(
+                                    &state_arc,
+                                    &job_id_cloned,
+                                    idx,
+                                    "dispatcher failed (non-zero exit code)".into(),
+                                    opts.continue_on_error,
+                                ) {
+                                    return notify_job_finished(
+                                        &app,
+                                        &state_arc,
+                                        &project_root_cloned,
+                                        &job_id_cloned,
+                                    );
+                                }
+                            }
+                            Err(e) => {
+                                if mark_file_failed(
+                                    &state_arc,
+                                    &job_id_cloned,
+                                    idx,
+                                    format!("failed to spawn python: {e}"),
+                                    opts.continue_on_error,
+                                ) {
+                                    return notify_job_finished(
+                                        &app,
+                                        &state_arc,
+                                        &project_root_cloned,
+                                        &job_id_cloned,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        if mark_file_failed(
+                            &state_arc,
+                            &job_id_cloned,
+                            idx,
+                            format!("failed to spawn python: {e}"),
+                            opts.continue_on_error,
+                        ) {
+                            return notify_job_finished(
+                                &app,
+                                &state_arc,
+                                &project_root_cloned,
+                                &job_id_cloned,
+                            );
+                        }
+                    }
+                }
+                break;
+            }
+        }
+
+        // set done
+        let mut hook_dirs = Vec::new();
+        let mut hook_message = None;
+        if let Ok(mut jobs) = state_arc.jobs.write() {
+            if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                set_progress(job, 100.0);
+                job.finish_batch();
+                if stopped_early {
+                    let note = format!(
+                        "停止しました（{}/{} ファイル処理済み）",
+                        outputs.len(),
+                        paths_len
+                    );
+                    job.log.push(LogEntry::app(note.clone()));
+                    job.current_message = Some(note);
+                    job.eta_seconds = None;
+                }
+                // 停止した場合に未処理ファイルの古い結果を拾わないよう、処理済みの入力だけを見る
+                let output_files = collect_output_files(
+                    &project_root_cloned,
+                    &outputs,
+                    &opts.formats,
+                    &output_names,
+                    opts.enable_figure,
+                );
+                job.outputs = output_file_names(&output_files);
+                // post_hook は成功したジョブだけ（Error / Cancelled では実行しない）。フックが終わるまでは
+                // Running のままにして、UI が完了と見てポーリングをやめないようにする
+                if job.status == JobStatus::Done && post_hook.is_some() {
+                    hook_dirs = output_dir_names(&output_files);
+                    if !hook_dirs.is_empty() {
+                        job.status = JobStatus::Running;
+                        job.finalizing = true;
+                        hook_message = job.current_message.replace(POST_HOOK_MESSAGE.into());
+                    }
+                }
+
+                // Markdownプレビュー: 最初に見つかった md を読む
+                if let Some(md_path) = output_files
+                    .iter()
+                    .find(|p| p.extension().map(|e| e == "md").unwrap_or(false))
+                {
+                    if let Ok(content) = fs::read_to_string(md_path) {
+                        job.preview = Some(content);
+                    } else {
+                        job.preview = Some(format!(
+                            "failed to read markdown preview: {}",
+                            md_path.display()
+                        ));
+                    }
+                } else {
+                    job.preview = Some(format!(
+                        "Converted markdown for: {} (md preview not found)",
+                        outputs.join(", ")
+                    ));
+                }
+            }
+        }
+        record_throughput(
+            &project_root_cloned,
+            opts.use_gpu,
+            Duration::from_secs_f32(page_secs),
+            pages_processed,
+        );
+        if let Some(hook) = &post_hook {
+            for dir in &hook_dirs {
+                if is_cancelled(&state_arc, &job_id_cloned) {
+                    break;
+                }
+                if !run_post_hook_for_job(&state_arc, &job_id_cloned, hook, &output_root.join(dir))
+                {
+                    break;
+                }
+            }
+            // 必須のフックが失敗して Error にした・フックの間にキャンセルされた場合はそのまま
+            if let Ok(mut jobs) = state_arc.jobs.write() {
+                if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                    job.finalizing = false;
+                    if matches!(job.status, JobStatus::Running | JobStatus::Paused) {
+                        job.status = JobStatus::Done;
+                        job.current_message = hook_message;
+                    }
+                }
+            }
+        }
+        notify_job_finished(&app, &state_arc, &project_root_cloned, &job_id_cloned);
+    });
+
+    Ok(job_id)
+}
+
+/// Download and load the OCR models ahead of the first real job (`dispatcher.py --warm-models`).
+/// Runs as a job so the download log streams through `get_progress`; the job ends `Done`
+/// once the models loaded, and `job://done` is emitted as for `run_job`.
+#[tauri::command]
+fn warm_models(
+    app: tauri::AppHandle,
+    device: Option<String>,
+    mode: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<RunJobResponse, String> {
+    let device = device
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "cpu".into());
+    if device.starts_with('-') {
+        return Err(format!("invalid device: {device}"));
+    }
+    let mode = Mode::parse_optional(mode.as_deref().unwrap_or(""))?;
+
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dispatcher = resolve_python_entry(&project_root, "dispatcher.py");
+    if !dispatcher.exists() {
+        return Err(format!(
+            "dispatcher.py not found at {}",
+            dispatcher.display()
+        ));
+    }
+    let settings = load_settings_from_disk(&project_root).ok();
+    let flush_interval =
+        progress_flush_interval(settings.as_ref().and_then(|s| s.progress_update_hz));
+    let extra_env = settings.and_then(|s| s.env).unwrap_or_default();
+
+    let mut cmd = Command::new(resolve_python_bin(&project_root));
+    apply_extra_env(&mut cmd, &extra_env);
+    apply_python_env(&mut cmd);
+    cmd.arg("-u")
+        .arg(&dispatcher)
+        .arg("--warm-models")
+        .arg("--device")
+        .arg(&device);
+    if let Some(mode) = mode {
+        cmd.arg("--mode").arg(mode.as_str());
+    }
+    cmd.current_dir(&project_root)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let job_id = Uuid::new_v4().to_string();
+    let mut job = JobInfo::new_running(&[]);
+    job.current_message = Some("モデルを準備しています".into());
+    job.log.push(LogEntry::app(spawn_log_line(&cmd)));
+    {
+        let mut jobs = state
+            .jobs
+            .write()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        jobs.insert(job_id.clone(), job);
+    }
+
+    let state_arc: Arc<AppState> = state.inner().clone();
+    let job_id_cloned = job_id.clone();
+    thread::spawn(move || {
+        let result = match cmd.spawn() {
+            Ok(mut child) => {
+                let stdout = child.stdout.take().expect("failed to get stdout");
+                let stderr = child.stderr.take().expect("failed to get stderr");
+                let (tx, rx) = mpsc::channel::<JobUpdate>();
+                let aggregator = spawn_job_aggregator(
+                    state_arc.clone(),
+                    job_id_cloned.clone(),
+                    rx,
+                    flush_interval,
+                    None,
+                );
+                let tx_err = tx.clone();
+                let stdout_handle = thread::spawn(move || {
+                    use std::io::{BufRead, BufReader};
+                    for l in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        let _ = tx.send(JobUpdate::Log(LogEntry::stdout(l)));
+                    }
+                });
+                let stderr_handle = thread::spawn(move || {
+                    use std::io::{BufRead, BufReader};
+                    for l in BufReader::new(stderr).lines().map_while(Result::ok) {
+                        let _ = tx_err.send(JobUpdate::Log(LogEntry::stderr(l)));
+                    }
+                });
+                if let Ok(mut children) = state_arc.children.lock() {
+                    children.insert(job_id_cloned.clone(), child);
+                }
+                let status = wait_job_child(&state_arc, &job_id_cloned);
+                stdout_handle.join().unwrap_or(());
+                stderr_handle.join().unwrap_or(());
+                aggregator.join().unwrap_or(());
+                match status {
+                    Ok(s) if s.success() => Ok(()),
+                    Ok(s) => Err(format!("モデルの準備に失敗しました（exit {s}）")),
+                    Err(e) => Err(format!("モデルの準備に失敗しました: {e}")),
+                }
+            }
+            Err(e) => Err(format!("failed to spawn python: {e}")),
+        };
+
+        if let Ok(mut jobs) = state_arc.jobs.write() {
+            if let Some(job) = jobs.get_mut(&job_id_cloned) {
+                // cancel_job で止められた場合はキャンセル済みのまま残す
+                if job.status != JobStatus::Cancelled {
+                    set_progress(job, 100.0);
+                    match result {
+                        Ok(()) => {
+                            job.status = JobStatus::Done;
+                            job.current_message = Some("モデルの準備が完了しました".into());
+                            job.log.push(LogEntry::app("models ready"));
+                        }
+                        Err(e) => {
+                            job.status = JobStatus::Error;
+                            job.current_message = None;
+                            job.log
+                                .push(LogEntry::new(LogSource::App, LogLevel::Error, e.clone()));
+                            job.error = Some(e);
+                        }
+                    }
+                }
+            }
+        }
+        notify_job_finished(&app, &state_arc, &project_root, &job_id_cloned);
+    });
+
+    Ok(RunJobResponse { job_id })
+}
+
+/// 学習値がまだ無いときの 1 ページあたり秒数（lite モードの実測目安）
+const DEFAULT_SECONDS_PER_PAGE_CPU: f64 = 15.0;
+const DEFAULT_SECONDS_PER_PAGE_GPU: f64 = 3.0;
+/// 新しい実測値をどれだけ反映するか（指数移動平均の重み）
+const SECONDS_PER_PAGE_SMOOTHING: f64 = 0.3;
+
+/// `configs/metrics.json`: 過去のジョブから学習した処理速度。CPU と GPU で桁が違うので分けて持つ。
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ThroughputMetrics {
+    #[serde(default)]
+    cpu: Option<DeviceThroughput>,
+    #[serde(default)]
+    gpu: Option<DeviceThroughput>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct DeviceThroughput {
+    avg_seconds_per_page: f64,
+    #[serde(default)]
+    samples: u32,
+}
+
+impl ThroughputMetrics {
+    fn device(&self, use_gpu: bool) -> Option<&DeviceThroughput> {
+        if use_gpu {
+            self.gpu.as_ref()
+        } else {
+            self.cpu.as_ref()
+        }
+    }
+
+    /// Learned seconds per page for the device, or the hardcoded default.
+    fn seconds_per_page(&self, use_gpu: bool) -> f64 {
+        self.device(use_gpu)
+            .map(|d| d.avg_seconds_per_page)
+            .filter(|v| v.is_finite() && *v > 0.0)
+            .unwrap_or(if use_gpu {
+                DEFAULT_SECONDS_PER_PAGE_GPU
+            } else {
+                DEFAULT_SECONDS_PER_PAGE_CPU
+            })
+    }
+
+    /// Blend one observation into the rolling average (first sample is taken as-is).
+    fn record(&mut self, use_gpu: bool, observed_seconds_per_page: f64) {
+        let slot = if use_gpu {
+            &mut self.gpu
+        } else {
+            &mut self.cpu
+        };
+        *slot = Some(match slot.take() {
+            Some(prev)
+                if prev.avg_seconds_per_page.is_finite() && prev.avg_seconds_per_page > 0.0 =>
+            {
+                DeviceThroughput {
+                    avg_seconds_per_page: prev.avg_seconds_per_page
+                        * (1.0 - SECONDS_PER_PAGE_SMOOTHING)
+                        + observed_seconds_per_page * SECONDS_PER_PAGE_SMOOTHING,
+                    samples: prev.samples.saturating_add(1),
+                }
+            }
+            _ => DeviceThroughput {
+                avg_seconds_per_page: observed_seconds_per_page,
+                samples: 1,
+            },
+        });
+    }
+}
+
+fn metrics_path(project_root: &std::path::Path) -> PathBuf {
+    project_root.join("configs").join("metrics.json")
+}
+
+/// Missing or corrupt metrics fall back to defaults (estimates just start cold again).
+fn load_metrics(project_root: &std::path::Path) -> ThroughputMetrics {
+    let path = metrics_path(project_root);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return ThroughputMetrics::default();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        log::warn!("ignoring unreadable {}: {e}", path.display());
+        ThroughputMetrics::default()
+    })
+}
+
+fn save_metrics(project_root: &std::path::Path, metrics: &ThroughputMetrics) -> Result<(), String> {
+    let path = metrics_path(project_root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(metrics).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+/// Fold a finished job's throughput into `configs/metrics.json` (best-effort).
+fn record_throughput(project_root: &std::path::Path, use_gpu: bool, elapsed: Duration, pages: u32) {
+    if pages == 0 {
+        return;
+    }
+    let observed = elapsed.as_secs_f64() / pages as f64;
+    if !observed.is_finite() || observed <= 0.0 {
+        return;
+    }
+    let mut metrics = load_metrics(project_root);
+    metrics.record(use_gpu, observed);
+    if let Err(e) = save_metrics(project_root, &metrics) {
+        log::warn!("failed to persist metrics: {e}");
+    }
+}
+
+/// ジョブの出力名。本体は結果ディレクトリ内のファイル名、図版は入力ごとに同じ名前になるので
+/// 結果ディレクトリ名を付けた `<dir>/figures/<name>`（`find_output_path` が result/ に join して探す）
+fn output_file_names(files: &[PathBuf]) -> Vec<String> {
+    files
+        .iter()
+        .map(|p| {
+            let name = p
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let result_dir = output_result_dir(p);
+            if result_dir == p.parent() {
+                return name;
+            }
+            let dir_name = result_dir
+                .and_then(|d| d.file_name())
+                .unwrap_or_default()
+                .to_string_lossy();
+            format!("{dir_name}/{FIGURE_DIR_NAME}/{name}")
+        })
+        .collect()
+}
+
+fn skip_pending_files(job: &mut JobInfo) {
+    for f in job.files.iter_mut() {
+        if f.status == FileStatus::Pending {
+            f.status = FileStatus::Skipped;
+        }
+    }
+}
+
+/// Record a failed file. Returns true when the whole job should end here
+/// (i.e. `continue_on_error` is off), in which case the job is marked `Error`.
+fn mark_file_failed(
+    state: &AppState,
+    job_id: &str,
+    idx: usize,
+    error: String,
+    continue_on_error: bool,
+) -> bool {
+    if let Ok(mut jobs) = state.jobs.write() {
+        if let Some(job) = jobs.get_mut(job_id) {
+            let path = job
+                .files
+                .get(idx)
+                .map(|f| f.path.clone())
+                .unwrap_or_default();
+            if let Some(f) = job.files.get_mut(idx) {
+                f.status = FileStatus::Error;
+                f.error = Some(error.clone());
+            }
+            job.log.push(LogEntry::new(
+                LogSource::App,
+                LogLevel::Error,
+                format!("{path}: {error}"),
+            ));
+            if !continue_on_error {
+                skip_pending_files(job);
+                job.status = JobStatus::Error;
+                job.error = Some(error);
+            }
+        }
+    }
+    !continue_on_error
+}
+
+/// Poll the job's child (stored in `AppState.children`) until it exits, then drop the handle.
+/// Polling instead of `wait()` keeps the lock free so the exit handler can kill it meanwhile.
+fn wait_job_child(state: &AppState, job_id: &str) -> std::io::Result<ExitStatus> {
+    wait_job_child_until(state, job_id, None)?
+        .ok_or_else(|| std::io::Error::other("child process timed out"))
+}
+
+/// `wait_job_child` with a deadline: once it passes, the process tree is killed and `None`
+/// is returned.
+fn wait_job_child_until(
+    state: &AppState,
+    job_id: &str,
+    deadline: Option<Instant>,
+) -> std::io::Result<Option<ExitStatus>> {
+    loop {
+        {
+            let mut children = state
+                .children
+                .lock()
+                .map_err(|e| std::io::Error::other(format!("lock poisoned: {e}")))?;
+            let child = children
+                .get_mut(job_id)
+                .ok_or_else(|| std::io::Error::other("child process handle not found"))?;
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    children.remove(job_id);
+                    return Ok(Some(status));
+                }
+                Ok(None) if deadline.is_some_and(|d| Instant::now() >= d) => {
+                    let _ = kill_child_tree(child);
+                    let _ = child.wait();
+                    children.remove(job_id);
+                    return Ok(None);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    children.remove(job_id);
+                    return Err(e);
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Kill every dispatcher process still running, with the processes it spawned (used on app
+/// exit). Returns how many were killed.
+fn kill_running_children(state: &AppState) -> usize {
+    let Ok(mut children) = state.children.lock() else {
+        return 0;
+    };
+    let mut killed = 0;
+    for (job_id, child) in children.iter_mut() {
+        if matches!(child.try_wait(), Ok(None)) {
+            // kill_job_child と同じく、一時停止中のツリーは再開してから終了する
+            if is_paused(state, job_id) {
+                let _ = suspend_process_tree(child.id(), SuspendAction::Resume);
+            }
+            match kill_child_tree(child) {
+                Ok(()) => killed += 1,
+                Err(e) => log::warn!("failed to kill dispatcher for job {job_id}: {e}"),
+            }
+        }
+    }
+    killed
+}
+
+/// dispatcher とそれが起動したプロセス（ocr_chanked.py など）をまとめて終了する。
+/// ツリーごと止められなかったときは dispatcher だけでも kill する。
+fn kill_child_tree(child: &mut Child) -> std::io::Result<()> {
+    match kill_process_tree(child.id()) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::warn!("failed to kill process tree {}: {e}", child.id());
+            child.kill()
+        }
+    }
+}
+
+fn running_job_count(state: &AppState) -> usize {
+    state
+        .jobs
+        .read()
+        .map(|jobs| jobs.values().filter(|j| j.status.is_active()).count())
+        .unwrap_or(0)
+}
+
+/// ページマーカーが出ないまま処理開始からこの時間が過ぎたら、出力ディレクトリの増え方で進捗を推定する
+const BYTE_PROGRESS_AFTER: Duration = Duration::from_secs(15);
+const BYTE_PROGRESS_POLL: Duration = Duration::from_secs(2);
+/// 新しい出力がこのバイト数に達するとファイル内進捗がおよそ 6 割になる（ページ画像込みで数ページ分）
+const BYTE_PROGRESS_SCALE: f64 = 8.0 * 1024.0 * 1024.0;
+/// バイト数推定で進めてよいファイル内進捗の上限。残りはマーカーか完了で進める
+const BYTE_PROGRESS_CAP: f32 = 0.9;
+
+/// Coarse in-file progress (0..BYTE_PROGRESS_CAP) from bytes written so far.
+/// The final size is unknown, so this approaches the cap asymptotically.
+fn byte_progress_fraction(bytes: u64) -> f32 {
+    (f64::from(BYTE_PROGRESS_CAP) * (1.0 - (-(bytes as f64) / BYTE_PROGRESS_SCALE).exp())) as f32
+}
+
+/// Total size of files under `dir` modified at or after `since` (recursive).
+fn new_output_bytes(dir: &std::path::Path, since: SystemTime) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|e| {
+            let Ok(meta) = e.metadata() else {
+                return 0;
+            };
+            if meta.is_dir() {
+                new_output_bytes(&e.path(), since)
+            } else if meta.modified().is_ok_and(|m| m >= since) {
+                meta.len()
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Fallback progress for backends whose stdout has no page markers: until the first marker
+/// of the file arrives, poll the size of its result dir (`result/<output name>` when the job
+/// names it, else `result/<stem>*`) and report it as a capped progress patch.
+/// Marker-based progress stays authoritative since patches only ever move progress forward.
+struct ByteProgressWatcher {
+    done: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl ByteProgressWatcher {
+    fn spawn(
+        tx: mpsc::Sender<JobUpdate>,
+        result_root: PathBuf,
+        input: &str,
+        output_name: Option<&String>,
+        file_start: f32,
+        file_span: f32,
+        marker_seen: Arc<AtomicBool>,
+    ) -> Self {
+        let stem = std::path::Path::new(input)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let output_name = output_name.cloned();
+        // outputNameTemplate で名前を決めた入力は、その名前のディレクトリだけを見る
+        let is_output_dir = move |name: &str| match &output_name {
+            Some(expected) => name == expected,
+            None => !stem.is_empty() && is_result_dir_for_stem(name, &stem),
+        };
+        let done = Arc::new(AtomicBool::new(false));
+        let done_flag = done.clone();
+        let started = SystemTime::now();
+        let handle = thread::spawn(move || {
+            let mut waited = Duration::ZERO;
+            while !done_flag.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+                waited += Duration::from_millis(100);
+                if waited < BYTE_PROGRESS_POLL {
+                    continue;
+                }
+                waited = Duration::ZERO;
+                // 1 ページが遅いだけのときに推定で実際のページ数より先へ進めないよう、
+                // マーカーが 1 つでも出たファイルでは使わない
+                if marker_seen.load(Ordering::Relaxed) {
+                    break;
+                }
+                if started.elapsed().unwrap_or_default() < BYTE_PROGRESS_AFTER {
+                    continue;
+                }
+                let bytes: u64 = fs::read_dir(&result_root)
+                    .map(|entries| {
+                        entries
+                            .flatten()
+                            .filter(|e| is_output_dir(&e.file_name().to_string_lossy()))
+                            .map(|e| new_output_bytes(&e.path(), started))
+                            .sum()
+                    })
+                    .unwrap_or(0);
+                if bytes == 0 {
+                    continue;
+                }
+                let patch = ProgressPatch {
+                    progress: Some(file_start + file_span * byte_progress_fraction(bytes)),
+                    ..ProgressPatch::default()
+                };
+                if tx.send(JobUpdate::Progress(patch)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { done, handle }
+    }
+
+    /// Stop polling and drop the sender so the aggregator can finish.
+    fn stop(self) {
+        self.done.store(true, Ordering::Relaxed);
+        self.handle.join().unwrap_or(());
+    }
+}
+
+fn is_stop_requested(state: &AppState, job_id: &str) -> bool {
+    state
+        .jobs
+        .read()
+        .ok()
+        .and_then(|jobs| jobs.get(job_id).map(|j| j.stop_requested))
+        .unwrap_or(false)
+}
+
+fn job_tags(state: &AppState, job_id: &str) -> Vec<String> {
+    state
+        .jobs
+        .read()
+        .ok()
+        .and_then(|jobs| jobs.get(job_id).map(|j| j.tags.clone()))
+        .unwrap_or_default()
+}
+
+/// `AppSettings.post_hook` / `post_hook_required`（ジョブ開始時の設定）
+struct PostHook {
+    program: String,
+    required: bool,
+}
+
+/// post_hook の実行中に `current_message` に出す文言（この間ジョブは Running のまま）
+const POST_HOOK_MESSAGE: &str = "後処理: post_hook 実行中";
+
+/// post_hook 1 回の制限時間（アップロードなどを想定して長めにする）
+const POST_HOOK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// post_hook を `dir` を引数に 1 回実行する。出力は `[post_hook]` 付きでログにし、失敗の理由を Err で返す
+fn run_post_hook(
+    state: &AppState,
+    job_id: &str,
+    program: &str,
+    dir: &std::path::Path,
+    timeout: Duration,
+    log: &mut Vec<LogEntry>,
+) -> Result<(), String> {
+    log.push(LogEntry::app(format!(
+        "[post_hook] {program} {}",
+        dir.display()
+    )));
+    let mut child = Command::new(program)
+        .arg(dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run post hook: {e}"))?;
+    let stdout_handle = read_to_end_in_thread(child.stdout.take());
+    let stderr_handle = read_to_end_in_thread(child.stderr.take());
+    // dispatcher と同じく AppState に預け、キャンセル・アプリ終了で kill できるようにする
+    {
+        let mut children = state
+            .children
+            .lock()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        if is_cancelled(state, job_id) {
+            let _ = kill_child_tree(&mut child);
+        }
+        children.insert(job_id.to_string(), child);
+    }
+    let status = wait_job_child_until(state, job_id, Some(Instant::now() + timeout))
+        .map_err(|e| format!("failed to run post hook: {e}"))?;
+    let stdout = stdout_handle.join().unwrap_or_default();
+    let stderr = stderr_handle.join().unwrap_or_default();
+    for line in String::from_utf8_lossy(&stdout).lines() {
+        log.push(LogEntry::stdout(format!("[post_hook] {line}")));
+    }
+    for line in String::from_utf8_lossy(&stderr).lines() {
+        log.push(LogEntry::stderr(format!("[post_hook] {line}")));
+    }
+    let status =
+        status.ok_or_else(|| format!("post hook timed out after {} s", timeout.as_secs()))?;
+    if !status.success() {
+        return Err(format!("post hook failed: {status}"));
+    }
+    Ok(())
+}
+
+/// 結果ディレクトリ 1 つ分の post_hook を実行してジョブに反映する。
+/// 必須のフックが失敗してジョブを Error にしたときは false（残りのディレクトリでは実行しない）
+fn run_post_hook_for_job(
+    state: &AppState,
+    job_id: &str,
+    hook: &PostHook,
+    dir: &std::path::Path,
+) -> bool {
+    let mut log = Vec::new();
+    let result = run_post_hook(
+        state,
+        job_id,
+        &hook.program,
+        dir,
+        POST_HOOK_TIMEOUT,
+        &mut log,
+    );
+    let Ok(mut jobs) = state.jobs.write() else {
+        return false;
+    };
+    let Some(job) = jobs.get_mut(job_id) else {
+        return false;
+    };
+    job.log.extend(log);
+    // キャンセルで kill された終了コードはフックの失敗として扱わない
+    if job.status == JobStatus::Cancelled {
+        return false;
+    }
+    let Err(e) = result else {
+        return true;
+    };
+    let message = format!("{}: {e}", dir.display());
+    if hook.required {
+        job.log.push(LogEntry::new(
+            LogSource::App,
+            LogLevel::Error,
+            message.clone(),
+        ));
+        job.status = JobStatus::Error;
+        job.error = Some(message);
+        return false;
+    }
+    job.log
+        .push(LogEntry::new(LogSource::App, LogLevel::Warn, message));
+    true
+}
+
+fn file_path(state: &AppState, job_id: &str, idx: usize) -> Option<String> {
+    state
+        .jobs
+        .read()
+        .ok()?
+        .get(job_id)?
+        .files
+        .get(idx)
+        .map(|f| f.path.clone())
+}
+
+fn file_status(state: &AppState, job_id: &str, idx: usize) -> Option<FileStatus> {
+    state
+        .jobs
+        .read()
+        .ok()?
+        .get(job_id)?
+        .files
+        .get(idx)
+        .map(|f| f.status)
+}
+
+fn is_cancelled(state: &AppState, job_id: &str) -> bool {
+    state
+        .jobs
+        .read()
+        .ok()
+        .and_then(|jobs| jobs.get(job_id).map(|j| j.status == JobStatus::Cancelled))
+        .unwrap_or(false)
+}
+
+/// Emergency stop: cancel every running job and kill its dispatcher. Returns how many jobs were affected.
+#[tauri::command]
+fn cancel_all_jobs(state: State<Arc<AppState>>) -> Result<usize, String> {
+    let cancelled: Vec<(String, bool)> = {
+        let mut jobs = state
+            .jobs
+            .write()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        jobs.iter_mut()
+            .filter(|(_, job)| job.status.is_active())
+            .map(|(id, job)| {
+                let was_paused = job.status == JobStatus::Paused;
+                job.cancel();
+                (id.clone(), was_paused)
+            })
+            .collect()
+    };
+
+    for (id, was_paused) in &cancelled {
+        kill_job_child(&state, id, *was_paused)?;
+    }
+    Ok(cancelled.len())
+}
+
+/// ハンドルは wait_job_child が終了を見て片付けるので、ここでは kill だけする
+fn kill_job_child(state: &AppState, job_id: &str, was_paused: bool) -> Result<(), String> {
+    let mut children = state
+        .children
+        .lock()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    if let Some(child) = children.get_mut(job_id) {
+        // 止めたままのプロセスを残さないよう、先に再開してからツリーごと終了する
+        if was_paused {
+            let _ = suspend_process_tree(child.id(), SuspendAction::Resume);
+        }
+        if let Err(e) = kill_child_tree(child) {
+            log::warn!("failed to kill dispatcher for job {job_id}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// `cancel_and_discard` が子プロセスの終了を待つ上限（書き込み中のファイルを消し損ねないため）
+const DISCARD_WAIT: Duration = Duration::from_secs(5);
+
+/// Cancel a job (like `cancel_all_jobs`, but for one job) and delete the result dirs it
+/// had created and started writing, plus their work dirs under `tempDir`, once its process
+/// tree has exited. Dirs that already have a `run.json` hold a finished result and are kept,
+/// as are dirs that existed before the job. Also works on an already cancelled job.
+#[tauri::command(async)]
+fn cancel_and_discard(
+    job_id: String,
+    state: State<Arc<AppState>>,
+) -> Result<CleanupResult, String> {
+    let was_paused = {
+        let mut jobs = state
+            .jobs
+            .write()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        let job = jobs.get_mut(&job_id).ok_or("job not found")?;
+        let was_paused = job.status == JobStatus::Paused;
+        if job.status.is_active() {
+            job.cancel();
+        } else if job.status != JobStatus::Cancelled {
+            return Err("job is not running".into());
+        }
+        was_paused
+    };
+    kill_job_child(&state, &job_id, was_paused)?;
+
+    // プロセスツリーが終わるまで待つ（書き込み中のディレクトリを消しても作り直されるため）
+    let deadline = Instant::now() + DISCARD_WAIT;
+    loop {
+        let running = state
+            .children
+            .lock()
+            .map_err(|e| format!("lock poisoned: {e}"))?
+            .contains_key(&job_id);
+        if !running {
+            break;
+        }
+        if Instant::now() >= deadline {
+            return Err("dispatcher is still running; partial outputs were kept".into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let temp_dir = configured_temp_dir(&project_root).ok().flatten();
+    let mut jobs = state
+        .jobs
+        .write()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let job = jobs.get_mut(&job_id).ok_or("job not found")?;
+    let result = discard_output_dirs(
+        &project_root,
+        &job.output_dirs,
+        &job.created_dirs,
+        temp_dir.as_deref(),
+    )?;
+    job.log.push(LogEntry::app(format!(
+        "discarded partial outputs: {} dirs, {} bytes",
+        result.removed.len(),
+        result.freed_bytes
+    )));
+    Ok(result)
+}
+
+/// Remove `result/<name>` for each name in `created` (resolved inside the result root) and
+/// `<tempDir>/<name>` for each name in `dir_names`. Missing dirs are skipped; so are result dirs
+/// that already have `run.json` and ones the job did not create (an older result it re-ran into).
+fn discard_output_dirs(
+    project_root: &std::path::Path,
+    dir_names: &[String],
+    created: &[String],
+    temp_dir: Option<&std::path::Path>,
+) -> Result<CleanupResult, String> {
+    let mut result = CleanupResult::default();
+    for name in dir_names {
+        validate_result_dir_name(name)?;
+        if let Ok(dir) = resolve_result_dir(project_root, name) {
+            if created.contains(name) && !dir.join(RUN_CONFIG_FILE).exists() {
+                remove_real_dir(&dir, &mut result)?;
+            }
+        }
+        if let Some(temp) = temp_dir {
+            remove_real_dir(&temp.join(name), &mut result)?;
+        }
+    }
+    Ok(result)
+}
+
+/// 終わったジョブを一覧から外す（結果ファイルには触れない）。実行中のジョブは外せない。
+#[tauri::command]
+fn remove_job(job_id: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    let mut jobs = state
+        .jobs
+        .write()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let job = jobs.get(&job_id).ok_or("job not found")?;
+    if job.status.is_active() {
+        return Err("job is still running".into());
+    }
+    jobs.remove(&job_id);
+    Ok(())
+}
+
+/// Suspend a running job's dispatcher (and the processes it spawned) at the OS level:
+/// SIGSTOP to the process group on Unix, thread suspension on Windows.
+/// 途中経過を保存するわけではないので、一時停止中にアプリを終了すると処理は失われる。
+#[tauri::command]
+fn pause_job(job_id: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    set_job_paused(&state, &job_id, true)
+}
+
+/// Resume a job suspended by `pause_job`.
+#[tauri::command]
+fn resume_job(job_id: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    set_job_paused(&state, &job_id, false)
+}
+
+fn set_job_paused(state: &AppState, job_id: &str, pause: bool) -> Result<(), String> {
+    // ロック順は children → jobs（子プロセス登録時と揃える）
+    let children = state
+        .children
+        .lock()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let mut jobs = state
+        .jobs
+        .write()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let job = jobs.get_mut(job_id).ok_or("job not found")?;
+    if job.finalizing {
+        return Err("job is running its post hook".into());
+    }
+    let (from, to, action) = if pause {
+        (
+            JobStatus::Running,
+            JobStatus::Paused,
+            SuspendAction::Suspend,
+        )
+    } else {
+        (JobStatus::Paused, JobStatus::Running, SuspendAction::Resume)
+    };
+    if job.status != from {
+        return Err(if pause {
+            "job is not running".into()
+        } else {
+            "job is not paused".into()
+        });
+    }
+    // ファイルの合間で子プロセスが無いときは状態だけ切り替える（次のファイルは再開まで始めない）
+    if let Some(child) = children.get(job_id) {
+        suspend_process_tree(child.id(), action)?;
+    }
+    job.status = to;
+    if pause {
+        job.eta_seconds = None;
+        job.current_message = Some("一時停止中".into());
+        job.log.push(LogEntry::app("job paused"));
+    } else {
+        job.current_message = None;
+        job.log.push(LogEntry::app("job resumed"));
+    }
+    Ok(())
+}
+
+fn is_paused(state: &AppState, job_id: &str) -> bool {
+    state
+        .jobs
+        .read()
+        .ok()
+        .and_then(|jobs| jobs.get(job_id).map(|j| j.status == JobStatus::Paused))
+        .unwrap_or(false)
+}
+
+/// Block the worker between files while the job is paused.
+fn wait_while_paused(state: &AppState, job_id: &str) {
+    while is_paused(state, job_id) {
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// Sleep for `duration` in short steps; returns false as soon as the job is cancelled.
+fn sleep_unless_cancelled(state: &AppState, job_id: &str, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        if is_cancelled(state, job_id) {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(200).min(deadline - Instant::now()));
+    }
+    !is_cancelled(state, job_id)
+}
+
+/// `maxRetries` の上限（バックオフが長くなりすぎないように）
+const MAX_RETRIES_LIMIT: u32 = 10;
+const RETRY_BACKOFF_BASE: Duration = Duration::from_secs(2);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Backoff before retry number `attempt` (1-based): 2s, 4s, 8s, ... capped at 60s.
+fn retry_backoff(attempt: u32) -> Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    RETRY_BACKOFF_BASE
+        .saturating_mul(factor)
+        .min(RETRY_BACKOFF_MAX)
+}
+
+/// 再試行してよい失敗（モデル取得のタイムアウト・接続断など）を示す stderr の文言（小文字で比較）。
+/// 入力ファイルの不備などはここに当たらないので再試行しない。
+const TRANSIENT_FAILURE_MARKERS: &[&str] = &[
+    "timed out",
+    "timeouterror",
+    "readtimeout",
+    "connecttimeout",
+    "connectionerror",
+    "connection reset",
+    "connection aborted",
+    "remotedisconnected",
+    "incompleteread",
+    "max retries exceeded",
+    "temporary failure in name resolution",
+    "name or service not known",
+    "urlerror",
+    "failed to download",
+];
+
+fn is_transient_failure_line(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    TRANSIENT_FAILURE_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SuspendAction {
+    Suspend,
+    Resume,
+}
+
+/// dispatcher は spawn 時に自分のプロセスグループのリーダーにしてあるので、グループごと止める
+/// （dispatcher が起動した ocr_chanked.py なども一緒に止まる）。
+#[cfg(unix)]
+fn suspend_process_tree(pid: u32, action: SuspendAction) -> Result<(), String> {
+    let signal = match action {
+        SuspendAction::Suspend => "-STOP",
+        SuspendAction::Resume => "-CONT",
+    };
+    let status = Command::new("kill")
+        .arg(signal)
+        .arg("--")
+        .arg(format!("-{pid}"))
+        .status()
+        .map_err(|e| format!("failed to run kill: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("kill {signal} failed for process group {pid}"))
+    }
+}
+
+/// プロセスグループごと SIGKILL する（dispatcher はグループリーダーとして起動している）。
+#[cfg(unix)]
+fn kill_process_tree(pid: u32) -> Result<(), String> {
+    let status = Command::new("kill")
+        .arg("-KILL")
+        .arg("--")
+        .arg(format!("-{pid}"))
+        .status()
+        .map_err(|e| format!("failed to run kill: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("kill -KILL failed for process group {pid}"))
+    }
+}
+
+/// `suspend_process_tree` と同じツリーを辿り、子孫から順に TerminateProcess する。
+#[cfg(windows)]
+fn kill_process_tree(pid: u32) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, TH32CS_SNAPPROCESS,
+    };
+    use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    // SAFETY: スナップショットと開いたプロセスのハンドルは同じスコープで閉じる。
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err("failed to snapshot processes".into());
+        }
+        let tree = process_tree(pid, &snapshot_parents(snapshot));
+        CloseHandle(snapshot);
+
+        let mut terminated = 0;
+        // 書き込み中の孫（ocr_chanked.py など）から先に終了する
+        for &p in tree.iter().rev() {
+            let handle = OpenProcess(PROCESS_TERMINATE, 0, p);
+            if !handle.is_null() {
+                if TerminateProcess(handle, 1) != 0 {
+                    terminated += 1;
+                }
+                CloseHandle(handle);
+            }
+        }
+        if terminated == 0 {
+            return Err(format!("failed to terminate process {pid}"));
+        }
+    }
+    Ok(())
+}
+
+/// Toolhelp のスナップショットにある全プロセスの (pid, 親 pid)。
+///
+/// # Safety
+/// `snapshot` は `TH32CS_SNAPPROCESS` を含めて作った有効なハンドルであること。
+#[cfg(windows)]
+unsafe fn snapshot_parents(snapshot: windows_sys::Win32::Foundation::HANDLE) -> Vec<(u32, u32)> {
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        Process32FirstW, Process32NextW, PROCESSENTRY32W,
+    };
+
+    let mut parents = Vec::new();
+    let mut process: PROCESSENTRY32W = std::mem::zeroed();
+    process.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+    let mut more = Process32FirstW(snapshot, &mut process) != 0;
+    while more {
+        parents.push((process.th32ProcessID, process.th32ParentProcessID));
+        more = Process32NextW(snapshot, &mut process) != 0;
+    }
+    parents
+}
+
+/// Windows にはプロセス単位の公開 API が無いので、dispatcher 配下の全プロセスのスレッドを
+/// SuspendThread / ResumeThread する。
+#[cfg(windows)]
+fn suspend_process_tree(pid: u32, action: SuspendAction) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPPROCESS,
+        TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows_sys::Win32::System::Threading::{
+        OpenThread, ResumeThread, SuspendThread, THREAD_SUSPEND_RESUME,
+    };
+
+    // SAFETY: Toolhelp のエントリは dwSize を設定したゼロ初期化構造体で受け取り、
+    // 開いたハンドルはすべて同じスコープで閉じる。
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS | TH32CS_SNAPTHREAD, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err("failed to snapshot processes".into());
+        }
+        let tree = process_tree(pid, &snapshot_parents(snapshot));
+
+        let mut touched = 0;
+        let mut thread: THREADENTRY32 = std::mem::zeroed();
+        thread.dwSize = std::mem::size_of::<THREADENTRY32>() as u32;
+        let mut more = Thread32First(snapshot, &mut thread) != 0;
+        while more {
+            if tree.contains(&thread.th32OwnerProcessID) {
+                let handle = OpenThread(THREAD_SUSPEND_RESUME, 0, thread.th32ThreadID);
+                if !handle.is_null() {
+                    match action {
+                        SuspendAction::Suspend => SuspendThread(handle),
+                        SuspendAction::Resume => ResumeThread(handle),
+                    };
+                    CloseHandle(handle);
+                    touched += 1;
+                }
+            }
+            more = Thread32Next(snapshot, &mut thread) != 0;
+        }
+        CloseHandle(snapshot);
+
+        if touched == 0 {
+            return Err(format!("no threads found for process {pid}"));
+        }
+    }
+    Ok(())
+}
+
+/// `root` とその子孫の pid（`parents` は (pid, 親 pid) の一覧）。
+#[cfg(any(windows, test))]
+fn process_tree(root: u32, parents: &[(u32, u32)]) -> Vec<u32> {
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        for &(pid, ppid) in parents {
+            if ppid == parent && pid != parent && !tree.contains(&pid) {
+                tree.push(pid);
+            }
+        }
+        i += 1;
+    }
+    tree
+}
+
+/// Drop one file from a running batch: a pending file is skipped, a running one has its
+/// dispatcher killed and is marked `Cancelled`. The batch then continues with the next file.
+#[tauri::command]
+fn skip_file(job_id: String, path: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    let (running, was_paused) = {
+        let mut jobs = state
+            .jobs
+            .write()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        let job = jobs.get_mut(&job_id).ok_or("job not found")?;
+        if !job.status.is_active() {
+            return Err("job is not running".into());
+        }
+        (job.skip_file(&path)?, job.status == JobStatus::Paused)
+    };
+    if running {
+        kill_job_child(&state, &job_id, was_paused)?;
+    }
+    Ok(())
+}
+
+/// Change the processing order of the files a running batch has not started yet.
+/// `new_order` must be a permutation of the job's pending files; finished and running
+/// files keep their place.
+#[tauri::command]
+fn reorder_job_files(
+    job_id: String,
+    new_order: Vec<String>,
+    state: State<Arc<AppState>>,
+) -> Result<(), String> {
+    let mut jobs = state
+        .jobs
+        .write()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let job = jobs.get_mut(&job_id).ok_or("job not found")?;
+    if !job.status.is_active() {
+        return Err("job is not running".into());
+    }
+    job.reorder_files(&new_order)
+}
+
+/// Soft-cancel: let the current file finish, then end the job as `Done` before the next file.
+#[tauri::command]
+fn request_stop(job_id: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    let mut jobs = state
+        .jobs
+        .write()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let job = jobs.get_mut(&job_id).ok_or("job not found")?;
+    if !job.status.is_active() {
+        return Err("job is not running".into());
+    }
+    if !job.stop_requested {
+        job.stop_requested = true;
+        job.log.push(LogEntry::app("stop requested"));
+        job.current_message = Some(match job.current_message.take() {
+            Some(msg) => format!("{msg}（{STOP_PENDING_SUFFIX}）"),
+            None => STOP_PENDING_SUFFIX.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Emit `job://done` for a finished job and, when `notify_on_complete` is enabled,
+/// show a native notification. Both are best-effort.
+fn notify_job_finished(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    project_root: &std::path::Path,
+    job_id: &str,
+) {
+    // どの終わり方（完了・失敗・キャンセル）でも必ずここを通るので、終了時刻もここで記録する
+    let payload = match state.jobs.write() {
+        Ok(mut jobs) => match jobs.get_mut(job_id) {
+            Some(job) => {
+                job.finished_at_ms.get_or_insert_with(now_ms);
+                JobDoneEvent {
+                    job_id: job_id.to_string(),
+                    status: job.status.clone(),
+                    output_count: job.outputs.len(),
+                    error: job.error.clone(),
+                }
+            }
+            None => return,
+        },
+        Err(_) => return,
+    };
+    let _ = app.emit("job://done", payload.clone());
+
+    let enabled = load_settings_from_disk(project_root)
+        .map(|s| s.notify_on_complete)
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+    // プラグイン未登録なら state が無いので何もしない
+    if app
+        .try_state::<tauri_plugin_notification::Notification<tauri::Wry>>()
+        .is_none()
+    {
+        return;
+    }
+    let granted = matches!(
+        app.notification().permission_state(),
+        Ok(tauri::plugin::PermissionState::Granted)
+    );
+    if !granted {
+        return;
+    }
+
+    let body = match payload.status {
+        // 自分で止めたジョブは通知しない
+        JobStatus::Cancelled => return,
+        JobStatus::Done => format!("処理が完了しました（出力 {} 件）", payload.output_count),
+        _ => format!(
+            "処理に失敗しました: {}",
+            payload.error.as_deref().unwrap_or("unknown error")
+        ),
+    };
+    show_completion_notification(app, body);
+}
+
+/// 完了通知を notification プラグインで出す。クリックはプラグインの `actionPerformed` イベントで
+/// フロントエンドに届くので、そこから `focus_main_window` を呼んでもらう
+fn show_completion_notification(app: &tauri::AppHandle, body: String) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("OCR to Doc")
+        .body(body)
+        .show()
+    {
+        log::warn!("failed to show completion notification: {e}");
+    }
+}
+
+/// 最小化・非表示を戻してメインウィンドウにフォーカスする
+#[tauri::command]
+fn focus_main_window(app: tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+#[tauri::command]
+// 引数はそのまま invoke のキーになるので、構造体にまとめず個別に受ける
+#[allow(clippy::too_many_arguments)]
+fn render_preview(
+    path: String,
+    page: Option<u32>,
+    crop: Option<CropRect>,
+    max_long_edge: Option<u32>,
+    rotate: Option<i32>,
+    auto_deskew: Option<bool>,
+    auto_orient: Option<bool>,
+    password: Option<String>,
+    format: Option<String>,
+    quality: Option<u8>,
+    state: State<Arc<AppState>>,
+) -> Result<PreviewResponse, String> {
+    validate_rotate(rotate)?;
+    validate_preview_format(format.as_deref(), quality)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    run_preview_helper(
+        &project_root,
+        std::path::Path::new(&path),
+        &PreviewParams {
+            page,
+            crop,
+            max_long_edge,
+            rotate,
+            auto_deskew,
+            auto_orient: auto_orient.unwrap_or(false),
+            outline: false,
+            password,
+            format,
+            quality,
+            tile: None,
+            exact_crop: false,
+            dpi: None,
+        },
+    )
+}
+
+/// 大きなページ（A0 図面など）をズーム表示するための 1 タイル分のプレビュー。
+/// ページを `zoom` 倍（1.0 = 通常プレビューと同じ 150dpi 相当）で描画し、`tile_size` px 四方の
+/// `(tile_x, tile_y)` 番目だけを返す。列数・行数は応答の `tile` にある。
+/// ページは最初のタイルで一度だけ描いて全タイルに切り分けて残し（ui_preview.py `TILE_CACHE_ROOT`）、
+/// 同じページ・倍率の残りのタイルはそこから返す
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn render_tile(
+    path: String,
+    page: Option<u32>,
+    tile_x: u32,
+    tile_y: u32,
+    tile_size: u32,
+    zoom: Option<f32>,
+    password: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<PreviewResponse, String> {
+    let tile = TileRequest {
+        x: tile_x,
+        y: tile_y,
+        size: tile_size,
+        zoom: zoom.unwrap_or(1.0),
+    };
+    validate_tile_request(&tile)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    run_preview_helper(
+        &project_root,
+        std::path::Path::new(&path),
+        &PreviewParams {
+            page,
+            password,
+            tile: Some(tile),
+            ..PreviewParams::default()
+        },
+    )
+}
+
+/// 実行時と同じ処理（crop_rect.py の丸め・pdfDpi での描画・回転→傾き補正→トリミングの順）で
+/// 切り抜いたプレビュー。UI 上の枠と実際の切り抜きのずれを確かめる用途。
+/// `crop` は回転・傾き補正後の画像に対する 0〜1 の割合（`left,top,width,height`）で、
+/// 各辺は `round(割合 × 画像の px)` に丸められる。応答の `effectiveCrop` に実際の px 範囲が入る
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn preview_effective_crop(
+    path: String,
+    page: Option<u32>,
+    crop: CropRect,
+    rotate: Option<i32>,
+    auto_deskew: Option<bool>,
+    pdf_dpi: Option<u32>,
+    max_long_edge: Option<u32>,
+    password: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<PreviewResponse, String> {
+    validate_rotate(rotate)?;
+    if let Some(dpi) = pdf_dpi.filter(|d| !(72..=600).contains(d)) {
+        return Err(format!("pdfDpi must be between 72 and 600: {dpi}"));
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    run_preview_helper(
+        &project_root,
+        std::path::Path::new(&path),
+        &PreviewParams {
+            page,
+            crop: Some(crop),
+            max_long_edge,
+            rotate,
+            auto_deskew,
+            password,
+            // 境界の 1px を見比べられるよう可逆の PNG にする
+            format: Some("png".into()),
+            exact_crop: true,
+            dpi: pdf_dpi,
+            ..PreviewParams::default()
+        },
+    )
+}
+
+/// `preview_batch_first_pages` で `max_long_edge` を省いたときの長辺（一覧のサムネイル向け）
+const PREVIEW_BATCH_LONG_EDGE: u32 = 240;
+
+/// 複数ファイルの 1 ページ目を小さく描く。ui_preview.py `--batch` を 1 回だけ起動するので、
+/// ファイルごとに Python とライブラリを読み込み直すより速い。
+/// 応答は `paths` と同じ順で、各要素の `path` に入力パスが入る。1 ファイルの失敗は全体のエラーにせず、
+/// その要素の `errorKind` / `error` で返す（パスワード付き PDF は `encrypted`）
+#[tauri::command(async)]
+fn preview_batch_first_pages(
+    paths: Vec<String>,
+    max_long_edge: Option<u32>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<PreviewResponse>, String> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let list = TempFile(
+        std::env::temp_dir().join(format!("ocr_to_doc_preview_batch_{}.json", Uuid::new_v4())),
+    );
+    let json = serde_json::to_string(&paths).map_err(|e| e.to_string())?;
+    fs::write(&list.0, json).map_err(|e| format!("failed to write preview list: {e}"))?;
+
+    let mut cmd = preview_helper_command(&project_root)?;
+    cmd.arg("--batch")
+        .arg(&list.0)
+        .arg("--max-long-edge")
+        .arg(max_long_edge.unwrap_or(PREVIEW_BATCH_LONG_EDGE).to_string())
+        .arg("--format")
+        .arg("auto");
+    // 1 回の起動で全ファイルを描くので、制限時間もファイル数に比例させる
+    let count = u32::try_from(paths.len()).unwrap_or(u32::MAX);
+    let timeout = preview_timeout(&project_root).saturating_mul(count);
+    let stdout = run_preview_command(&project_root, &mut cmd, timeout)?;
+    parse_batch_preview_output(&stdout, paths.len())
+}
+
+fn parse_batch_preview_output(
+    stdout: &str,
+    expected: usize,
+) -> Result<Vec<PreviewResponse>, String> {
+    let responses = serde_json::from_str::<Vec<PreviewResponse>>(stdout).map_err(|e| {
+        format!("{PREVIEW_ERROR_RENDER_FAILED}: failed to parse preview helper output: {e}")
+    })?;
+    if responses.len() != expected {
+        return Err(format!(
+            "{PREVIEW_ERROR_RENDER_FAILED}: preview helper returned {} pages for {expected} files",
+            responses.len()
+        ));
+    }
+    Ok(responses)
+}
+
+/// `validate_crop_selection` の結果: トリミング後の画像と、枠を描いた全体画像
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CropCheck {
+    cropped: PreviewResponse,
+    outlined: PreviewResponse,
+}
+
+/// 長時間の実行前に、代表ページでトリミング範囲が意図どおりかを並べて確認する。
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn validate_crop_selection(
+    path: String,
+    page: Option<u32>,
+    crop: CropRect,
+    max_long_edge: Option<u32>,
+    rotate: Option<i32>,
+    auto_deskew: Option<bool>,
+    password: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<CropCheck, String> {
+    validate_rotate(rotate)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let input = std::path::Path::new(&path);
+    let params = PreviewParams {
+        page,
+        crop: Some(crop),
+        max_long_edge,
+        rotate,
+        auto_deskew,
+        auto_orient: false,
+        outline: false,
+        password,
+        // 枠と切り抜きの位置を見比べる用途なので可逆の PNG のままにする
+        format: Some("png".into()),
+        quality: None,
+        tile: None,
+        exact_crop: false,
+        dpi: None,
+    };
+    let cropped = run_preview_helper(&project_root, input, &params)?;
+    if cropped.error_kind.is_some() {
+        return Err(preview_error_message(&cropped));
+    }
+    let outlined = run_preview_helper(
+        &project_root,
+        input,
+        &PreviewParams {
+            outline: true,
+            ..params
+        },
+    )?;
+    Ok(CropCheck { cropped, outlined })
+}
+
+/// クリップボードから貼り付けた画像など、ファイルになっていないデータのプレビュー。
+/// `data` は base64（`data:image/png;base64,` の接頭辞付きでも可）。
+#[tauri::command]
+fn render_preview_bytes(
+    data: String,
+    crop: Option<CropRect>,
+    max_long_edge: Option<u32>,
+    rotate: Option<i32>,
+    auto_deskew: Option<bool>,
+    state: State<Arc<AppState>>,
+) -> Result<PreviewResponse, String> {
+    validate_rotate(rotate)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let payload = data
+        .split_once(";base64,")
+        .map_or(data.as_str(), |(_, b)| b);
+    let bytes = BASE64_STANDARD
+        .decode(payload.trim())
+        .map_err(|e| format!("invalid base64 data: {e}"))?;
+    let ext = sniff_image_extension(&bytes).ok_or("unsupported image data")?;
+
+    let tmp =
+        TempFile(std::env::temp_dir().join(format!("ocr_to_doc_paste_{}.{ext}", Uuid::new_v4())));
+    fs::write(&tmp.0, &bytes).map_err(|e| format!("failed to write temp image: {e}"))?;
+    run_preview_helper(
+        &project_root,
+        &tmp.0,
+        &PreviewParams {
+            page: None,
+            crop,
+            max_long_edge,
+            rotate,
+            auto_deskew,
+            ..PreviewParams::default()
+        },
+    )
+}
+
+/// Removes the file when dropped, so temp inputs are cleaned up on every return path.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Guess a file extension ui_preview.py understands from the leading magic bytes.
+fn sniff_image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"GIF8") {
+        Some("gif")
+    } else if bytes.starts_with(b"BM") {
+        Some("bmp")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        Some("tiff")
+    } else {
+        None
+    }
+}
+
+/// ui_preview.py に渡すプレビュー条件
+#[derive(Debug, Default, Clone)]
+struct PreviewParams {
+    page: Option<u32>,
+    crop: Option<CropRect>,
+    max_long_edge: Option<u32>,
+    rotate: Option<i32>,
+    auto_deskew: Option<bool>,
+    /// 実行時の `autoOrient` と同じ判定で横倒しのページを回す
+    auto_orient: bool,
+    /// トリミングせず、全体画像にトリミング枠を描く
+    outline: bool,
+    password: Option<String>,
+    /// `PREVIEW_FORMATS` のいずれか。未指定は auto（大きいページだけ JPEG）
+    format: Option<String>,
+    /// jpeg / webp の品質（1〜100）。未指定は ui_preview.py の既定（80）
+    quality: Option<u8>,
+    /// 指定があればページ全体ではなくこのタイル 1 枚だけを返す（crop / max_long_edge は無視）
+    tile: Option<TileRequest>,
+    /// 実行時と同じ解像度で描いてから切り抜く（`--exact-crop`。outline は無視）
+    exact_crop: bool,
+    /// `exact_crop` で PDF を描く DPI（実行時の pdfDpi。未指定は ocr_chanked.py と同じ 300）
+    dpi: Option<u32>,
+}
+
+/// `render_tile` で切り出すタイル（列・行は 0 起点）
+#[derive(Debug, Clone, Copy)]
+struct TileRequest {
+    x: u32,
+    y: u32,
+    size: u32,
+    zoom: f32,
+}
+
+/// ui_preview.py の `--tile-size` / `--zoom` と同じ範囲
+const TILE_SIZE_RANGE: std::ops::RangeInclusive<u32> = 64..=2048;
+const TILE_ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.0;
+
+fn validate_tile_request(tile: &TileRequest) -> Result<(), String> {
+    if !TILE_SIZE_RANGE.contains(&tile.size) {
+        return Err(format!(
+            "tile_size must be between {} and {}: {}",
+            TILE_SIZE_RANGE.start(),
+            TILE_SIZE_RANGE.end(),
+            tile.size
+        ));
+    }
+    if !TILE_ZOOM_RANGE.contains(&tile.zoom) {
+        return Err(format!(
+            "zoom must be between {} and {}: {}",
+            TILE_ZOOM_RANGE.start(),
+            TILE_ZOOM_RANGE.end(),
+            tile.zoom
+        ));
+    }
+    Ok(())
+}
+
+/// ui_preview.py `--format` が受け付ける値
+const PREVIEW_FORMATS: &[&str] = &["auto", "png", "jpeg", "webp"];
+
+fn validate_preview_format(format: Option<&str>, quality: Option<u8>) -> Result<(), String> {
+    if let Some(f) = format.filter(|f| !PREVIEW_FORMATS.contains(f)) {
+        return Err(format!(
+            "unsupported preview format: {f} (valid: {})",
+            PREVIEW_FORMATS.join(", ")
+        ));
+    }
+    if let Some(q) = quality.filter(|q| !(1..=100).contains(q)) {
+        return Err(format!("quality must be between 1 and 100: {q}"));
+    }
+    Ok(())
+}
+
+fn run_preview_helper(
+    project_root: &std::path::Path,
+    path: &std::path::Path,
+    params: &PreviewParams,
+) -> Result<PreviewResponse, String> {
+    let PreviewParams {
+        page,
+        crop,
+        max_long_edge,
+        rotate,
+        auto_deskew,
+        auto_orient,
+        outline,
+        password,
+        format,
+        quality,
+        tile,
+        exact_crop,
+        dpi,
+    } = params;
+    let mut cmd = preview_helper_command(project_root)?;
+    if !path.is_file() {
+        return Err(format!(
+            "{PREVIEW_ERROR_NOT_FOUND}: ファイルが見つかりません: {}",
+            path.display()
+        ));
+    }
+
+    cmd.arg("--input")
+        .arg(path)
+        .arg("--page")
+        .arg(page.unwrap_or(1).to_string());
+
+    if let Some(c) = crop {
+        cmd.arg("--crop").arg(c.to_arg());
+    }
+    if *exact_crop {
+        cmd.arg("--exact-crop");
+        if let Some(dpi) = dpi {
+            cmd.arg("--dpi").arg(dpi.to_string());
+        }
+    }
+    if let Some(max_le) = max_long_edge {
+        cmd.arg("--max-long-edge").arg(max_le.to_string());
+    }
+    // 実行時と同じ向きで表示する（トリミング枠もこの向きで指定される）
+    if let Some(r) = rotate.filter(|r| *r != 0) {
+        cmd.arg("--rotate").arg(r.to_string());
+    }
+    if auto_deskew.unwrap_or(false) {
+        cmd.arg("--deskew");
+    }
+    if *auto_orient {
+        cmd.arg("--auto-orient");
+    }
+    if *outline {
+        cmd.arg("--outline");
+    }
+    apply_pdf_password(&mut cmd, password.as_deref());
+    cmd.arg("--format").arg(format.as_deref().unwrap_or("auto"));
+    if let Some(q) = quality {
+        cmd.arg("--quality").arg(q.to_string());
+    }
+    if let Some(t) = tile {
+        cmd.arg("--tile-size")
+            .arg(t.size.to_string())
+            .arg("--tile-x")
+            .arg(t.x.to_string())
+            .arg("--tile-y")
+            .arg(t.y.to_string())
+            .arg("--zoom")
+            .arg(t.zoom.to_string());
+    }
+
+    let stdout = run_preview_command(project_root, &mut cmd, preview_timeout(project_root))?;
+    serde_json::from_str::<PreviewResponse>(&stdout).map_err(|e| {
+        format!("{PREVIEW_ERROR_RENDER_FAILED}: failed to parse preview helper output: {e}")
+    })
+}
+
+/// `python -u ui_preview.py` までを組み立てたコマンド（引数は呼び出し側で足す）
+fn preview_helper_command(project_root: &std::path::Path) -> Result<Command, String> {
+    let helper = resolve_python_entry(project_root, "ui_preview.py");
+    if !helper.exists() {
+        return Err(format!("ui_preview.py not found at {}", helper.display()));
+    }
+    let mut cmd = Command::new(resolve_python_bin(project_root));
+    apply_extra_env(&mut cmd, &configured_extra_env(project_root));
+    apply_python_env(&mut cmd);
+    cmd.arg("-u").arg(helper);
+    Ok(cmd)
+}
+
+/// ui_preview.py を制限時間付きで実行して stdout を返す。失敗は `<errorKind>: ...` の文字列にする
+fn run_preview_command(
+    project_root: &std::path::Path,
+    cmd: &mut Command,
+    timeout: Duration,
+) -> Result<String, String> {
+    cmd.current_dir(project_root);
+    let output = output_with_timeout(cmd, timeout)
+        .map_err(|e| format!("{PREVIEW_ERROR_RENDER_FAILED}: failed to run preview helper: {e}"))?
+        .ok_or_else(|| {
+            format!(
+                "{PREVIEW_ERROR_TIMEOUT}: プレビューの作成が {} 秒を超えたため中止しました",
+                timeout.as_secs()
+            )
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(format!(
+            "{}: preview helper failed: {}",
+            classify_preview_failure(&stderr),
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// ui_preview.py の失敗を UI が対処を出し分けられる種別にする（stderr の末尾の例外から判断）。
+fn classify_preview_failure(stderr: &str) -> &'static str {
+    let lower = stderr.to_lowercase();
+    if lower.contains("input not found") || lower.contains("filenotfounderror: [errno") {
+        PREVIEW_ERROR_NOT_FOUND
+    } else if lower.contains("password") {
+        PREVIEW_ERROR_ENCRYPTED
+    } else if PREVIEW_UNSUPPORTED_MARKERS
+        .iter()
+        .any(|m| lower.contains(m))
+    {
+        PREVIEW_ERROR_UNSUPPORTED
+    } else {
+        PREVIEW_ERROR_RENDER_FAILED
+    }
+}
+
+/// 読めない・対応していない形式のときに Pillow / poppler が出すメッセージ（小文字）
+const PREVIEW_UNSUPPORTED_MARKERS: &[&str] = &[
+    "cannot identify image file",
+    "unidentifiedimageerror",
+    "unable to get page count",
+    "pdfpagecounterror",
+    "pdfsyntaxerror",
+    "unsupported",
+];
+
+/// ui_preview.py の既定の制限時間（壊れた PDF などで固まったときに打ち切る）
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 制限時間を超えて打ち切ったときのエラー種別
+const PREVIEW_ERROR_TIMEOUT: &str = "timeout";
+
+/// settings.json の `previewTimeoutSeconds`（未指定・読めなければ `PREVIEW_TIMEOUT`）
+fn preview_timeout(project_root: &std::path::Path) -> Duration {
+    load_settings_from_disk(project_root)
+        .ok()
+        .and_then(|s| s.preview_timeout_seconds)
+        .map_or(PREVIEW_TIMEOUT, |secs| Duration::from_secs(u64::from(secs)))
+}
+
+/// `python --version` / `pip freeze` / `--list-languages` など短い問い合わせの制限時間
+const PYTHON_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `Command::output` with a deadline: stdout/stderr are drained on threads (the preview
+/// data URL can exceed the pipe buffer) and the child is killed once `timeout` passes.
+/// Returns `Ok(None)` on timeout.
+/// パイプを別スレッドで最後まで読む（stdout と stderr の片方が詰まって子プロセスが止まらないように）
+fn read_to_end_in_thread<R: std::io::Read + Send + 'static>(
+    stream: Option<R>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut r) = stream {
+            let _ = r.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+fn output_with_timeout(
+    cmd: &mut Command,
+    timeout: Duration,
+) -> Result<Option<std::process::Output>, String> {
+    cmd.stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    let started = Instant::now();
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+    let stdout_handle = read_to_end_in_thread(child.stdout.take());
+    let stderr_handle = read_to_end_in_thread(child.stderr.take());
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = kill_child_tree(&mut child);
+                let _ = child.wait();
+                break None;
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => {
+                let _ = child.kill();
+                return Err(format!("プロセスの待機に失敗しました: {e}"));
+            }
+        }
+    };
+    let stdout = stdout_handle.join().unwrap_or_default();
+    let stderr = stderr_handle.join().unwrap_or_default();
+    Ok(status.map(|status| std::process::Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+/// Resolve python entry script path with priority:
+/// 1) project_root/resources/py/<filename>
+/// 2) project_root/<filename> (legacy)
+fn resolve_python_entry(project_root: &std::path::Path, filename: &str) -> PathBuf {
+    let res = project_root.join("resources").join("py").join(filename);
+    if res.exists() {
+        return res;
+    }
+    project_root.join(filename)
+}
+
+/// Resolve python binary path with priority:
+/// 1) env PYTHON_BIN
+/// 2) project_root/resources/python/python(.exe) (portable runtime)
+/// 3) project_root/resources/.venv/(Scripts|bin)/python(.exe) (legacy)
+/// 4) project_root/.venv/(Scripts|bin)/python(.exe)
+/// 5) "python"
+fn resolve_python_bin(project_root: &std::path::Path) -> String {
+    resolve_python_bin_with_source(project_root).0
+}
+
+/// `resolve_python_bin` がどこから python を選んだか（上から順に優先）
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum PythonSource {
+    /// settings.json の `pythonBinOverride`
+    Override,
+    /// 環境変数 `PYTHON_BIN`
+    EnvVar,
+    /// `resources/python`（同梱のポータブル Python）
+    ResourcesPython,
+    /// `resources/.venv`
+    ResourcesVenv,
+    /// プロジェクト直下の `.venv`
+    ProjectVenv,
+    /// どれも無いので PATH 上の `python`
+    Path,
+    /// settings.json の `pythonCandidates`（`list_python_candidates` の候補にだけ出る）
+    Registered,
+}
+
+fn resolve_python_bin_with_source(project_root: &std::path::Path) -> (String, PythonSource) {
+    if let Some(bin) = configured_python_override(project_root) {
+        return (bin, PythonSource::Override);
+    }
+    if let Some(bin) = env_python_bin() {
+        return (bin, PythonSource::EnvVar);
+    }
+    bundled_python_bins(project_root)
+        .into_iter()
+        .find(|(path, _)| path.exists())
+        .map(|(path, source)| (path.to_string_lossy().to_string(), source))
+        .unwrap_or_else(|| ("python".into(), PythonSource::Path))
+}
+
+fn configured_python_override(project_root: &std::path::Path) -> Option<String> {
+    let settings = load_settings_from_disk(project_root).ok()?;
+    let bin = settings.python_bin_override?.trim().to_string();
+    (!bin.is_empty()).then_some(bin)
+}
+
+fn env_python_bin() -> Option<String> {
+    std::env::var("PYTHON_BIN")
+        .ok()
+        .filter(|bin| !bin.is_empty())
+}
+
+/// 同梱・プロジェクトの python の置き場所（`resolve_python_bin` が見る順）
+fn bundled_python_bins(project_root: &std::path::Path) -> [(PathBuf, PythonSource); 3] {
+    // resources/python (portable runtime)
+    #[cfg(target_os = "windows")]
+    let res_python = project_root
+        .join("resources")
+        .join("python")
+        .join("python.exe");
+    #[cfg(not(target_os = "windows"))]
+    let res_python = project_root
+        .join("resources")
+        .join("python")
+        .join("bin")
+        .join("python");
+    [
+        (res_python, PythonSource::ResourcesPython),
+        // resources/.venv (配布用に同梱する場合)
+        (
+            venv_python(&project_root.join("resources").join(".venv")),
+            PythonSource::ResourcesVenv,
+        ),
+        (
+            venv_python(&project_root.join(".venv")),
+            PythonSource::ProjectVenv,
+        ),
+    ]
+}
+
+fn venv_python(venv: &std::path::Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+/// PATH 上で最初に見つかる python（`resolve_python_bin` の最後の候補を実体のパスにしたもの）
+fn python_on_path() -> Option<PathBuf> {
+    let name = if cfg!(target_os = "windows") {
+        "python.exe"
+    } else {
+        "python"
+    };
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// `list_python_candidates` の 1 件
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PythonCandidate {
+    python_bin: String,
+    source: PythonSource,
+    /// `resolve_python_bin` が今選んでいるもの
+    active: bool,
+}
+
+/// Every python the user could pick: the override, `PYTHON_BIN`, the bundled/project
+/// venvs that exist, the PATH python and the registered paths (duplicates dropped).
+fn python_candidates(
+    project_root: &std::path::Path,
+    settings: &AppSettings,
+) -> Vec<PythonCandidate> {
+    let mut found: Vec<(String, PythonSource)> = vec![];
+    if let Some(bin) = settings.python_bin_override.as_deref() {
+        found.push((bin.trim().to_string(), PythonSource::Override));
+    }
+    if let Some(bin) = env_python_bin() {
+        found.push((bin, PythonSource::EnvVar));
+    }
+    for (path, source) in bundled_python_bins(project_root) {
+        if path.exists() {
+            found.push((path.to_string_lossy().to_string(), source));
+        }
+    }
+    if let Some(path) = python_on_path() {
+        found.push((path.to_string_lossy().to_string(), PythonSource::Path));
+    }
+    for bin in settings.python_candidates.iter().flatten() {
+        found.push((bin.trim().to_string(), PythonSource::Registered));
+    }
+
+    let (active_bin, _) = resolve_python_bin_with_source(project_root);
+    let mut candidates: Vec<PythonCandidate> = vec![];
+    for (python_bin, source) in found {
+        if python_bin.is_empty() || candidates.iter().any(|c| c.python_bin == python_bin) {
+            continue;
+        }
+        candidates.push(PythonCandidate {
+            active: python_bin == active_bin,
+            python_bin,
+            source,
+        });
+    }
+    candidates
+}
+
+/// `get_progress` のログ絞り込み条件。どちらも未指定なら全件を返す。
+struct LogFilter {
+    /// この重要度以上のみ（例: `warn` なら warn と error）
+    min_level: Option<LogLevel>,
+    /// 大文字小文字を区別しない部分一致
+    text_lower: Option<String>,
+}
+
+impl LogFilter {
+    fn new(level_filter: Option<LogLevel>, text_filter: Option<String>) -> Self {
+        let text_lower = text_filter
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty());
+        Self {
+            min_level: level_filter,
+            text_lower,
+        }
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(min) = self.min_level {
+            if entry.level < min {
+                return false;
+            }
+        }
+        if let Some(t) = &self.text_lower {
+            if !entry.text.to_lowercase().contains(t.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn apply(&self, log: &[LogEntry]) -> Vec<LogEntry> {
+        if self.min_level.is_none() && self.text_lower.is_none() {
+            return log.to_vec();
+        }
+        log.iter().filter(|e| self.matches(e)).cloned().collect()
+    }
+}
+
+/// `get_log_tail` の既定行数
+const DEFAULT_LOG_TAIL: usize = 100;
+
+/// ログの末尾 `n` 件だけを返す（サポートへの貼り付け用。全件を複製しない）。
+#[tauri::command]
+fn get_log_tail(
+    job_id: String,
+    n: Option<usize>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<LogEntry>, String> {
+    let jobs = state.read_jobs("get_log_tail")?;
+    let job = jobs.get(&job_id).ok_or("job not found")?;
+    Ok(log_tail(&job.log, n.unwrap_or(DEFAULT_LOG_TAIL)).to_vec())
+}
+
+fn log_tail(log: &[LogEntry], n: usize) -> &[LogEntry] {
+    &log[log.len().saturating_sub(n)..]
+}
+
+/// Write the whole job log to `dest_path`, headed by environment info, for bug reports.
+#[tauri::command(async)]
+fn export_job_log(
+    job_id: String,
+    dest_path: String,
+    state: State<Arc<AppState>>,
+) -> Result<(), String> {
+    let dest = dest_path.trim();
+    if dest.is_empty() {
+        return Err("destPath is empty".into());
+    }
+    let log = {
+        let jobs = state
+            .jobs
+            .read()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        jobs.get(&job_id).ok_or("job not found")?.log.clone()
+    };
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let env = environment_status(&project_root);
+    let version = python_version(&env.python_bin);
+    let content = format_job_log(&job_id, &env, version.as_deref(), &log);
+    fs::write(dest, content).map_err(|e| format!("failed to write log: {e}"))
+}
+
+fn format_job_log(
+    job_id: &str,
+    env: &EnvironmentStatus,
+    python_version: Option<&str>,
+    log: &[LogEntry],
+) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# OCR_to_doc job log ({job_id})\n"));
+    out.push_str(&format!("app version: {}\n", env!("CARGO_PKG_VERSION")));
+    out.push_str(&format!(
+        "os: {} ({})\n",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    ));
+    out.push_str(&format!("python: {}\n", env.python_bin));
+    out.push_str(&format!(
+        "python version: {}\n",
+        python_version.unwrap_or("unknown")
+    ));
+    out.push_str(&format!(
+        "dispatcher: {} (found: {})\n",
+        env.dispatcher_path, env.dispatcher_found
+    ));
+    out.push_str(&format!("project root: {}\n\n", env.project_root));
+    for entry in log {
+        out.push_str(&format_log_line(entry));
+    }
+    out
+}
+
+/// ログファイル 1 行分（時刻・レベル・出力元・本文）
+fn format_log_line(entry: &LogEntry) -> String {
+    format!(
+        "{} {:<5} {:<6} {}\n",
+        format_utc_ms(entry.ts_ms),
+        format!("{:?}", entry.level).to_uppercase(),
+        format!("{:?}", entry.source).to_lowercase(),
+        entry.text
+    )
+}
+
+/// job.log の `spawn:` 行（起動時刻の UTC とコマンド）
+fn spawn_log_line(cmd: &Command) -> String {
+    format!(
+        "spawn: [{}] {}",
+        format_utc_ms(now_ms()),
+        describe_command(cmd)
+    )
+}
+
+/// Unix ミリ秒を `YYYY-MM-DDTHH:MM:SS.mmmZ` にする（日付計算は Howard Hinnant の civil_from_days）。
+fn format_utc_ms(ms: u64) -> String {
+    let secs = ms / 1000;
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        ms % 1000
+    )
+}
+
+#[tauri::command]
+fn get_progress(
+    job_id: String,
+    level_filter: Option<LogLevel>,
+    text_filter: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<ProgressResponse, String> {
+    let filter = LogFilter::new(level_filter, text_filter);
+    let jobs = state.read_jobs("get_progress")?;
+    if let Some(job) = jobs.get(&job_id) {
+        return Ok(ProgressResponse {
+            status: job.status.clone(),
+            progress: job.progress,
+            log: filter.apply(&job.log),
+            error: job.error.clone(),
+            current_message: job.current_message.clone(),
+            page_current: job.page_current,
+            page_total: job.page_total,
+            eta_seconds: job.batch_eta_seconds(),
+            file_eta_seconds: job.eta_seconds,
+            batch_eta_seconds: job.batch_eta_seconds(),
+            files: job.files.clone(),
+        });
+    }
+    Err("job not found".into())
+}
+
+/// Snapshot every job (newest first) for a dashboard; `active_only` keeps just running jobs.
+#[tauri::command]
+fn get_all_progress(
+    active_only: Option<bool>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<JobProgressSummary>, String> {
+    let active_only = active_only.unwrap_or(false);
+    let jobs = state.read_jobs("get_all_progress")?;
+    let mut summaries: Vec<JobProgressSummary> = jobs
+        .iter()
+        .filter(|(_, job)| !active_only || job.status.is_active())
+        .map(|(job_id, job)| JobProgressSummary {
+            job_id: job_id.clone(),
+            status: job.status.clone(),
+            progress: job.progress,
+            error: job.error.clone(),
+            current_message: job.current_message.clone(),
+            page_current: job.page_current,
+            page_total: job.page_total,
+            eta_seconds: job.batch_eta_seconds(),
+            started_at_ms: job.started_at_ms,
+        })
+        .collect();
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.started_at_ms));
+    Ok(summaries)
+}
+
+/// `list_jobs_by_status` の 1 件（ログやファイル別の状態は含めない）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobSummary {
+    job_id: String,
+    progress: f32,
+    started_at_ms: u64,
+    finished_at_ms: Option<u64>,
+    output_count: usize,
+    tags: Vec<String>,
+}
+
+/// Compact summaries of the jobs currently in `status` (newest first), so a UI can show
+/// "active" and "history" lists without fetching every job's log. With `tag`, only jobs
+/// carrying that tag are listed.
+#[tauri::command]
+fn list_jobs_by_status(
+    status: JobStatus,
+    tag: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<JobSummary>, String> {
+    let jobs = state
+        .jobs
+        .read()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    let tag = tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    Ok(jobs_with_status(&jobs, &status, tag))
+}
+
+fn jobs_with_status(
+    jobs: &HashMap<String, JobInfo>,
+    status: &JobStatus,
+    tag: Option<&str>,
+) -> Vec<JobSummary> {
+    let mut summaries: Vec<JobSummary> = jobs
+        .iter()
+        .filter(|(_, job)| job.status == *status)
+        .filter(|(_, job)| tag.map_or(true, |t| job.tags.iter().any(|jt| jt == t)))
+        .map(|(job_id, job)| JobSummary {
+            job_id: job_id.clone(),
+            progress: job.progress,
+            started_at_ms: job.started_at_ms,
+            finished_at_ms: job.finished_at_ms,
+            output_count: job.outputs.len(),
+            tags: job.tags.clone(),
+        })
+        .collect();
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.started_at_ms));
+    summaries
+}
+
+/// 1 ジョブに付けられるタグの数と、1 つのタグの長さ（文字数）の上限
+const MAX_JOB_TAGS: usize = 16;
+const MAX_JOB_TAG_CHARS: usize = 32;
+
+/// Replace a job's tags (an empty list clears them). Tags are trimmed and deduplicated;
+/// the normalized list is returned. Finished results also get the tags in their `run.json`.
+#[tauri::command]
+fn set_job_tags(
+    job_id: String,
+    tags: Vec<String>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<String>, String> {
+    let tags = normalize_job_tags(tags)?;
+    let dirs = {
+        let mut jobs = state
+            .jobs
+            .write()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        let job = jobs.get_mut(&job_id).ok_or("job not found")?;
+        job.tags = tags.clone();
+        job.output_dirs.clone()
+    };
+    if let Some(project_root) = state.project_root() {
+        for note in write_run_config_tags(&project_root, &dirs, &tags) {
+            log::warn!("{}", note.text);
+        }
+    }
+    Ok(tags)
+}
+
+fn normalize_job_tags(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if tag.is_empty() {
+            return Err("tag is empty".into());
+        }
+        if tag.chars().count() > MAX_JOB_TAG_CHARS {
+            return Err(format!(
+                "tag must be {MAX_JOB_TAG_CHARS} characters or less: {tag}"
+            ));
+        }
+        if tag.chars().any(char::is_control) {
+            return Err(format!("tag contains a control character: {tag:?}"));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_JOB_TAGS {
+        return Err(format!("a job can have at most {MAX_JOB_TAGS} tags"));
+    }
+    Ok(normalized)
+}
+
+/// 結果ディレクトリ名 `name` が入力 `stem` のものか（`<stem>` そのものか、ページ範囲などの suffix 付きの `<stem>_*`）。
+/// `docs` は `doc` の結果ではない
+fn is_result_dir_for_stem(name: &str, stem: &str) -> bool {
+    name.strip_prefix(stem)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('_'))
+}
+
+/// `result/<stem>` か `result/<stem>_*` のうち、更新が最も新しいディレクトリ
+fn pick_latest_result_dir(result_root: &std::path::Path, stem: &str) -> Option<PathBuf> {
+    let candidates = result_dirs_for_stem(result_root, stem)
+        .into_iter()
+        .map(|path| (modified_time(&path), path));
+    newest_path(candidates.collect())
+}
+
+/// `result/<stem>` と `result/<stem>_*`（`is_result_dir_for_stem`）のディレクトリ
+fn result_dirs_for_stem(result_root: &std::path::Path, stem: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(result_root) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|name| is_result_dir_for_stem(&name.to_string_lossy(), stem))
+        })
+        .collect()
+}
+
+/// 更新時刻が最も新しいパス。同時刻なら名前順で先のもの（`<stem>` が `<stem>_*` より先）にして、
+/// `read_dir` の列挙順で結果が変わらないようにする
+fn newest_path(mut candidates: Vec<(SystemTime, PathBuf)>) -> Option<PathBuf> {
+    candidates.sort_by(|(ta, a), (tb, b)| tb.cmp(ta).then_with(|| a.cmp(b)));
+    candidates.into_iter().next().map(|(_, p)| p)
+}
+
+/// `skip_unchanged` 用: この入力を同じ設定（`same_result_options`）で処理し終えていて、入力が
+/// 変わっていない結果ディレクトリ（新しいものから見る）。run.json に入力のハッシュがあれば内容で
+/// 比べ（触っただけの入力は処理し直さない）、無ければ結果が入力より後に更新されたかで決める。
+/// run.json の無いもの（途中で止まった実行）や、別の入力の結果（`report` に対する `report_2024`）は使わない
+fn up_to_date_result_dir(
+    result_root: &std::path::Path,
+    input: &str,
+    output_name: Option<&String>,
+    input_hash: Option<&str>,
+    opts: &RunOptions,
+) -> Option<PathBuf> {
+    let mut candidates = match output_name {
+        Some(name) => vec![result_root.join(name)],
+        None => {
+            let stem = std::path::Path::new(input).file_stem()?.to_str()?;
+            result_dirs_for_stem(result_root, stem)
+        }
+    };
+    candidates.sort_by_key(|dir| std::cmp::Reverse(modified_time(dir)));
+    candidates.into_iter().find(|dir| {
+        let Ok(config) = read_run_config(dir) else {
+            return false;
+        };
+        if config.input != input || !same_result_options(&config.options, opts, input) {
+            return false;
+        }
+        if let (Some(recorded), Some(current)) = (config.input_hash.as_deref(), input_hash) {
+            return recorded == current;
+        }
+        let input_modified = fs::metadata(input).and_then(|m| m.modified());
+        input_modified.is_ok_and(|t| modified_time(dir) > t)
+    })
+}
+
+fn modified_time(path: &std::path::Path) -> SystemTime {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// ファイルの内容の BLAKE3 ハッシュ（16 進）。大きな PDF も全体を読み込まずに少しずつ読む
+fn hash_file(path: &std::path::Path) -> Result<String, String> {
+    let file = fs::File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut hasher = blake3::Hasher::new();
+    hasher
+        .update_reader(file)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// `hash_file` の結果をジョブの間だけ覚えておく（読めない入力も None として覚える）
+fn cached_input_hash(cache: &mut HashMap<String, Option<String>>, path: &str) -> Option<String> {
+    cache
+        .entry(path.to_string())
+        .or_insert_with(|| hash_file(std::path::Path::new(path)).ok())
+        .clone()
+}
+
+/// 入力ファイルの内容のハッシュ。run.json の `inputHash` と比べれば、更新時刻に関係なく
+/// 入力が変わったかが分かる
+#[tauri::command(async)]
+fn hash_input(path: String) -> Result<String, String> {
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(format!("input not found: {}", path.display()));
+    }
+    hash_file(&path)
+}
+
+/// `result_root` 直下にあるディレクトリの名前
+fn result_dir_names(result_root: &std::path::Path) -> std::collections::HashSet<String> {
+    fs::read_dir(result_root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 入力パスに応じて出力候補を探す
+/// `output_names` は `--output-name` を渡した入力の結果ディレクトリ名。無い入力は
+/// `result/<stem>` か `result/<stem>_*` の最新を探す。
+fn collect_output_files(
+    project_root: &std::path::Path,
+    inputs: &[String],
+    formats: &[String],
+    output_names: &HashMap<String, String>,
+    include_figures: bool,
+) -> Vec<PathBuf> {
+    fn push_unique(found: &mut Vec<PathBuf>, path: PathBuf) {
+        if path.exists() && !found.contains(&path) {
+            found.push(path);
+        }
+    }
+
+    let mut found = Vec::new();
+    for input in inputs {
+        let input_path = PathBuf::from(input);
+        let stem_owned = input_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output")
+            .to_string();
+        let stem = stem_owned.as_str();
+
+        // result/<stem> もしくは result/<stem>_*（ページ範囲指定などの suffix 付き）の最新ディレクトリ内
+        let result_root = configured_result_root(project_root);
+        let result_dir = match output_names.get(input) {
+            Some(name) => Some(result_root.join(name)).filter(|dir| dir.is_dir()),
+            None => pick_latest_result_dir(&result_root, stem),
+        };
+        if let Some(result_dir) = result_dir {
+            let dir_name = result_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            for fmt in formats {
+                if fmt == "xlsx" {
+                    // dispatcher は <output_dir.name>.xlsx を作る
+                    push_unique(&mut found, result_dir.join(format!("{dir_name}.xlsx")));
+                    push_unique(&mut found, result_dir.join(format!("{stem}.xlsx")));
+                    // 念のため
+                    push_unique(
+                        &mut found,
+                        result_dir.join(format!("{dir_name}_merged.xlsx")),
+                    );
+                    push_unique(&mut found, result_dir.join(format!("{stem}_merged.xlsx")));
+                    continue;
+                }
+                if fmt == "csv" {
+                    if let Ok(entries) = fs::read_dir(&result_dir) {
+                        for entry in entries.flatten() {
+                            let path = entry.path();
+                            if !path.is_file() {
+                                continue;
+                            }
+                            if path.extension().map(|e| e == "csv").unwrap_or(false) {
+                                push_unique(&mut found, path);
+                            }
+                        }
+                    }
+                    continue;
+                }
+
+                // ocr_chanked のマージ出力 + export_docx の変換結果は <output_dir.name>_merged.<fmt>
+                push_unique(
+                    &mut found,
+                    result_dir.join(format!("{dir_name}_merged.{fmt}")),
+                );
+                // 旧ルール互換
+                push_unique(&mut found, result_dir.join(format!("{stem}_merged.{fmt}")));
+                push_unique(&mut found, result_dir.join(format!("{stem}.{fmt}")));
+                push_unique(&mut found, result_dir.join(format!("{dir_name}.{fmt}")));
+            }
+
+            if include_figures {
+                for figure in figure_files(&result_dir) {
+                    push_unique(&mut found, figure);
+                }
+            }
+        }
+
+        // ルート直下に <stem>_merged.<fmt> / <stem>.<fmt>
+        for fmt in formats {
+            let c1 = project_root.join(format!("{}_merged.{}", stem, fmt));
+            push_unique(&mut found, c1);
+            let c2 = project_root.join(format!("{}.{}", stem, fmt));
+            push_unique(&mut found, c2);
+        }
+    }
+    found
+}
+
+/// 図版の置き場所（ocr.py の `rename_figure_assets` が `fig_pageNNN_MM.<ext>` にそろえる）
+const FIGURE_DIR_NAME: &str = "figures";
+const FIGURE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+/// 結果ディレクトリの `figures/` にある図版（ページ順）。アイコン判定のログなどは含めない
+fn figure_files(result_dir: &std::path::Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(result_dir.join(FIGURE_DIR_NAME)) else {
+        return vec![];
+    };
+    let mut figures: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with("fig_page"))
+                && p.extension().is_some_and(|e| {
+                    FIGURE_EXTENSIONS.contains(&e.to_string_lossy().to_ascii_lowercase().as_str())
+                })
+        })
+        .collect();
+    figures.sort();
+    figures
+}
+
+/// 出力ファイルの結果ディレクトリ（図版は `figures/` の 1 つ上）
+fn output_result_dir(path: &std::path::Path) -> Option<&std::path::Path> {
+    let parent = path.parent()?;
+    if parent.file_name().is_some_and(|n| n == FIGURE_DIR_NAME) {
+        parent.parent()
+    } else {
+        Some(parent)
+    }
+}
+
+/// Walk ancestors from exe_dir to find dispatcher.py; return its parent (project root)
+fn resolve_project_root(exe_dir: &std::path::Path) -> Option<PathBuf> {
+    for anc in exe_dir.ancestors() {
+        let legacy = anc.join("dispatcher.py");
+        let res = anc.join("resources").join("py").join("dispatcher.py");
+        if legacy.exists() || res.exists() {
+            return Some(anc.to_path_buf());
+        }
+    }
+    None
+}
+
+#[tauri::command]
+fn get_result(
+    job_id: String,
+    as_html: Option<bool>,
+    state: State<Arc<AppState>>,
+) -> Result<ResultResponse, String> {
+    let jobs = state.read_jobs("get_result")?;
+    if let Some(job) = jobs.get(&job_id) {
+        let preview = if as_html.unwrap_or(false) {
+            job.preview.as_deref().map(markdown_to_safe_html)
+        } else {
+            job.preview.clone()
+        };
+        return Ok(ResultResponse {
+            outputs: job.outputs.clone(),
+            preview,
+            metadata: job.metadata.clone(),
+        });
+    }
+    Err("job not found".into())
+}
+
+/// Markdown プレビューを HTML にする。OCR 結果に紛れた script や on* 属性が
+/// webview で動かないよう、ammonia で許可リスト外のタグ・属性を落とす。
+fn markdown_to_safe_html(markdown: &str) -> String {
+    use pulldown_cmark::{html, Options, Parser};
+
+    let parser = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    );
+    let mut raw = String::new();
+    html::push_html(&mut raw, parser);
+    ammonia::clean(&raw)
+}
+
+#[tauri::command]
+fn save_file(
+    job_id: String,
+    filename: String,
+    dest_path: String,
+    state: State<Arc<AppState>>,
+) -> Result<(), String> {
+    println!(
+        "[save_file] called with job_id={}, filename={}, dest_path={}",
+        job_id, filename, dest_path
+    );
+    let jobs = state
+        .jobs
+        .read()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+
+    if let Some(job) = jobs.get(&job_id) {
+        println!("[save_file] job found. outputs={:?}", job.outputs);
+        // filename が outputs に含まれているか確認 (セキュリティ対策)
+        if !job.outputs.contains(&filename) {
+            println!("[save_file] filename not in outputs");
+            return Err(format!("file not found in job outputs: {}", filename));
+        }
+
+        // 元ファイルを探す
+        let project_root = state
+            .project_root()
+            .ok_or("failed to resolve project root")?;
+
+        if let Some(src) = find_output_path(&project_root, &filename, &job.output_dirs) {
+            fs::copy(&src, &dest_path).map_err(|e| format!("failed to copy file: {e}"))?;
+            return Ok(());
+        } else {
+            return Err(format!("source file not found: {}", filename));
+        }
+    }
+    Err("job not found".into())
+}
+
+/// ジョブの出力名（`output_file_names`）から実際のファイルを探す。`output_dirs` はジョブの
+/// 結果ディレクトリ名で、あればその中だけを探す（古いジョブなど無いときは result/* を順に見る）
+fn find_output_path(
+    project_root: &std::path::Path,
+    filename: &str,
+    output_dirs: &[String],
+) -> Option<PathBuf> {
+    let result_dir = configured_result_root(project_root);
+    // 図版は `<dir>/figures/<name>` と結果ディレクトリ名付き
+    if filename.contains('/') {
+        let candidate = result_dir.join(filename);
+        return candidate.is_file().then_some(candidate);
+    }
+    if !output_dirs.is_empty() {
+        return output_dirs
+            .iter()
+            .map(|dir| result_dir.join(dir).join(filename))
+            .find(|candidate| candidate.exists());
+    }
+
+    // 1. result ディレクトリ内を探索
+    if result_dir.exists() {
+        if let Ok(entries) = fs::read_dir(&result_dir) {
+            for entry in entries.flatten() {
+                let candidate = entry.path().join(filename);
+                if candidate.exists() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    // 2. プロジェクトルート直下
+    let candidate = project_root.join(filename);
+    if candidate.exists() {
+        return Some(candidate);
+    }
+
+    None
+}
+
+/// Open `path` with the app configured for its extension in `openWith`, falling back to
+/// the OS default opener.
+fn open_path_with(
+    path: &std::path::Path,
+    open_with: Option<&HashMap<String, String>>,
+) -> Result<(), String> {
+    if let Some(app) = open_with_app(path, open_with) {
+        if !std::path::Path::new(app).exists() {
+            return Err(format!("設定されたアプリが見つかりません: {app}"));
+        }
+        // macOS の .app はバンドル（ディレクトリ）なので open -a 経由で起動する
+        #[cfg(target_os = "macos")]
+        let mut cmd = if app.ends_with(".app") {
+            let mut c = Command::new("open");
+            c.arg("-a").arg(app).arg(path);
+            c
+        } else {
+            let mut c = Command::new(app);
+            c.arg(path);
+            c
+        };
+        #[cfg(not(target_os = "macos"))]
+        let mut cmd = {
+            let mut c = Command::new(app);
+            c.arg(path);
+            c
+        };
+        return cmd
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("failed to open path with {app}: {e}"));
+    }
+
+    #[cfg(target_os = "macos")]
+    let mut cmd = {
+        let mut c = Command::new("open");
+        c.arg(path);
+        c
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        // explorer はファイル/フォルダ両方を開ける
+        let mut c = Command::new("explorer");
+        c.arg(path);
+        c
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut cmd = {
+        let mut c = Command::new("xdg-open");
+        c.arg(path);
+        c
+    };
+
+    cmd.spawn()
+        .map(|_| ())
+        .map_err(|e| format!("failed to open path: {e}"))
+}
+
+/// ヘルプファイルが同梱されていないときに開くオンラインのドキュメント
+const HELP_URL: &str = "https://github.com/ricopen19/OCR_to_doc#readme";
+
+/// Bundled help document under the project root, most specific first.
+fn find_help_file(project_root: &std::path::Path) -> Option<PathBuf> {
+    [
+        project_root.join("resources").join("docs").join("help.md"),
+        project_root.join("docs").join("help.md"),
+        project_root.join("README.md"),
+        project_root.join("readme.md"),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+#[tauri::command]
+fn open_help(state: State<Arc<AppState>>) -> Result<(), String> {
+    match state.project_root().and_then(|root| find_help_file(&root)) {
+        Some(path) => open_path_with(&path, None),
+        // explorer / open / xdg-open は URL もそのまま既定ブラウザで開ける
+        None => open_path_with(std::path::Path::new(HELP_URL), None),
+    }
+}
+
+fn validate_result_dir_name(dir_name: &str) -> Result<(), String> {
+    if dir_name.is_empty() {
+        return Err("dirName is empty".into());
+    }
+    if dir_name.contains('/') || dir_name.contains('\\') || dir_name.contains("..") {
+        return Err("invalid dirName".into());
+    }
+    Ok(())
+}
+
+fn canonicalize_dir(path: &std::path::Path) -> Result<PathBuf, String> {
+    fs::canonicalize(path).map_err(|e| format!("failed to canonicalize path: {e}"))
+}
+
+/// `cleanup_intermediates` で消す結果ディレクトリ直下の中間生成物。
+/// `figures/`（md から参照）と `yomi_formats/`（xlsx/csv の再書き出しに使う）は残す。
+/// `page_images/` も、再書き出しが読む結果では残す（`page_images_needed_for_reexport`）
+const INTERMEDIATE_DIRS: &[&str] = &["page_images", "converted", "preprocessed"];
+
+/// xlsx/csv の再書き出し（`convert_existing` → dispatcher.py `--export-only`）は
+/// `yomi_formats/json` の表を `page_images/page_NNN.png` で記号補完するので、JSON のある結果ではページ画像を消さない
+fn page_images_needed_for_reexport(result_dir: &std::path::Path) -> bool {
+    result_dir.join("yomi_formats").join("json").is_dir()
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CleanupResult {
+    freed_bytes: u64,
+    /// 消したディレクトリ（絶対パス）
+    removed: Vec<String>,
+}
+
+/// Remove page images and other intermediates from `result/<dir_name>` (and the job's
+/// work dir under `tempDir`), keeping the md/docx/xlsx/csv outputs. Page images stay when a
+/// later xlsx/csv re-export would read them. Returns the bytes freed.
+#[tauri::command(async)]
+fn cleanup_intermediates(
+    dir_name: String,
+    state: State<Arc<AppState>>,
+) -> Result<CleanupResult, String> {
+    validate_result_dir_name(&dir_name)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dir = resolve_result_dir(&project_root, &dir_name)?;
+    let temp_dir = configured_temp_dir(&project_root).ok().flatten();
+    remove_intermediates(&dir, temp_dir.as_deref())
+}
+
+/// `result_dir` must already be resolved inside the result root. Symlinks are never
+/// followed, so nothing outside the result dir / temp dir can be removed.
+fn remove_intermediates(
+    result_dir: &std::path::Path,
+    temp_dir: Option<&std::path::Path>,
+) -> Result<CleanupResult, String> {
+    let keep_page_images = page_images_needed_for_reexport(result_dir);
+    let mut targets: Vec<PathBuf> = INTERMEDIATE_DIRS
+        .iter()
+        .filter(|name| !(keep_page_images && **name == "page_images"))
+        .map(|name| result_dir.join(name))
+        .collect();
+    // ocr_chanked.py は --temp-dir 指定時 `<tempDir>/<出力名>/page_images` に描画する
+    if let (Some(temp), Some(name)) = (temp_dir, result_dir.file_name()) {
+        targets.push(temp.join(name));
+    }
+
+    let mut result = CleanupResult::default();
+    for target in targets {
+        remove_real_dir(&target, &mut result)?;
+    }
+    Ok(result)
+}
+
+/// Remove `target` if it is a real directory (not a symlink) and add it to `result`.
+fn remove_real_dir(target: &std::path::Path, result: &mut CleanupResult) -> Result<(), String> {
+    let is_real_dir = fs::symlink_metadata(target)
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    if !is_real_dir {
+        return Ok(());
+    }
+    let size = dir_size(target);
+    fs::remove_dir_all(target)
+        .map_err(|e| format!("failed to remove {}: {e}", target.display()))?;
+    result.freed_bytes += size;
+    result.removed.push(target.to_string_lossy().to_string());
+    Ok(())
+}
+
+/// Total size of regular files under `dir` (symlinks are not followed).
+fn dir_size(dir: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// `autoCleanup` 用: 出力ファイルのある結果ディレクトリを片付け、ジョブログに残す行を返す。
+fn auto_cleanup_notes(
+    project_root: &std::path::Path,
+    output_files: &[PathBuf],
+    temp_dir: Option<&std::path::Path>,
+) -> Vec<LogEntry> {
+    output_dir_names(output_files)
+        .into_iter()
+        .filter_map(|name| {
+            let removed = resolve_result_dir(project_root, &name)
+                .and_then(|dir| remove_intermediates(&dir, temp_dir));
+            match removed {
+                Ok(r) if r.removed.is_empty() => None,
+                Ok(r) => Some(LogEntry::app(format!(
+                    "cleanup: {name} の中間ファイルを削除しました（{} KB）",
+                    r.freed_bytes / 1024
+                ))),
+                Err(e) => Some(LogEntry::new(
+                    LogSource::App,
+                    LogLevel::Warn,
+                    format!("cleanup: {name}: {e}"),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// 出力ファイルの親ディレクトリ名（= 結果ディレクトリ名）を重複なしで並べる。
+fn output_dir_names(output_files: &[PathBuf]) -> Vec<String> {
+    let mut dirs: Vec<String> = Vec::new();
+    for name in output_files
+        .iter()
+        .filter_map(|f| output_result_dir(f)?.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+    {
+        if !dirs.contains(&name) {
+            dirs.push(name);
+        }
+    }
+    dirs
+}
+
+/// 結果ディレクトリに保存する実行時の入力と設定（`reprocess_result` が読む）
+const RUN_CONFIG_FILE: &str = "run.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunConfig {
+    input: String,
+    /// パスワードは保存しない（`RunOptions.password` は skip_serializing）
+    options: RunOptions,
+    /// 実際に渡した dispatcher.py の引数（パスワードは環境変数で渡すのでここには無い）
+    #[serde(default)]
+    dispatcher_args: Vec<String>,
+    #[serde(default)]
+    app_version: String,
+    /// pyproject.toml の version（読めなければ None）
+    #[serde(default)]
+    dispatcher_version: Option<String>,
+    #[serde(default)]
+    written_at_ms: u64,
+    /// 実行時の Python 環境（`capture_env_fingerprint`）。取得できなかったときは None
+    #[serde(default)]
+    env_fingerprint: Option<EnvFingerprint>,
+    /// ジョブのタグ（`set_job_tags`）
+    #[serde(default)]
+    tags: Vec<String>,
+    /// 入力の内容のハッシュ（`hash_input`。skip_unchanged のときは処理を始める前、それ以外は
+    /// run.json を書くときに取る）。読めなかった入力・古い run.json は None
+    #[serde(default)]
+    input_hash: Option<String>,
+}
+
+/// Python のバージョンとインストール済みパッケージ（`pip freeze`）。`hash` はその要約で、
+/// 2 つの実行で値が違えば環境が変わっている。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct EnvFingerprint {
+    hash: String,
+    python_version: String,
+    /// 正規化したパッケージ名 → バージョン（`name @ url` 形式は url）
+    packages: BTreeMap<String, String>,
+}
+
+impl EnvFingerprint {
+    fn new(python_version: String, packages: BTreeMap<String, String>) -> Self {
+        // 実行ごとに変わらない FNV-1a（std の DefaultHasher は版によって結果が変わりうる）
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |text: &str| {
+            for b in text.bytes().chain(std::iter::once(b'\n')) {
+                hash ^= u64::from(b);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        feed(&python_version);
+        for (name, version) in &packages {
+            feed(&format!("{name}=={version}"));
+        }
+        EnvFingerprint {
+            hash: format!("{hash:016x}"),
+            python_version,
+            packages,
+        }
+    }
+}
+
+/// `pip freeze` の出力をパッケージ名 → バージョンにする。名前は PEP 503 と同じく小文字・`-` 区切りに揃える。
+fn parse_pip_freeze(stdout: &str) -> BTreeMap<String, String> {
+    let normalize = |name: &str| name.trim().to_ascii_lowercase().replace(['_', '.'], "-");
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('-'))
+        .filter_map(|line| {
+            if let Some((name, version)) = line.split_once("==") {
+                Some((normalize(name), version.trim().to_string()))
+            } else {
+                let (name, url) = line.split_once(" @ ")?;
+                Some((normalize(name), url.trim().to_string()))
+            }
+        })
+        .collect()
+}
+
+fn query_env_fingerprint(project_root: &std::path::Path) -> Result<EnvFingerprint, String> {
+    let run = |args: &[&str]| -> Result<String, String> {
+        let mut cmd = Command::new(resolve_python_bin(project_root));
+        apply_extra_env(&mut cmd, &configured_extra_env(project_root));
+        apply_python_env(&mut cmd);
+        cmd.args(args).current_dir(project_root);
+        let output = output_with_timeout(&mut cmd, PYTHON_QUERY_TIMEOUT)
+            .map_err(|e| format!("failed to run python: {e}"))?
+            .ok_or_else(|| {
+                format!(
+                    "python {} timed out after {} s",
+                    args.join(" "),
+                    PYTHON_QUERY_TIMEOUT.as_secs()
+                )
+            })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(format!(
+                "python {} failed: {}",
+                args.join(" "),
+                stderr.trim()
+            ));
+        }
+        // 古い Python は --version を stderr に出す
+        let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        Ok(text)
+    };
+    let python_version = run(&["--version"])?
+        .trim()
+        .trim_start_matches("Python ")
+        .to_string();
+    let packages = parse_pip_freeze(&run(&["-m", "pip", "freeze"])?);
+    Ok(EnvFingerprint::new(python_version, packages))
+}
+
+/// 現在の Python 環境の指紋（run.json にも同じものを保存する）。常に取り直し、以降のジョブが使う
+/// キャッシュも更新する
+#[tauri::command(async)]
+fn capture_env_fingerprint(state: State<Arc<AppState>>) -> Result<EnvFingerprint, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    state.env_fingerprint(&project_root, true)
+}
+
+/// `diff_env` の 1 パッケージ分
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PackageDiff {
+    name: String,
+    kind: DiffKind,
+    version_a: Option<String>,
+    version_b: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvDiff {
+    same: bool,
+    python_a: String,
+    python_b: String,
+    packages: Vec<PackageDiff>,
+}
+
+fn diff_env_fingerprints(a: &EnvFingerprint, b: &EnvFingerprint) -> EnvDiff {
+    let mut names: Vec<&String> = a.packages.keys().chain(b.packages.keys()).collect();
+    names.sort();
+    names.dedup();
+    let packages = names
+        .into_iter()
+        .filter_map(|name| {
+            let (va, vb) = (a.packages.get(name), b.packages.get(name));
+            let kind = match (va, vb) {
+                (Some(x), Some(y)) if x == y => return None,
+                (Some(_), Some(_)) => DiffKind::Changed,
+                (Some(_), None) => DiffKind::Removed,
+                _ => DiffKind::Added,
+            };
+            Some(PackageDiff {
+                name: name.clone(),
+                kind,
+                version_a: va.cloned(),
+                version_b: vb.cloned(),
+            })
+        })
+        .collect();
+    EnvDiff {
+        same: a.hash == b.hash,
+        python_a: a.python_version.clone(),
+        python_b: b.python_version.clone(),
+        packages,
+    }
+}
+
+/// 2 つの結果を作ったときの Python 環境の違い（run.json の `envFingerprint` を比べる）。
+#[tauri::command]
+fn diff_env(dir_a: String, dir_b: String, state: State<Arc<AppState>>) -> Result<EnvDiff, String> {
+    validate_result_dir_name(&dir_a)?;
+    validate_result_dir_name(&dir_b)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let fingerprint = |dir_name: &str| -> Result<EnvFingerprint, String> {
+        let dir = resolve_result_dir(&project_root, dir_name)?;
+        read_run_config(&dir)?
+            .env_fingerprint
+            .ok_or_else(|| format!("{dir_name} の {RUN_CONFIG_FILE} に環境の記録がありません"))
+    };
+    Ok(diff_env_fingerprints(
+        &fingerprint(&dir_a)?,
+        &fingerprint(&dir_b)?,
+    ))
+}
+
+/// Python 側（dispatcher.py を含む ocr_to_doc パッケージ）の版。pyproject.toml の最初の `version = "..."`
+fn read_dispatcher_version(project_root: &std::path::Path) -> Option<String> {
+    let content = fs::read_to_string(project_root.join("pyproject.toml")).ok()?;
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        if key.trim() != "version" {
+            return None;
+        }
+        Some(value.trim().trim_matches('"').to_string()).filter(|v| !v.is_empty())
+    })
+}
+
+/// 成功したファイルの結果ディレクトリに `run.json` を書く。失敗はジョブを止めず警告ログにする。
+#[allow(clippy::too_many_arguments)]
+fn write_run_configs(
+    project_root: &std::path::Path,
+    output_files: &[PathBuf],
+    input: &str,
+    input_hash: Option<&str>,
+    opts: &RunOptions,
+    dispatcher_args: &[String],
+    dispatcher_version: Option<&str>,
+    env_fingerprint: Option<&EnvFingerprint>,
+) -> Vec<LogEntry> {
+    let config = RunConfig {
+        input: input.to_string(),
+        options: run_config_options(opts, input),
+        dispatcher_args: dispatcher_args.to_vec(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        dispatcher_version: dispatcher_version.map(str::to_string),
+        written_at_ms: now_ms(),
+        env_fingerprint: env_fingerprint.cloned(),
+        tags: vec![],
+        input_hash: input_hash.map(str::to_string),
+    };
+    let content = match serde_json::to_string_pretty(&config) {
+        Ok(c) => c,
+        Err(e) => {
+            return vec![LogEntry::new(
+                LogSource::App,
+                LogLevel::Warn,
+                format!("{RUN_CONFIG_FILE}: {e}"),
+            )]
+        }
+    };
+    output_dir_names(output_files)
+        .into_iter()
+        .filter_map(|name| {
+            let written = resolve_result_dir(project_root, &name).and_then(|dir| {
+                fs::write(dir.join(RUN_CONFIG_FILE), &content).map_err(|e| e.to_string())
+            });
+            written.err().map(|e| {
+                LogEntry::new(
+                    LogSource::App,
+                    LogLevel::Warn,
+                    format!("{RUN_CONFIG_FILE}: {name}: {e}"),
+                )
+            })
+        })
+        .collect()
+}
+
+/// run.json に残す `input` 向けの設定（ほかのファイル向けの個別設定までは残さない）
+fn run_config_options(opts: &RunOptions, input: &str) -> RunOptions {
+    let mut options = opts.clone();
+    options.file_options = opts.file_options_for(input).map(|f| {
+        let mut only = HashMap::new();
+        only.insert(input.to_string(), f.clone());
+        only
+    });
+    options
+}
+
+/// 実行の進め方だけに関わり、結果の中身を変えない `RunOptions` の項目（`skip_unchanged` の比較で無視する）
+const RUN_CONTROL_OPTIONS: &[&str] = &[
+    "skipUnchanged",
+    "continueOnError",
+    "requireFreeSpace",
+    "maxRetries",
+    "confirmLarge",
+    "enableRest",
+    "restSeconds",
+    "autoCleanup",
+];
+
+/// `recorded`（run.json の設定）と今回の `input` 向けの設定が、結果の中身に関わる項目で同じか
+fn same_result_options(recorded: &RunOptions, opts: &RunOptions, input: &str) -> bool {
+    let comparable = |options: &RunOptions| {
+        let mut value = serde_json::to_value(options).unwrap_or_default();
+        if let Some(map) = value.as_object_mut() {
+            for key in RUN_CONTROL_OPTIONS {
+                map.remove(*key);
+            }
+        }
+        value
+    };
+    comparable(recorded) == comparable(&run_config_options(opts, input))
+}
+
+fn read_run_config(result_dir: &std::path::Path) -> Result<RunConfig, String> {
+    let path = result_dir.join(RUN_CONFIG_FILE);
+    let content = fs::read_to_string(&path).map_err(|_| {
+        format!("{RUN_CONFIG_FILE} がありません（実行設定を保存する前に作られた結果です）")
+    })?;
+    serde_json::from_str(&content).map_err(|e| format!("invalid {RUN_CONFIG_FILE}: {e}"))
+}
+
+/// Rewrite `tags` in the `run.json` of each result dir. Dirs without a `run.json` yet
+/// (still running) are skipped; the worker writes the tags when it saves the file.
+fn write_run_config_tags(
+    project_root: &std::path::Path,
+    dir_names: &[String],
+    tags: &[String],
+) -> Vec<LogEntry> {
+    dir_names
+        .iter()
+        .filter_map(|name| {
+            let dir = resolve_result_dir(project_root, name).ok()?;
+            if !dir.join(RUN_CONFIG_FILE).is_file() {
+                return None;
+            }
+            let written = read_run_config(&dir).and_then(|mut config| {
+                config.tags = tags.to_vec();
+                let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+                fs::write(dir.join(RUN_CONFIG_FILE), content).map_err(|e| e.to_string())
+            });
+            written.err().map(|e| {
+                LogEntry::new(
+                    LogSource::App,
+                    LogLevel::Warn,
+                    format!("{RUN_CONFIG_FILE}: {name}: {e}"),
+                )
+            })
+        })
+        .collect()
+}
+
+/// 再処理の出力先ラベル（ocr_chanked.py `--label`）。元の結果ディレクトリ名に `_dpi<N>` を付けた
+/// 名前になるようにし、前回の再処理で付いた `_dpi<N>` は付け替える。
+fn reprocess_label(dir_name: &str, input_stem: &str, dpi: u32) -> String {
+    let suffix = dir_name
+        .strip_prefix(input_stem)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .unwrap_or("");
+    let is_dpi_tag = |s: &str| {
+        s.strip_prefix("dpi")
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    };
+    let base = match suffix.rsplit_once('_') {
+        Some((head, tail)) if is_dpi_tag(tail) => head,
+        _ if is_dpi_tag(suffix) => "",
+        _ => suffix,
+    };
+    if base.is_empty() {
+        format!("dpi{dpi}")
+    } else {
+        format!("{base}_dpi{dpi}")
+    }
+}
+
+/// `extra_ocr_args` の `--label` を `label` に差し替える。
+fn replace_label_arg(args: &mut Vec<String>, label: &str) {
+    let mut kept = Vec::with_capacity(args.len() + 2);
+    let mut iter = args.drain(..);
+    while let Some(arg) = iter.next() {
+        if arg == "--label" {
+            iter.next();
+        } else if !arg.starts_with("--label=") {
+            kept.push(arg);
+        }
+    }
+    drop(iter);
+    kept.push("--label".into());
+    kept.push(label.into());
+    *args = kept;
+}
+
+/// 保存済みの `run.json` から入力と設定を復元し、DPI だけ変えて別の結果ディレクトリ
+/// （`<元の名前>_dpi<N>`）に再実行する。ページ画像の DPI が効く PDF 入力のみ対応。
+/// run.json にパスワードは残らないので、保護された PDF は `password` を渡す（無い・違うときは
+/// ジョブを始めずに `encrypted:` のエラーを返す）。
+#[tauri::command(async)]
+fn reprocess_result(
+    app: tauri::AppHandle,
+    dir_name: String,
+    new_dpi: u32,
+    password: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<RunJobResponse, String> {
+    validate_result_dir_name(&dir_name)?;
+    if !(72..=600).contains(&new_dpi) {
+        return Err(format!("newDpi must be between 72 and 600: {new_dpi}"));
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dir = resolve_listed_result_dir(&project_root, &dir_name)?;
+    let RunConfig {
+        input, mut options, ..
+    } = read_run_config(&dir)?;
+
+    let input_path = PathBuf::from(&input);
+    let is_pdf = input_path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+    if !is_pdf {
+        return Err(format!(
+            "DPI を変えた再処理は PDF 入力のみ対応しています: {input}"
+        ));
+    }
+    if !input_path.is_file() {
+        return Err(format!("元の入力ファイルが見つかりません: {input}"));
+    }
+    let stem = input_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Err(e) = query_page_count(&project_root, &input, password.as_deref()) {
+        if e.starts_with(PREVIEW_ERROR_ENCRYPTED) {
+            return Err(format!(
+                "{PREVIEW_ERROR_ENCRYPTED}: PDF にパスワードが設定されています。パスワードを指定して再処理してください"
+            ));
+        }
+    }
+    options.password = password;
+    options.pdf_dpi = Some(new_dpi);
+    replace_label_arg(
+        &mut options.extra_ocr_args,
+        &reprocess_label(&dir_name, &stem, new_dpi),
+    );
+
+    let job_id = start_job(&app, state.inner(), vec![input], Some(options))?;
+    Ok(RunJobResponse { job_id })
+}
+
+/// `result/<dir_name>` を正規化し、result ルートの外を指していないことを確かめる。
+fn resolve_result_dir(project_root: &std::path::Path, dir_name: &str) -> Result<PathBuf, String> {
+    let result_root = configured_result_root(project_root);
+    let dir_path = result_root.join(dir_name);
+    if !dir_path.is_dir() {
+        return Err("result dir not found".into());
+    }
+
+    let result_root_canon = canonicalize_dir(&result_root)?;
+    let dir_canon = canonicalize_dir(&dir_path)?;
+    if !dir_canon.starts_with(&result_root_canon) {
+        return Err("invalid result dir".into());
+    }
+    Ok(dir_canon)
+}
+
+fn parse_page_range_from_dir(dir_name: &str) -> Option<String> {
+    // 例: foo_p3-9 -> "p3-9", foo_p1-3_7 -> "p1-3, 7"（--pages 由来）, foo_p5- -> "p5-"
+    let pos = dir_name.rfind("_p")?;
+    let segments = dir_name[(pos + 2)..]
+        .split('_')
+        .map(parse_page_label_segment)
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("p{}", segments.join(", ")))
+}
+
+/// One `_`-separated piece of a page label: "7", "1-3" or open-ended "5-".
+fn parse_page_label_segment(seg: &str) -> Option<String> {
+    let number = |v: &str| -> Option<u32> {
+        if v.is_empty() || !v.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        v.parse().ok()
+    };
+    match seg.split_once('-') {
+        None => number(seg).map(|p| p.to_string()),
+        Some((start, "")) => number(start).map(|s| format!("{s}-")),
+        Some((start, end)) => {
+            let (s, e) = (number(start)?, number(end)?);
+            (s <= e).then(|| format!("{s}-{e}"))
+        }
+    }
+}
+
+fn pick_best_file_in_dir(dir: &std::path::Path, dir_name: &str) -> Option<String> {
+    let candidates = [
+        // docx
+        format!("{dir_name}_merged.docx"),
+        format!("{dir_name}.docx"),
+        // xlsx (dispatcher は <output_dir.name>.xlsx)
+        format!("{dir_name}.xlsx"),
+        format!("{dir_name}_merged.xlsx"),
+        // csv（複数になる可能性があるので、代表として単体名も見る）
+        format!("{dir_name}.csv"),
+        format!("{dir_name}_merged.csv"),
+        // md
+        format!("{dir_name}_merged.md"),
+        format!("{dir_name}.md"),
+    ];
+
+    for filename in candidates {
+        if dir.join(&filename).exists() {
+            return Some(filename);
+        }
+    }
+
+    // fallback: scan directory for known extensions
+    if let Ok(entries) = fs::read_dir(dir) {
+        let mut docx = None;
+        let mut xlsx = None;
+        let mut csv = None;
+        let mut md = None;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let lower = name.to_lowercase();
+            if docx.is_none() && lower.ends_with(".docx") {
+                docx = Some(name);
+                continue;
+            }
+            if xlsx.is_none() && lower.ends_with(".xlsx") {
+                xlsx = Some(name);
+                continue;
+            }
+            if csv.is_none() && lower.ends_with(".csv") {
+                csv = Some(name);
+                continue;
+            }
+            if md.is_none() && lower.ends_with(".md") {
+                md = Some(name);
+            }
+        }
+        return docx.or(xlsx).or(csv).or(md);
+    }
+
+    None
+}
+
+/// `pick_best_file_in_dir` と同じ優先順で md だけを探す（差分は docx ではなく本文で取る）。
+fn pick_markdown_in_dir(dir: &std::path::Path, dir_name: &str) -> Option<String> {
+    for filename in [format!("{dir_name}_merged.md"), format!("{dir_name}.md")] {
+        if dir.join(&filename).is_file() {
+            return Some(filename);
+        }
+    }
+    let mut md: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.to_lowercase().ends_with(".md"))
+        .collect();
+    md.sort();
+    md.into_iter().next()
+}
+
+/// Line-level diff of two texts, keeping only the differing lines.
+/// A replaced block is paired line by line as `Changed`; any surplus becomes `Removed`/`Added`.
+fn diff_lines(a: &str, b: &str) -> Vec<DiffLine> {
+    use similar::{DiffOp, TextDiff};
+
+    let diff = TextDiff::from_lines(a, b);
+    let old: Vec<&str> = diff.old_slices().to_vec();
+    let new: Vec<&str> = diff.new_slices().to_vec();
+    let text = |s: &str| s.trim_end_matches(['\r', '\n']).to_string();
+    let line_no = |i: usize| Some(i as u32 + 1);
+
+    let mut lines = Vec::new();
+    for op in diff.ops() {
+        match *op {
+            DiffOp::Equal { .. } => {}
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => {
+                for (i, s) in old.iter().enumerate().skip(old_index).take(old_len) {
+                    lines.push(DiffLine {
+                        kind: DiffKind::Removed,
+                        line_a: line_no(i),
+                        line_b: None,
+                        text_a: Some(text(s)),
+                        text_b: None,
+                    });
+                }
+            }
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => {
+                for (j, s) in new.iter().enumerate().skip(new_index).take(new_len) {
+                    lines.push(DiffLine {
+                        kind: DiffKind::Added,
+                        line_a: None,
+                        line_b: line_no(j),
+                        text_a: None,
+                        text_b: Some(text(s)),
+                    });
+                }
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => {
+                for k in 0..old_len.max(new_len) {
+                    let (i, j) = (old_index + k, new_index + k);
+                    let (line_a, text_a) = if k < old_len {
+                        (line_no(i), Some(text(old[i])))
+                    } else {
+                        (None, None)
+                    };
+                    let (line_b, text_b) = if k < new_len {
+                        (line_no(j), Some(text(new[j])))
+                    } else {
+                        (None, None)
+                    };
+                    let kind = match (&text_a, &text_b) {
+                        (Some(_), Some(_)) => DiffKind::Changed,
+                        (Some(_), None) => DiffKind::Removed,
+                        _ => DiffKind::Added,
+                    };
+                    lines.push(DiffLine {
+                        kind,
+                        line_a,
+                        line_b,
+                        text_a,
+                        text_b,
+                    });
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// 2 つの結果ディレクトリの md を行単位で比較する（DPI やモードを変えた再実行の比較用）。
+#[tauri::command(async)]
+fn diff_results(
+    dir_a: String,
+    dir_b: String,
+    state: State<Arc<AppState>>,
+) -> Result<ResultDiff, String> {
+    validate_result_dir_name(&dir_a)?;
+    validate_result_dir_name(&dir_b)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let read_md = |dir_name: &str| -> Result<(String, String), String> {
+        let dir = resolve_result_dir(&project_root, dir_name)?;
+        let file = pick_markdown_in_dir(&dir, dir_name)
+            .ok_or_else(|| format!("no markdown found in {dir_name}"))?;
+        let content = fs::read_to_string(dir.join(&file))
+            .map_err(|e| format!("failed to read {file}: {e}"))?;
+        Ok((file, content))
+    };
+    let (file_a, content_a) = read_md(&dir_a)?;
+    let (file_b, content_b) = read_md(&dir_b)?;
+
+    let lines = diff_lines(&content_a, &content_b);
+    let count = |kind: DiffKind| lines.iter().filter(|l| l.kind == kind).count() as u32;
+    Ok(ResultDiff {
+        file_a,
+        file_b,
+        added: count(DiffKind::Added),
+        removed: count(DiffKind::Removed),
+        changed: count(DiffKind::Changed),
+        lines,
+    })
+}
+
+/// 改ページの行（pandoc の `\newpage`。export_docx.py は Word の改ページにする）。postprocess.py の
+/// `PAGE_BREAK` と同じ
+const PAGE_BREAK_MARKER: &str = "\\newpage";
+
+/// Join `(dir_name, markdown)` parts. `Page` (the default) starts each result on a new page,
+/// `Hr` puts a horizontal rule between them, `Heading` starts each one with `## <dir_name>`,
+/// `None` only leaves a blank line.
+fn join_merged_parts(parts: &[(String, String)], separator: MergeSeparator) -> String {
+    let join_with = |sep: &str| {
+        parts
+            .iter()
+            .map(|(_, md)| md.as_str())
+            .collect::<Vec<_>>()
+            .join(sep)
+    };
+    match separator {
+        MergeSeparator::Page => join_with(&format!("\n\n{PAGE_BREAK_MARKER}\n\n")),
+        MergeSeparator::Hr => join_with("\n\n---\n\n"),
+        MergeSeparator::Heading => parts
+            .iter()
+            .map(|(name, md)| format!("## {name}\n\n{md}"))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        MergeSeparator::None => join_with("\n\n"),
+    }
+}
+
+/// 複数の結果ディレクトリの md を指定順に連結し、`result/<output_name>/<output_name>_merged.md`
+/// として書き出す。書き出したファイルのパスを返す。
+#[tauri::command(async)]
+fn merge_results(
+    dir_names: Vec<String>,
+    output_name: String,
+    separator: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<String, String> {
+    if dir_names.is_empty() {
+        return Err("dirNames is empty".into());
+    }
+    let separator = MergeSeparator::parse_optional(separator.as_deref().unwrap_or(""))?
+        .unwrap_or(MergeSeparator::Page);
+    let output_name = output_name.trim().to_string();
+    validate_result_dir_name(&output_name)?;
+    for dir_name in &dir_names {
+        validate_result_dir_name(dir_name)?;
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+
+    let mut parts = Vec::with_capacity(dir_names.len());
+    for dir_name in &dir_names {
+        let dir = resolve_result_dir(&project_root, dir_name)?;
+        let file = pick_markdown_in_dir(&dir, dir_name)
+            .ok_or_else(|| format!("no markdown found in {dir_name}"))?;
+        let content = fs::read_to_string(dir.join(&file))
+            .map_err(|e| format!("failed to read {file}: {e}"))?;
+        // 図などの相対リンクは元のディレクトリを指したままにする
+        parts.push((
+            dir_name.clone(),
+            rebase_relative_links(content.trim(), &format!("../{dir_name}/")),
+        ));
+    }
+
+    let dest_dir = configured_result_root(&project_root).join(&output_name);
+    if dest_dir.exists() {
+        return Err(format!("result dir already exists: {output_name}"));
+    }
+    fs::create_dir_all(&dest_dir).map_err(|e| format!("failed to create result dir: {e}"))?;
+    let dest = dest_dir.join(format!("{output_name}_merged.md"));
+    let mut merged = join_merged_parts(&parts, separator);
+    merged.push('\n');
+    fs::write(&dest, merged).map_err(|e| format!("failed to write merged markdown: {e}"))?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Prefix relative markdown link/image targets (`](figures/a.png)`) with `prefix`.
+/// URLs, absolute paths and in-page anchors are left alone.
+fn rebase_relative_links(markdown: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+    while let Some(pos) = rest.find("](") {
+        let (head, tail) = rest.split_at(pos + 2);
+        out.push_str(head);
+        let target = tail.split([')', ' ']).next().unwrap_or_default();
+        let is_relative =
+            !(target.is_empty() || target.starts_with(['/', '#', '<']) || target.contains(':'));
+        if is_relative {
+            out.push_str(prefix);
+        }
+        rest = tail;
+    }
+    out.push_str(rest);
+    out
+}
+
+#[tauri::command]
+fn open_output(
+    job_id: String,
+    filename: String,
+    state: State<Arc<AppState>>,
+) -> Result<(), String> {
+    let jobs = state
+        .jobs
+        .read()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+
+    let job = jobs.get(&job_id).ok_or("job not found")?;
+    // filename が outputs に含まれているか確認 (セキュリティ対策)
+    if !job.outputs.contains(&filename) {
+        return Err(format!("file not found in job outputs: {}", filename));
+    }
+
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let src = resolve_output_file(&project_root, &filename, &job.output_dirs)?;
+    open_path_with(&src, configured_open_with(&project_root).as_ref())
+}
+
+/// `find_output_path` を正規化し、プロジェクトルートの外を指していないことを確かめる。
+fn resolve_output_file(
+    project_root: &std::path::Path,
+    filename: &str,
+    output_dirs: &[String],
+) -> Result<PathBuf, String> {
+    let src =
+        find_output_path(project_root, filename, output_dirs).ok_or("source file not found")?;
+    let root_canon = canonicalize_dir(project_root)?;
+    let src_canon = canonicalize_dir(&src)?;
+    if !src_canon.starts_with(&root_canon) {
+        return Err("invalid output file".into());
+    }
+    Ok(src_canon)
+}
+
+/// ジョブの出力のうち代表として渡すもの（`pick_best_file_in_dir` と同じく docx > xlsx > csv > md）
+fn pick_best_output(outputs: &[String]) -> Option<&String> {
+    const PREFERRED_EXTS: &[&str] = &["docx", "xlsx", "csv", "md"];
+    let rank = |name: &String| {
+        let lower = name.to_lowercase();
+        PREFERRED_EXTS
+            .iter()
+            .position(|ext| lower.ends_with(&format!(".{ext}")))
+            .unwrap_or(PREFERRED_EXTS.len())
+    };
+    outputs.iter().min_by_key(|name| rank(name))
+}
+
+/// Absolute path of the job's representative output, without opening it.
+#[tauri::command]
+fn resolve_best_output(job_id: String, state: State<Arc<AppState>>) -> Result<String, String> {
+    let (best, output_dirs) = {
+        let jobs = state
+            .jobs
+            .read()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        let job = jobs.get(&job_id).ok_or("job not found")?;
+        let best = pick_best_output(&job.outputs)
+            .cloned()
+            .ok_or("no output file found")?;
+        (best, job.output_dirs.clone())
+    };
+
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let path = resolve_output_file(&project_root, &best, &output_dirs)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn open_output_dir(job_id: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    let jobs = state
+        .jobs
+        .read()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+
+    let job = jobs.get(&job_id).ok_or("job not found")?;
+
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+
+    if let Some(first) = job.outputs.first() {
+        if let Some(src) = find_output_path(&project_root, first, &job.output_dirs) {
+            if let Some(parent) = src.parent() {
+                return open_path_with(parent, None);
+            }
+        }
+    }
+
+    // 出力が見つからない場合は result フォルダを開く
+    open_path_with(&configured_result_root(&project_root), None)
+}
+
+#[tauri::command]
+fn open_input_file(path: String) -> Result<(), String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("path is empty".into());
+    }
+    let p = PathBuf::from(trimmed);
+    if !p.exists() {
+        return Err(format!("file not found: {}", p.display()));
+    }
+    open_path_with(&p, None)
+}
+
+/// `format_filter` を正規化する（`.XLSX` → `xlsx`）。空なら絞り込まない
+fn parse_format_filter(format_filter: Option<&str>) -> Result<Option<String>, String> {
+    let Some(raw) = format_filter else {
+        return Ok(None);
+    };
+    let format = raw.trim().trim_start_matches('.').to_ascii_lowercase();
+    if format.is_empty() {
+        return Ok(None);
+    }
+    if !SUPPORTED_FORMATS.contains(&format.as_str()) {
+        return Err(format!(
+            "unsupported format: {format} (valid: {})",
+            SUPPORTED_FORMATS.join(", ")
+        ));
+    }
+    Ok(Some(format))
+}
+
+/// 結果ディレクトリ直下にその拡張子のファイルがあるか（代表ファイル以外も見る）
+fn dir_has_output_format(dir: &std::path::Path, format: &str) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            let path = entry.path();
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(format))
+        })
+    })
+}
+
+/// `format_filter`（md / docx / xlsx / csv）を渡すと、その形式の出力がある結果だけを新しい順に返す
+#[tauri::command(async)]
+fn list_recent_results(
+    limit: Option<u32>,
+    format_filter: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<RecentResultEntry>, String> {
+    let format_filter = parse_format_filter(format_filter.as_deref())?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+
+    let result_root = configured_result_root(&project_root);
+    let dirs = recent_result_dirs(&result_root);
+    let take_n = limit.unwrap_or(10).max(1) as usize;
+    let mut results = Vec::new();
+
+    // 絞り込みは件数制限の前にかける（limit 件に満たないまま終わらないように）
+    let matching = dirs.into_iter().filter(|(_, dir_name)| {
+        format_filter.as_deref().map_or(true, |f| {
+            dir_has_output_format(&result_root.join(dir_name), f)
+        })
+    });
+    for (updated_at_ms, dir_name) in matching.take(take_n) {
+        results.push(recent_result_entry(
+            &state,
+            &result_root,
+            dir_name,
+            updated_at_ms,
+        ));
+    }
+
+    Ok(results)
+}
+
+/// 一覧の 1 件分（代表ファイル・作り済みのサムネイル・run.json）を集める
+fn recent_result_entry(
+    state: &AppState,
+    result_root: &std::path::Path,
+    dir_name: String,
+    updated_at_ms: u64,
+) -> RecentResultEntry {
+    let dir_path = result_root.join(&dir_name);
+    let best_file = pick_best_file_in_dir(&dir_path, &dir_name);
+    let page_range = parse_page_range_from_dir(&dir_name);
+    let thumbnail = cached_thumbnail(&dir_path);
+    let run = read_run_config(&dir_path).ok();
+    let csv_encoding = run.as_ref().and_then(|r| r.options.csv_encoding);
+    let best_file_status = best_file.as_deref().map(|f| {
+        state
+            .check_output_file(&dir_path.join(f), csv_encoding)
+            .status
+    });
+    RecentResultEntry {
+        dir_name,
+        updated_at_ms,
+        page_range,
+        best_file,
+        thumbnail,
+        run,
+        best_file_status,
+    }
+}
+
+/// 入力をすでに処理した結果があれば、その最新（`collect_output_files` と同じく
+/// `result/<stem>` か `result/<stem>_*`）を返す。再実行前に「処理済みです」と知らせる用途
+#[tauri::command(async)]
+fn find_existing_result(
+    path: String,
+    state: State<Arc<AppState>>,
+) -> Result<Option<RecentResultEntry>, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let stem = std::path::Path::new(&path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| format!("invalid input path: {path}"))?;
+    let result_root = configured_result_root(&project_root);
+    let Some(dir) = pick_latest_result_dir(&result_root, stem) else {
+        return Ok(None);
+    };
+    let dir_name = dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let updated_at_ms = dir
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64);
+    Ok(Some(recent_result_entry(
+        &state,
+        &result_root,
+        dir_name,
+        updated_at_ms,
+    )))
+}
+
+/// `list_incomplete_results` の 1 件
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IncompleteResultEntry {
+    dir_name: String,
+    updated_at_ms: u64,
+    /// 消したときに空く容量
+    size_bytes: u64,
+}
+
+/// 実行中のジョブが書き込み中の結果ディレクトリ名（まだ出力が無くても消させない）
+fn active_output_dirs(state: &AppState) -> Vec<String> {
+    state
+        .jobs
+        .read()
+        .map(|jobs| {
+            jobs.values()
+                .filter(|job| job.status.is_active())
+                .flat_map(|job| job.output_dirs.iter().cloned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 代表ファイル（docx / xlsx / csv / md）が 1 つも無い結果ディレクトリを新しい順に返す。`busy` は除く
+fn incomplete_result_dirs(
+    result_root: &std::path::Path,
+    busy: &[String],
+) -> Vec<IncompleteResultEntry> {
+    recent_result_dirs(result_root)
+        .into_iter()
+        .filter(|(_, dir_name)| !busy.contains(dir_name))
+        .filter_map(|(updated_at_ms, dir_name)| {
+            let dir = result_root.join(&dir_name);
+            if pick_best_file_in_dir(&dir, &dir_name).is_some() {
+                return None;
+            }
+            Some(IncompleteResultEntry {
+                size_bytes: dir_size(&dir),
+                dir_name,
+                updated_at_ms,
+            })
+        })
+        .collect()
+}
+
+/// 失敗した実行などで出力が残らなかった結果ディレクトリ（中身が空か中間ファイルだけ）の一覧。
+/// 読むだけで何も消さない（片付けは `delete_result_dir`）
+#[tauri::command(async)]
+fn list_incomplete_results(
+    state: State<Arc<AppState>>,
+) -> Result<Vec<IncompleteResultEntry>, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    Ok(incomplete_result_dirs(
+        &configured_result_root(&project_root),
+        &active_output_dirs(&state),
+    ))
+}
+
+/// `result/<dir_name>` を丸ごと消す。実行中のジョブが書き込んでいるディレクトリは消さない
+#[tauri::command(async)]
+fn delete_result_dir(
+    dir_name: String,
+    state: State<Arc<AppState>>,
+) -> Result<CleanupResult, String> {
+    validate_result_dir_name(&dir_name)?;
+    if active_output_dirs(&state).contains(&dir_name) {
+        return Err(format!("{dir_name} is in use by a running job"));
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dir = resolve_listed_result_dir(&project_root, &dir_name)?;
+    let mut result = CleanupResult::default();
+    remove_real_dir(&dir, &mut result)?;
+    Ok(result)
+}
+
+/// `result/` 直下のディレクトリを更新時刻（ミリ秒）の新しい順に返す
+fn recent_result_dirs(result_root: &std::path::Path) -> Vec<(u64, String)> {
+    let mut dirs: Vec<(u64, String)> = Vec::new();
+    if let Ok(entries) = fs::read_dir(result_root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let modified = path
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            let ms = modified
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            dirs.push((ms, name));
+        }
+    }
+
+    dirs.sort_by(|(a, _), (b, _)| b.cmp(a));
+    dirs
+}
+
+/// いちばん新しい結果の代表ファイルを開く（ジョブ ID を知らなくてよい `open_output`）
+#[tauri::command]
+fn open_latest_result(state: State<Arc<AppState>>) -> Result<(), String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let (_, dir_name) = recent_result_dirs(&configured_result_root(&project_root))
+        .into_iter()
+        .next()
+        .ok_or("no results yet")?;
+    let file_canon = resolve_best_result_file(&project_root, &dir_name)?;
+    open_path_with(&file_canon, configured_open_with(&project_root).as_ref())
+}
+
+/// サムネイルのキャッシュ（結果ディレクトリごと）
+const THUMBNAIL_FILE_NAME: &str = ".thumb.png";
+const THUMBNAIL_LONG_EDGE: u32 = 160;
+
+/// First page image in a result dir: `page_images/` first, then images at the top level.
+fn find_thumbnail_source(dir: &std::path::Path) -> Option<PathBuf> {
+    let first_image = |d: &std::path::Path| -> Option<PathBuf> {
+        let mut images: Vec<PathBuf> = fs::read_dir(d)
+            .ok()?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .filter(|p| {
+                let name = p
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_lowercase();
+                name != THUMBNAIL_FILE_NAME
+                    && (name.ends_with(".png") || name.ends_with(".jpg") || name.ends_with(".jpeg"))
+            })
+            .collect();
+        images.sort();
+        images.into_iter().next()
+    };
+    first_image(&dir.join("page_images")).or_else(|| first_image(dir))
+}
+
+fn png_data_url(bytes: &[u8]) -> String {
+    format!("data:image/png;base64,{}", BASE64_STANDARD.encode(bytes))
+}
+
+/// Already generated `.thumb.png` for a result dir (never spawns the preview helper).
+fn cached_thumbnail(dir: &std::path::Path) -> Option<String> {
+    fs::read(dir.join(THUMBNAIL_FILE_NAME))
+        .ok()
+        .map(|bytes| png_data_url(&bytes))
+}
+
+/// Cached thumbnail for a result dir, generating `.thumb.png` via ui_preview.py on first use.
+/// Best-effort: any failure just means no thumbnail.
+fn result_thumbnail(project_root: &std::path::Path, dir: &std::path::Path) -> Option<String> {
+    if let Some(cached) = cached_thumbnail(dir) {
+        return Some(cached);
+    }
+
+    let cache = dir.join(THUMBNAIL_FILE_NAME);
+    let source = find_thumbnail_source(dir)?;
+    let mut cmd = preview_helper_command(project_root).ok()?;
+    cmd.arg("--input")
+        .arg(&source)
+        .arg("--max-long-edge")
+        .arg(THUMBNAIL_LONG_EDGE.to_string())
+        .arg("--output")
+        .arg(&cache);
+    match run_preview_command(project_root, &mut cmd, preview_timeout(project_root)) {
+        Ok(stdout) => serde_json::from_str::<PreviewResponse>(&stdout)
+            .ok()
+            .map(|r| r.data_url),
+        Err(e) => {
+            log::warn!("thumbnail generation failed for {}: {e}", dir.display());
+            None
+        }
+    }
+}
+
+/// 一覧（`list_recent_results`）に `thumbnail` が無い結果のサムネイル。初回は ui_preview.py で作って
+/// `.thumb.png` に残す。一覧を待たせないよう、UI は表示する行の分だけ個別に（並行して）呼ぶ
+#[tauri::command(async)]
+fn get_result_thumbnail(
+    dir_name: String,
+    state: State<Arc<AppState>>,
+) -> Result<Option<String>, String> {
+    validate_result_dir_name(&dir_name)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dir = resolve_result_dir(&project_root, &dir_name)?;
+    Ok(result_thumbnail(&project_root, &dir))
+}
+
+#[tauri::command]
+fn open_result_dir(dir_name: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    validate_result_dir_name(&dir_name)?;
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+
+    let dir_canon = resolve_listed_result_dir(&project_root, &dir_name)?;
+
+    open_path_with(&dir_canon, None)
+}
+
+/// 一覧の取得後に消えた結果ディレクトリを開こうとしたときのエラー種別（UI は一覧を取り直す）
+const RESULT_ERROR_DELETED: &str = "deleted";
+
+fn result_deleted_error(dir_name: &str) -> String {
+    format!("{RESULT_ERROR_DELETED}: 結果フォルダ {dir_name} は削除されています")
+}
+
+/// `resolve_result_dir` for entries picked from `list_recent_results`: a dir that has
+/// disappeared since the list was taken gets the `deleted:` error instead of a generic one.
+fn resolve_listed_result_dir(
+    project_root: &std::path::Path,
+    dir_name: &str,
+) -> Result<PathBuf, String> {
+    let result_root = configured_result_root(project_root);
+    if !result_root.join(dir_name).exists() {
+        return Err(result_deleted_error(dir_name));
+    }
+    resolve_result_dir(project_root, dir_name).map_err(|e| {
+        // 存在確認と正規化の間に消された場合も同じ扱い
+        if result_root.join(dir_name).exists() {
+            e
+        } else {
+            result_deleted_error(dir_name)
+        }
+    })
+}
+
+/// ocr_chanked.py が結果に残したページ画像（`page_images/page_NNN.png`、NNN は元のページ番号）。
+/// シンボリックリンクなどで結果ディレクトリの外を指すものは使わない
+fn result_page_image(dir: &std::path::Path, page: u32) -> Option<PathBuf> {
+    let path = dir.join("page_images").join(format!("page_{page:03}.png"));
+    let canon = fs::canonicalize(path).ok()?;
+    (canon.is_file() && canon.starts_with(dir)).then_some(canon)
+}
+
+/// 結果の 1 ページを画像で返す。OCR はやり直さず、残っているページ画像を縮小して使う。
+/// ページ画像が無い（自動削除済み・画像入力など）ときは run.json の元ファイルから描画する
+#[tauri::command(async)]
+fn preview_result_page(
+    dir_name: String,
+    page: u32,
+    max_long_edge: Option<u32>,
+    state: State<Arc<AppState>>,
+) -> Result<PreviewResponse, String> {
+    if page == 0 {
+        return Err("page must be 1 or greater".into());
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dir = resolve_listed_result_dir(&project_root, &dir_name)?;
+
+    if let Some(image) = result_page_image(&dir, page) {
+        let mut preview = run_preview_helper(
+            &project_root,
+            &image,
+            &PreviewParams {
+                max_long_edge,
+                ..PreviewParams::default()
+            },
+        )?;
+        // ページ画像は 1 枚の PNG なので、ヘルパーの page / page_count は元文書の値に直す
+        preview.page = Some(page);
+        preview.page_count = None;
+        return Ok(preview);
+    }
+
+    let run = read_run_config(&dir).map_err(|e| {
+        format!("{PREVIEW_ERROR_NOT_FOUND}: ページ {page} の画像が結果にありません（{e}）")
+    })?;
+    let file_opts = run.options.file_options_for(&run.input);
+    run_preview_helper(
+        &project_root,
+        std::path::Path::new(&run.input),
+        &PreviewParams {
+            page: Some(page),
+            crop: file_opts.and_then(|o| o.crop.clone()),
+            max_long_edge,
+            rotate: file_opts.and_then(|o| o.rotate).or(run.options.rotate),
+            auto_deskew: Some(
+                file_opts
+                    .and_then(|o| o.auto_deskew)
+                    .unwrap_or(run.options.auto_deskew),
+            ),
+            auto_orient: run.options.auto_orient,
+            ..PreviewParams::default()
+        },
+    )
+}
+
+#[tauri::command]
+fn open_result_file(dir_name: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let file_canon = resolve_best_result_file(&project_root, &dir_name)?;
+    open_path_with(&file_canon, configured_open_with(&project_root).as_ref())
+}
+
+/// Absolute path of the file `open_result_file` would open, without opening it.
+#[tauri::command]
+fn resolve_result_file(dir_name: String, state: State<Arc<AppState>>) -> Result<String, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let file_canon = resolve_best_result_file(&project_root, &dir_name)?;
+    Ok(file_canon.to_string_lossy().to_string())
+}
+
+/// 結果ディレクトリの代表ファイル（`pick_best_file_in_dir`）を正規化し、ディレクトリ内に収まることを確かめる。
+fn resolve_best_result_file(
+    project_root: &std::path::Path,
+    dir_name: &str,
+) -> Result<PathBuf, String> {
+    validate_result_dir_name(dir_name)?;
+    let dir_canon = resolve_listed_result_dir(project_root, dir_name)?;
+
+    let deleted_or = |e: String| {
+        if dir_canon.exists() {
+            e
+        } else {
+            result_deleted_error(dir_name)
+        }
+    };
+    let best = pick_best_file_in_dir(&dir_canon, dir_name)
+        .ok_or_else(|| deleted_or("no output file found".into()))?;
+    let file_path = dir_canon.join(&best);
+    let file_canon = canonicalize_dir(&file_path).map_err(deleted_or)?;
+    if !file_canon.starts_with(&dir_canon) {
+        return Err("invalid output file".into());
+    }
+    Ok(file_canon)
+}
+
+/// 結果の md（`<dir>_merged.md` を優先）をクリップボードに入れる。チャットやメールに貼る用途。
+/// コピーした文字数を返す
+#[tauri::command(async)]
+fn copy_result_text(
+    app: tauri::AppHandle,
+    dir_name: String,
+    state: State<Arc<AppState>>,
+) -> Result<usize, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let file = resolve_result_markdown(&project_root, &dir_name)?;
+    let text =
+        fs::read_to_string(&file).map_err(|e| format!("failed to read {}: {e}", file.display()))?;
+    let chars = text.chars().count();
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| format!("failed to copy to clipboard: {e}"))?;
+    Ok(chars)
+}
+
+/// 結果ディレクトリの md（`pick_markdown_in_dir`）を正規化し、ディレクトリ内に収まることを確かめる。
+fn resolve_result_markdown(
+    project_root: &std::path::Path,
+    dir_name: &str,
+) -> Result<PathBuf, String> {
+    validate_result_dir_name(dir_name)?;
+    let dir_canon = resolve_listed_result_dir(project_root, dir_name)?;
+    let md = pick_markdown_in_dir(&dir_canon, dir_name)
+        .ok_or_else(|| format!("no markdown found in {dir_name}"))?;
+    let file_canon = canonicalize_dir(&dir_canon.join(&md))?;
+    if !file_canon.starts_with(&dir_canon) {
+        return Err("invalid output file".into());
+    }
+    Ok(file_canon)
+}
+
+/// 結果ディレクトリ内の指定ファイルを開く（`open_result_file` の代表ファイル以外を開きたいとき）。
+#[tauri::command]
+fn open_result_file_named(
+    dir_name: String,
+    filename: String,
+    state: State<Arc<AppState>>,
+) -> Result<(), String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let file_canon = resolve_named_result_file(&project_root, &dir_name, &filename)?;
+    open_path_with(&file_canon, configured_open_with(&project_root).as_ref())
+}
+
+/// `verify_output` の判定
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum OutputIntegrity {
+    Ok,
+    /// 0 バイト
+    Empty,
+    /// zip の終端レコードや必須エントリが無い（書き込み途中で止まった docx/xlsx など）
+    Corrupt,
+    /// md/csv が UTF-8 として読めない
+    InvalidEncoding,
+    /// 中身を確かめない形式（画像など）
+    Unchecked,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputVerification {
+    filename: String,
+    status: OutputIntegrity,
+    size_bytes: u64,
+    /// 壊れていると判断した理由
+    detail: Option<String>,
+}
+
+/// `AppState::check_output_file` のキャッシュ 1 件（更新時刻・サイズ・csv の文字コードが同じ間だけ使う）
+struct CachedOutputCheck {
+    modified: Option<SystemTime>,
+    len: u64,
+    csv_encoding: Option<CsvEncoding>,
+    verification: OutputVerification,
+}
+
+/// Cheap structural check of one output file, so a truncated docx/xlsx is reported before
+/// the user tries to open it. Nothing is repaired.
+#[tauri::command(async)]
+fn verify_output(
+    dir_name: String,
+    filename: String,
+    state: State<Arc<AppState>>,
+) -> Result<OutputVerification, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let path = resolve_named_result_file(&project_root, &dir_name, &filename)?;
+    let csv_encoding = path
+        .parent()
+        .and_then(|dir| read_run_config(dir).ok())
+        .and_then(|run| run.options.csv_encoding);
+    Ok(state.check_output_file(&path, csv_encoding))
+}
+
+/// docx/xlsx は zip として、md/csv は UTF-8 テキストとして読めるかを見る。
+/// `csv_encoding` が Shift_JIS の結果では csv の文字コードは確かめない。
+fn check_output_file(
+    path: &std::path::Path,
+    csv_encoding: Option<CsvEncoding>,
+) -> OutputVerification {
+    let filename = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let verification = |status, size_bytes, detail| OutputVerification {
+        filename: filename.clone(),
+        status,
+        size_bytes,
+        detail,
+    };
+    let size = match fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        Err(e) => {
+            return verification(
+                OutputIntegrity::Corrupt,
+                0,
+                Some(format!("failed to read: {e}")),
+            )
+        }
+    };
+    if size == 0 {
+        return verification(OutputIntegrity::Empty, 0, None);
+    }
+    let required: &[&str] = match ext.as_str() {
+        "docx" => &["[Content_Types].xml", "word/document.xml"],
+        "xlsx" => &["[Content_Types].xml", "xl/workbook.xml"],
+        "md" | "csv" => {
+            if ext == "csv" && csv_encoding == Some(CsvEncoding::ShiftJis) {
+                return verification(OutputIntegrity::Ok, size, None);
+            }
+            return match fs::read(path).map(String::from_utf8) {
+                Ok(Ok(_)) => verification(OutputIntegrity::Ok, size, None),
+                Ok(Err(e)) => verification(
+                    OutputIntegrity::InvalidEncoding,
+                    size,
+                    Some(format!(
+                        "not UTF-8 at byte {}",
+                        e.utf8_error().valid_up_to()
+                    )),
+                ),
+                Err(e) => verification(
+                    OutputIntegrity::Corrupt,
+                    size,
+                    Some(format!("failed to read: {e}")),
+                ),
+            };
+        }
+        _ => return verification(OutputIntegrity::Unchecked, size, None),
+    };
+    match zip_entry_names(path) {
+        Ok(names) => match required.iter().find(|r| !names.iter().any(|n| n == *r)) {
+            Some(missing) => verification(
+                OutputIntegrity::Corrupt,
+                size,
+                Some(format!("missing {missing}")),
+            ),
+            None => verification(OutputIntegrity::Ok, size, None),
+        },
+        Err(e) => verification(OutputIntegrity::Corrupt, size, Some(e)),
+    }
+}
+
+/// Entry names of a docx/xlsx. Each entry's local header is read too, so a file cut off
+/// mid-write fails even when the central directory points past what was written.
+fn zip_entry_names(path: &std::path::Path) -> Result<Vec<String>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("failed to read: {e}"))?;
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("broken zip: {e}"))?;
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    for (i, name) in names.iter().enumerate() {
+        archive
+            .by_index_raw(i)
+            .map_err(|e| format!("zip entry {name} is broken: {e}"))?;
+    }
+    Ok(names)
+}
+
+/// `result/<dir_name>/<filename>` を正規化し、結果ディレクトリの中のファイルであることを確かめる。
+fn resolve_named_result_file(
+    project_root: &std::path::Path,
+    dir_name: &str,
+    filename: &str,
+) -> Result<PathBuf, String> {
+    validate_result_dir_name(dir_name)?;
+    if filename.is_empty() {
+        return Err("filename is empty".into());
+    }
+    if filename.contains('/') || filename.contains('\\') || filename.contains("..") {
+        return Err("invalid filename".into());
+    }
+    let dir_canon = resolve_listed_result_dir(project_root, dir_name)?;
+    let file_path = dir_canon.join(filename);
+    if !file_path.is_file() {
+        return Err(format!("file not found: {filename}"));
+    }
+    let file_canon = canonicalize_dir(&file_path)?;
+    if !file_canon.starts_with(&dir_canon) {
+        return Err("invalid output file".into());
+    }
+    Ok(file_canon)
+}
+
+/// 結果ディレクトリ名を変更する。中の `<旧名>_merged.md` などディレクトリ名を前提にした
+/// ファイル名も新しい名前に揃える（pick_best_file_in_dir や再エクスポートが名前で探すため）。
+#[tauri::command]
+fn rename_result_dir(
+    old_name: String,
+    new_name: String,
+    state: State<Arc<AppState>>,
+) -> Result<String, String> {
+    let new_name = new_name.trim().to_string();
+    validate_result_dir_name(&old_name)?;
+    validate_result_dir_name(&new_name)?;
+    if old_name == new_name {
+        return Ok(new_name);
+    }
+    if active_output_dirs(&state).contains(&old_name) {
+        return Err(format!("{old_name} is in use by a running job"));
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let src = resolve_result_dir(&project_root, &old_name)?;
+    let dest = src.parent().ok_or("invalid result dir")?.join(&new_name);
+    if dest.exists() {
+        return Err(format!("result dir already exists: {new_name}"));
+    }
+    fs::rename(&src, &dest).map_err(|e| format!("failed to rename result dir: {e}"))?;
+    rename_prefixed_outputs(&dest, &old_name, &new_name);
+    // 終わったジョブの出力一覧からも新しい名前で開けるようにする
+    if let Ok(mut jobs) = state.jobs.write() {
+        for job in jobs.values_mut() {
+            job.rename_output_dir(&old_name, &new_name);
+        }
+    }
+    Ok(new_name)
+}
+
+/// `<old>.ext` / `<old>_merged.ext` のようにディレクトリ名で始まるファイル名を新しい名前にしたもの
+fn renamed_output(name: &str, old_name: &str, new_name: &str) -> Option<String> {
+    let rest = name.strip_prefix(old_name)?;
+    (rest.starts_with('.') || rest.starts_with('_')).then(|| format!("{new_name}{rest}"))
+}
+
+/// `<old>.ext` / `<old>_merged.ext` のようにディレクトリ名で始まる出力ファイルを改名する（best-effort）。
+fn rename_prefixed_outputs(dir: &std::path::Path, old_name: &str, new_name: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(renamed) = renamed_output(&name, old_name, new_name) else {
+            continue;
+        };
+        let target = dir.join(renamed);
+        if target.exists() {
+            continue;
+        }
+        if let Err(e) = fs::rename(&path, &target) {
+            log::warn!("failed to rename {}: {e}", path.display());
+        }
+    }
+}
+
+/// 1 ファイルあたりに返すヒット数の上限（同じ語が何百回も出る文書で結果が埋まらないように）
+const SEARCH_MAX_HITS_PER_FILE: usize = 5;
+/// スニペットとしてヒット位置の前後に残す文字数
+const SEARCH_SNIPPET_CONTEXT_CHARS: usize = 40;
+
+/// Text outputs worth searching in a result dir: md/csv, minus per-page md when a merged md exists.
+fn searchable_files(dir: &std::path::Path, dir_name: &str) -> Vec<PathBuf> {
+    let has_merged = dir.join(format!("{dir_name}_merged.md")).is_file();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            let name = p
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            if has_merged && name.starts_with("page_") {
+                return false;
+            }
+            name.ends_with(".md") || name.ends_with(".csv")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Cut `line` down to the match plus some context on either side (char-boundary safe).
+fn make_snippet(line: &str, match_start: usize, match_len: usize) -> String {
+    let before: Vec<char> = line[..match_start].chars().collect();
+    let after: Vec<char> = line[match_start..].chars().collect();
+    let head_from = before.len().saturating_sub(SEARCH_SNIPPET_CONTEXT_CHARS);
+    let match_chars = line[match_start..match_start + match_len].chars().count();
+    let tail_to = after.len().min(match_chars + SEARCH_SNIPPET_CONTEXT_CHARS);
+    let mut snippet = String::new();
+    if head_from > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&before[head_from..]);
+    snippet.extend(&after[..tail_to]);
+    if tail_to < after.len() {
+        snippet.push('…');
+    }
+    snippet.trim().to_string()
+}
+
+/// Stream `path` line by line and return up to `max_hits` (line number, snippet) pairs.
+fn search_file(path: &std::path::Path, needle_lower: &str, max_hits: usize) -> Vec<(u32, String)> {
+    use std::io::BufRead;
+
+    let Ok(file) = fs::File::open(path) else {
+        return vec![];
+    };
+    let mut reader = std::io::BufReader::new(file);
+    let mut buf = Vec::new();
+    let mut hits = Vec::new();
+    let mut line_number = 0u32;
+    while hits.len() < max_hits {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        line_number += 1;
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\r', '\n']);
+        // 小文字化で長さが変わる行はヒット位置が元の行とずれるので、小文字化した行から切り出す
+        let lower = line.to_lowercase();
+        if let Some(pos) = lower.find(needle_lower) {
+            let snippet = if lower.len() == line.len() {
+                make_snippet(line, pos, needle_lower.len())
+            } else {
+                make_snippet(&lower, pos, needle_lower.len())
+            };
+            hits.push((line_number, snippet));
+        }
+    }
+    hits
+}
+
+/// result/ 配下の md/csv を全文検索する（新しい結果ディレクトリから順に見る）。
+#[tauri::command(async)]
+fn search_results(
+    query: String,
+    limit: Option<u32>,
+    state: State<Arc<AppState>>,
+) -> Result<Vec<SearchHit>, String> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Err("query is empty".into());
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let result_root = configured_result_root(&project_root);
+    if !result_root.exists() {
+        return Ok(vec![]);
+    }
+    let result_root_canon = canonicalize_dir(&result_root)?;
+
+    let mut dirs: Vec<(SystemTime, String, PathBuf)> = Vec::new();
+    if let Ok(entries) = fs::read_dir(&result_root_canon) {
+        for entry in entries.flatten() {
+            let Ok(dir_canon) = canonicalize_dir(&entry.path()) else {
+                continue;
+            };
+            // シンボリックリンク等で result の外を指すものは見ない
+            if !dir_canon.is_dir() || !dir_canon.starts_with(&result_root_canon) {
+                continue;
+            }
+            let modified = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            let name = entry.file_name().to_string_lossy().to_string();
+            dirs.push((modified, name, dir_canon));
+        }
+    }
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.0));
+
+    let take_n = limit.unwrap_or(50).max(1) as usize;
+    let mut hits = Vec::new();
+    'dirs: for (_, dir_name, dir_canon) in dirs {
+        for file in searchable_files(&dir_canon, &dir_name) {
+            let file_name = file
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            for (line_number, snippet) in search_file(&file, &needle, SEARCH_MAX_HITS_PER_FILE) {
+                hits.push(SearchHit {
+                    dir_name: dir_name.clone(),
+                    file_name: file_name.clone(),
+                    line_number,
+                    snippet,
+                });
+                if hits.len() >= take_n {
+                    break 'dirs;
+                }
+            }
+        }
+    }
+    Ok(hits)
+}
+
+/// `convert_existing` が書き出せるフォーマット（md は OCR の生成物そのもの）
+const CONVERTIBLE_FORMATS: [&str; 3] = ["docx", "xlsx", "csv"];
+
+/// `convert_existing` の書き出しを打ち切るまでの時間（止まった Python を残さない）
+const CONVERT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Newest file in `dir` with the given extension.
+fn newest_file_with_extension(dir: &std::path::Path, ext: &str) -> Option<String> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .is_some_and(|x| x.to_string_lossy().eq_ignore_ascii_case(ext))
+        })
+        .max_by_key(|e| {
+            e.metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        })
+        .map(|e| e.file_name().to_string_lossy().to_string())
+}
+
+/// 既存の結果ディレクトリの md から別フォーマットを書き出す（OCR はやり直さない）。
+/// 戻り値は生成されたファイル名。
+#[tauri::command(async)]
+fn convert_existing(
+    dir_name: String,
+    target_format: String,
+    state: State<Arc<AppState>>,
+) -> Result<String, String> {
+    validate_result_dir_name(&dir_name)?;
+    let format = target_format.trim().to_lowercase();
+    if !CONVERTIBLE_FORMATS.contains(&format.as_str()) {
+        return Err(format!("unsupported target format: {target_format}"));
+    }
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let dir_canon = resolve_result_dir(&project_root, &dir_name)?;
+    if newest_file_with_extension(&dir_canon, "md").is_none() {
+        return Err("no markdown found in result dir".into());
+    }
+
+    let dispatcher = resolve_python_entry(&project_root, "dispatcher.py");
+    if !dispatcher.exists() {
+        return Err(format!(
+            "dispatcher.py not found at {}",
+            dispatcher.display()
+        ));
+    }
+    let mut cmd = Command::new(resolve_python_bin(&project_root));
+    apply_extra_env(&mut cmd, &configured_extra_env(&project_root));
+    apply_python_env(&mut cmd);
+    cmd.arg("-u")
+        .arg(dispatcher)
+        .arg(&dir_canon)
+        .arg("--export-only")
+        .arg("--formats")
+        .arg(&format)
+        .current_dir(&project_root);
+    let output = output_with_timeout(&mut cmd, CONVERT_TIMEOUT)
+        .map_err(|e| format!("failed to run dispatcher: {e}"))?
+        .ok_or_else(|| format!("conversion timed out after {} s", CONVERT_TIMEOUT.as_secs()))?;
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let detail = if stderr.trim().is_empty() {
+            stdout
+        } else {
+            stderr
+        };
+        return Err(format!("conversion failed: {}", detail.trim()));
+    }
+
+    newest_file_with_extension(&dir_canon, &format)
+        .ok_or_else(|| format!("no {format} output was produced"))
+}
+
+#[tauri::command]
+fn check_environment(state: State<Arc<AppState>>) -> Result<EnvironmentStatus, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    Ok(environment_status(&project_root))
+}
+
+fn environment_status(project_root: &std::path::Path) -> EnvironmentStatus {
+    let dispatcher_path = resolve_python_entry(project_root, "dispatcher.py");
+    let dispatcher_found = dispatcher_path.exists();
+    let preview_helper_found = resolve_python_entry(project_root, "ui_preview.py").exists();
+    let result_dir_found = configured_result_root(project_root).exists();
+    let python_bin = resolve_python_bin(project_root);
+
+    EnvironmentStatus {
+        project_root: project_root.to_string_lossy().to_string(),
+        dispatcher_found,
+        dispatcher_path: dispatcher_path.to_string_lossy().to_string(),
+        preview_helper_found,
+        result_dir_found,
+        python_bin,
+    }
+}
+
+/// `python --version` の出力（取れなければ None）。Python 3.4 未満は stderr に出すので両方見る。
+fn python_version(python_bin: &str) -> Option<String> {
+    let mut cmd = Command::new(python_bin);
+    apply_python_env(&mut cmd);
+    cmd.arg("--version");
+    let output = output_with_timeout(&mut cmd, PYTHON_QUERY_TIMEOUT).ok()??;
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    let version = String::from_utf8_lossy(&text).trim().to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+/// `test_python` の結果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PythonProbe {
+    python_bin: String,
+    source: PythonSource,
+    ok: bool,
+    version: Option<String>,
+    /// `sys.executable`（venv の場合はそのインタプリタ）
+    executable: Option<String>,
+    error: Option<String>,
+    elapsed_ms: u64,
+}
+
+/// 起動確認だけなので、これ以上かかる環境は壊れているとみなす
+const PYTHON_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const PYTHON_PROBE_SCRIPT: &str =
+    "import json, sys; print(json.dumps({'version': sys.version, 'executable': sys.executable}))";
+
+/// Run the python `resolve_python_bin` picks and report which one it is and where it came from.
+#[tauri::command(async)]
+fn test_python(state: State<Arc<AppState>>) -> Result<PythonProbe, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let (python_bin, source) = resolve_python_bin_with_source(&project_root);
+    Ok(probe_python(python_bin, source, PYTHON_PROBE_TIMEOUT))
+}
+
+/// List the pythons `set_python_bin_override` can switch to.
+#[tauri::command]
+fn list_python_candidates(state: State<Arc<AppState>>) -> Result<Vec<PythonCandidate>, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let settings =
+        load_settings_from_disk(&project_root).unwrap_or_else(|_| AppSettings::default_values());
+    Ok(python_candidates(&project_root, &settings))
+}
+
+/// Make `python_bin` the python every job uses (`None` goes back to the usual lookup).
+/// The binary is started the same way as `test_python` first and is only saved if that works.
+#[tauri::command(async)]
+fn set_python_bin_override(
+    python_bin: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<Option<PythonProbe>, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let python_bin = python_bin
+        .map(|bin| bin.trim().to_string())
+        .filter(|bin| !bin.is_empty());
+    let probe = python_bin
+        .as_deref()
+        .map(probe_python_override)
+        .transpose()?;
+    let mut settings =
+        load_settings_from_disk(&project_root).unwrap_or_else(|_| AppSettings::default_values());
+    settings.python_bin_override = python_bin;
+    save_settings_to_disk(&project_root, &settings)?;
+    Ok(probe)
+}
+
+/// `pythonBinOverride` に使えるか `test_python` と同じ方法で起動して確かめる。
+fn probe_python_override(bin: &str) -> Result<PythonProbe, String> {
+    let probe = probe_python(
+        bin.to_string(),
+        PythonSource::Override,
+        PYTHON_PROBE_TIMEOUT,
+    );
+    if !probe.ok {
+        return Err(format!(
+            "{bin} を使えません: {}",
+            probe.error.as_deref().unwrap_or("起動に失敗しました")
+        ));
+    }
+    Ok(probe)
+}
+
+/// `outputRoot` に使えるフォルダか確かめる（存在して、実際にファイルを作れること）。
+/// 読み取り専用属性だけでは判断できない（Windows のフォルダなど）ので、書き込んで消してみる
+fn validate_output_root(path: &std::path::Path) -> Result<(), String> {
+    if !path.is_dir() {
+        return Err(format!("フォルダが見つかりません: {}", path.display()));
+    }
+    let probe = path.join(format!(".ocr_to_doc_write_test_{}", Uuid::new_v4()));
+    fs::write(&probe, b"")
+        .map_err(|e| format!("このフォルダには書き込めません: {}（{e}）", path.display()))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// フォルダ選択ダイアログで出力先を選び、書き込めることを確かめてから settings.json の
+/// `outputRoot` に保存する。キャンセルしたときは何も変えずに None を返す
+#[tauri::command(async)]
+fn choose_output_root(
+    app: tauri::AppHandle,
+    state: State<Arc<AppState>>,
+) -> Result<Option<String>, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let mut settings =
+        load_settings_from_disk(&project_root).unwrap_or_else(|_| AppSettings::default_values());
+    let start_dir = settings
+        .output_root
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|p| p.is_dir())
+        .unwrap_or_else(|| configured_result_root(&project_root));
+
+    let Some(picked) = app
+        .dialog()
+        .file()
+        .set_title("出力先フォルダを選択")
+        .set_directory(start_dir)
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let path = picked
+        .into_path()
+        .map_err(|e| format!("選択したフォルダを開けません: {e}"))?;
+    validate_output_root(&path)?;
+
+    let chosen = path.to_string_lossy().to_string();
+    settings.output_root = Some(chosen.clone());
+    save_settings_to_disk(&project_root, &settings)?;
+    Ok(Some(chosen))
+}
+
+fn probe_python(python_bin: String, source: PythonSource, timeout: Duration) -> PythonProbe {
+    let started = Instant::now();
+    let mut probe = PythonProbe {
+        python_bin,
+        source,
+        ok: false,
+        version: None,
+        executable: None,
+        error: None,
+        elapsed_ms: 0,
+    };
+    let mut cmd = Command::new(&probe.python_bin);
+    apply_python_env(&mut cmd);
+    cmd.arg("-c").arg(PYTHON_PROBE_SCRIPT);
+    let output = match output_with_timeout(&mut cmd, timeout) {
+        Ok(output) => output,
+        Err(e) => {
+            probe.error = Some(format!("Python を起動できません: {e}"));
+            return probe;
+        }
+    };
+    probe.elapsed_ms = started.elapsed().as_millis() as u64;
+
+    match output {
+        None => probe.error = Some(format!("タイムアウトしました（{} 秒）", timeout.as_secs())),
+        Some(output) if !output.status.success() => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            probe.error = Some(format!("status={}: {}", output.status, stderr.trim()));
+        }
+        Some(output) => match serde_json::from_slice::<serde_json::Value>(&output.stdout) {
+            Ok(info) => {
+                let field = |key: &str| info.get(key).and_then(|v| v.as_str()).map(str::to_string);
+                probe.version = field("version");
+                probe.executable = field("executable");
+                probe.ok = true;
+            }
+            Err(e) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                probe.error = Some(format!("unexpected output ({e}): {}", stdout.trim()));
+            }
+        },
+    }
+    probe
+}
+
+/// OCR language codes the dispatcher accepts in this environment (`--list-languages`).
+#[tauri::command(async)]
+fn list_supported_languages(state: State<Arc<AppState>>) -> Result<Vec<String>, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    state.supported_languages(&project_root)
+}
+
+fn query_supported_languages(project_root: &std::path::Path) -> Result<Vec<String>, String> {
+    let dispatcher = resolve_python_entry(project_root, "dispatcher.py");
+    if !dispatcher.exists() {
+        return Err(format!(
+            "dispatcher.py not found at {}",
+            dispatcher.display()
+        ));
+    }
+    let mut cmd = Command::new(resolve_python_bin(project_root));
+    apply_extra_env(&mut cmd, &configured_extra_env(project_root));
+    apply_python_env(&mut cmd);
+    cmd.arg("-u")
+        .arg(dispatcher)
+        .arg("--list-languages")
+        .current_dir(project_root);
+    let output = output_with_timeout(&mut cmd, PYTHON_QUERY_TIMEOUT)
+        .map_err(|e| format!("failed to run dispatcher: {e}"))?
+        .ok_or_else(|| {
+            format!(
+                "language query timed out after {} s",
+                PYTHON_QUERY_TIMEOUT.as_secs()
+            )
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(format!("language query failed: {}", stderr.trim()));
+    }
+    parse_supported_languages(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `--list-languages` prints one JSON array; take the last non-empty line in case
+/// an imported module printed something first.
+fn parse_supported_languages(stdout: &str) -> Result<Vec<String>, String> {
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .ok_or("language query returned nothing")?;
+    serde_json::from_str(line.trim()).map_err(|e| format!("failed to parse language list: {e}"))
+}
+
+/// Page count of an input via `ui_preview.py --page-count` (images are always 1).
+fn query_page_count(
+    project_root: &std::path::Path,
+    path: &str,
+    password: Option<&str>,
+) -> Result<u32, String> {
+    let helper = resolve_python_entry(project_root, "ui_preview.py");
+    if !helper.exists() {
+        return Err(format!("ui_preview.py not found at {}", helper.display()));
+    }
+    let mut cmd = Command::new(resolve_python_bin(project_root));
+    apply_extra_env(&mut cmd, &configured_extra_env(project_root));
+    apply_python_env(&mut cmd);
+    cmd.arg("-u")
+        .arg(helper)
+        .arg("--input")
+        .arg(path)
+        .arg("--page-count")
+        .current_dir(project_root);
+    apply_pdf_password(&mut cmd, password);
+    let timeout = preview_timeout(project_root);
+    let output = output_with_timeout(&mut cmd, timeout)
+        .map_err(|e| format!("failed to run preview helper: {e}"))?
+        .ok_or_else(|| format!("page count timed out after {} s", timeout.as_secs()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(format!("page count failed: {}", stderr.trim()));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let resp: PreviewResponse = serde_json::from_str(&stdout)
+        .map_err(|e| format!("failed to parse page count output: {e}"))?;
+    if resp.error_kind.is_some() {
+        return Err(preview_error_message(&resp));
+    }
+    resp.page_count.ok_or_else(|| "page count missing".into())
+}
+
+/// `error_kind` を `"<kind>: <説明>"` の文字列エラーにする（UI は接頭辞で判別できる）。
+fn preview_error_message(resp: &PreviewResponse) -> String {
+    match resp.error_kind.as_deref() {
+        Some(kind @ PREVIEW_ERROR_ENCRYPTED) => {
+            format!("{kind}: PDF にパスワードが設定されています")
+        }
+        Some(kind) => format!("{kind}: preview failed"),
+        None => "preview failed".into(),
+    }
+}
+
+/// ui_preview.py がパスワード付き PDF で返す `errorKind`
+const PREVIEW_ERROR_ENCRYPTED: &str = "encrypted";
+
+/// プレビューの失敗の種別（`"<kind>: <説明>"` の接頭辞）。`encrypted` / `timeout` と並ぶ
+const PREVIEW_ERROR_NOT_FOUND: &str = "not_found";
+const PREVIEW_ERROR_UNSUPPORTED: &str = "unsupported";
+const PREVIEW_ERROR_RENDER_FAILED: &str = "render_failed";
+
+#[tauri::command(async)]
+fn get_page_count(
+    path: String,
+    password: Option<String>,
+    state: State<Arc<AppState>>,
+) -> Result<u32, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    query_page_count(&project_root, &path, password.as_deref())
+}
+
+/// Pages that will actually be processed out of `total`, honoring `pages` or `start`/`end`.
+fn pages_in_selection(total: u32, file_opts: Option<&FileSpecificOptions>) -> u32 {
+    let Some(f) = file_opts else {
+        return total;
+    };
+    if let Some(ranges) = f.pages.as_deref().and_then(|p| parse_page_spec(p).ok()) {
+        return ranges
+            .iter()
+            .filter(|(s, _)| *s <= total)
+            .map(|(s, e)| e.min(&total) - s + 1)
+            .sum();
+    }
+    let start = f.start.unwrap_or(1).max(1);
+    let end = f.end.unwrap_or(total).min(total);
+    if start > end {
+        return 0;
+    }
+    end - start + 1
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobEstimate {
+    total_pages: u32,
+    estimated_seconds: u32,
+    seconds_per_page: f64,
+}
+
+/// Pre-run estimate: page counts (respecting per-file ranges) times the learned seconds per page.
+#[tauri::command(async)]
+fn estimate_job(
+    paths: Vec<String>,
+    options: Option<RunOptions>,
+    state: State<Arc<AppState>>,
+) -> Result<JobEstimate, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let options = options.unwrap_or_default();
+    let total_pages = count_selected_pages(&project_root, &paths, &options)?;
+    let seconds_per_page = load_metrics(&project_root).seconds_per_page(options.use_gpu);
+    Ok(JobEstimate {
+        total_pages,
+        estimated_seconds: (seconds_per_page * total_pages as f64).round() as u32,
+        seconds_per_page,
+    })
+}
+
+/// Pages that will actually be processed across `paths` (per-file ranges applied).
+fn count_selected_pages(
+    project_root: &std::path::Path,
+    paths: &[String],
+    options: &RunOptions,
+) -> Result<u32, String> {
+    let mut total_pages = 0;
+    for p in paths {
+        let count = query_page_count(project_root, p, options.password.as_deref())?;
+        total_pages += pages_in_selection(count, options.file_options_for(p));
+    }
+    Ok(total_pages)
+}
+
+/// 大きすぎるジョブを止めたときのエラー種別（UI は確認のうえ `confirmLarge` を付けて再実行する）
+const RUN_ERROR_TOO_MANY_PAGES: &str = "too_many_pages";
+
+fn check_page_limit(total_pages: u32, max_pages: u32) -> Result<(), String> {
+    if total_pages <= max_pages {
+        return Ok(());
+    }
+    Err(format!(
+        "{RUN_ERROR_TOO_MANY_PAGES}: ページ数 {total_pages} が上限 {max_pages} を超えています（確認のうえ confirmLarge を付けると実行できます）"
+    ))
+}
+
+/// 300 DPI で 1 ページあたりに使うディスク量の目安（ページ画像 PNG・前処理画像・Markdown/JSON 等）
+const DISK_BYTES_PER_PAGE_AT_300_DPI: u64 = 4 * 1024 * 1024;
+/// `pdfDpi` 未指定時に ocr_chanked.py が使う DPI
+const DEFAULT_PDF_DPI: u32 = 300;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskEstimate {
+    total_pages: u32,
+    estimated_bytes: u64,
+    /// 出力先ボリュームの空き容量（取れなかったときは None）
+    free_bytes: Option<u64>,
+    /// 空き容量を調べた場所（`result/`）
+    volume_path: String,
+    /// 見積もりが空き容量を超える（空き容量が不明なら false）
+    insufficient: bool,
+    warning: Option<String>,
+}
+
+/// Rough bytes for `pages` pages rendered at `dpi` (image size grows with the square of the DPI).
+fn estimate_disk_bytes(pages: u32, dpi: Option<u32>) -> u64 {
+    let scale = f64::from(dpi.unwrap_or(DEFAULT_PDF_DPI)) / f64::from(DEFAULT_PDF_DPI);
+    let per_page = (DISK_BYTES_PER_PAGE_AT_300_DPI as f64 * scale * scale).round() as u64;
+    per_page.saturating_mul(u64::from(pages))
+}
+
+/// Pre-run check that the output volume can hold the rendered pages of this job.
+#[tauri::command(async)]
+fn estimate_disk_needed(
+    paths: Vec<String>,
+    options: Option<RunOptions>,
+    state: State<Arc<AppState>>,
+) -> Result<DiskEstimate, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let options = options.unwrap_or_default();
+    let total_pages = count_selected_pages(&project_root, &paths, &options)?;
+    Ok(disk_estimate_for_pages(
+        &project_root,
+        total_pages,
+        &options,
+    ))
+}
+
+fn disk_estimate_for_pages(
+    project_root: &std::path::Path,
+    total_pages: u32,
+    options: &RunOptions,
+) -> DiskEstimate {
+    let estimated_bytes = estimate_disk_bytes(total_pages, options.pdf_dpi);
+    // result/ がまだ無ければ同じボリュームのプロジェクトルートで調べる
+    let result_dir = configured_result_root(project_root);
+    let volume = if result_dir.exists() {
+        result_dir
+    } else {
+        project_root.to_path_buf()
+    };
+    let free_bytes = free_disk_space(&volume);
+    let insufficient = free_bytes.is_some_and(|free| estimated_bytes > free);
+    let warning = free_bytes.filter(|_| insufficient).map(|free| {
+        format!(
+            "空き容量が不足する可能性があります（必要見込み {} MB / 空き {} MB: {}）",
+            estimated_bytes / (1024 * 1024),
+            free / (1024 * 1024),
+            volume.display()
+        )
+    });
+    DiskEstimate {
+        total_pages,
+        estimated_bytes,
+        free_bytes,
+        volume_path: volume.to_string_lossy().to_string(),
+        insufficient,
+        warning,
+    }
+}
+
+/// 空き容量は `df -Pk` の Available 列（1 KiB 単位）で取る。
+#[cfg(unix)]
+fn free_disk_space(path: &std::path::Path) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df_available_kib(&String::from_utf8_lossy(&output.stdout)).map(|kib| kib * 1024)
+}
+
+#[cfg(windows)]
+fn free_disk_space(path: &std::path::Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut available: u64 = 0;
+    // SAFETY: `wide` は NUL 終端で、呼び出し中だけ借用する。不要な出力は null を渡す。
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+/// `df -P` の 2 行目 4 列目（Available）。
+#[cfg(any(unix, test))]
+fn parse_df_available_kib(stdout: &str) -> Option<u64> {
+    stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()
+}
+
+/// 自己テストの既定タイムアウト（初回はモデル取得が走るため長め）
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SelfTestResult {
+    passed: bool,
+    message: String,
+    input: String,
+    md_path: Option<String>,
+    stdout: String,
+    stderr: String,
+    elapsed_ms: u64,
+}
+
+/// Run the pipeline on the bundled sample (`resources/fixtures/sample.png`) and check
+/// that a markdown file comes out. Same dispatcher argv as `run_job`, but synchronous
+/// with a timeout and writing under `result_ci/` so real results are untouched.
+#[tauri::command(async)]
+fn self_test(
+    timeout_seconds: Option<u64>,
+    state: State<Arc<AppState>>,
+) -> Result<SelfTestResult, String> {
+    let project_root = state
+        .project_root()
+        .ok_or("failed to resolve project root")?;
+    let timeout = timeout_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(SELF_TEST_TIMEOUT);
+    let fixture = project_root
+        .join("resources")
+        .join("fixtures")
+        .join("sample.png");
+    let started = Instant::now();
+    let mut result = SelfTestResult {
+        passed: false,
+        message: String::new(),
+        input: fixture.to_string_lossy().to_string(),
+        md_path: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        elapsed_ms: 0,
+    };
+
+    let dispatcher = resolve_python_entry(&project_root, "dispatcher.py");
+    if !dispatcher.exists() {
+        result.message = format!("dispatcher.py が見つかりません: {}", dispatcher.display());
+        return Ok(result);
+    }
+    if !fixture.exists() {
+        result.message = format!("サンプル画像が見つかりません: {}", fixture.display());
+        return Ok(result);
+    }
+    let output_root = project_root.join("result_ci");
+    fs::create_dir_all(&output_root)
+        .map_err(|e| format!("failed to create {}: {e}", output_root.display()))?;
+
+    let temp_dir = match configured_temp_dir(&project_root) {
+        Ok(dir) => dir,
+        Err(e) => {
+            result.message = e;
+            return Ok(result);
+        }
+    };
+    let mut opts = RunOptions {
+        enable_figure: false,
+        mode: Some(Mode::Lite),
+        extra_dispatcher_args: vec![
+            "--output-root".into(),
+            output_root.to_string_lossy().to_string(),
+        ],
+        ..RunOptions::default()
+    };
+    push_temp_dir_arg(&mut opts.extra_dispatcher_args, temp_dir.as_deref());
+    let mut cmd = Command::new(resolve_python_bin(&project_root));
+    apply_python_env(&mut cmd);
+    cmd.arg("-u")
+        .arg(&dispatcher)
+        .args(build_dispatcher_args(&result.input, &opts, None))
+        .current_dir(&project_root);
+
+    let output = match output_with_timeout(&mut cmd, timeout) {
+        Ok(output) => output,
+        Err(e) => {
+            result.message = format!("Python を起動できません: {e}");
+            return Ok(result);
+        }
+    };
+    result.elapsed_ms = started.elapsed().as_millis() as u64;
+    if let Some(output) = &output {
+        result.stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        result.stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    }
+
+    match output.map(|o| o.status) {
+        None => {
+            result.message = format!("タイムアウトしました（{} 秒）", timeout.as_secs());
+        }
+        Some(status) if !status.success() => {
+            result.message = format!("dispatcher が失敗しました (status={status})");
+        }
+        Some(_) => {
+            let stem = fixture
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("sample");
+            let md = fs::read_dir(output_root.join(stem))
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .find(|p| {
+                    p.extension().map(|e| e == "md").unwrap_or(false)
+                        && p.metadata().map(|m| m.len() >= 10).unwrap_or(false)
+                });
+            match md {
+                Some(md) => {
+                    result.passed = true;
+                    result.message = "OK".into();
+                    result.md_path = Some(md.to_string_lossy().to_string());
+                }
+                None => {
+                    result.message = "Markdown が出力されませんでした".into();
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+fn load_settings(state: State<Arc<AppState>>) -> Result<AppSettings, String> {
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+    load_settings_from_disk(&project_root)
+}
+
+#[tauri::command]
+fn save_settings(settings: AppSettings, state: State<Arc<AppState>>) -> Result<(), String> {
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+    store_settings(&project_root, settings).map(|_| ())
+}
+
+/// `save_settings` と `import_settings` の共通の保存処理。書いた設定を返す
+fn store_settings(
+    project_root: &std::path::Path,
+    mut settings: AppSettings,
+) -> Result<AppSettings, String> {
+    // UI から来る設定・取り込んだ設定（parse_settings で移行済み）は常に現行構造
+    settings.schema_version = SETTINGS_SCHEMA_VERSION;
+    if let Some(open_with) = &settings.open_with {
+        validate_open_with(open_with)?;
+    }
+    if let Some(template) = &settings.output_name_template {
+        validate_output_name_template(template)?;
+    }
+    // 変わったときだけ起動を確かめる（保存のたびに Python を待たせない）
+    settings.python_bin_override = settings
+        .python_bin_override
+        .as_deref()
+        .map(str::trim)
+        .filter(|bin| !bin.is_empty())
+        .map(str::to_string);
+    let saved_override = load_settings_from_disk(project_root)
+        .ok()
+        .and_then(|s| s.python_bin_override);
+    if let Some(bin) = settings.python_bin_override.as_deref() {
+        if saved_override.as_deref().map(str::trim) != Some(bin) {
+            probe_python_override(bin)?;
+        }
+    }
+    save_settings_to_disk(project_root, &settings)?;
+    Ok(settings)
+}
+
+fn save_settings_to_disk(
+    project_root: &std::path::Path,
+    settings: &AppSettings,
+) -> Result<(), String> {
+    let config_dir = project_root.join("configs");
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+    }
+
+    let settings_path = config_dir.join("settings.json");
+    // 新しい版が書いた settings.json を上書きすると、知らないフィールドが消えて版も下がる
+    if let Some(version) = fs::read_to_string(&settings_path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|v| v.get("schemaVersion").and_then(|v| v.as_u64()))
+        .filter(|v| *v > u64::from(SETTINGS_SCHEMA_VERSION))
+    {
+        return Err(format!(
+            "settings.json は新しいバージョンのアプリで保存されています（schemaVersion {version} > {SETTINGS_SCHEMA_VERSION}）。上書きしないよう保存を中止しました"
+        ));
+    }
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+
+    fs::write(settings_path, content).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Copy the current `configs/settings.json` to `settings.json.bak` (no-op if it doesn't exist).
+fn backup_settings_file(project_root: &std::path::Path) -> Result<(), String> {
+    let settings_path = project_root.join("configs").join("settings.json");
+    if !settings_path.exists() {
+        return Ok(());
+    }
+    let backup_path = project_root.join("configs").join("settings.json.bak");
+    fs::copy(&settings_path, &backup_path)
+        .map(|_| ())
+        .map_err(|e| format!("failed to back up settings: {e}"))
+}
+
+/// Overwrite `configs/settings.json` with the defaults (previous file kept as `.bak`).
+#[tauri::command]
+fn reset_settings(state: State<Arc<AppState>>) -> Result<AppSettings, String> {
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+    let settings = AppSettings::default_values();
+    backup_settings_file(&project_root)?;
+    save_settings_to_disk(&project_root, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+fn export_settings(dest_path: String, state: State<Arc<AppState>>) -> Result<(), String> {
+    let dest = dest_path.trim();
+    if dest.is_empty() {
+        return Err("destPath is empty".into());
+    }
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+    let settings = load_settings_from_disk(&project_root)?;
+    let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    fs::write(dest, content).map_err(|e| format!("failed to write settings: {e}"))
+}
+
+#[tauri::command]
+fn import_settings(src_path: String, state: State<Arc<AppState>>) -> Result<AppSettings, String> {
+    let src = src_path.trim();
+    if src.is_empty() {
+        return Err("srcPath is empty".into());
+    }
+    let content = fs::read_to_string(src).map_err(|e| format!("failed to read settings: {e}"))?;
+    let (settings, _) =
+        parse_settings(&content).map_err(|e| format!("invalid settings file: {e}"))?;
+    // 現行の版に書き直すと、新しい版にしか無いフィールドが消える
+    if settings.schema_version > SETTINGS_SCHEMA_VERSION {
+        return Err(format!(
+            "このファイルは新しいバージョンのアプリで保存されています（schemaVersion {} > {SETTINGS_SCHEMA_VERSION}）",
+            settings.schema_version
+        ));
+    }
+    settings.validate()?;
+
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+    backup_settings_file(&project_root)?;
+    store_settings(&project_root, settings)
+}
+
+/// 監視フォルダモードで自動処理の対象にする拡張子（UI のファイル選択と揃える）
+const SUPPORTED_INPUT_EXTENSIONS: &[&str] =
+    &["pdf", "heic", "heif", "jpg", "jpeg", "png", "tif", "tiff"];
+
+/// 書き込み途中のファイルを拾わないよう、サイズが変化しなくなるまで待つ間隔と回数
+const WATCH_STABLE_INTERVAL: Duration = Duration::from_secs(1);
+const WATCH_STABLE_CHECKS: u32 = 2;
+const WATCH_STABLE_TIMEOUT: Duration = Duration::from_secs(600);
+const WATCH_RECENT_LIMIT: usize = 20;
+
+fn is_supported_input(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| {
+            let lower = e.to_lowercase();
+            SUPPORTED_INPUT_EXTENSIONS.contains(&lower.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// Wait until the file size stays the same for `WATCH_STABLE_CHECKS` consecutive polls.
+/// Returns false if the file disappears, the watch is stopped, or the timeout elapses.
+fn wait_for_stable_size(path: &std::path::Path, stop: &AtomicBool) -> bool {
+    let started = Instant::now();
+    let mut last_len: Option<u64> = None;
+    let mut stable = 0u32;
+    while started.elapsed() < WATCH_STABLE_TIMEOUT {
+        if stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        let len = match fs::metadata(path) {
+            Ok(m) if m.is_file() => m.len(),
+            _ => return false,
+        };
+        if last_len == Some(len) && len > 0 {
+            stable += 1;
+            if stable >= WATCH_STABLE_CHECKS {
+                return true;
+            }
+        } else {
+            stable = 0;
+        }
+        last_len = Some(len);
+        thread::sleep(WATCH_STABLE_INTERVAL);
+    }
+    false
+}
+
+/// 監視で同じ内容のファイルを二度処理しないための目印（更新時刻とサイズ）
+fn file_stamp(path: &std::path::Path) -> Option<(Option<SystemTime>, u64)> {
+    fs::metadata(path)
+        .ok()
+        .map(|m| (m.modified().ok(), m.len()))
+}
+
+fn emit_watch_status(app: &tauri::AppHandle, state: &AppState) {
+    let status = current_watch_status(state);
+    let _ = app.emit("watch://status", status);
+}
+
+fn current_watch_status(state: &AppState) -> WatchStatus {
+    match state.watch.lock() {
+        Ok(guard) => match guard.as_ref() {
+            Some(handle) => WatchStatus {
+                active: true,
+                folder: Some(handle.folder.to_string_lossy().to_string()),
+                recent: handle.recent.lock().map(|r| r.clone()).unwrap_or_default(),
+            },
+            None => WatchStatus {
+                active: false,
+                folder: None,
+                recent: vec![],
+            },
+        },
+        Err(_) => WatchStatus {
+            active: false,
+            folder: None,
+            recent: vec![],
+        },
+    }
+}
+
+/// Start watching `folder`, replacing any existing watch. New supported files are
+/// debounced until their size is stable and then started as jobs via `start_job`.
+fn start_watching(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    folder: PathBuf,
+    options: Option<RunOptions>,
+) -> Result<(), String> {
+    use notify::Watcher;
+
+    if !folder.is_dir() {
+        return Err(format!("watch folder not found: {}", folder.display()));
+    }
+
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("failed to create watcher: {e}"))?;
+    watcher
+        .watch(&folder, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| format!("failed to watch folder: {e}"))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let recent: Arc<Mutex<Vec<WatchFileEvent>>> = Arc::new(Mutex::new(Vec::new()));
+    {
+        let mut guard = state
+            .watch
+            .lock()
+            .map_err(|e| format!("lock poisoned: {e}"))?;
+        if let Some(prev) = guard.take() {
+            prev.stop.store(true, Ordering::Relaxed);
+        }
+        *guard = Some(WatchHandle {
+            folder: folder.clone(),
+            _watcher: watcher,
+            stop: stop.clone(),
+            recent: recent.clone(),
+        });
+    }
+
+    emit_watch_status(app, state);
+
+    let app = app.clone();
+    let state = state.clone();
+    thread::spawn(move || {
+        // サイズの安定待ち中は None、ジョブを始めたファイルはそのときの file_stamp。
+        // 同じ内容への後続イベントは無視し、上書きされたら改めて処理する
+        type Seen = HashMap<PathBuf, Option<(Option<SystemTime>, u64)>>;
+        let seen: Arc<Mutex<Seen>> = Arc::new(Mutex::new(HashMap::new()));
+        // watcher が drop されると送信側も閉じ、recv がエラーになってループを抜ける
+        for res in rx {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let event = match res {
+                Ok(ev) => ev,
+                Err(_) => continue,
+            };
+            if !matches!(
+                event.kind,
+                notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+            ) {
+                continue;
+            }
+            for path in event.paths {
+                if !is_supported_input(&path) {
+                    continue;
+                }
+                let stamp = file_stamp(&path);
+                match seen.lock() {
+                    Ok(mut s) => match s.get(&path) {
+                        Some(None) => continue,
+                        Some(Some(done)) if stamp.as_ref() == Some(done) => continue,
+                        _ => {
+                            s.insert(path.clone(), None);
+                        }
+                    },
+                    Err(_) => continue,
+                }
+
+                let app = app.clone();
+                let state = state.clone();
+                let stop = stop.clone();
+                let recent = recent.clone();
+                let options = options.clone();
+                let seen = seen.clone();
+                thread::spawn(move || {
+                    if !wait_for_stable_size(&path, &stop) {
+                        // 後続の変更イベントで再判定できるよう、未処理扱いに戻す
+                        if let Ok(mut s) = seen.lock() {
+                            s.remove(&path);
+                        }
+                        return;
+                    }
+                    let path_str = path.to_string_lossy().to_string();
+                    let stamp = file_stamp(&path);
+                    let (job_id, error) =
+                        match start_job(&app, &state, vec![path_str.clone()], options) {
+                            Ok(id) => (Some(id), None),
+                            Err(e) => (None, Some(e)),
+                        };
+                    if let Ok(mut s) = seen.lock() {
+                        match stamp {
+                            Some(stamp) => s.insert(path.clone(), Some(stamp)),
+                            None => s.remove(&path),
+                        };
+                    }
+                    let event = WatchFileEvent {
+                        path: path_str,
+                        job_id,
+                        error,
+                        detected_at_ms: now_ms(),
+                    };
+                    if let Ok(mut r) = recent.lock() {
+                        r.insert(0, event.clone());
+                        r.truncate(WATCH_RECENT_LIMIT);
+                    }
+                    let _ = app.emit("watch://file", event);
+                });
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+fn start_watch(
+    app: tauri::AppHandle,
+    folder: String,
+    options: Option<RunOptions>,
+    state: State<Arc<AppState>>,
+) -> Result<WatchStatus, String> {
+    let trimmed = folder.trim();
+    if trimmed.is_empty() {
+        return Err("folder is empty".into());
+    }
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+
+    start_watching(&app, state.inner(), PathBuf::from(trimmed), options.clone())?;
+
+    let saved = load_settings_from_disk(&project_root).and_then(|mut settings| {
+        settings.watch = Some(WatchConfig {
+            folder: trimmed.to_string(),
+            options,
+        });
+        save_settings_to_disk(&project_root, &settings)
+    });
+    // 保存できなければ監視も止める（次回起動時に復元されない監視を残さない）
+    if let Err(e) = saved {
+        stop_watching(state.inner())?;
+        emit_watch_status(&app, state.inner());
+        return Err(e);
+    }
+    Ok(current_watch_status(state.inner()))
+}
+
+fn stop_watching(state: &AppState) -> Result<(), String> {
+    let mut guard = state
+        .watch
+        .lock()
+        .map_err(|e| format!("lock poisoned: {e}"))?;
+    if let Some(handle) = guard.take() {
+        handle.stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_watch(app: tauri::AppHandle, state: State<Arc<AppState>>) -> Result<WatchStatus, String> {
+    stop_watching(state.inner())?;
+
+    let project_root = state.project_root().unwrap_or_else(|| PathBuf::from("."));
+    let mut settings = load_settings_from_disk(&project_root)?;
+    if settings.watch.is_some() {
+        settings.watch = None;
+        save_settings_to_disk(&project_root, &settings)?;
+    }
+
+    emit_watch_status(&app, state.inner());
+    Ok(current_watch_status(state.inner()))
+}
+
+#[tauri::command]
+fn get_watch_status(state: State<Arc<AppState>>) -> WatchStatus {
+    current_watch_status(state.inner())
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .manage(Arc::new(AppState::default()))
+        .invoke_handler(tauri::generate_handler![
+            run_job,
+            render_preview,
+            render_preview_bytes,
+            validate_crop_selection,
+            get_page_count,
+            estimate_job,
+            estimate_disk_needed,
+            warm_models,
+            cleanup_intermediates,
+            list_jobs_by_status,
+            resolve_best_output,
+            capture_env_fingerprint,
+            diff_env,
+            resolve_result_file,
+            open_result_file_named,
+            reprocess_result,
+            cancel_and_discard,
+            verify_output,
+            set_job_tags,
+            preview_result_page,
+            open_latest_result,
+            render_tile,
+            choose_output_root,
+            focus_main_window,
+            preview_effective_crop,
+            skip_file,
+            list_incomplete_results,
+            delete_result_dir,
+            preview_batch_first_pages,
+            reorder_job_files,
+            copy_result_text,
+            find_existing_result,
+            hash_input,
+            get_progress,
+            get_log_tail,
+            export_job_log,
+            get_all_progress,
+            request_stop,
+            cancel_all_jobs,
+            remove_job,
+            pause_job,
+            resume_job,
+            get_result,
+            save_file,
+            open_output,
+            open_output_dir,
+            open_input_file,
+            list_recent_results,
+            get_result_thumbnail,
+            open_result_dir,
+            open_result_file,
+            open_help,
+            convert_existing,
+            rename_result_dir,
+            diff_results,
+            merge_results,
+            describe_run_options,
+            test_python,
+            list_python_candidates,
+            set_python_bin_override,
+            list_supported_languages,
+            search_results,
+            check_environment,
+            self_test,
+            load_settings,
+            save_settings,
+            start_watch,
+            stop_watch,
+            get_watch_status,
+            reset_settings,
+            export_settings,
+            import_settings
+        ])
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .setup(|app| {
+            if cfg!(debug_assertions) {
+                app.handle().plugin(
+                    tauri_plugin_log::Builder::default()
+                        .level(log::LevelFilter::Info)
+                        .build(),
+                )?;
+            }
+
+            let state = app.state::<Arc<AppState>>().inner().clone();
+            if let Some(project_root) = state.project_root() {
+                apply_window_settings(app.handle(), &project_root);
+
+                // 前回の監視フォルダ設定があれば再開する（失敗してもアプリ起動は続ける）
+                if let Some(watch) = load_settings_from_disk(&project_root)
+                    .ok()
+                    .and_then(|s| s.watch)
+                {
+                    if let Err(e) = start_watching(
+                        app.handle(),
+                        &state,
+                        PathBuf::from(&watch.folder),
+                        watch.options,
+                    ) {
+                        log::warn!("failed to resume watch folder {}: {e}", watch.folder);
+                    }
+                }
+            }
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // 実行中のジョブがあれば閉じる前に確認する
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                let state = window.state::<Arc<AppState>>();
+                let running = running_job_count(&state);
+                if running == 0 {
+                    return;
+                }
+                api.prevent_close();
+                let app = window.app_handle().clone();
+                window
+                    .dialog()
+                    .message(format!(
+                        "実行中のジョブが {running} 件あります。中断して終了しますか？"
+                    ))
+                    .title("OCR to Doc")
+                    .kind(MessageDialogKind::Warning)
+                    .buttons(MessageDialogButtons::OkCancelCustom(
+                        "中断して終了".into(),
+                        "キャンセル".into(),
+                    ))
+                    .show(move |confirmed| {
+                        if confirmed {
+                            kill_running_children(&app.state::<Arc<AppState>>());
+                            app.exit(0);
+                        }
+                    });
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // どの経路で終了しても Python プロセスを残さない
+            if let tauri::RunEvent::Exit = event {
+                let killed = kill_running_children(&app.state::<Arc<AppState>>());
+                if killed > 0 {
+                    log::info!("killed {killed} running dispatcher process(es) on exit");
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_at_separator(args: &[String]) -> (&[String], &[String]) {
+        match args.iter().position(|a| a == "--") {
+            Some(i) => (&args[..i], &args[i + 1..]),
+            None => (args, &[]),
+        }
+    }
+
+    /// テスト用の一時ディレクトリ。アサーションで panic しても drop で消える
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("ocr_to_doc_{name}_{}", Uuid::new_v4())))
+        }
+    }
+
+    impl std::ops::Deref for TempDir {
+        type Target = PathBuf;
+
+        fn deref(&self) -> &PathBuf {
+            &self.0
+        }
+    }
+
+    impl AsRef<std::path::Path> for TempDir {
+        fn as_ref(&self) -> &std::path::Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn dispatcher_args_route_dpi_and_page_range_before_separator() {
+        let opts = RunOptions {
+            pdf_dpi: Some(200),
+            chunk_size: Some(5),
+            enable_rest: true,
+            rest_seconds: Some(3),
+            ..RunOptions::default()
+        };
+        let file_opts = FileSpecificOptions {
+            start: Some(2),
+            end: Some(4),
+            ..FileSpecificOptions::default()
+        };
+        let args = build_dispatcher_args("a.pdf", &opts, Some(&file_opts));
+        let (dispatcher, passthrough) = split_at_separator(&args);
+
+        assert_eq!(dispatcher[0], "a.pdf");
+        for flag in ["--dpi", "--start", "--end"] {
+            assert!(dispatcher.iter().any(|a| a == flag), "{flag} missing");
+            assert!(!passthrough.iter().any(|a| a == flag), "{flag} after --");
+        }
+        assert_eq!(
+            passthrough,
+            ["--chunk-size", "5", "--enable-rest", "--rest-seconds", "3"]
+        );
+    }
+
+    #[test]
+    fn dispatcher_args_omit_separator_without_stability_settings() {
+        let args = build_dispatcher_args("a.png", &RunOptions::default(), None);
+        assert!(!args.iter().any(|a| a == "--"));
+        assert!(!args.iter().any(|a| a == "--mode"));
+    }
+
+    #[test]
+    fn mode_accepts_known_values_and_empty_default() {
+        assert_eq!(Mode::parse_optional(""), Ok(None));
+        assert_eq!(Mode::parse_optional("lite"), Ok(Some(Mode::Lite)));
+        assert_eq!(Mode::parse_optional(" full "), Ok(Some(Mode::Full)));
+    }
+
+    #[test]
+    fn mode_rejects_unknown_value_with_valid_options() {
+        let err = Mode::parse_optional("fast").unwrap_err();
+        assert!(err.contains("lite, full"), "{err}");
+    }
+
+    #[test]
+    fn run_options_reject_unknown_mode() {
+        let err = serde_json::from_str::<RunOptions>(r#"{"mode":"lit"}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown mode"));
+        let opts: RunOptions = serde_json::from_str(r#"{"mode":""}"#).unwrap();
+        assert_eq!(opts.mode, None);
+    }
+
+    #[test]
+    fn log_levels_are_inferred_from_markers_and_stderr() {
+        assert_eq!(
+            LogEntry::stdout("--- Page 3/9 (abs 3/12) ---").level,
+            LogLevel::Info
+        );
+        assert_eq!(
+            LogEntry::stdout("some yomitoku chatter").level,
+            LogLevel::Debug
+        );
+        assert_eq!(
+            LogEntry::stdout("[dispatcher] エラー: boom").level,
+            LogLevel::Error
+        );
+        assert_eq!(LogEntry::stderr("UserWarning: slow").level, LogLevel::Warn);
+        assert_eq!(LogEntry::stderr("ValueError: bad").level, LogLevel::Error);
+    }
+
+    #[test]
+    fn log_entry_displays_legacy_string_form() {
+        assert_eq!(LogEntry::stderr("oops").to_string(), "[err] oops");
+        assert_eq!(LogEntry::app("job started").to_string(), "job started");
+    }
+
+    #[test]
+    fn log_filter_matches_min_level_and_text() {
+        let log = vec![
+            LogEntry::app("job started"),
+            LogEntry::stderr("UserWarning: slow"),
+            LogEntry::stderr("ValueError: bad page"),
+        ];
+        let all = LogFilter::new(None, None).apply(&log);
+        assert_eq!(all.len(), 3);
+
+        let errors = LogFilter::new(Some(LogLevel::Error), None).apply(&log);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].text, "ValueError: bad page");
+
+        let warn_up = LogFilter::new(Some(LogLevel::Warn), Some("SLOW".into())).apply(&log);
+        assert_eq!(warn_up.len(), 1);
+
+        let blank_text = LogFilter::new(None, Some("  ".into())).apply(&log);
+        assert_eq!(blank_text.len(), 3);
+    }
+
+    #[test]
+    fn settings_validation_rejects_out_of_range_values() {
+        let base: AppSettings = serde_json::from_str("{}").unwrap();
+        assert!(base.validate().is_ok());
+
+        let bad_format = AppSettings {
+            formats: vec!["pdf".into()],
+            ..base.clone()
+        };
+        assert!(bad_format.validate().unwrap_err().contains("pdf"));
+
+        let bad_dpi = AppSettings {
+            pdf_dpi: Some(10),
+            ..base.clone()
+        };
+        assert!(bad_dpi.validate().is_err());
+    }
+
+    #[test]
+    fn settings_migration_renames_dpi_and_stamps_version() {
+        let (settings, migrated) = parse_settings(r#"{"dpi": 200}"#).unwrap();
+        assert_eq!(migrated.len(), 1);
+        assert_eq!(settings.pdf_dpi, Some(200));
+        assert_eq!(settings.schema_version, SETTINGS_SCHEMA_VERSION);
+
+        // 既に pdfDpi がある場合はそちらを優先
+        let (settings, _) = parse_settings(r#"{"dpi": 200, "pdfDpi": 300}"#).unwrap();
+        assert_eq!(settings.pdf_dpi, Some(300));
+    }
+
+    #[test]
+    fn settings_migration_skips_current_version() {
+        let content = format!(r#"{{"schemaVersion": {SETTINGS_SCHEMA_VERSION}}}"#);
+        let (_, migrated) = parse_settings(&content).unwrap();
+        assert!(migrated.is_empty());
+    }
+
+    #[test]
+    fn settings_from_a_newer_schema_are_not_overwritten() {
+        let root = TempDir::new("settings_newer");
+        fs::create_dir_all(root.join("configs")).unwrap();
+        let newer = format!(
+            r#"{{"schemaVersion": {}, "futureField": true}}"#,
+            SETTINGS_SCHEMA_VERSION + 1
+        );
+        fs::write(root.join("configs").join("settings.json"), &newer).unwrap();
+
+        let err = save_settings_to_disk(&root, &AppSettings::default_values()).unwrap_err();
+        assert!(err.contains("schemaVersion"), "{err}");
+        assert_eq!(
+            fs::read_to_string(root.join("configs").join("settings.json")).unwrap(),
+            newer
+        );
+    }
+
+    #[test]
+    fn default_settings_pass_validation() {
+        let settings = AppSettings::default_values();
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.schema_version, SETTINGS_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn progress_never_moves_backwards() {
+        let mut job = JobInfo::new_running(&["a.pdf".into(), "b.pdf".into()]);
+        let mut seen = vec![job.progress];
+        // 1 件目の OCR が 45% まで進んだ後、2 件目の開始（基準 50%）と再試行（基準 0%）が来る
+        for update in [10.0, 45.0, 30.0, f32::NAN, 50.0, 0.0, 72.5, 100.0, 99.0] {
+            set_progress(&mut job, update);
+            seen.push(job.progress);
+        }
+        ProgressPatch {
+            progress: Some(60.0),
+            ..Default::default()
+        }
+        .apply(&mut job);
+        seen.push(job.progress);
+
+        assert!(seen.windows(2).all(|w| w[0] <= w[1]), "{seen:?}");
+        assert_eq!(job.progress, 100.0);
+    }
+
+    #[test]
+    fn progress_patch_merge_keeps_latest_values_and_max_progress() {
+        let mut patch = ProgressPatch {
+            progress: Some(40.0),
+            current_message: Some("a".into()),
+            eta_seconds: Some(Some(30)),
+            ..Default::default()
+        };
+        patch.merge(ProgressPatch {
+            progress: Some(20.0),
+            page_current: Some(3),
+            eta_seconds: Some(None),
+            ..Default::default()
+        });
+        assert_eq!(patch.progress, Some(40.0));
+        assert_eq!(patch.current_message.as_deref(), Some("a"));
+        assert_eq!(patch.page_current, Some(3));
+        assert_eq!(patch.eta_seconds, Some(None));
+    }
+
+    #[test]
+    fn dispatcher_args_append_extra_args_on_each_side_of_separator() {
+        let opts = RunOptions {
+            chunk_size: Some(5),
+            extra_dispatcher_args: vec!["--foo".into(), "1".into()],
+            extra_ocr_args: vec!["--bar".into()],
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        let sep = args.iter().position(|a| a == "--").unwrap();
+        let foo = args.iter().position(|a| a == "--foo").unwrap();
+        assert!(foo < sep);
+        assert_eq!(args[foo + 1], "1");
+        assert_eq!(args.last().map(String::as_str), Some("--bar"));
+
+        // OCR 側だけ指定された場合も区切りが入る
+        let opts = RunOptions {
+            extra_ocr_args: vec!["--bar".into()],
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        assert_eq!(&args[args.len() - 2..], ["--", "--bar"]);
+    }
+
+    #[test]
+    fn extra_args_reject_empty_and_separator() {
+        let mut opts = RunOptions {
+            extra_ocr_args: vec!["".into()],
+            ..RunOptions::default()
+        };
+        assert!(opts.validate_extra_args().is_err());
+        opts.extra_ocr_args.clear();
+        opts.extra_dispatcher_args = vec!["--".into()];
+        assert!(opts.validate_extra_args().is_err());
+        opts.extra_dispatcher_args = vec!["--foo".into()];
+        assert!(opts.validate_extra_args().is_ok());
+    }
+
+    fn batch(statuses: &[FileStatus]) -> JobInfo {
+        let paths: Vec<String> = (0..statuses.len()).map(|i| format!("{i}.pdf")).collect();
+        let mut job = JobInfo::new_running(&paths);
+        for (f, status) in job.files.iter_mut().zip(statuses) {
+            f.status = *status;
+        }
+        job
+    }
+
+    #[test]
+    fn batch_eta_adds_pending_files_at_cross_file_page_rate() {
+        let mut job = batch(&[FileStatus::Done, FileStatus::Running, FileStatus::Pending]);
+        job.eta_seconds = Some(20);
+        job.batch_stats.seed_secs_per_page = 9.0;
+        // 1 ファイル目: 4 ページを 8 秒（2 秒/ページ）
+        job.batch_stats.record_file(4, 8.0);
+        // 未着手 1 ファイル × 平均 4 ページ × 2 秒
+        assert_eq!(job.batch_eta_seconds(), Some(28));
+
+        // 最初のファイルの途中は、そのファイルのページ数と過去の実績で見込む
+        let mut job = batch(&[
+            FileStatus::Running,
+            FileStatus::Pending,
+            FileStatus::Pending,
+        ]);
+        job.eta_seconds = Some(10);
+        job.page_total = Some(5);
+        job.batch_stats.seed_secs_per_page = 3.0;
+        assert_eq!(job.batch_eta_seconds(), Some(40));
+
+        // 最後のファイルではファイルの ETA と同じ
+        let mut job = batch(&[FileStatus::Done, FileStatus::Running]);
+        job.eta_seconds = Some(7);
+        assert_eq!(job.batch_eta_seconds(), Some(7));
+
+        job.cancel();
+        assert_eq!(job.batch_eta_seconds(), None);
+    }
+
+    #[test]
+    fn finish_batch_is_done_when_some_files_succeed() {
+        let mut job = batch(&[FileStatus::Done, FileStatus::Error, FileStatus::Done]);
+        job.finish_batch();
+        assert_eq!(job.status, JobStatus::Done);
+        assert_eq!(job.error.as_deref(), Some("1/3 files failed"));
+    }
+
+    #[test]
+    fn finish_batch_is_error_when_every_file_fails() {
+        let mut job = batch(&[FileStatus::Error, FileStatus::Error]);
+        job.finish_batch();
+        assert_eq!(job.status, JobStatus::Error);
+    }
+
+    #[test]
+    fn skip_file_skips_pending_and_cancels_running_files() {
+        let mut job = batch(&[FileStatus::Done, FileStatus::Running, FileStatus::Pending]);
+        assert_eq!(job.skip_file("2.pdf"), Ok(false));
+        assert_eq!(job.files[2].status, FileStatus::Skipped);
+        assert_eq!(job.skip_file("1.pdf"), Ok(true));
+        assert_eq!(job.files[1].status, FileStatus::Cancelled);
+        // 終わったファイルや外したファイルは対象外
+        assert!(job.skip_file("0.pdf").is_err());
+        assert!(job.skip_file("2.pdf").is_err());
+        assert!(job.skip_file("missing.pdf").is_err());
+
+        job.finish_batch();
+        assert_eq!(job.status, JobStatus::Done);
+        assert!(job.error.is_none());
+    }
+
+    #[test]
+    fn reorder_files_permutes_only_pending_files() {
+        let mut job = batch(&[
+            FileStatus::Done,
+            FileStatus::Running,
+            FileStatus::Pending,
+            FileStatus::Skipped,
+            FileStatus::Pending,
+        ]);
+        let order = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        job.reorder_files(&order(&["4.pdf", "2.pdf"])).unwrap();
+        let paths: Vec<&str> = job.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["0.pdf", "1.pdf", "4.pdf", "3.pdf", "2.pdf"]);
+        assert_eq!(job.files[2].status, FileStatus::Pending);
+        assert_eq!(job.files[3].status, FileStatus::Skipped);
+
+        // 処理中・処理済みのファイルを含む、足りない、重複するものは受け付けない
+        assert!(job
+            .reorder_files(&order(&["1.pdf", "2.pdf", "4.pdf"]))
+            .is_err());
+        assert!(job.reorder_files(&order(&["2.pdf"])).is_err());
+        assert!(job.reorder_files(&order(&["2.pdf", "2.pdf"])).is_err());
+        assert_eq!(job.files[2].path, "4.pdf");
+    }
+
+    #[test]
+    fn finish_batch_counts_files_skipped_as_up_to_date() {
+        let mut job = batch(&[FileStatus::Pending, FileStatus::Pending]);
+        job.skip_up_to_date(0, vec!["0.md".into()]);
+        job.skip_up_to_date(1, vec![]);
+        assert_eq!(job.files[0].status, FileStatus::Skipped);
+        assert_eq!(job.files[0].outputs, ["0.md"]);
+        job.finish_batch();
+        assert_eq!(job.status, JobStatus::Done);
+        assert!(job.error.is_none());
+        assert_eq!(
+            job.current_message.as_deref(),
+            Some("2/2 files skipped (up to date)")
+        );
+
+        let mut job = batch(&[FileStatus::Error, FileStatus::Pending, FileStatus::Done]);
+        job.skip_up_to_date(1, vec![]);
+        job.finish_batch();
+        assert_eq!(
+            job.error.as_deref(),
+            Some("1/3 files failed, 1 skipped (up to date)")
+        );
+    }
+
+    #[test]
+    fn finish_batch_marks_unprocessed_files_skipped() {
+        let mut job = batch(&[FileStatus::Done, FileStatus::Pending]);
+        job.finish_batch();
+        assert_eq!(job.status, JobStatus::Done);
+        assert!(job.error.is_none());
+        assert_eq!(job.files[1].status, FileStatus::Skipped);
+    }
+
+    #[test]
+    fn dispatcher_args_prefer_file_rotation_over_global() {
+        let opts = RunOptions {
+            rotate: Some(90),
+            auto_deskew: true,
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("a.png", &opts, None);
+        let (dispatcher, _) = split_at_separator(&args);
+        assert!(dispatcher.windows(2).any(|w| w == ["--rotate", "90"]));
+        assert!(dispatcher.contains(&"--deskew".to_string()));
+
+        let file_opts = FileSpecificOptions {
+            rotate: Some(0),
+            auto_deskew: Some(false),
+            ..FileSpecificOptions::default()
+        };
+        let args = build_dispatcher_args("a.png", &opts, Some(&file_opts));
+        assert!(!args.iter().any(|a| a == "--rotate" || a == "--deskew"));
+    }
+
+    #[test]
+    fn rotate_must_be_a_right_angle() {
+        assert!(validate_rotate(Some(270)).is_ok());
+        assert!(validate_rotate(None).is_ok());
+        assert!(validate_rotate(Some(45)).is_err());
+
+        let mut file_options = HashMap::new();
+        file_options.insert(
+            "a.png".to_string(),
+            FileSpecificOptions {
+                rotate: Some(-90),
+                ..FileSpecificOptions::default()
+            },
+        );
+        let opts = RunOptions {
+            file_options: Some(file_options),
+            ..RunOptions::default()
+        };
+        assert!(opts.validate().is_err());
+
+        let mut file_options = HashMap::new();
+        file_options.insert(
+            "a.pdf".to_string(),
+            FileSpecificOptions {
+                crop: Some(CropRect {
+                    left: 0.0,
+                    top: 0.0,
+                    width: 0.5,
+                    height: 0.5,
+                }),
+                ..FileSpecificOptions::default()
+            },
+        );
+        let mut opts = RunOptions {
+            file_options: Some(file_options),
+            ..RunOptions::default()
+        };
+        assert!(opts.validate().is_ok());
+        opts.auto_orient = true;
+        assert!(opts.validate().unwrap_err().contains("autoOrient"));
+    }
+
+    #[test]
+    fn page_spec_merges_overlaps_and_sorts() {
+        assert_eq!(
+            parse_page_spec("10-12, 1-3,7").unwrap(),
+            vec![(1, 3), (7, 7), (10, 12)]
+        );
+        assert_eq!(parse_page_spec("1-5,3-7,8").unwrap(), vec![(1, 8)]);
+        assert_eq!(parse_page_spec("4,4").unwrap(), vec![(4, 4)]);
+        assert_eq!(
+            format_page_spec(&parse_page_spec("7,1-3").unwrap()),
+            "1-3,7"
+        );
+    }
+
+    #[test]
+    fn page_spec_rejects_reversed_zero_and_garbage() {
+        for spec in ["5-3", "0-2", "0", "1,,2", "a-b", "-3", ""] {
+            assert!(
+                parse_page_spec(spec).is_err(),
+                "{spec:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn dispatcher_args_prefer_pages_over_start_end() {
+        let file_opts = FileSpecificOptions {
+            start: Some(2),
+            end: Some(4),
+            pages: Some("7, 1-3".into()),
+            ..FileSpecificOptions::default()
+        };
+        let args = build_dispatcher_args("a.pdf", &RunOptions::default(), Some(&file_opts));
+        assert!(args.windows(2).any(|w| w == ["--pages", "1-3,7"]));
+        assert!(!args.iter().any(|a| a == "--start" || a == "--end"));
+    }
+
+    #[test]
+    fn page_range_label_from_legacy_dir_name() {
+        assert_eq!(
+            parse_page_range_from_dir("report_p3-9").as_deref(),
+            Some("p3-9")
+        );
+        assert_eq!(
+            parse_page_range_from_dir("my_photo_p11-20").as_deref(),
+            Some("p11-20")
+        );
+    }
+
+    #[test]
+    fn page_range_label_from_multi_range_and_open_names() {
+        assert_eq!(
+            parse_page_range_from_dir("foo_p1-3_7_10-12").as_deref(),
+            Some("p1-3, 7, 10-12")
+        );
+        assert_eq!(parse_page_range_from_dir("foo_p5-").as_deref(), Some("p5-"));
+        assert_eq!(parse_page_range_from_dir("foo_p4").as_deref(), Some("p4"));
+    }
+
+    #[test]
+    fn page_range_label_rejects_malformed_names() {
+        for name in [
+            "foo",
+            "foo_p",
+            "foo_p-3",
+            "foo_p3-9x",
+            "foo_p1-3__7",
+            "foo_p9-3",
+            "foo_px",
+        ] {
+            assert_eq!(parse_page_range_from_dir(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn pages_in_selection_respects_ranges() {
+        assert_eq!(pages_in_selection(20, None), 20);
+        let range = FileSpecificOptions {
+            start: Some(5),
+            end: Some(30),
+            ..FileSpecificOptions::default()
+        };
+        assert_eq!(pages_in_selection(20, Some(&range)), 16);
+        let past_end = FileSpecificOptions {
+            start: Some(25),
+            ..FileSpecificOptions::default()
+        };
+        assert_eq!(pages_in_selection(20, Some(&past_end)), 0);
+        let spec = FileSpecificOptions {
+            pages: Some("1-3,7,18-25,40".into()),
+            ..FileSpecificOptions::default()
+        };
+        assert_eq!(pages_in_selection(20, Some(&spec)), 3 + 1 + 3);
+    }
+
+    #[test]
+    fn throughput_metrics_default_per_device_and_blend() {
+        let mut metrics = ThroughputMetrics::default();
+        assert_eq!(
+            metrics.seconds_per_page(false),
+            DEFAULT_SECONDS_PER_PAGE_CPU
+        );
+        assert_eq!(metrics.seconds_per_page(true), DEFAULT_SECONDS_PER_PAGE_GPU);
+
+        metrics.record(false, 12.0);
+        assert_eq!(metrics.seconds_per_page(false), 12.0);
+        metrics.record(false, 22.0);
+        let blended = metrics.seconds_per_page(false);
+        assert!(blended > 12.0 && blended < 22.0);
+        assert_eq!(metrics.cpu.as_ref().map(|c| c.samples), Some(2));
+        // GPU 側は独立
+        assert_eq!(metrics.seconds_per_page(true), DEFAULT_SECONDS_PER_PAGE_GPU);
+    }
+
+    #[test]
+    fn corrupt_metrics_file_falls_back_to_defaults() {
+        let root = TempDir::new("metrics");
+        fs::create_dir_all(root.join("configs")).unwrap();
+        fs::write(metrics_path(&root), "{not json").unwrap();
+        assert_eq!(load_metrics(&root), ThroughputMetrics::default());
+    }
+
+    #[test]
+    fn resolve_result_dir_stays_inside_result_root() {
+        let root = TempDir::new("result");
+        fs::create_dir_all(root.join("result").join("sample")).unwrap();
+        fs::write(root.join("result").join("sample").join("sample.MD"), "# x").unwrap();
+
+        let dir = resolve_result_dir(&root, "sample").unwrap();
+        assert_eq!(
+            newest_file_with_extension(&dir, "md").as_deref(),
+            Some("sample.MD")
+        );
+        assert_eq!(newest_file_with_extension(&dir, "docx"), None);
+        assert!(resolve_result_dir(&root, "missing").is_err());
+    }
+
+    #[test]
+    fn snippet_keeps_context_around_match_on_char_boundaries() {
+        let line = format!("{}請求書番号 12345{}", "あ".repeat(60), "い".repeat(60));
+        let pos = line.find("請求書").unwrap();
+        let snippet = make_snippet(&line, pos, "請求書".len());
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("請求書番号 12345"));
+        assert_eq!(make_snippet("short hit", 6, 3), "short hit");
+    }
+
+    #[test]
+    fn search_file_caps_hits_and_skips_page_md_when_merged_exists() {
+        let dir = TempDir::new("search");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("doc_merged.md"),
+            "Total\nnone\ntotal due\n".repeat(10),
+        )
+        .unwrap();
+        fs::write(dir.join("page_001.md"), "total").unwrap();
+
+        let files = searchable_files(&dir, "doc");
+        assert_eq!(files, vec![dir.join("doc_merged.md")]);
+        let hits = search_file(&files[0], "total", 3);
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0], (1, "Total".to_string()));
+        assert_eq!(hits[1].0, 3);
+    }
+
+    #[test]
+    fn rename_prefixed_outputs_follows_dir_name() {
+        let dir = TempDir::new("rename");
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "scan_p3-9_merged.md",
+            "scan_p3-9.docx",
+            "scan_p3-9x.md",
+            "page_001.md",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        rename_prefixed_outputs(&dir, "scan_p3-9", "invoice");
+        assert!(dir.join("invoice_merged.md").is_file());
+        assert!(dir.join("invoice.docx").is_file());
+        assert!(dir.join("scan_p3-9x.md").is_file());
+        assert!(dir.join("page_001.md").is_file());
+    }
+
+    #[test]
+    fn rename_output_dir_updates_finished_job() {
+        let mut job = batch(&[FileStatus::Done]);
+        job.output_dirs = vec!["scan".into()];
+        job.created_dirs = vec!["scan".into()];
+        job.outputs = vec!["scan_merged.md".into(), "scan/figures/fig_1.png".into()];
+        job.files[0].outputs = job.outputs.clone();
+        job.rename_output_dir("scan", "invoice");
+        assert_eq!(job.output_dirs, ["invoice"]);
+        assert_eq!(job.created_dirs, ["invoice"]);
+        assert_eq!(
+            job.outputs,
+            ["invoice_merged.md", "invoice/figures/fig_1.png"]
+        );
+        assert_eq!(job.files[0].outputs, job.outputs);
+
+        // 結果が複数あるジョブでは、どのディレクトリの物か分からない出力名は変えない
+        let mut job = batch(&[FileStatus::Done, FileStatus::Done]);
+        job.output_dirs = vec!["scan".into(), "scan_p1-2".into()];
+        job.outputs = vec!["scan_p1-2.docx".into()];
+        job.rename_output_dir("scan", "invoice");
+        assert_eq!(job.output_dirs, ["invoice", "scan_p1-2"]);
+        assert_eq!(job.outputs, ["scan_p1-2.docx"]);
+    }
+
+    #[test]
+    fn thumbnail_source_prefers_page_images() {
+        let dir = TempDir::new("thumb");
+        fs::create_dir_all(dir.join("page_images")).unwrap();
+        fs::write(dir.join(THUMBNAIL_FILE_NAME), "").unwrap();
+        assert_eq!(find_thumbnail_source(&dir), None);
+
+        fs::write(dir.join("scan_oriented.png"), "").unwrap();
+        assert_eq!(
+            find_thumbnail_source(&dir),
+            Some(dir.join("scan_oriented.png"))
+        );
+        fs::write(dir.join("page_images").join("page_002.png"), "").unwrap();
+        fs::write(dir.join("page_images").join("page_001.png"), "").unwrap();
+        assert_eq!(
+            find_thumbnail_source(&dir),
+            Some(dir.join("page_images").join("page_001.png"))
+        );
+    }
+
+    #[test]
+    fn markdown_preview_html_keeps_tables_and_drops_scripts() {
+        let md = "| a | b |\n|---|---|\n| 1 | 2 |\n\n<script>alert(1)</script>\n\n<img src=x onerror=alert(1)>\n";
+        let html = markdown_to_safe_html(md);
+        assert!(html.contains("<table>"));
+        assert!(html.contains("<td>1</td>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+    }
+
+    #[test]
+    fn diff_lines_reports_added_removed_and_changed() {
+        let a = "title\nkeep\nold value\ngone\n";
+        let b = "title\nkeep\nnew value\ntail\nextra\n";
+        let lines = diff_lines(a, b);
+        assert!(lines.iter().all(|l| l.text_a.as_deref() != Some("keep")));
+        let changed: Vec<_> = lines
+            .iter()
+            .filter(|l| l.kind == DiffKind::Changed)
+            .collect();
+        assert_eq!(changed[0].line_a, Some(3));
+        assert_eq!(changed[0].text_b.as_deref(), Some("new value"));
+        assert!(lines
+            .iter()
+            .any(|l| l.kind == DiffKind::Added && l.text_b.as_deref() == Some("extra")));
+        assert!(diff_lines("same\n", "same\n").is_empty());
+    }
+
+    #[test]
+    fn sniff_image_extension_by_magic_bytes() {
+        assert_eq!(sniff_image_extension(b"\x89PNG\r\n\x1a\nrest"), Some("png"));
+        assert_eq!(
+            sniff_image_extension(&[0xff, 0xd8, 0xff, 0xe0]),
+            Some("jpg")
+        );
+        assert_eq!(sniff_image_extension(b"RIFF\0\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(sniff_image_extension(b"<script>"), None);
+    }
+
+    #[test]
+    fn help_file_prefers_docs_help_over_readme() {
+        let root = TempDir::new("help");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        assert_eq!(find_help_file(&root), None);
+        fs::write(root.join("readme.md"), "").unwrap();
+        assert!(find_help_file(&root).is_some());
+        fs::write(root.join("docs").join("help.md"), "").unwrap();
+        assert_eq!(
+            find_help_file(&root),
+            Some(root.join("docs").join("help.md"))
+        );
+    }
+
+    #[test]
+    fn cancel_skips_unfinished_files() {
+        let mut job = JobInfo::new_running(&["a.pdf".into(), "b.pdf".into(), "c.pdf".into()]);
+        job.files[0].status = FileStatus::Done;
+        job.files[1].status = FileStatus::Running;
+        job.cancel();
+        assert_eq!(job.status, JobStatus::Cancelled);
+        assert!(job.stop_requested);
+        assert_eq!(
+            job.files.iter().map(|f| f.status).collect::<Vec<_>>(),
+            vec![FileStatus::Done, FileStatus::Skipped, FileStatus::Skipped]
+        );
+        job.finish_batch();
+        assert_eq!(job.status, JobStatus::Cancelled);
+    }
+
+    #[test]
+    fn temp_dir_is_created_and_forwarded_before_separator() {
+        let root = TempDir::new("tmp");
+        let dir = validate_temp_dir(&root.join("scratch").to_string_lossy()).unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let mut opts = RunOptions {
+            extra_ocr_args: vec!["--chunk-size".into(), "4".into()],
+            ..RunOptions::default()
+        };
+        push_temp_dir_arg(&mut opts.extra_dispatcher_args, Some(&dir));
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        let (before, _) = split_at_separator(&args);
+        assert!(before.windows(2).any(|w| w[0] == "--temp-dir"));
+    }
+
+    #[test]
+    fn byte_progress_grows_monotonically_below_cap() {
+        assert_eq!(byte_progress_fraction(0), 0.0);
+        let small = byte_progress_fraction(1024 * 1024);
+        let large = byte_progress_fraction(64 * 1024 * 1024);
+        assert!(small > 0.0 && small < large);
+        assert!(large <= BYTE_PROGRESS_CAP);
+    }
+
+    #[test]
+    fn password_is_passed_by_env_and_kept_out_of_args_and_spawn_log() {
+        let opts = RunOptions {
+            password: Some("s3cret".into()),
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("locked.pdf", &opts, None);
+        assert!(!args
+            .iter()
+            .any(|a| a.contains("s3cret") || a == "--password"));
+
+        let mut cmd = Command::new("python");
+        cmd.arg("dispatcher.py").args(&args);
+        apply_pdf_password(&mut cmd, opts.password.as_deref());
+        assert!(cmd
+            .get_envs()
+            .any(|(k, v)| k == PDF_PASSWORD_ENV && v == Some(std::ffi::OsStr::new("s3cret"))));
+        assert!(!describe_command(&cmd).contains("s3cret"));
+
+        let extra = RunOptions {
+            extra_ocr_args: vec!["--password=s3cret".into()],
+            ..RunOptions::default()
+        };
+        assert!(extra.validate_extra_args().is_err());
+        // 監視設定として保存されるときにパスワードは書き出さない
+        assert!(!serde_json::to_string(&opts).unwrap().contains("s3cret"));
+    }
+
+    #[test]
+    fn encrypted_preview_response_becomes_prefixed_error() {
+        let resp: PreviewResponse =
+            serde_json::from_str(r#"{"errorKind":"encrypted","error":"locked"}"#).unwrap();
+        assert!(resp.data_url.is_empty());
+        assert!(preview_error_message(&resp).starts_with("encrypted:"));
+    }
+
+    #[test]
+    fn log_tail_returns_last_entries_in_order() {
+        let log: Vec<LogEntry> = (0..5).map(|i| LogEntry::app(format!("line {i}"))).collect();
+        let tail = log_tail(&log, 2);
+        assert_eq!(tail.len(), 2);
+        assert_eq!(tail[0].text, "line 3");
+        assert_eq!(log_tail(&log, 50).len(), 5);
+        assert!(log_tail(&log, 0).is_empty());
+    }
+
+    #[test]
+    fn watch_folder_accepts_tiff_inputs() {
+        assert!(is_supported_input(std::path::Path::new("scan.TIF")));
+        assert!(is_supported_input(std::path::Path::new("scan.tiff")));
+        assert!(!is_supported_input(std::path::Path::new("scan.gif")));
+    }
+
+    #[test]
+    fn progress_flush_interval_clamps_update_rate() {
+        assert_eq!(progress_flush_interval(None), Duration::from_millis(100));
+        assert_eq!(
+            progress_flush_interval(Some(0)),
+            Duration::from_millis(1000)
+        );
+        assert_eq!(progress_flush_interval(Some(4)), Duration::from_millis(250));
+        assert_eq!(
+            progress_flush_interval(Some(120)),
+            Duration::from_millis(33)
+        );
+    }
+
+    #[test]
+    fn format_utc_ms_renders_iso_timestamp() {
+        assert_eq!(format_utc_ms(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_utc_ms(1_709_210_096_789), "2024-02-29T12:34:56.789Z");
+
+        let job = JobInfo::new_running(&[]);
+        assert_eq!(
+            job.log[0].text,
+            format!("job started at {}", format_utc_ms(job.started_at_ms))
+        );
+        let line = spawn_log_line(&Command::new("python"));
+        assert!(
+            line.starts_with("spawn: [") && line.contains("Z] "),
+            "{line}"
+        );
+    }
+
+    #[test]
+    fn format_job_log_includes_environment_and_entries() {
+        let env = EnvironmentStatus {
+            project_root: "/proj".into(),
+            dispatcher_found: true,
+            dispatcher_path: "/proj/dispatcher.py".into(),
+            preview_helper_found: true,
+            result_dir_found: true,
+            python_bin: "python3".into(),
+        };
+        let mut entry = LogEntry::stderr("boom");
+        entry.ts_ms = 0;
+        let text = format_job_log("job-1", &env, Some("Python 3.11.9"), &[entry]);
+        assert!(text.contains("python version: Python 3.11.9"));
+        assert!(text.contains("dispatcher: /proj/dispatcher.py (found: true)"));
+        assert!(text.contains("1970-01-01T00:00:00.000Z"));
+        assert!(text.contains("stderr boom"));
+    }
+
+    #[test]
+    fn file_options_match_keys_with_mixed_separators() {
+        let mut file_options = HashMap::new();
+        file_options.insert(
+            "C:/scans/Report.pdf".to_string(),
+            FileSpecificOptions {
+                start: Some(2),
+                ..FileSpecificOptions::default()
+            },
+        );
+        let opts = RunOptions {
+            file_options: Some(file_options),
+            ..RunOptions::default()
+        };
+        let found = opts.file_options_for("C:\\scans\\Report.pdf");
+        assert_eq!(found.and_then(|f| f.start), Some(2));
+        assert!(opts.file_options_for("C:\\scans\\other.pdf").is_none());
+    }
+
+    #[test]
+    fn process_tree_collects_descendants_only() {
+        let parents = [(10, 1), (11, 10), (12, 11), (13, 1), (14, 13)];
+        let mut tree = process_tree(10, &parents);
+        tree.sort();
+        assert_eq!(tree, vec![10, 11, 12]);
+    }
+
+    #[test]
+    fn paused_jobs_count_as_active() {
+        assert!(JobStatus::Paused.is_active());
+        assert!(JobStatus::Running.is_active());
+        assert!(!JobStatus::Cancelled.is_active());
+        let mut job = JobInfo::new_running(&["a.pdf".to_string()]);
+        job.status = JobStatus::Paused;
+        job.cancel();
+        assert_eq!(job.status, JobStatus::Cancelled);
+    }
 
-        candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
-        candidates.first().map(|(_, p)| p.clone())
+    #[test]
+    fn rebase_relative_links_points_figures_at_source_dir() {
+        let md = "![fig](figures/p1.png) [site](https://example.com) [top](#a) ![abs](/x.png)";
+        assert_eq!(
+            rebase_relative_links(md, "../doc/"),
+            "![fig](../doc/figures/p1.png) [site](https://example.com) [top](#a) ![abs](/x.png)"
+        );
     }
 
-    let mut found = Vec::new();
-    for input in inputs {
-        let input_path = PathBuf::from(input);
-        let stem_owned = input_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output")
-            .to_string();
-        let stem = stem_owned.as_str();
+    #[test]
+    fn preview_format_and_quality_are_validated() {
+        assert!(validate_preview_format(None, None).is_ok());
+        assert!(validate_preview_format(Some("jpeg"), Some(80)).is_ok());
+        assert!(validate_preview_format(Some("gif"), None).is_err());
+        assert!(validate_preview_format(Some("webp"), Some(0)).is_err());
+        assert!(validate_preview_format(None, Some(101)).is_err());
+    }
 
-        // result/<stem> もしくは result/<stem>_*（ページ範囲指定などの suffix 付き）の最新ディレクトリ内
-        let result_root = project_root.join("result");
-        if let Some(result_dir) = pick_latest_result_dir(&result_root, stem) {
-            let dir_name = result_dir
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
+    #[test]
+    fn job_log_file_rotates_past_the_size_limit() {
+        let root = TempDir::new("joblog");
+        let file = JobLogFile::new(&root, "job", Some(120));
+        let entry = |text: &str| LogEntry::stdout(text);
+
+        file.append(&[entry("first line")]).unwrap();
+        file.append(&[entry("second line")]).unwrap();
+        assert!(!root.join("logs").join("job.log.1").exists());
+
+        file.append(&[entry(&"x".repeat(80))]).unwrap();
+        let rotated = fs::read_to_string(root.join("logs").join("job.log.1")).unwrap();
+        let current = fs::read_to_string(root.join("logs").join("job.log")).unwrap();
+        assert!(rotated.contains("first line") && rotated.contains("second line"));
+        assert!(current.contains(&"x".repeat(80)) && !current.contains("first line"));
+    }
 
-            for fmt in formats {
-                if fmt == "xlsx" {
-                    // dispatcher は <output_dir.name>.xlsx を作る
-                    push_unique(&mut found, result_dir.join(format!("{dir_name}.xlsx")));
-                    push_unique(&mut found, result_dir.join(format!("{stem}.xlsx")));
-                    // 念のため
-                    push_unique(
-                        &mut found,
-                        result_dir.join(format!("{dir_name}_merged.xlsx")),
-                    );
-                    push_unique(&mut found, result_dir.join(format!("{stem}_merged.xlsx")));
-                    continue;
-                }
-                if fmt == "csv" {
-                    if let Ok(entries) = fs::read_dir(&result_dir) {
-                        for entry in entries.flatten() {
-                            let path = entry.path();
-                            if !path.is_file() {
-                                continue;
-                            }
-                            if path.extension().map(|e| e == "csv").unwrap_or(false) {
-                                push_unique(&mut found, path);
-                            }
-                        }
-                    }
-                    continue;
-                }
+    #[test]
+    fn describe_run_options_covers_every_field() {
+        let defaults = serde_json::to_value(RunOptions::default()).unwrap();
+        let mut serialized: Vec<&str> = defaults
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        // password は skip_serializing なので既定値には出てこない
+        serialized.push("password");
+        serialized.sort();
+        let mut described: Vec<&str> = describe_run_options().iter().map(|f| f.name).collect();
+        described.sort();
+        assert_eq!(described, serialized);
+
+        let fields = describe_run_options();
+        let mode = fields.iter().find(|f| f.name == "mode").unwrap();
+        assert_eq!(
+            mode.allowed_values,
+            Some(vec![serde_json::json!("lite"), serde_json::json!("full")])
+        );
+        let formats = fields.iter().find(|f| f.name == "formats").unwrap();
+        assert_eq!(formats.default, serde_json::json!(["md"]));
+    }
 
-                // ocr_chanked のマージ出力 + export_docx の変換結果は <output_dir.name>_merged.<fmt>
-                push_unique(
-                    &mut found,
-                    result_dir.join(format!("{dir_name}_merged.{fmt}")),
-                );
-                // 旧ルール互換
-                push_unique(&mut found, result_dir.join(format!("{stem}_merged.{fmt}")));
-                push_unique(&mut found, result_dir.join(format!("{stem}.{fmt}")));
-                push_unique(&mut found, result_dir.join(format!("{dir_name}.{fmt}")));
-            }
-        }
+    #[test]
+    fn run_options_validate_excel_mode() {
+        let opts: RunOptions = serde_json::from_str(r#"{"excelMode":"table"}"#).unwrap();
+        assert_eq!(opts.excel_mode, Some(ExcelMode::Table));
+        let args = build_dispatcher_args("a.pdf", &opts, None);
+        assert!(args.windows(2).any(|w| w == ["--excel-mode", "table"]));
 
-        // ルート直下に <stem>_merged.<fmt> / <stem>.<fmt>
-        for fmt in formats {
-            let c1 = project_root.join(format!("{}_merged.{}", stem, fmt));
-            push_unique(&mut found, c1);
-            let c2 = project_root.join(format!("{}.{}", stem, fmt));
-            push_unique(&mut found, c2);
-        }
+        let opts: RunOptions = serde_json::from_str(r#"{"excelMode":""}"#).unwrap();
+        assert_eq!(opts.excel_mode, None);
+
+        let err = serde_json::from_str::<RunOptions>(r#"{"excelMode":"tabel"}"#).unwrap_err();
+        assert!(err.to_string().contains("layout, table"), "{err}");
     }
-    found
-}
 
-/// Walk ancestors from exe_dir to find dispatcher.py; return its parent (project root)
-fn resolve_project_root(exe_dir: &std::path::Path) -> Option<PathBuf> {
-    for anc in exe_dir.ancestors() {
-        let legacy = anc.join("dispatcher.py");
-        let res = anc.join("resources").join("py").join("dispatcher.py");
-        if legacy.exists() || res.exists() {
-            return Some(anc.to_path_buf());
+    #[test]
+    fn python_bin_source_falls_back_to_path() {
+        if std::env::var("PYTHON_BIN").is_ok_and(|v| !v.is_empty()) {
+            return;
         }
+        let root = TempDir::new("pyprobe");
+        let (bin, source) = resolve_python_bin_with_source(&root);
+        assert_eq!((bin.as_str(), source), ("python", PythonSource::Path));
     }
-    None
-}
 
-#[tauri::command]
-fn get_result(job_id: String, state: State<Arc<AppState>>) -> Result<ResultResponse, String> {
-    let jobs = state
-        .jobs
-        .lock()
-        .map_err(|e| format!("lock poisoned: {e}"))?;
-    if let Some(job) = jobs.get(&job_id) {
-        return Ok(ResultResponse {
-            outputs: job.outputs.clone(),
-            preview: job.preview.clone(),
-        });
+    #[test]
+    fn python_override_comes_first_and_candidates_are_deduplicated() {
+        let root = TempDir::new("pyoverride");
+        let venv = venv_python(&root.join(".venv"));
+        fs::create_dir_all(venv.parent().unwrap()).unwrap();
+        fs::write(&venv, "").unwrap();
+        let venv_bin = venv.to_string_lossy().to_string();
+
+        let mut settings = AppSettings::default_values();
+        settings.python_bin_override = Some("/opt/py312/bin/python".into());
+        settings.python_candidates = Some(vec![venv_bin.clone(), "/opt/conda/bin/python".into()]);
+        save_settings_to_disk(&root, &settings).unwrap();
+
+        let (bin, source) = resolve_python_bin_with_source(&root);
+        assert_eq!(
+            (bin.as_str(), source),
+            ("/opt/py312/bin/python", PythonSource::Override)
+        );
+        let candidates = python_candidates(&root, &settings);
+        assert_eq!(candidates[0].source, PythonSource::Override);
+        assert!(candidates[0].active);
+        let venv_entries: Vec<_> = candidates
+            .iter()
+            .filter(|c| c.python_bin == venv_bin)
+            .collect();
+        assert_eq!(venv_entries.len(), 1);
+        assert_eq!(venv_entries[0].source, PythonSource::ProjectVenv);
+        assert!(!venv_entries[0].active);
+        assert!(candidates.iter().any(
+            |c| c.python_bin == "/opt/conda/bin/python" && c.source == PythonSource::Registered
+        ));
+
+        settings.python_bin_override = Some("  ".into());
+        assert!(settings.validate().is_err());
     }
-    Err("job not found".into())
-}
 
-#[tauri::command]
-fn save_file(
-    job_id: String,
-    filename: String,
-    dest_path: String,
-    state: State<Arc<AppState>>,
-) -> Result<(), String> {
-    println!(
-        "[save_file] called with job_id={}, filename={}, dest_path={}",
-        job_id, filename, dest_path
-    );
-    let jobs = state
-        .jobs
-        .lock()
-        .map_err(|e| format!("lock poisoned: {e}"))?;
+    #[test]
+    fn probe_python_reports_missing_interpreter() {
+        let probe = probe_python(
+            "ocr_to_doc_no_such_python".into(),
+            PythonSource::EnvVar,
+            Duration::from_secs(1),
+        );
+        assert!(!probe.ok);
+        assert!(probe.error.is_some());
+        assert_eq!(probe.version, None);
+    }
 
-    if let Some(job) = jobs.get(&job_id) {
-        println!("[save_file] job found. outputs={:?}", job.outputs);
-        // filename が outputs に含まれているか確認 (セキュリティ対策)
-        if !job.outputs.contains(&filename) {
-            println!("[save_file] filename not in outputs");
-            return Err(format!("file not found in job outputs: {}", filename));
-        }
+    #[test]
+    fn extra_env_skips_protected_vars_and_redacts_secrets() {
+        let env = HashMap::from([
+            ("PYTHONPATH".to_string(), "/opt/models".to_string()),
+            ("pythonutf8".to_string(), "0".to_string()),
+            ("HF_TOKEN".to_string(), "hf_abc".to_string()),
+        ]);
+        let mut cmd = Command::new("python");
+        apply_extra_env(&mut cmd, &env);
+        apply_python_env(&mut cmd);
+        cmd.arg("dispatcher.py");
+
+        let utf8: Vec<_> = cmd
+            .get_envs()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("PYTHONUTF8"))
+            .collect();
+        assert!(utf8
+            .iter()
+            .all(|(_, v)| *v == Some(std::ffi::OsStr::new("1"))));
+
+        let logged = describe_command(&cmd);
+        assert!(logged.contains("PYTHONPATH=\"/opt/models\""), "{logged}");
+        assert!(logged.contains("HF_TOKEN=\"***\""), "{logged}");
+        assert!(!logged.contains("hf_abc"));
+        assert!(logged.ends_with("\"python\" \"dispatcher.py\""), "{logged}");
+    }
 
-        // 元ファイルを探す
-        let exe_dir =
-            std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-        let project_root =
-            resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
-
-        let mut source_path = None;
-
-        // 1. result ディレクトリ内を探索
-        let result_dir = project_root.join("result");
-        if result_dir.exists() {
-            if let Ok(entries) = fs::read_dir(&result_dir) {
-                for entry in entries.flatten() {
-                    let candidate = entry.path().join(&filename);
-                    if candidate.exists() {
-                        source_path = Some(candidate);
-                        break;
-                    }
-                }
+    #[test]
+    fn result_metadata_parses_result_line_and_ignores_others() {
+        let meta = ResultMetadata::parse_line(
+            r#"@RESULT {"pages": 3, "languages": ["ja"], "tables": 1, "figures": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            meta,
+            ResultMetadata {
+                pages: 3,
+                languages: vec!["ja".into()],
+                tables: 1,
+                figures: 2,
+                font_warnings: vec![],
+                rotated_pages: vec![],
             }
-        }
+        );
+        assert_eq!(ResultMetadata::parse_line("--- Done 3/9 ---"), None);
+        assert_eq!(ResultMetadata::parse_line("@RESULT {broken"), None);
+        // 欠けたフィールドは 0 / 空として読む
+        assert_eq!(
+            ResultMetadata::parse_line(r#"@RESULT {"pages": 1}"#).map(|m| m.pages),
+            Some(1)
+        );
+    }
 
-        if source_path.is_none() {
-            // プロジェクトルート直下
-            let candidate = project_root.join(&filename);
-            if candidate.exists() {
-                source_path = Some(candidate);
-            }
+    #[test]
+    fn progress_patch_merges_result_metadata_across_files() {
+        let mut job = JobInfo::new_running(&["a.pdf".into(), "b.pdf".into()]);
+        ProgressPatch {
+            metadata: ResultMetadata::parse_line(
+                r#"@RESULT {"pages": 2, "languages": ["ja"], "tables": 1, "figures": 0}"#,
+            ),
+            ..ProgressPatch::default()
         }
-
-        if let Some(src) = source_path {
-            fs::copy(&src, &dest_path).map_err(|e| format!("failed to copy file: {e}"))?;
-            return Ok(());
-        } else {
-            return Err(format!("source file not found: {}", filename));
+        .apply(&mut job);
+        ProgressPatch {
+            metadata: ResultMetadata::parse_line(
+                r#"@RESULT {"pages": 1, "languages": ["ja", "en"], "tables": 0, "figures": 3}"#,
+            ),
+            ..ProgressPatch::default()
         }
+        .apply(&mut job);
+        let meta = job.metadata.unwrap();
+        assert_eq!((meta.pages, meta.tables, meta.figures), (3, 1, 3));
+        assert_eq!(meta.languages, vec!["ja".to_string(), "en".to_string()]);
     }
-    Err("job not found".into())
-}
 
-fn find_output_path(project_root: &std::path::Path, filename: &str) -> Option<PathBuf> {
-    // 1. result ディレクトリ内を探索
-    let result_dir = project_root.join("result");
-    if result_dir.exists() {
-        if let Ok(entries) = fs::read_dir(&result_dir) {
-            for entry in entries.flatten() {
-                let candidate = entry.path().join(filename);
-                if candidate.exists() {
-                    return Some(candidate);
-                }
-            }
-        }
+    #[test]
+    fn result_metadata_collects_rotated_pages_across_files() {
+        let mut meta = ResultMetadata::parse_line(
+            r#"@RESULT {"pages": 3, "rotated_pages": [{"dir_name": "a", "page": 2, "degrees": 90}]}"#,
+        )
+        .unwrap();
+        meta.merge(
+            ResultMetadata::parse_line(
+                r#"@RESULT {"pages": 1, "rotated_pages": [{"dir_name": "b", "page": 2, "degrees": 270}]}"#,
+            )
+            .unwrap(),
+        );
+        let rotated: Vec<(&str, u32, u32)> = meta
+            .rotated_pages
+            .iter()
+            .map(|r| (r.dir_name.as_str(), r.page, r.degrees))
+            .collect();
+        assert_eq!(rotated, [("a", 2, 90), ("b", 2, 270)]);
     }
 
-    // 2. プロジェクトルート直下
-    let candidate = project_root.join(filename);
-    if candidate.exists() {
-        return Some(candidate);
+    #[test]
+    fn font_warnings_are_merged_and_logged_as_warnings() {
+        let mut job = JobInfo::new_running(&["a.pdf".into(), "b.pdf".into()]);
+        for line in [
+            r#"@RESULT {"pages": 1, "font_warnings": ["a.docx: フォント Century が見つからない"]}"#,
+            r#"@RESULT {"pages": 1, "font_warnings": []}"#,
+        ] {
+            ProgressPatch {
+                metadata: ResultMetadata::parse_line(line),
+                ..ProgressPatch::default()
+            }
+            .apply(&mut job);
+        }
+        let warnings: Vec<&LogEntry> = job
+            .log
+            .iter()
+            .filter(|e| e.level == LogLevel::Warn)
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].text.contains("Century"));
+        assert_eq!(job.metadata.unwrap().font_warnings.len(), 1);
     }
 
-    None
-}
-
-fn open_path_with_default_app(path: &std::path::Path) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    let mut cmd = {
-        let mut c = Command::new("open");
-        c.arg(path);
-        c
-    };
+    #[test]
+    fn dispatcher_args_pass_languages_with_per_file_override() {
+        let opts = RunOptions {
+            languages: vec!["ja".into(), "ko".into()],
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        assert!(args.windows(2).any(|w| w[0] == "--lang" && w[1] == "ja,ko"));
 
-    #[cfg(target_os = "windows")]
-    let mut cmd = {
-        // explorer はファイル/フォルダ両方を開ける
-        let mut c = Command::new("explorer");
-        c.arg(path);
-        c
-    };
+        let auto = FileSpecificOptions {
+            languages: Some(vec![]),
+            ..FileSpecificOptions::default()
+        };
+        let args = build_dispatcher_args("in.pdf", &opts, Some(&auto));
+        assert!(!args.iter().any(|a| a == "--lang"));
 
-    #[cfg(all(unix, not(target_os = "macos")))]
-    let mut cmd = {
-        let mut c = Command::new("xdg-open");
-        c.arg(path);
-        c
-    };
+        let args = build_dispatcher_args("in.pdf", &RunOptions::default(), None);
+        assert!(!args.iter().any(|a| a == "--lang"));
+    }
 
-    cmd.spawn()
-        .map(|_| ())
-        .map_err(|e| format!("failed to open path: {e}"))
-}
+    #[test]
+    fn languages_are_checked_against_supported_list() {
+        let supported =
+            parse_supported_languages("warning from import\n[\"ja\", \"en\"]\n").unwrap();
+        assert_eq!(supported, vec!["ja".to_string(), "en".to_string()]);
 
-fn validate_result_dir_name(dir_name: &str) -> Result<(), String> {
-    if dir_name.is_empty() {
-        return Err("dirName is empty".into());
-    }
-    if dir_name.contains('/') || dir_name.contains('\\') || dir_name.contains("..") {
-        return Err("invalid dirName".into());
+        let mut opts = RunOptions {
+            languages: vec!["en".into()],
+            ..RunOptions::default()
+        };
+        assert!(validate_languages(opts.requested_languages(), &supported).is_ok());
+        opts.file_options = Some(HashMap::from([(
+            "a.pdf".to_string(),
+            FileSpecificOptions {
+                languages: Some(vec!["ko".into()]),
+                ..FileSpecificOptions::default()
+            },
+        )]));
+        let err = validate_languages(opts.requested_languages(), &supported).unwrap_err();
+        assert!(err.contains("ko"));
+        assert!(parse_supported_languages("").is_err());
     }
-    Ok(())
-}
 
-fn canonicalize_dir(path: &std::path::Path) -> Result<PathBuf, String> {
-    fs::canonicalize(path).map_err(|e| format!("failed to canonicalize path: {e}"))
-}
+    #[test]
+    fn page_limit_reports_count_and_limit() {
+        assert!(check_page_limit(500, 500).is_ok());
+        let err = check_page_limit(5000, 500).unwrap_err();
+        assert!(err.starts_with("too_many_pages: "), "{err}");
+        assert!(err.contains("5000") && err.contains("500"), "{err}");
 
-fn parse_page_range_from_dir(dir_name: &str) -> Option<String> {
-    // 例: foo_p3-9 -> "p3-9"
-    let pos = dir_name.rfind("_p")?;
-    let rest = &dir_name[(pos + 2)..];
-    let mut parts = rest.splitn(2, '-');
-    let start = parts.next()?;
-    let end = parts.next()?;
-    if start.is_empty() || end.is_empty() {
-        return None;
+        let zero = AppSettings {
+            max_pages: Some(0),
+            ..AppSettings::default_values()
+        };
+        assert!(zero.validate().is_err());
     }
-    if !start.chars().all(|c| c.is_ascii_digit()) || !end.chars().all(|c| c.is_ascii_digit()) {
-        return None;
+
+    #[test]
+    fn disk_estimate_scales_with_dpi_squared() {
+        let at_300 = estimate_disk_bytes(10, None);
+        assert_eq!(at_300, 10 * DISK_BYTES_PER_PAGE_AT_300_DPI);
+        assert_eq!(estimate_disk_bytes(10, Some(600)), at_300 * 4);
+        assert_eq!(estimate_disk_bytes(0, Some(600)), 0);
     }
-    Some(format!("p{}-{}", start, end))
-}
 
-fn pick_best_file_in_dir(dir: &std::path::Path, dir_name: &str) -> Option<String> {
-    let candidates = [
-        // docx
-        format!("{dir_name}_merged.docx"),
-        format!("{dir_name}.docx"),
-        // xlsx (dispatcher は <output_dir.name>.xlsx)
-        format!("{dir_name}.xlsx"),
-        format!("{dir_name}_merged.xlsx"),
-        // csv（複数になる可能性があるので、代表として単体名も見る）
-        format!("{dir_name}.csv"),
-        format!("{dir_name}_merged.csv"),
-        // md
-        format!("{dir_name}_merged.md"),
-        format!("{dir_name}.md"),
-    ];
+    #[test]
+    fn parse_df_available_reads_fourth_column() {
+        let out = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                   /dev/sda1        102400000  51200000  51200000      50% /\n";
+        assert_eq!(parse_df_available_kib(out), Some(51_200_000));
+        assert_eq!(parse_df_available_kib("Filesystem\n"), None);
+    }
 
-    for filename in candidates {
-        if dir.join(&filename).exists() {
-            return Some(filename);
-        }
+    #[test]
+    fn retry_backoff_doubles_and_caps() {
+        assert_eq!(retry_backoff(1), Duration::from_secs(2));
+        assert_eq!(retry_backoff(2), Duration::from_secs(4));
+        assert_eq!(retry_backoff(3), Duration::from_secs(8));
+        assert_eq!(retry_backoff(10), RETRY_BACKOFF_MAX);
     }
 
-    // fallback: scan directory for known extensions
-    if let Ok(entries) = fs::read_dir(dir) {
-        let mut docx = None;
-        let mut xlsx = None;
-        let mut csv = None;
-        let mut md = None;
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
-            let name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-            let lower = name.to_lowercase();
-            if docx.is_none() && lower.ends_with(".docx") {
-                docx = Some(name);
-                continue;
-            }
-            if xlsx.is_none() && lower.ends_with(".xlsx") {
-                xlsx = Some(name);
-                continue;
-            }
-            if csv.is_none() && lower.ends_with(".csv") {
-                csv = Some(name);
-                continue;
-            }
-            if md.is_none() && lower.ends_with(".md") {
-                md = Some(name);
-            }
-        }
-        return docx.or(xlsx).or(csv).or(md);
+    #[test]
+    fn transient_failures_are_recognised_from_stderr() {
+        assert!(is_transient_failure_line(
+            "requests.exceptions.ReadTimeout: HTTPSConnectionPool(host='huggingface.co'): Read timed out."
+        ));
+        assert!(is_transient_failure_line(
+            "urllib3.exceptions.MaxRetryError: Max retries exceeded with url: /model.safetensors"
+        ));
+        assert!(!is_transient_failure_line(
+            "[dispatcher] エラー: 未対応の入力種別です: foo.txt"
+        ));
+        assert!(!is_transient_failure_line(
+            "FileNotFoundError: [Errno 2] No such file or directory"
+        ));
     }
 
-    None
-}
+    #[test]
+    fn merge_separator_is_parsed_and_forwarded() {
+        let opts: RunOptions = serde_json::from_value(serde_json::json!({
+            "mergeSeparator": "heading"
+        }))
+        .unwrap();
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        assert!(args
+            .windows(2)
+            .any(|w| w[0] == "--merge-sep" && w[1] == "heading"));
+        assert!(
+            !build_dispatcher_args("in.pdf", &RunOptions::default(), None)
+                .iter()
+                .any(|a| a == "--merge-sep")
+        );
+        assert!(serde_json::from_value::<RunOptions>(serde_json::json!({
+            "mergeSeparator": "dash"
+        }))
+        .is_err());
+    }
 
-#[tauri::command]
-fn open_output(
-    job_id: String,
-    filename: String,
-    state: State<Arc<AppState>>,
-) -> Result<(), String> {
-    let jobs = state
-        .jobs
-        .lock()
-        .map_err(|e| format!("lock poisoned: {e}"))?;
+    #[test]
+    fn figure_format_is_forwarded_only_with_figures() {
+        let mut opts: RunOptions = serde_json::from_value(serde_json::json!({
+            "enableFigure": true,
+            "figureFormat": "jpeg"
+        }))
+        .unwrap();
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        assert!(args
+            .windows(2)
+            .any(|w| w[0] == "--figure-format" && w[1] == "jpeg"));
+
+        opts.enable_figure = false;
+        assert!(!build_dispatcher_args("in.pdf", &opts, None)
+            .iter()
+            .any(|a| a == "--figure-format"));
+        assert!(serde_json::from_value::<RunOptions>(serde_json::json!({
+            "figureFormat": "gif"
+        }))
+        .is_err());
+    }
 
-    let job = jobs.get(&job_id).ok_or("job not found")?;
-    // filename が outputs に含まれているか確認 (セキュリティ対策)
-    if !job.outputs.contains(&filename) {
-        return Err(format!("file not found in job outputs: {}", filename));
+    #[test]
+    fn collect_output_files_lists_figures_when_enabled() {
+        let root = TempDir::new("figures");
+        let dir = root.join("result").join("report");
+        fs::create_dir_all(dir.join(FIGURE_DIR_NAME)).unwrap();
+        fs::write(dir.join("report_merged.md"), "# report").unwrap();
+        for name in [
+            "fig_page002_01.jpg",
+            "fig_page001_01.png",
+            "icon_candidates.jsonl",
+        ] {
+            fs::write(dir.join(FIGURE_DIR_NAME).join(name), b"x").unwrap();
+        }
+        let inputs = vec!["/in/report.pdf".to_string()];
+        let formats = vec!["md".to_string()];
+
+        let without = collect_output_files(&root, &inputs, &formats, &HashMap::new(), false);
+        assert_eq!(output_file_names(&without), vec!["report_merged.md"]);
+
+        let with = collect_output_files(&root, &inputs, &formats, &HashMap::new(), true);
+        assert_eq!(
+            output_file_names(&with),
+            vec![
+                "report_merged.md",
+                "report/figures/fig_page001_01.png",
+                "report/figures/fig_page002_01.jpg"
+            ]
+        );
+        // 図版はそのジョブの結果ディレクトリのものを返す（別の入力の同名の図版を拾わない）
+        let other = root.join("result").join("another");
+        fs::create_dir_all(other.join(FIGURE_DIR_NAME)).unwrap();
+        fs::write(other.join(FIGURE_DIR_NAME).join("fig_page001_01.png"), b"y").unwrap();
+        fs::write(other.join("report_merged.md"), "# other").unwrap();
+        for name in output_file_names(&with) {
+            let found = find_output_path(&root, &name, &["report".to_string()]).unwrap();
+            assert!(found.starts_with(&dir), "{name}: {}", found.display());
+        }
+        assert!(find_output_path(&root, "report/figures/missing.png", &[]).is_none());
+        assert_eq!(output_dir_names(&with), vec!["report"]);
     }
 
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
-    let src = find_output_path(&project_root, &filename).ok_or("source file not found")?;
-    open_path_with_default_app(&src)
-}
+    #[test]
+    fn csv_options_are_validated_and_forwarded() {
+        let opts: RunOptions = serde_json::from_value(serde_json::json!({
+            "csvDelimiter": "\t",
+            "csvEncoding": "shift_jis"
+        }))
+        .unwrap();
+        assert!(opts.validate().is_ok());
+        let args = build_dispatcher_args("in.pdf", &opts, None);
+        assert!(args
+            .windows(2)
+            .any(|w| w[0] == "--csv-delimiter" && w[1] == "\t"));
+        assert!(args
+            .windows(2)
+            .any(|w| w[0] == "--csv-encoding" && w[1] == "shift_jis"));
+
+        assert!(serde_json::from_value::<RunOptions>(serde_json::json!({
+            "csvEncoding": "latin-1"
+        }))
+        .is_err());
+        for bad in [";;", "", "\""] {
+            let opts = RunOptions {
+                csv_delimiter: Some(bad.into()),
+                ..RunOptions::default()
+            };
+            assert!(opts.validate().is_err(), "{bad:?}");
+        }
+    }
 
-#[tauri::command]
-fn open_output_dir(job_id: String, state: State<Arc<AppState>>) -> Result<(), String> {
-    let jobs = state
-        .jobs
-        .lock()
-        .map_err(|e| format!("lock poisoned: {e}"))?;
+    #[test]
+    fn merged_parts_follow_separator_style() {
+        let parts = vec![
+            ("a".to_string(), "A".to_string()),
+            ("b".to_string(), "B".to_string()),
+        ];
+        assert_eq!(
+            join_merged_parts(&parts, MergeSeparator::Hr),
+            "A\n\n---\n\nB"
+        );
+        assert_eq!(
+            join_merged_parts(&parts, MergeSeparator::Heading),
+            "## a\n\nA\n\n## b\n\nB"
+        );
+        assert_eq!(join_merged_parts(&parts, MergeSeparator::None), "A\n\nB");
+        assert_eq!(
+            join_merged_parts(&parts, MergeSeparator::Page),
+            "A\n\n\\newpage\n\nB"
+        );
+    }
 
-    let job = jobs.get(&job_id).ok_or("job not found")?;
+    #[test]
+    fn weighted_page_ratio_follows_page_weights() {
+        assert_eq!(weighted_page_ratio(&HashMap::new(), 1, 1, 4), None);
+        let weights = HashMap::from([(1, 1.0), (2, 7.0), (3, 1.0), (4, 1.0)]);
+        assert_eq!(weighted_page_ratio(&weights, 1, 1, 4), Some(0.1));
+        assert_eq!(weighted_page_ratio(&weights, 1, 2, 4), Some(0.8));
+        assert_eq!(weighted_page_ratio(&weights, 1, 4, 4), Some(1.0));
+        // 重みの無いページは既知の平均（ここでは 2.0）として数える
+        let partial = HashMap::from([(1, 1.0), (2, 3.0)]);
+        assert_eq!(weighted_page_ratio(&partial, 1, 2, 3), Some(4.0 / 6.0));
+        assert_eq!(
+            PageWeight::parse_line(r#"@PROGRESS {"page": 2, "weight": 3.5}"#),
+            Some(PageWeight {
+                page: 2,
+                weight: 3.5
+            })
+        );
+        assert_eq!(
+            PageWeight::parse_line(r#"@PROGRESS {"page": 2, "weight": 0}"#),
+            None
+        );
+        assert_eq!(PageWeight::parse_line("--- Done 2/4 ---"), None);
+    }
 
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
+    /// Stored (uncompressed) zip with empty entries.
+    fn tiny_zip(names: &[&str]) -> Vec<u8> {
+        use std::io::Write;
 
-    if let Some(first) = job.outputs.first() {
-        if let Some(src) = find_output_path(&project_root, first) {
-            if let Some(parent) = src.parent() {
-                return open_path_with_default_app(parent);
-            }
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for name in names {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(b"").unwrap();
         }
+        writer.finish().unwrap().into_inner()
     }
 
-    // 出力が見つからない場合は result フォルダを開く
-    open_path_with_default_app(&project_root.join("result"))
-}
+    #[test]
+    fn check_output_file_detects_truncated_zip_and_bad_text() {
+        let dir = TempDir::new("verify");
+        fs::create_dir_all(&dir).unwrap();
+        let docx = tiny_zip(&["[Content_Types].xml", "word/document.xml"]);
+        fs::write(dir.join("names.docx"), &docx).unwrap();
+        assert_eq!(
+            zip_entry_names(&dir.join("names.docx")).unwrap(),
+            ["[Content_Types].xml", "word/document.xml"]
+        );
+        let check = |name: &str, bytes: &[u8], enc: Option<CsvEncoding>| {
+            let path = dir.join(name);
+            fs::write(&path, bytes).unwrap();
+            check_output_file(&path, enc).status
+        };
 
-#[tauri::command]
-fn open_input_file(path: String) -> Result<(), String> {
-    let trimmed = path.trim();
-    if trimmed.is_empty() {
-        return Err("path is empty".into());
+        assert_eq!(check("a.docx", &docx, None), OutputIntegrity::Ok);
+        assert_eq!(
+            check("b.docx", &docx[..docx.len() - 10], None),
+            OutputIntegrity::Corrupt
+        );
+        // 中身が xlsx ではない zip
+        assert_eq!(check("c.xlsx", &docx, None), OutputIntegrity::Corrupt);
+        assert_eq!(check("d.xlsx", b"", None), OutputIntegrity::Empty);
+        assert_eq!(
+            check("e.md", "# 見出し".as_bytes(), None),
+            OutputIntegrity::Ok
+        );
+        assert_eq!(
+            check("f.csv", b"\x83\x65,1", None),
+            OutputIntegrity::InvalidEncoding
+        );
+        assert_eq!(
+            check("g.csv", b"\x83\x65,1", Some(CsvEncoding::ShiftJis)),
+            OutputIntegrity::Ok
+        );
+        assert_eq!(check("h.png", b"\x89PNG", None), OutputIntegrity::Unchecked);
     }
-    let p = PathBuf::from(trimmed);
-    if !p.exists() {
-        return Err(format!("file not found: {}", p.display()));
+
+    #[test]
+    fn output_name_template_renders_placeholders() {
+        let vars = OutputNameVars {
+            stem: "scan",
+            started_at_ms: 1_709_210_096_789,
+            dpi: 200,
+            mode: Mode::Full,
+            index: 3,
+        };
+        assert_eq!(
+            render_output_name("{stem}_{date}_{dpi}dpi", &vars).unwrap(),
+            "scan_20240229_200dpi"
+        );
+        assert_eq!(
+            render_output_name("{index}-{stem}-{mode}-{time}", &vars).unwrap(),
+            "3-scan-full-123456"
+        );
+        let err = render_output_name("{stem}_{page}", &vars).unwrap_err();
+        assert!(err.contains("{page}"), "{err}");
+        assert!(render_output_name("{stem", &vars).is_err());
+        assert!(render_output_name("out/{stem}", &vars).is_err());
+        assert!(render_output_name("{date}..{stem}", &vars).is_err());
+
+        let mut opts = RunOptions {
+            pdf_dpi: Some(150),
+            ..RunOptions::default()
+        };
+        let paths = vec!["/in/a.pdf".to_string(), "/in/b.png".to_string()];
+        let names = render_output_names(Some("{index}_{stem}_{dpi}"), &paths, &opts, 0).unwrap();
+        assert_eq!(names["/in/a.pdf"], "1_a_150");
+        assert_eq!(names["/in/b.png"], "2_b_150");
+        assert!(render_output_names(Some("  "), &paths, &opts, 0)
+            .unwrap()
+            .is_empty());
+        // 再処理のように --label を明示したジョブはテンプレートを使わない
+        opts.extra_ocr_args = vec!["--label".into(), "dpi200".into()];
+        assert!(render_output_names(Some("{stem}_x"), &paths, &opts, 0)
+            .unwrap()
+            .is_empty());
+
+        let mut args = vec![
+            "a.pdf".to_string(),
+            "--".into(),
+            "--chunk-size".into(),
+            "4".into(),
+        ];
+        push_output_name_arg(&mut args, Some(&"a_x".to_string()));
+        assert_eq!(
+            args,
+            ["a.pdf", "--output-name", "a_x", "--", "--chunk-size", "4"]
+        );
     }
-    open_path_with_default_app(&p)
-}
 
-#[tauri::command]
-fn list_recent_results(limit: Option<u32>) -> Result<Vec<RecentResultEntry>, String> {
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
+    #[test]
+    fn output_root_must_be_an_existing_writable_dir() {
+        let dir = TempDir::new("output_root");
+        assert!(validate_output_root(&dir)
+            .unwrap_err()
+            .starts_with("フォルダが見つかりません"));
+
+        fs::create_dir_all(&dir).unwrap();
+        assert!(validate_output_root(&dir).is_ok());
+        // 書き込み確認のファイルは残さない
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let file = dir.join("not_a_dir.txt");
+        fs::write(&file, "x").unwrap();
+        assert!(validate_output_root(&file).is_err());
+    }
 
-    let result_root = project_root.join("result");
-    if !result_root.exists() {
-        return Ok(vec![]);
+    #[test]
+    fn crop_arg_is_shared_by_dispatcher_and_preview() {
+        let crop = CropRect {
+            left: 0.1,
+            top: 1.0 / 3.0,
+            width: 0.5,
+            height: 0.25,
+        };
+        assert_eq!(crop.to_arg(), "0.100000,0.333333,0.500000,0.250000");
+
+        let opts = RunOptions {
+            file_options: Some(HashMap::from([(
+                "a.pdf".to_string(),
+                FileSpecificOptions {
+                    crop: Some(crop.clone()),
+                    ..FileSpecificOptions::default()
+                },
+            )])),
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("a.pdf", &opts, opts.file_options_for("a.pdf"));
+        assert!(args.windows(2).any(|w| w == ["--crop", &crop.to_arg()]));
     }
 
-    let mut dirs: Vec<(u64, String)> = Vec::new();
-    if let Ok(entries) = fs::read_dir(&result_root) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-            let name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-            let modified = path
-                .metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or(SystemTime::UNIX_EPOCH);
-            let ms = modified
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as u64;
-            dirs.push((ms, name));
-        }
+    #[test]
+    fn tile_request_limits_size_and_zoom() {
+        let tile = |size, zoom| TileRequest {
+            x: 0,
+            y: 0,
+            size,
+            zoom,
+        };
+        assert!(validate_tile_request(&tile(512, 1.0)).is_ok());
+        assert!(validate_tile_request(&tile(2048, 4.0)).is_ok());
+        assert!(validate_tile_request(&tile(32, 1.0)).is_err());
+        assert!(validate_tile_request(&tile(512, 8.0)).is_err());
+        assert!(validate_tile_request(&tile(512, f32::NAN)).is_err());
     }
 
-    dirs.sort_by(|(a, _), (b, _)| b.cmp(a));
-    let take_n = limit.unwrap_or(10).max(1) as usize;
-    let mut results = Vec::new();
+    #[test]
+    fn batch_preview_output_keeps_per_file_errors() {
+        let stdout = r#"[
+            {"dataUrl": "data:image/png;base64,AA==", "pageCount": 3, "page": 1, "path": "a.pdf"},
+            {"errorKind": "not_found", "error": "input not found: b.png", "path": "b.png"}
+        ]"#;
+        let responses = parse_batch_preview_output(stdout, 2).unwrap();
+        assert_eq!(responses[0].path.as_deref(), Some("a.pdf"));
+        assert_eq!(responses[0].page_count, Some(3));
+        assert_eq!(
+            responses[1].error_kind.as_deref(),
+            Some(PREVIEW_ERROR_NOT_FOUND)
+        );
+        assert!(responses[1].data_url.is_empty());
 
-    for (updated_at_ms, dir_name) in dirs.into_iter().take(take_n) {
-        let dir_path = result_root.join(&dir_name);
-        let best_file = pick_best_file_in_dir(&dir_path, &dir_name);
-        let page_range = parse_page_range_from_dir(&dir_name);
-        results.push(RecentResultEntry {
-            dir_name,
-            updated_at_ms,
-            page_range,
-            best_file,
-        });
+        // 入力と数が合わなければラベルがずれるので全体のエラーにする
+        let err = parse_batch_preview_output(stdout, 3).unwrap_err();
+        assert!(err.starts_with(PREVIEW_ERROR_RENDER_FAILED));
     }
 
-    Ok(results)
-}
+    #[test]
+    fn latest_result_dir_matches_stem_and_suffixed_dirs_only() {
+        let root = TempDir::new("latest");
+        assert!(pick_latest_result_dir(&root, "doc").is_none());
+
+        // 別の入力（docs.pdf / other.pdf）の結果は拾わない
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("doc_notes.md"), "x").unwrap();
+        assert!(pick_latest_result_dir(&root, "doc").is_none());
+
+        fs::create_dir_all(root.join("doc_p1-3")).unwrap();
+        assert_eq!(
+            pick_latest_result_dir(&root, "doc"),
+            Some(root.join("doc_p1-3"))
+        );
+        fs::create_dir_all(root.join("doc")).unwrap();
+        let latest = pick_latest_result_dir(&root, "doc").unwrap();
+        assert!(latest == root.join("doc") || latest == root.join("doc_p1-3"));
+
+        assert!(is_result_dir_for_stem("doc", "doc"));
+        assert!(is_result_dir_for_stem("doc_dpi200", "doc"));
+        assert!(!is_result_dir_for_stem("docs", "doc"));
+        assert!(!is_result_dir_for_stem("do", "doc"));
+    }
 
-#[tauri::command]
-fn open_result_dir(dir_name: String) -> Result<(), String> {
-    validate_result_dir_name(&dir_name)?;
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
+    #[test]
+    fn up_to_date_result_dir_needs_newer_finished_result() {
+        let root = TempDir::new("uptodate");
+        let result_root = root.join("result");
+        fs::create_dir_all(result_root.join("doc")).unwrap();
+        let input = root.join("doc.pdf");
+        fs::write(&input, "x").unwrap();
+        let input_str = input.to_string_lossy().to_string();
+        let set_input_mtime = |t: SystemTime| {
+            let file = fs::OpenOptions::new().write(true).open(&input).unwrap();
+            file.set_modified(t).unwrap();
+        };
+        set_input_mtime(UNIX_EPOCH + Duration::from_secs(1_000_000));
+        let opts = RunOptions::default();
+        let write_config = |dir: &str, input: &str, opts: &RunOptions, hash: Option<&str>| {
+            let config = serde_json::json!({
+                "input": input,
+                "options": run_config_options(opts, input),
+                "inputHash": hash,
+            });
+            fs::create_dir_all(result_root.join(dir)).unwrap();
+            fs::write(
+                result_root.join(dir).join(RUN_CONFIG_FILE),
+                config.to_string(),
+            )
+            .unwrap();
+        };
+        let check = |input: &str, name: Option<&String>, hash: Option<&str>, opts: &RunOptions| {
+            up_to_date_result_dir(&result_root, input, name, hash, opts)
+        };
 
-    let result_root = project_root.join("result");
-    let dir_path = result_root.join(&dir_name);
-    if !dir_path.is_dir() {
-        return Err("result dir not found".into());
+        // run.json の無い（途中で止まった）・読めない結果は最新とみなさない
+        assert!(check(&input_str, None, None, &opts).is_none());
+        fs::write(result_root.join("doc").join(RUN_CONFIG_FILE), "{}").unwrap();
+        assert!(check(&input_str, None, None, &opts).is_none());
+        write_config("doc", &input_str, &opts, None);
+        assert_eq!(
+            check(&input_str, None, None, &opts),
+            Some(result_root.join("doc"))
+        );
+        // --output-name の結果ディレクトリはその名前だけを見る
+        let named = "named".to_string();
+        assert!(check(&input_str, Some(&named), None, &opts).is_none());
+
+        // 出力形式などが違えば処理し直す。再試行回数など結果に関わらない項目は無視する
+        let docx = RunOptions {
+            formats: vec!["md".into(), "docx".into()],
+            ..RunOptions::default()
+        };
+        assert!(check(&input_str, None, None, &docx).is_none());
+        let control = RunOptions {
+            skip_unchanged: true,
+            max_retries: 3,
+            ..RunOptions::default()
+        };
+        assert!(check(&input_str, None, None, &control).is_some());
+
+        // 名前が前方一致するだけの別の入力の結果（report_2024）は使わない
+        let report = root.join("report.pdf");
+        fs::write(&report, "r").unwrap();
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&report)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000))
+            .unwrap();
+        write_config("report_2024", "/in/report_2024.pdf", &opts, None);
+        assert!(check(&report.to_string_lossy(), None, None, &opts).is_none());
+
+        // 結果より後に入力が更新されたら処理し直す
+        set_input_mtime(SystemTime::now() + Duration::from_secs(3600));
+        assert!(check(&input_str, None, None, &opts).is_none());
+
+        // run.json にハッシュがあれば更新時刻ではなく内容で比べる
+        let hash = hash_file(&input).unwrap();
+        write_config("doc", &input_str, &opts, Some(&hash));
+        assert_eq!(
+            check(&input_str, None, Some(&hash), &opts),
+            Some(result_root.join("doc"))
+        );
+        fs::write(&input, "y").unwrap();
+        set_input_mtime(UNIX_EPOCH + Duration::from_secs(1_000_000));
+        let changed = hash_file(&input).unwrap();
+        assert_ne!(changed, hash);
+        assert!(check(&input_str, None, Some(&changed), &opts).is_none());
     }
 
-    let result_root_canon = canonicalize_dir(&result_root)?;
-    let dir_canon = canonicalize_dir(&dir_path)?;
-    if !dir_canon.starts_with(&result_root_canon) {
-        return Err("invalid result dir".into());
+    #[test]
+    fn hash_file_streams_content_hash() {
+        let root = TempDir::new("hash");
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("big.pdf");
+        // 読み込みのバッファより大きいファイルでも一度に読んだときと同じ値になる
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content).unwrap();
+        assert_eq!(
+            hash_file(&path).unwrap(),
+            blake3::hash(&content).to_hex().to_string()
+        );
+        assert_eq!(hash_file(&path).unwrap().len(), 64);
+        assert!(hash_file(&root.join("missing.pdf")).is_err());
     }
 
-    open_path_with_default_app(&dir_canon)
-}
+    #[test]
+    fn newest_path_prefers_newer_then_plain_stem_on_ties() {
+        let t = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        let p = |name: &str| PathBuf::from("result").join(name);
+        assert_eq!(
+            newest_path(vec![(t(10), p("doc")), (t(20), p("doc_p1-3"))]),
+            Some(p("doc_p1-3"))
+        );
+        // 同時刻なら列挙順によらず `doc` → `doc_a` → `doc_b` の順
+        assert_eq!(
+            newest_path(vec![
+                (t(20), p("doc_b")),
+                (t(20), p("doc")),
+                (t(20), p("doc_a"))
+            ]),
+            Some(p("doc"))
+        );
+        assert_eq!(
+            newest_path(vec![(t(20), p("doc_b")), (t(20), p("doc_a"))]),
+            Some(p("doc_a"))
+        );
+        assert_eq!(newest_path(vec![]), None);
+    }
 
-#[tauri::command]
-fn open_result_file(dir_name: String) -> Result<(), String> {
-    validate_result_dir_name(&dir_name)?;
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
+    #[test]
+    fn recent_result_dirs_lists_dirs_newest_first() {
+        let root = TempDir::new("recent");
+        // result/ がまだ無い
+        assert!(recent_result_dirs(&root).is_empty());
+
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("stray.md"), "x").unwrap();
+
+        let dirs = recent_result_dirs(&root);
+        let mut names: Vec<&str> = dirs.iter().map(|(_, name)| name.as_str()).collect();
+        assert!(dirs.windows(2).all(|w| w[0].0 >= w[1].0));
+        names.sort();
+        assert_eq!(names, vec!["a", "b"]);
+    }
 
-    let result_root = project_root.join("result");
-    let dir_path = result_root.join(&dir_name);
-    if !dir_path.is_dir() {
-        return Err("result dir not found".into());
+    #[test]
+    fn incomplete_result_dirs_lists_dirs_without_a_best_file() {
+        let root = TempDir::new("incomplete");
+        fs::create_dir_all(root.join("done")).unwrap();
+        fs::write(root.join("done").join("done.docx"), "x").unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::create_dir_all(root.join("failed").join("page_images")).unwrap();
+        fs::write(
+            root.join("failed").join("page_images").join("page_001.png"),
+            "png",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("running")).unwrap();
+
+        let mut found = incomplete_result_dirs(&root, &["running".to_string()]);
+        found.sort_by(|a, b| a.dir_name.cmp(&b.dir_name));
+        let names: Vec<(&str, u64)> = found
+            .iter()
+            .map(|e| (e.dir_name.as_str(), e.size_bytes))
+            .collect();
+        assert_eq!(names, vec![("empty", 0), ("failed", 3)]);
     }
 
-    let result_root_canon = canonicalize_dir(&result_root)?;
-    let dir_canon = canonicalize_dir(&dir_path)?;
-    if !dir_canon.starts_with(&result_root_canon) {
-        return Err("invalid result dir".into());
+    #[test]
+    fn format_filter_matches_any_output_with_that_extension() {
+        assert_eq!(parse_format_filter(None), Ok(None));
+        assert_eq!(parse_format_filter(Some(" ")), Ok(None));
+        assert_eq!(parse_format_filter(Some(".XLSX")), Ok(Some("xlsx".into())));
+        assert!(parse_format_filter(Some("pdf")).is_err());
+
+        let dir = TempDir::new("format_filter");
+        fs::create_dir_all(dir.join("sub.xlsx")).unwrap();
+        fs::write(dir.join("report_merged.md"), "# report").unwrap();
+        fs::write(dir.join("table_2.CSV"), "a,b").unwrap();
+
+        assert!(dir_has_output_format(&dir, "md"));
+        // 代表ファイル（md）以外の csv も拾う
+        assert!(dir_has_output_format(&dir, "csv"));
+        // 同名のディレクトリは数えない
+        assert!(!dir_has_output_format(&dir, "xlsx"));
     }
 
-    let best = pick_best_file_in_dir(&dir_canon, &dir_name).ok_or("no output file found")?;
-    let file_path = dir_canon.join(&best);
-    let file_canon = canonicalize_dir(&file_path)?;
-    if !file_canon.starts_with(&dir_canon) {
-        return Err("invalid output file".into());
+    #[test]
+    fn result_page_image_requires_saved_page_inside_dir() {
+        let base = TempDir::new("page_image");
+        fs::create_dir_all(base.join("page_images")).unwrap();
+        fs::write(base.join("page_images").join("page_007.png"), b"png").unwrap();
+        fs::create_dir_all(base.join("page_images").join("page_008.png")).unwrap();
+        let dir = fs::canonicalize(&base).unwrap();
+
+        assert_eq!(
+            result_page_image(&dir, 7),
+            Some(dir.join("page_images").join("page_007.png"))
+        );
+        assert_eq!(result_page_image(&dir, 1), None);
+        // 同名のディレクトリは画像として扱わない
+        assert_eq!(result_page_image(&dir, 8), None);
     }
-    open_path_with_default_app(&file_canon)
-}
 
-#[tauri::command]
-fn check_environment() -> Result<EnvironmentStatus, String> {
-    let exe_dir = std::env::current_exe().map_err(|e| format!("failed to get exe path: {e}"))?;
-    let project_root = resolve_project_root(&exe_dir).ok_or("failed to resolve project root")?;
-    let dispatcher_found = resolve_python_entry(&project_root, "dispatcher.py").exists();
-    let result_dir_found = project_root.join("result").exists();
-    let python_bin = resolve_python_bin(&project_root);
+    #[test]
+    fn discard_output_dirs_removes_partial_results_only() {
+        let root = TempDir::new("discard");
+        let partial = root.join("result").join("scan");
+        let finished = root.join("result").join("report");
+        let temp = root.join("tmp");
+        fs::create_dir_all(partial.join("page_images")).unwrap();
+        fs::create_dir_all(&finished).unwrap();
+        fs::create_dir_all(temp.join("scan")).unwrap();
+        fs::write(partial.join("page_images").join("page_001.png"), [0u8; 40]).unwrap();
+        fs::write(partial.join("page_001.md"), [0u8; 2]).unwrap();
+        fs::write(temp.join("scan").join("page_002.png"), [0u8; 8]).unwrap();
+        fs::write(finished.join(RUN_CONFIG_FILE), "{}").unwrap();
+        // run.json の無い古い結果（CLI で作ったものなど）に再実行で書き込んでいた
+        let older = root.join("result").join("old");
+        fs::create_dir_all(&older).unwrap();
+        fs::write(older.join("old.md"), "keep").unwrap();
+
+        let names = vec![
+            "scan".to_string(),
+            "report".to_string(),
+            "gone".to_string(),
+            "old".to_string(),
+        ];
+        let created = vec!["scan".to_string(), "report".to_string()];
+        let result = discard_output_dirs(&root, &names, &created, Some(&temp)).unwrap();
+        assert_eq!(result.freed_bytes, 50);
+        assert_eq!(result.removed.len(), 2);
+        assert!(!partial.exists());
+        assert!(!temp.join("scan").exists());
+        assert!(finished.join(RUN_CONFIG_FILE).exists());
+        assert!(older.join("old.md").exists());
+        assert!(discard_output_dirs(&root, &["../tmp".to_string()], &[], None).is_err());
+        assert_eq!(
+            parse_output_dir_line("@OUTPUT_DIR /work/result/scan_p1-3\n").as_deref(),
+            Some("scan_p1-3")
+        );
+        assert_eq!(
+            parse_output_dir_line("出力ディレクトリ: /work/result/scan"),
+            None
+        );
+    }
 
-    Ok(EnvironmentStatus {
-        project_root: project_root.to_string_lossy().to_string(),
-        dispatcher_found,
-        result_dir_found,
-        python_bin,
-    })
-}
+    #[test]
+    fn remove_intermediates_keeps_final_outputs() {
+        let root = TempDir::new("cleanup");
+        let dir = root.join("result").join("doc_p1-2");
+        let temp = root.join("tmp");
+        fs::create_dir_all(dir.join("page_images")).unwrap();
+        fs::create_dir_all(dir.join("figures")).unwrap();
+        fs::create_dir_all(dir.join("converted")).unwrap();
+        fs::create_dir_all(temp.join("doc_p1-2").join("page_images")).unwrap();
+        fs::write(dir.join("page_images").join("page_001.png"), [0u8; 100]).unwrap();
+        fs::write(dir.join("converted").join("doc.png"), [0u8; 20]).unwrap();
+        fs::write(
+            temp.join("doc_p1-2")
+                .join("page_images")
+                .join("page_002.png"),
+            [0u8; 30],
+        )
+        .unwrap();
+        fs::write(dir.join("figures").join("fig_page001_01.png"), [0u8; 5]).unwrap();
+        fs::write(dir.join("doc_p1-2_merged.md"), "# doc").unwrap();
+        fs::write(dir.join("doc_p1-2_merged.docx"), "docx").unwrap();
+
+        let result = remove_intermediates(&dir, Some(&temp)).unwrap();
+        assert_eq!(result.freed_bytes, 150);
+        assert_eq!(result.removed.len(), 3);
+        assert!(!dir.join("page_images").exists());
+        assert!(!temp.join("doc_p1-2").exists());
+        assert!(dir.join("figures").join("fig_page001_01.png").exists());
+        assert!(dir.join("doc_p1-2_merged.md").exists());
+        assert!(dir.join("doc_p1-2_merged.docx").exists());
+        // 2 回目は消すものが無い
+        assert_eq!(
+            remove_intermediates(&dir, Some(&temp)).unwrap().freed_bytes,
+            0
+        );
+    }
 
-#[tauri::command]
-fn load_settings() -> Result<AppSettings, String> {
-    let exe_dir = std::env::current_exe().map_err(|e| e.to_string())?;
-    let project_root = resolve_project_root(&exe_dir).unwrap_or_else(|| PathBuf::from("."));
-    load_settings_from_disk(&project_root)
-}
+    #[test]
+    fn remove_intermediates_keeps_page_images_for_table_reexport() {
+        let root = TempDir::new("cleanup_json");
+        let dir = root.join("result").join("doc");
+        fs::create_dir_all(dir.join("page_images")).unwrap();
+        fs::create_dir_all(dir.join("converted")).unwrap();
+        fs::create_dir_all(dir.join("yomi_formats").join("json")).unwrap();
+        fs::write(dir.join("page_images").join("page_001.png"), [0u8; 100]).unwrap();
+        fs::write(dir.join("converted").join("doc.png"), [0u8; 20]).unwrap();
+
+        let result = remove_intermediates(&dir, None).unwrap();
+        assert_eq!(result.freed_bytes, 20);
+        assert!(dir.join("page_images").join("page_001.png").exists());
+        assert!(!dir.join("converted").exists());
+    }
 
-#[tauri::command]
-fn save_settings(settings: AppSettings) -> Result<(), String> {
-    let exe_dir = std::env::current_exe().map_err(|e| e.to_string())?;
-    let project_root = resolve_project_root(&exe_dir).unwrap_or_else(|| PathBuf::from("."));
+    #[test]
+    fn jobs_with_status_filters_and_sorts_newest_first() {
+        let mut jobs = HashMap::new();
+        for (id, status, started) in [
+            ("old", JobStatus::Done, 1),
+            ("new", JobStatus::Done, 3),
+            ("run", JobStatus::Running, 2),
+        ] {
+            let mut job = JobInfo::new_running(&["a.pdf".into()]);
+            job.status = status;
+            job.started_at_ms = started;
+            job.outputs = vec!["a.md".into()];
+            jobs.insert(id.to_string(), job);
+        }
+        let done = jobs_with_status(&jobs, &JobStatus::Done, None);
+        let ids: Vec<&str> = done.iter().map(|s| s.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["new", "old"]);
+        assert_eq!(done[0].output_count, 1);
+        assert!(jobs_with_status(&jobs, &JobStatus::Paused, None).is_empty());
+
+        jobs.get_mut("old").unwrap().tags = vec!["請求書".into()];
+        let tagged = jobs_with_status(&jobs, &JobStatus::Done, Some("請求書"));
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].job_id, "old");
+        assert_eq!(tagged[0].tags, ["請求書"]);
+    }
 
-    let config_dir = project_root.join("configs");
-    if !config_dir.exists() {
-        fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+    #[test]
+    fn job_tags_are_trimmed_deduplicated_and_capped() {
+        assert_eq!(
+            normalize_job_tags(vec![" 2024 ".into(), "見積".into(), "2024".into()]).unwrap(),
+            ["2024", "見積"]
+        );
+        assert!(normalize_job_tags(vec!["  ".into()]).is_err());
+        assert!(normalize_job_tags(vec!["あ".repeat(MAX_JOB_TAG_CHARS)]).is_ok());
+        assert!(normalize_job_tags(vec!["あ".repeat(MAX_JOB_TAG_CHARS + 1)]).is_err());
+        let many: Vec<String> = (0..=MAX_JOB_TAGS).map(|i| i.to_string()).collect();
+        assert!(normalize_job_tags(many).is_err());
+        assert!(normalize_job_tags(vec![]).unwrap().is_empty());
     }
 
-    let settings_path = config_dir.join("settings.json");
-    let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    #[test]
+    fn listed_result_dir_reports_deleted_kind() {
+        let root = TempDir::new("deleted");
+        fs::create_dir_all(root.join("result").join("kept")).unwrap();
 
-    fs::write(settings_path, content).map_err(|e| e.to_string())?;
-    Ok(())
-}
+        assert!(resolve_listed_result_dir(&root, "kept").is_ok());
+        let err = resolve_listed_result_dir(&root, "gone").unwrap_err();
+        assert!(err.starts_with("deleted: "), "{err}");
+    }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .manage(Arc::new(AppState::default()))
-        .invoke_handler(tauri::generate_handler![
-            run_job,
-            render_preview,
-            get_progress,
-            get_result,
-            save_file,
-            open_output,
-            open_output_dir,
-            open_input_file,
-            list_recent_results,
-            open_result_dir,
-            open_result_file,
-            check_environment,
-            load_settings,
-            save_settings
-        ])
-        .plugin(tauri_plugin_dialog::init())
-        .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+    #[test]
+    fn open_with_matches_extension_case_insensitively() {
+        let app = std::env::current_exe()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let open_with = HashMap::from([(".DOCX".to_string(), app.clone())]);
+        assert!(validate_open_with(&open_with).is_ok());
+        assert_eq!(
+            open_with_app(std::path::Path::new("out/a.docx"), Some(&open_with)),
+            Some(app.as_str())
+        );
+        assert_eq!(
+            open_with_app(std::path::Path::new("out/a.md"), Some(&open_with)),
+            None
+        );
+        assert_eq!(
+            open_with_app(std::path::Path::new("out/a.docx"), None),
+            None
+        );
 
-            let exe_dir = std::env::current_exe().map_err(|e| e.to_string())?;
-            if let Some(project_root) = resolve_project_root(&exe_dir) {
-                apply_window_settings(app.handle(), &project_root);
+        let missing = HashMap::from([("docx".to_string(), "/no/such/viewer".to_string())]);
+        assert!(validate_open_with(&missing).is_err());
+        let empty_ext = HashMap::from([(".".to_string(), app)]);
+        assert!(validate_open_with(&empty_ext).is_err());
+    }
+
+    #[test]
+    fn best_output_prefers_docx_then_xlsx_csv_md() {
+        let outputs: Vec<String> = ["a.md", "a.csv", "a.xlsx", "a_merged.docx"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(pick_best_output(&outputs).unwrap(), "a_merged.docx");
+        assert_eq!(pick_best_output(&outputs[..2]).unwrap(), "a.csv");
+        assert_eq!(
+            pick_best_output(&["x.txt".to_string(), "a.md".to_string()]).unwrap(),
+            "a.md"
+        );
+        assert!(pick_best_output(&[]).is_none());
+    }
+
+    #[test]
+    fn best_result_file_resolves_inside_result_dir() {
+        let root = TempDir::new("best");
+        let dir = root.join("result").join("doc");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("doc.md"), "# a").unwrap();
+        fs::write(dir.join("doc_merged.docx"), "x").unwrap();
+
+        let path = resolve_best_result_file(&root, "doc").unwrap();
+        assert!(path.is_absolute());
+        assert!(path.ends_with("doc_merged.docx"));
+        assert!(resolve_best_result_file(&root, "../doc").is_err());
+        assert!(resolve_best_result_file(&root, "gone")
+            .unwrap_err()
+            .starts_with("deleted:"));
+    }
+
+    #[test]
+    fn result_markdown_prefers_merged_md_inside_result_dir() {
+        let root = TempDir::new("copy");
+        let dir = root.join("result").join("doc");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("doc_merged.docx"), "x").unwrap();
+        fs::write(dir.join("doc_merged.md"), "# a").unwrap();
+        fs::write(dir.join("doc.md"), "# b").unwrap();
+
+        // docx があっても md を返す
+        let path = resolve_result_markdown(&root, "doc").unwrap();
+        assert!(path.ends_with("doc_merged.md"));
+        assert!(resolve_result_markdown(&root, "../doc").is_err());
+        fs::create_dir_all(root.join("result").join("empty")).unwrap();
+        assert!(resolve_result_markdown(&root, "empty").is_err());
+
+        #[cfg(unix)]
+        {
+            fs::write(root.join("secret.md"), "x").unwrap();
+            let linked = root.join("result").join("linked");
+            fs::create_dir_all(&linked).unwrap();
+            std::os::unix::fs::symlink(root.join("secret.md"), linked.join("linked.md")).unwrap();
+            assert!(resolve_result_markdown(&root, "linked").is_err());
+        }
+    }
+
+    #[test]
+    fn named_result_file_must_stay_inside_result_dir() {
+        let root = TempDir::new("named");
+        let dir = root.join("result").join("doc");
+        fs::create_dir_all(dir.join("figures")).unwrap();
+        fs::write(dir.join("page_001.md"), "# a").unwrap();
+        fs::write(root.join("result").join("secret.txt"), "x").unwrap();
+
+        let path = resolve_named_result_file(&root, "doc", "page_001.md").unwrap();
+        assert!(path.ends_with("page_001.md"));
+        for bad in ["", "../secret.txt", "figures/x.png", "..\\secret.txt", ".."] {
+            assert!(
+                resolve_named_result_file(&root, "doc", bad).is_err(),
+                "{bad:?}"
+            );
+        }
+        assert!(resolve_named_result_file(&root, "doc", "missing.md").is_err());
+        // ディレクトリはファイルとして開かない
+        assert!(resolve_named_result_file(&root, "doc", "figures").is_err());
+        assert!(resolve_named_result_file(&root, "gone", "page_001.md")
+            .unwrap_err()
+            .starts_with("deleted:"));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(
+                root.join("result").join("secret.txt"),
+                dir.join("link.txt"),
+            )
+            .unwrap();
+            assert!(resolve_named_result_file(&root, "doc", "link.txt").is_err());
+        }
+    }
+
+    #[test]
+    fn env_fingerprint_parses_pip_freeze_and_diffs() {
+        let a = EnvFingerprint::new(
+            "3.11.9".into(),
+            parse_pip_freeze(
+                "# comment\nNumPy==1.26.4\nyomitoku==0.8.0\n-e git+https://x/y#egg=z\nfoo_bar @ file:///tmp/foo\n",
+            ),
+        );
+        assert_eq!(a.packages["numpy"], "1.26.4");
+        assert_eq!(a.packages["foo-bar"], "file:///tmp/foo");
+        assert_eq!(a.packages.len(), 3);
+        assert_eq!(a.hash.len(), 16);
+        assert_eq!(
+            a,
+            EnvFingerprint::new(a.python_version.clone(), a.packages.clone())
+        );
+
+        let b = EnvFingerprint::new(
+            "3.11.9".into(),
+            parse_pip_freeze("numpy==2.0.0\nyomitoku==0.8.0\nopencv-python==4.10\n"),
+        );
+        assert_ne!(a.hash, b.hash);
+        let diff = diff_env_fingerprints(&a, &b);
+        assert!(!diff.same);
+        let summary: Vec<(&str, DiffKind)> = diff
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("foo-bar", DiffKind::Removed),
+                ("numpy", DiffKind::Changed),
+                ("opencv-python", DiffKind::Added),
+            ]
+        );
+        assert!(diff_env_fingerprints(&a, &a).packages.is_empty());
+    }
+
+    #[test]
+    fn reprocess_label_appends_or_replaces_dpi_tag() {
+        assert_eq!(reprocess_label("doc", "doc", 400), "dpi400");
+        assert_eq!(reprocess_label("doc_p1-3", "doc", 400), "p1-3_dpi400");
+        assert_eq!(
+            reprocess_label("doc_p1-3_dpi400", "doc", 600),
+            "p1-3_dpi600"
+        );
+        assert_eq!(reprocess_label("doc_dpi400", "doc", 600), "dpi600");
+        assert_eq!(reprocess_label("doc_dpix", "doc", 600), "dpix_dpi600");
+
+        let mut args: Vec<String> = ["--chunk-size", "5", "--label", "old", "--label=x"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        replace_label_arg(&mut args, "dpi400");
+        assert_eq!(args, ["--chunk-size", "5", "--label", "dpi400"]);
+    }
+
+    #[test]
+    fn run_config_is_written_next_to_outputs() {
+        let root = TempDir::new("runcfg");
+        let dir = root.join("result").join("doc");
+        fs::create_dir_all(&dir).unwrap();
+        let opts: RunOptions = serde_json::from_value(serde_json::json!({
+            "pdfDpi": 200,
+            "password": "secret",
+            "fileOptions": {
+                "/in/doc.pdf": { "pages": "1-3" },
+                "/in/other.pdf": { "pages": "2" }
             }
-            Ok(())
-        })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        }))
+        .unwrap();
+
+        let args =
+            build_dispatcher_args("/in/doc.pdf", &opts, opts.file_options_for("/in/doc.pdf"));
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\nname = \"x\"\nversion = \"0.3.1\"\n",
+        )
+        .unwrap();
+        let version = read_dispatcher_version(&root);
+        assert_eq!(version.as_deref(), Some("0.3.1"));
+
+        let notes = write_run_configs(
+            &root,
+            &[dir.join("doc.md")],
+            "/in/doc.pdf",
+            Some("abc123"),
+            &opts,
+            &args,
+            version.as_deref(),
+            None,
+        );
+        assert!(notes.is_empty());
+        let raw = fs::read_to_string(dir.join(RUN_CONFIG_FILE)).unwrap();
+        assert!(!raw.contains("secret"));
+        let config = read_run_config(&dir).unwrap();
+        assert_eq!(config.input, "/in/doc.pdf");
+        assert_eq!(
+            config.dispatcher_args.first().map(String::as_str),
+            Some("/in/doc.pdf")
+        );
+        assert_eq!(config.app_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(config.dispatcher_version.as_deref(), Some("0.3.1"));
+        assert_eq!(config.input_hash.as_deref(), Some("abc123"));
+        assert_eq!(config.options.pdf_dpi, Some(200));
+        let file_options = config.options.file_options.unwrap();
+        assert_eq!(file_options.len(), 1);
+        assert!(file_options.contains_key("/in/doc.pdf"));
+
+        assert!(read_run_config(&root.join("result")).is_err());
+    }
+
+    #[test]
+    fn preview_failures_are_classified_from_stderr() {
+        assert_eq!(
+            classify_preview_failure("SystemExit: input not found: a.pdf"),
+            PREVIEW_ERROR_NOT_FOUND
+        );
+        assert_eq!(
+            classify_preview_failure(
+                "PIL.UnidentifiedImageError: cannot identify image file 'a.png'"
+            ),
+            PREVIEW_ERROR_UNSUPPORTED
+        );
+        assert_eq!(
+            classify_preview_failure(
+                "pdf2image.exceptions.PDFPageCountError: Unable to get page count.\nSyntax Error"
+            ),
+            PREVIEW_ERROR_UNSUPPORTED
+        );
+        assert_eq!(
+            classify_preview_failure("PDFPageCountError: Command Line Error: Incorrect password"),
+            PREVIEW_ERROR_ENCRYPTED
+        );
+        assert_eq!(
+            classify_preview_failure("MemoryError"),
+            PREVIEW_ERROR_RENDER_FAILED
+        );
+
+        let root = TempDir::new("prev");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("ui_preview.py"), "").unwrap();
+        let err = run_preview_helper(&root, &root.join("missing.pdf"), &PreviewParams::default())
+            .unwrap_err();
+        assert!(err.starts_with("not_found: "), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn post_hook_gets_the_result_dir_and_its_output_is_logged() {
+        let dir = TempDir::new("post_hook");
+        fs::create_dir_all(&dir).unwrap();
+        let ok = dir.join("ok.sh");
+        fs::write(&ok, "#!/bin/sh\necho \"uploaded $1\"\n").unwrap();
+        let failing = dir.join("fail.sh");
+        fs::write(&failing, "#!/bin/sh\necho oops >&2\nexit 3\n").unwrap();
+        for script in [&ok, &failing] {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(script, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let timeout = Duration::from_secs(5);
+        let state = AppState::default();
+
+        let mut log = Vec::new();
+        run_post_hook(
+            &state,
+            "hook",
+            ok.to_str().unwrap(),
+            &dir,
+            timeout,
+            &mut log,
+        )
+        .unwrap();
+        let expected = format!("[post_hook] uploaded {}", dir.display());
+        assert!(log
+            .iter()
+            .any(|e| e.source == LogSource::Stdout && e.text == expected));
+
+        let mut log = Vec::new();
+        let err = run_post_hook(
+            &state,
+            "hook",
+            failing.to_str().unwrap(),
+            &dir,
+            timeout,
+            &mut log,
+        )
+        .unwrap_err();
+        assert!(err.starts_with("post hook failed"), "{err}");
+        assert!(log.iter().any(|e| e.text == "[post_hook] oops"));
+
+        assert!(run_post_hook(
+            &state,
+            "hook",
+            "/nonexistent/hook",
+            &dir,
+            timeout,
+            &mut Vec::new()
+        )
+        .is_err());
+        assert!(state.children.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pollers_read_the_job_table_concurrently() {
+        let state = Arc::new(AppState::default());
+        let held = state.read_jobs("test").unwrap();
+        // Mutex だとここで止まる
+        let other = state.clone();
+        let len = thread::spawn(move || other.read_jobs("test").map(|jobs| jobs.len()))
+            .join()
+            .unwrap();
+        assert_eq!(len, Ok(0));
+        drop(held);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_with_timeout_kills_a_hung_child() {
+        let started = Instant::now();
+        let mut hung = Command::new("sleep");
+        hung.arg("5");
+        assert!(output_with_timeout(&mut hung, Duration::from_millis(200))
+            .unwrap()
+            .is_none());
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        let mut quick = Command::new("echo");
+        quick.arg("hi");
+        let output = output_with_timeout(&mut quick, Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hi\n");
+    }
+
+    #[test]
+    fn dispatcher_args_skip_rest_seconds_when_rest_disabled() {
+        let opts = RunOptions {
+            rest_seconds: Some(10),
+            ..RunOptions::default()
+        };
+        let args = build_dispatcher_args("a.pdf", &opts, None);
+        assert!(!args.iter().any(|a| a == "--rest-seconds"));
+    }
 }
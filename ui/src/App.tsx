@@ -11,7 +11,8 @@ import {
   Button,
   Stack,
 } from '@mantine/core'
-import { runJob, getProgress, getResult } from './api/runJob'
+import { notifications } from '@mantine/notifications'
+import { runJob, getProgress, getResult, formatLogEntry, focusOnNotificationAction } from './api/runJob'
 import { Sidebar, type PageKey } from './components/Layout/Sidebar'
 import { Home } from './pages/Home'
 import { RunJob } from './pages/RunJob'
@@ -42,6 +43,7 @@ function App() {
   const [progress, setProgress] = useState<number>(0)
   const [currentMessage, setCurrentMessage] = useState<string>('')
   const [etaSeconds, setEtaSeconds] = useState<number | null>(null)
+  const [fileEtaSeconds, setFileEtaSeconds] = useState<number | null>(null)
   const [outputs, setOutputs] = useState<string[]>([])
   const [jobInputPaths, setJobInputPaths] = useState<string[]>([])
   const settingsRef = useRef<SettingsHandle | null>(null)
@@ -111,6 +113,14 @@ function App() {
     }).catch(console.error)
   }, [])
 
+  useEffect(() => {
+    // 完了通知をクリックしたらウィンドウを前面に出す
+    const unlisten = focusOnNotificationAction()
+    return () => {
+      unlisten.then((stop) => stop())
+    }
+  }, [])
+
   useEffect(() => {
     const onResize = () => setViewportWidth(window.innerWidth)
     onResize()
@@ -145,10 +155,11 @@ function App() {
         setProgress(p.progress ?? 0)
         setCurrentMessage(p.currentMessage ?? '')
         setEtaSeconds(typeof p.etaSeconds === 'number' ? p.etaSeconds : null)
+        setFileEtaSeconds(typeof p.fileEtaSeconds === 'number' ? p.fileEtaSeconds : null)
         if (p.log) {
           const prevLen = logCursorRef.current
           const nextLen = p.log.length
-          const newLines = nextLen >= prevLen ? p.log.slice(prevLen) : p.log
+          const newLines = (nextLen >= prevLen ? p.log.slice(prevLen) : p.log).map(formatLogEntry)
           logCursorRef.current = nextLen
           if (newLines.length) setLog((prev) => [...prev, ...newLines].slice(-200))
         }
@@ -158,6 +169,15 @@ function App() {
           const res = await getResult(jobId)
           setResultText(res.preview ?? '')
           setOutputs(res.outputs ?? [])
+          const fontWarnings = res.metadata?.fontWarnings ?? []
+          if (fontWarnings.length) {
+            notifications.show({
+              title: 'Word で一部のフォントが置き換えられます',
+              message: fontWarnings.join('\n'),
+              color: 'yellow',
+              autoClose: false,
+            })
+          }
           setPage('result')
         } else if (p.status === 'error') {
           setError(p.error ?? 'unknown error')
@@ -184,6 +204,7 @@ function App() {
     setProgress(0)
     setCurrentMessage('')
     setEtaSeconds(null)
+    setFileEtaSeconds(null)
     try {
       const job = await runJob(filePaths, options)
       setJobId(job.jobId)
@@ -300,6 +321,7 @@ function App() {
                 progress={progress}
                 currentMessage={currentMessage}
                 etaSeconds={etaSeconds}
+                fileEtaSeconds={fileEtaSeconds}
                 log={log}
                 error={error}
                 setError={setError}
@@ -1,25 +1,133 @@
 import { invoke } from '@tauri-apps/api/core'
+import type { CleanupResult, RunOptions } from './runJob'
 
 export type RecentResultEntry = {
   dirName: string
   updatedAtMs: number
   pageRange?: string
   bestFile?: string
+  /** 作り済みの先頭ページの縮小画像（data URL）。無ければ getResultThumbnail で作る */
+  thumbnail?: string
+  /** この結果を作った実行の入力と設定（result/<dirName>/run.json。古い結果は null） */
+  run?: RunConfig | null
+  /** bestFile の verifyOutput 結果（'corrupt' などは開く前に警告できる） */
+  bestFileStatus?: OutputIntegrity | null
+}
+
+/** verifyOutput の判定。'unchecked' は中身を確かめない形式 */
+export type OutputIntegrity = 'ok' | 'empty' | 'corrupt' | 'invalidEncoding' | 'unchecked'
+
+export type OutputVerification = {
+  filename: string
+  status: OutputIntegrity
+  sizeBytes: number
+  /** 壊れていると判断した理由 */
+  detail?: string | null
+}
+
+export type RunConfig = {
+  input: string
+  options: RunOptions
+  /** dispatcher.py に渡した引数（パスワードは環境変数で渡すので含まれない） */
+  dispatcherArgs: string[]
+  appVersion: string
+  dispatcherVersion?: string | null
+  writtenAtMs: number
+  /** 実行時の Python 環境（取得できなかった実行・古い run.json は null） */
+  envFingerprint?: EnvFingerprint | null
+  /** ジョブに付けたタグ（setJobTags） */
+  tags?: string[]
+  /** 入力の hashInput の値（skipUnchanged のときは処理前、それ以外は結果を書いたときの内容。読めなかった入力・古い run.json は null） */
+  inputHash?: string | null
+}
+
+export type EnvFingerprint = {
+  /** pythonVersion と packages の要約。違えば環境が変わっている */
+  hash: string
+  pythonVersion: string
+  packages: Record<string, string>
+}
+
+export type PackageDiff = {
+  name: string
+  kind: 'added' | 'removed' | 'changed'
+  versionA?: string | null
+  versionB?: string | null
+}
+
+export type EnvDiff = {
+  same: boolean
+  pythonA: string
+  pythonB: string
+  packages: PackageDiff[]
 }
 
 export type EnvironmentStatus = {
   projectRoot: string
   dispatcherFound: boolean
+  dispatcherPath: string
+  previewHelperFound: boolean
   resultDirFound: boolean
   pythonBin: string
 }
 
-export async function listRecentResults(limit = 10): Promise<RecentResultEntry[]> {
+/** formatFilter を渡すとその形式（'xlsx' など）の出力がある結果だけを返す */
+export async function listRecentResults(limit = 10, formatFilter?: string): Promise<RecentResultEntry[]> {
   const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
-  if (hasTauri) return invoke<RecentResultEntry[]>('list_recent_results', { limit })
+  if (hasTauri) return invoke<RecentResultEntry[]>('list_recent_results', { limit, formatFilter })
   return []
 }
 
+/**
+ * 結果のサムネイル（data URL）。初回は先頭ページから作るので時間がかかる。
+ * 一覧を待たせないよう、thumbnail が無い行の分だけ表示するときに呼ぶ（作れなければ null）
+ */
+export async function getResultThumbnail(dirName: string): Promise<string | null> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<string | null>('get_result_thumbnail', { dirName })
+  return null
+}
+
+/** 入力ファイルをすでに処理した結果があれば最新の 1 件（再実行前の確認用。無ければ null） */
+export async function findExistingResult(path: string): Promise<RecentResultEntry | null> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<RecentResultEntry | null>('find_existing_result', { path })
+  return null
+}
+
+/** 入力ファイルの内容のハッシュ（BLAKE3 の 16 進）。RunConfig.inputHash と違えば入力が変わっている */
+export async function hashInput(path: string): Promise<string> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<string>('hash_input', { path })
+  return 'mock-hash'
+}
+
+export type IncompleteResultEntry = {
+  dirName: string
+  updatedAtMs: number
+  /** 消したときに空く容量 */
+  sizeBytes: number
+}
+
+/** 出力（docx / xlsx / csv / md）が 1 つも無い結果フォルダ（失敗した実行の残りなど）。実行中のジョブの分は含まない */
+export async function listIncompleteResults(): Promise<IncompleteResultEntry[]> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<IncompleteResultEntry[]>('list_incomplete_results')
+  return []
+}
+
+/** 結果フォルダを丸ごと消す（実行中のジョブが書き込んでいるものはエラー） */
+export async function deleteResultDir(dirName: string): Promise<CleanupResult> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<CleanupResult>('delete_result_dir', { dirName })
+  return { freedBytes: 0, removed: [] }
+}
+
+/** openResultDir / openResultFile のエラーが「一覧取得後に削除された」ものか（一覧を取り直す） */
+export function isResultDeletedError(error: unknown): boolean {
+  return String(error).startsWith('deleted:')
+}
+
 export async function openResultDir(dirName: string): Promise<void> {
   const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
   if (hasTauri) return invoke('open_result_dir', { dirName })
@@ -32,14 +140,65 @@ export async function openResultFile(dirName: string): Promise<void> {
   console.log('Mock open result file:', dirName)
 }
 
+/** 結果の Markdown（結合版を優先）をクリップボードにコピーし、コピーした文字数を返す */
+export async function copyResultText(dirName: string): Promise<number> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<number>('copy_result_text', { dirName })
+  console.log('Mock copy result text:', dirName)
+  return 0
+}
+
+/** いちばん新しい結果フォルダの代表ファイルを開く（結果が 1 つも無ければエラー） */
+export async function openLatestResult(): Promise<void> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke('open_latest_result')
+  console.log('Mock open latest result')
+}
+
+/** 結果フォルダ内の指定ファイルを開く（filename はフォルダ直下のファイル名のみ） */
+export async function openResultFileNamed(dirName: string, filename: string): Promise<void> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke('open_result_file_named', { dirName, filename })
+  console.log('Mock open result file:', dirName, filename)
+}
+
+/** docx/xlsx は zip として、md/csv は UTF-8 として読めるかを確かめる（修復はしない） */
+export async function verifyOutput(dirName: string, filename: string): Promise<OutputVerification> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<OutputVerification>('verify_output', { dirName, filename })
+  return { filename, status: 'ok', sizeBytes: 0 }
+}
+
+/** openResultFile が開くファイルの絶対パス（開かずに返す） */
+export async function resolveResultFile(dirName: string): Promise<string> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<string>('resolve_result_file', { dirName })
+  return `/mock/result/${dirName}/${dirName}.md`
+}
+
 export async function checkEnvironment(): Promise<EnvironmentStatus> {
   const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
   if (hasTauri) return invoke<EnvironmentStatus>('check_environment')
   return {
     projectRoot: '',
     dispatcherFound: false,
+    dispatcherPath: '',
+    previewHelperFound: false,
     resultDirFound: false,
     pythonBin: '',
   }
 }
 
+/** 現在の Python 環境（python --version と pip freeze）。常に取り直し、以降のジョブが run.json に残す値も更新する */
+export async function captureEnvFingerprint(): Promise<EnvFingerprint> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<EnvFingerprint>('capture_env_fingerprint')
+  return { hash: '0000000000000000', pythonVersion: '3.11.0', packages: {} }
+}
+
+/** 2 つの結果を作ったときのパッケージの違い（run.json に環境の記録がある結果のみ） */
+export async function diffEnv(dirA: string, dirB: string): Promise<EnvDiff> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (hasTauri) return invoke<EnvDiff>('diff_env', { dirA, dirB })
+  return { same: true, pythonA: '3.11.0', pythonB: '3.11.0', packages: [] }
+}
@@ -1,15 +1,81 @@
 import { invoke } from '@tauri-apps/api/core'
 import type { CropRect } from '../types/crop'
+import type { Rotation } from './runJob'
 
 export type PreviewPayload = {
   dataUrl: string
   pageCount?: number | null
   page?: number | null
+  /** dataUrl の形式（'image/png' | 'image/jpeg' | 'image/webp'） */
+  mimeType?: string
+  /**
+   * 'encrypted' = パスワード付き PDF（password を付けて再要求する）。
+   * previewBatchFirstPages では読めなかったファイルに 'not_found' | 'render_failed' も付く
+   */
+  errorKind?: 'encrypted' | 'not_found' | 'render_failed'
+  /** errorKind の詳細 */
+  error?: string
+  /** previewBatchFirstPages のときだけ付く（渡したパスのまま） */
+  path?: string
+  /** renderTile のときだけ付く */
+  tile?: TileInfo
+  /** previewEffectiveCrop のときだけ付く */
+  effectiveCrop?: EffectiveCrop
+  /** autoOrient のとき、自動で足した時計回りの回転角（0 は回していない） */
+  autoRotation?: 0 | 90 | 270
 }
 
+/** 回転・傾き補正後の画像（実行時の解像度）上で実際に切り出した範囲（px、右端・下端は含まない） */
+export type EffectiveCrop = {
+  left: number
+  top: number
+  right: number
+  bottom: number
+  imageWidth: number
+  imageHeight: number
+}
+
+export type TileInfo = {
+  /** 列・行（0 始まり） */
+  x: number
+  y: number
+  size: number
+  zoom: number
+  columns: number
+  rows: number
+  /** zoom をかけたページ全体の大きさ（px） */
+  imageWidth: number
+  imageHeight: number
+}
+
+/** renderPreview が失敗したときのエラー種別（エラー文字列の `"<kind>: "` 接頭辞） */
+export type PreviewErrorKind = 'not_found' | 'unsupported' | 'render_failed' | 'timeout' | 'encrypted'
+
+const PREVIEW_ERROR_KINDS: PreviewErrorKind[] = ['not_found', 'unsupported', 'render_failed', 'timeout', 'encrypted']
+
+/** プレビューのエラーから種別を取り出す（種別の付いていないエラーは null） */
+export function previewErrorKind(error: unknown): PreviewErrorKind | null {
+  const text = String(error)
+  return PREVIEW_ERROR_KINDS.find((kind) => text.startsWith(`${kind}:`)) ?? null
+}
+
+/** 'auto' は大きいページだけ JPEG にして IPC のデータ量を抑える（既定） */
+export type PreviewFormat = 'auto' | 'png' | 'jpeg' | 'webp'
+
 export async function renderPreview(
   path: string,
-  opts?: { page?: number; crop?: CropRect; maxLongEdge?: number },
+  opts?: {
+    page?: number
+    crop?: CropRect
+    maxLongEdge?: number
+    rotate?: Rotation
+    autoDeskew?: boolean
+    /** 実行時の autoOrient と同じ判定で横倒しのページを回す（回した角度は autoRotation） */
+    autoOrient?: boolean
+    password?: string
+    format?: PreviewFormat
+    quality?: number
+  },
 ): Promise<PreviewPayload> {
   const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
   if (!hasTauri) throw new Error('Tauri 環境外ではプレビューできません')
@@ -18,5 +84,65 @@ export async function renderPreview(
     page: opts?.page,
     crop: opts?.crop,
     maxLongEdge: opts?.maxLongEdge,
+    rotate: opts?.rotate,
+    autoDeskew: opts?.autoDeskew,
+    autoOrient: opts?.autoOrient,
+    password: opts?.password,
+    format: opts?.format,
+    quality: opts?.quality,
   })
 }
+
+/**
+ * 結果フォルダの 1 ページ（1 始まり、元文書のページ番号）を画像で返す。OCR はやり直さない。
+ * ページ画像が残っていなければ run.json の元ファイルから描画する
+ */
+export async function previewResultPage(dirName: string, page: number, maxLongEdge?: number): Promise<PreviewPayload> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (!hasTauri) throw new Error('Tauri 環境外ではプレビューできません')
+  return invoke<PreviewPayload>('preview_result_page', { dirName, page, maxLongEdge })
+}
+
+/**
+ * 実行時と同じ処理（pdfDpi での描画・回転→傾き補正→トリミング・同じ丸め）で切り抜いたプレビュー。
+ * crop は回転・傾き補正後の画像に対する 0〜1 の割合で、各辺は round(割合 × px) に丸められる
+ */
+export async function previewEffectiveCrop(
+  path: string,
+  crop: CropRect,
+  opts?: {
+    page?: number
+    rotate?: Rotation
+    autoDeskew?: boolean
+    pdfDpi?: number
+    maxLongEdge?: number
+    password?: string
+  },
+): Promise<PreviewPayload> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (!hasTauri) throw new Error('Tauri 環境外ではプレビューできません')
+  return invoke<PreviewPayload>('preview_effective_crop', { path, crop, ...opts })
+}
+
+/**
+ * 複数ファイルの 1 ページ目をまとめて小さく描く（ファイル一覧のサムネイル用。maxLongEdge の既定は 240）。
+ * 結果は paths と同じ順で、各要素の path で対応が分かる。読めないファイルは errorKind 付きで返る
+ */
+export async function previewBatchFirstPages(paths: string[], maxLongEdge?: number): Promise<PreviewPayload[]> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (!hasTauri) throw new Error('Tauri 環境外ではプレビューできません')
+  return invoke<PreviewPayload[]>('preview_batch_first_pages', { paths, maxLongEdge })
+}
+
+/**
+ * 大きなページを tileSize px 四方のタイルに分けて 1 枚だけ描画する（ズームビューアの遅延読み込み用）。
+ * zoom は 0.25〜4（1 = 通常プレビューと同じ 150dpi 相当）、tileSize は 64〜2048
+ */
+export async function renderTile(
+  path: string,
+  tile: { page?: number; tileX: number; tileY: number; tileSize: number; zoom?: number; password?: string },
+): Promise<PreviewPayload> {
+  const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+  if (!hasTauri) throw new Error('Tauri 環境外ではプレビューできません')
+  return invoke<PreviewPayload>('render_tile', { path, ...tile })
+}
@@ -1,10 +1,18 @@
-import { invoke } from '@tauri-apps/api/core'
+import { addPluginListener, invoke } from '@tauri-apps/api/core'
 import type { CropRect } from '../types/crop'
 
+export type Rotation = 0 | 90 | 180 | 270
+
 export type FileSpecificOptions = {
   start?: number
   end?: number
+  /** 複数範囲のページ指定（例: "1-3,7,10-12"）。start/end より優先 */
+  pages?: string
   crop?: CropRect
+  rotate?: Rotation
+  autoDeskew?: boolean
+  /** RunOptions.languages より優先（[] はこのファイルだけ自動） */
+  languages?: string[]
 }
 
 export type RunOptions = {
@@ -20,22 +28,105 @@ export type RunOptions = {
   restSeconds?: number
   pdfDpi?: number
   fileOptions?: Record<string, FileSpecificOptions>
+  extraDispatcherArgs?: string[]
+  extraOcrArgs?: string[]
+  continueOnError?: boolean
+  rotate?: Rotation
+  autoDeskew?: boolean
+  /** 横倒しのページを見つけてページごとに回す（横書き前提。回したページは ResultMetadata.rotatedPages）。fileOptions の crop とは併用できない */
+  autoOrient?: boolean
+  /** true なら同じ入力を同じ出力設定（formats など）で処理済みで、内容が変わっていない（run.json の inputHash が同じ。古い結果は入力より新しい）ファイルを処理しない（FileProgress は 'skipped'、outputs は既存の結果） */
+  skipUnchanged?: boolean
+  /** パスワード付き PDF 用。ログや settings.json には残らない */
+  password?: string
+  /** OCR 言語（listSupportedLanguages のコード）。空・未指定は自動（日英） */
+  languages?: string[]
+  /** true なら空き容量の見積もりが足りないときに開始しない */
+  requireFreeSpace?: boolean
+  /** モデル取得のタイムアウトなど一時的な失敗での再試行回数（既定 0、最大 10） */
+  maxRetries?: number
+  /** 結合 Markdown のページ区切り（未指定は従来の `# Page N` 見出し） */
+  mergeSeparator?: MergeSeparator
+  /** true なら各ファイルの成功後に page_images/ などの中間生成物を消す（xlsx/csv の再書き出しに使う結果では page_images/ を残す） */
+  autoCleanup?: boolean
+  /** csv の区切り文字（1 文字。タブは '\t'）。未指定は `,` */
+  csvDelimiter?: string
+  /** csv の文字コード（Excel 日本語版向けには 'shift_jis' か 'utf-8-sig'）。未指定は UTF-8 */
+  csvEncoding?: CsvEncoding
+  /** true なら設定の maxPages を超えるジョブも実行する（確認ダイアログの後に付ける） */
+  confirmLarge?: boolean
+  /** figures/ に保存する図版の形式（enableFigure のときだけ。未指定は png）。図版は outputs に結果フォルダ名付きの `<dirName>/figures/<name>` で並ぶ */
+  figureFormat?: FigureFormat
+}
+
+/** 'page' は改ページ（md は `\newpage` の行、docx は Word の改ページ） */
+export type MergeSeparator = 'hr' | 'heading' | 'none' | 'page'
+
+export type CsvEncoding = 'utf-8' | 'utf-8-sig' | 'shift_jis'
+
+export type FigureFormat = 'png' | 'jpeg'
+
+
+export type LogEntry = {
+  tsMs: number
+  level: 'debug' | 'info' | 'warn' | 'error'
+  source: 'app' | 'stdout' | 'stderr'
+  text: string
+}
+
+/** 従来の文字列形式（stderr は `[err] ` 付き）に戻す */
+export function formatLogEntry(entry: LogEntry): string {
+  return entry.source === 'stderr' ? `[err] ${entry.text}` : entry.text
 }
 
 export type ProgressPayload = {
-  status: 'idle' | 'running' | 'done' | 'error'
+  status: 'idle' | 'running' | 'done' | 'error' | 'cancelled' | 'paused'
   progress?: number
-  log?: string[]
+  log?: LogEntry[]
   error?: string
   currentMessage?: string
   pageCurrent?: number
   pageTotal?: number
+  /** batchEtaSeconds と同じ値（互換用） */
   etaSeconds?: number
+  /** 処理中のファイルの残り秒数 */
+  fileEtaSeconds?: number
+  /** 未着手のファイルも含めたバッチ全体の残り秒数 */
+  batchEtaSeconds?: number
+  files?: FileProgress[]
+}
+
+export type FileProgress = {
+  path: string
+  /** skipped = 処理しなかった（停止・エラー中断・skipFile・skipUnchanged）、cancelled = 処理中に skipFile で止めた */
+  status: 'pending' | 'running' | 'done' | 'error' | 'skipped' | 'cancelled'
+  outputs: string[]
+  error?: string
+}
+
+export type ResultMetadata = {
+  pages: number
+  languages: string[]
+  tables: number
+  figures: number
+  /** docx が指定したフォントのうちこの PC に無いもの（ファイルごとの警告文）。処理自体は成功している */
+  fontWarnings: string[]
+  /** autoOrient で自動回転したページ（dirName は結果フォルダ名） */
+  rotatedPages: RotatedPage[]
+}
+
+export type RotatedPage = {
+  dirName: string
+  page: number
+  /** 時計回りの角度 */
+  degrees: 90 | 270
 }
 
 export type ResultPayload = {
   outputs?: string[]
   preview?: string
+  // dispatcher が結果の概要を出さなかったジョブでは null
+  metadata?: ResultMetadata | null
 }
 
 export async function runJob(paths: string[], options: RunOptions) {
@@ -49,15 +140,23 @@ export async function runJob(paths: string[], options: RunOptions) {
   return { jobId: 'mock-job' }
 }
 
-export async function getProgress(jobId: string): Promise<ProgressPayload> {
+export async function getProgress(
+  jobId: string,
+  filters?: { levelFilter?: LogEntry['level']; textFilter?: string },
+): Promise<ProgressPayload> {
   const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
-  if (hasTauri) return invoke<ProgressPayload>('get_progress', { jobId })
+  if (hasTauri)
+    return invoke<ProgressPayload>('get_progress', {
+      jobId,
+      levelFilter: filters?.levelFilter,
+      textFilter: filters?.textFilter,
+    })
   return { status: 'done', progress: 100 }
 }
 
-export async function getResult(jobId: string): Promise<ResultPayload> {
+export async function getResult(jobId: string, asHtml = false): Promise<ResultPayload> {
   const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
-  if (hasTauri) return invoke<ResultPayload>('get_result', { jobId })
+  if (hasTauri) return invoke<ResultPayload>('get_result', { jobId, asHtml })
   return {
     outputs: ['sample.md', 'sample.docx', 'sample.xlsx'],
     preview: 'Converted markdown for: sample.pdf',
@@ -82,6 +181,15 @@ export async function openOutput(jobId: string, filename: string): Promise<void>
   console.log('Mock open:', filename)
 }
 
+/** ジョブの代表出力（docx > xlsx > csv > md）の絶対パス。開かずに返す */
+export async function resolveBestOutput(jobId: string): Promise<string> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) {
+    return invoke<string>('resolve_best_output', { jobId })
+  }
+  return `/mock/result/${jobId}/output.md`
+}
+
 export async function openOutputDir(jobId: string): Promise<void> {
   const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
   if (hasTauri) {
@@ -97,3 +205,99 @@ export async function openInputFile(path: string): Promise<void> {
   }
   console.log('Mock open input file:', path)
 }
+
+export async function listSupportedLanguages(): Promise<string[]> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke<string[]>('list_supported_languages')
+  return ['ja', 'en']
+}
+
+/**
+ * 完了通知（notification プラグイン）がクリックされたらメインウィンドウを前面に出す。
+ * プラグインが `actionPerformed` を送らない環境では何もしない。戻り値で登録を解除する
+ */
+export async function focusOnNotificationAction(): Promise<() => void> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (!hasTauri) return () => {}
+  try {
+    const listener = await addPluginListener('notification', 'actionPerformed', () => {
+      invoke('focus_main_window').catch(console.error)
+    })
+    return () => {
+      listener.unregister().catch(console.error)
+    }
+  } catch {
+    return () => {}
+  }
+}
+
+/** 初回セットアップ用: OCR モデルを取得・ロードするジョブを開始する（進捗は getProgress で見る） */
+export async function warmModels(device?: string, mode?: 'lite' | 'full') {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke<{ jobId: string }>('warm_models', { device, mode })
+  return { jobId: 'mock-warm-models' }
+}
+
+/**
+ * 結果フォルダの run.json（実行時の入力と設定）から DPI だけ変えて再実行する。
+ * 出力は `<dirName>_dpi<newDpi>` 相当の別フォルダ。PDF 入力のみ。
+ * run.json にパスワードは残らないため、保護された PDF は password が必要
+ * （無い・違うときは `encrypted:` で始まるエラーで reject する）
+ */
+export async function reprocessResult(dirName: string, newDpi: number, password?: string) {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke<{ jobId: string }>('reprocess_result', { dirName, newDpi, password })
+  return { jobId: `mock-reprocess-${dirName}` }
+}
+
+export type CleanupResult = {
+  freedBytes: number
+  /** 消したディレクトリ（絶対パス） */
+  removed: string[]
+}
+
+/** ジョブをキャンセルし、このジョブが作った書きかけの結果フォルダ（run.json の無いもの）と作業フォルダを消す。実行前からあったフォルダは残す */
+export async function cancelAndDiscard(jobId: string): Promise<CleanupResult> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke<CleanupResult>('cancel_and_discard', { jobId })
+  return { freedBytes: 0, removed: [] }
+}
+
+/** バッチから 1 ファイルだけ外す（未着手なら飛ばし、処理中なら止めて次のファイルへ進む） */
+export async function skipFile(jobId: string, path: string): Promise<void> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke('skip_file', { jobId, path })
+}
+
+/**
+ * 実行中のバッチで、まだ始まっていないファイル（status が 'pending'）の処理順を変える。
+ * newOrder は未着手のファイルのパスをちょうど 1 回ずつ並べたもの（処理済み・処理中のファイルは含めない）
+ */
+export async function reorderJobFiles(jobId: string, newOrder: string[]): Promise<void> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke('reorder_job_files', { jobId, newOrder })
+}
+
+export type JobSummary = {
+  jobId: string
+  progress: number
+  startedAtMs: number
+  /** 実行中・一時停止中は null */
+  finishedAtMs?: number | null
+  outputCount: number
+  tags: string[]
+}
+
+/** tag を渡すとそのタグが付いたジョブだけを返す */
+export async function listJobsByStatus(status: ProgressPayload['status'], tag?: string): Promise<JobSummary[]> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke<JobSummary[]>('list_jobs_by_status', { status, tag })
+  return []
+}
+
+/** ジョブのタグを置き換える（空配列で外す）。1 つ 32 文字まで・16 個まで。整えた後のタグを返す */
+export async function setJobTags(jobId: string, tags: string[]): Promise<string[]> {
+  const hasTauri = typeof window !== 'undefined' && '__TAURI__' in window
+  if (hasTauri) return invoke<string[]>('set_job_tags', { jobId, tags })
+  return tags.map((t) => t.trim())
+}
@@ -2,6 +2,8 @@
 import { invoke } from '@tauri-apps/api/core'
 
 export interface AppSettings {
+    // settings.json の構造バージョン。アプリより新しい版が書いたファイルは上書きせず、保存は reject する
+    schemaVersion?: number
     formats: string[]
     imageAsPdf: boolean
     enableFigure: boolean
@@ -14,6 +16,34 @@ export interface AppSettings {
     windowWidth?: number
     windowHeight?: number
     useGpu?: boolean
+    notifyOnComplete?: boolean
+    tempDir?: string
+    // 進捗バーの更新頻度（毎秒, 1〜30）。未指定なら 10
+    progressUpdateHz?: number
+    // ジョブのログを logs/<jobId>.log にも書き出す（maxLogFileBytes を超えたら .log.1 に回す）
+    logToFile?: boolean
+    maxLogFileBytes?: number
+    // dispatcher / プレビューに足す環境変数（PYTHONPATH など。PYTHONUTF8 / PYTHONIOENCODING は上書き不可）
+    env?: Record<string, string>
+    // 拡張子 → 出力を開くアプリのパス（例: { docx: 'C:\\Program Files\\Viewer\\viewer.exe' }）。未設定なら OS の既定アプリ
+    openWith?: Record<string, string>
+    // プレビュー作成の制限時間（秒, 1〜600）。超えると "timeout:" で始まるエラーになる。未指定なら 30
+    previewTimeoutSeconds?: number
+    // 1 ジョブのページ数の上限。超えると "too_many_pages:" で始まるエラー（RunOptions.confirmLarge で実行可）
+    maxPages?: number
+    // ジョブとプレビューで使う python。PYTHON_BIN より優先。saveSettings でも変わっていれば起動を確かめ、使えなければ reject する
+    pythonBinOverride?: string
+    // listPythonCandidates に追加で並べる python のパス
+    pythonCandidates?: string[]
+    // 結果フォルダ名のテンプレート（例: '{stem}_{date}_{dpi}dpi'）。{stem} {date} {time} {dpi} {mode} {index} が使える。
+    // date / time はジョブ開始時刻（UTC）の YYYYMMDD / HHMMSS。未指定なら入力ファイル名
+    outputNameTemplate?: string
+    // ジョブが完了（done）した後に結果フォルダごとに実行するプログラムのパス。引数は結果フォルダの絶対パス。
+    // 出力はジョブのログに残る。失敗したジョブ・キャンセルしたジョブでは実行しない。
+    // フックが終わるまでジョブは running のまま（currentMessage は「後処理: post_hook 実行中」）
+    postHook?: string
+    // true なら postHook の失敗でジョブを error にする（既定は警告としてログに残すだけ）
+    postHookRequired?: boolean
 }
 
 const DEFAULT_SETTINGS: AppSettings = {
@@ -48,6 +78,55 @@ export async function saveSettings(settings: AppSettings): Promise<void> {
     return invoke('save_settings', { settings })
 }
 
+export type PythonSource =
+    | 'override'
+    | 'envVar'
+    | 'resourcesPython'
+    | 'resourcesVenv'
+    | 'projectVenv'
+    | 'path'
+    | 'registered'
+
+export interface PythonCandidate {
+    pythonBin: string
+    source: PythonSource
+    // 今ジョブで使われる python
+    active: boolean
+}
+
+export interface PythonProbe {
+    pythonBin: string
+    source: PythonSource
+    ok: boolean
+    version?: string | null
+    executable?: string | null
+    error?: string | null
+    elapsedMs: number
+}
+
+export async function listPythonCandidates(): Promise<PythonCandidate[]> {
+    const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+    if (!hasTauri) return [{ pythonBin: 'python', source: 'path', active: true }]
+    return invoke<PythonCandidate[]>('list_python_candidates')
+}
+
+// pythonBin を起動できたときだけ保存する（null で通常の探索に戻す）
+export async function setPythonBinOverride(pythonBin: string | null): Promise<PythonProbe | null> {
+    const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+    if (!hasTauri) {
+        console.log('[Mock] python override:', pythonBin)
+        return null
+    }
+    return invoke<PythonProbe | null>('set_python_bin_override', { pythonBin })
+}
+
+// フォルダを選んで outputRoot に保存する（書き込めないフォルダはエラー）。キャンセル時は null
+export async function chooseOutputRoot(): Promise<string | null> {
+    const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
+    if (!hasTauri) return null
+    return invoke<string | null>('choose_output_root')
+}
+
 export async function getCurrentWindowSize(): Promise<{ width: number; height: number }> {
     const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
     if (hasTauri) {
@@ -24,8 +24,8 @@ import {
     IconChevronDown,
     IconChevronUp,
 } from '@tabler/icons-react'
-import { useEffect, useState } from 'react'
-import { checkEnvironment, listRecentResults, openResultDir, openResultFile, type EnvironmentStatus, type RecentResultEntry } from '../api/history'
+import { useCallback, useEffect, useState } from 'react'
+import { checkEnvironment, isResultDeletedError, listRecentResults, openResultDir, openResultFile, type EnvironmentStatus, type RecentResultEntry } from '../api/history'
 
 interface HomeProps {
     onNavigate: (page: 'run') => void
@@ -38,12 +38,7 @@ export function Home({ onNavigate }: HomeProps) {
     const [env, setEnv] = useState<EnvironmentStatus | null>(null)
     const hasTauri = typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window)
 
-    useEffect(() => {
-        if (!hasTauri) {
-            setRecent([])
-            setEnv(null)
-            return
-        }
+    const refreshRecent = useCallback(() => {
         setLoadingRecent(true)
         listRecentResults(10)
             .then((items) => setRecent(items))
@@ -52,6 +47,26 @@ export function Home({ onNavigate }: HomeProps) {
                 setRecent([])
             })
             .finally(() => setLoadingRecent(false))
+    }, [])
+
+    // 一覧を取った後で外部から消された結果フォルダなら、一覧を取り直す
+    const openRecent = (open: (dirName: string) => Promise<void>, dirName: string) => {
+        open(dirName).catch((e) => {
+            if (isResultDeletedError(e)) {
+                refreshRecent()
+            } else {
+                console.error(e)
+            }
+        })
+    }
+
+    useEffect(() => {
+        if (!hasTauri) {
+            setRecent([])
+            setEnv(null)
+            return
+        }
+        refreshRecent()
 
         checkEnvironment()
             .then(setEnv)
@@ -59,7 +74,7 @@ export function Home({ onNavigate }: HomeProps) {
                 console.error(e)
                 setEnv(null)
             })
-    }, [hasTauri])
+    }, [hasTauri, refreshRecent])
 
     const formatUpdated = (ms: number) => {
         try {
@@ -185,7 +200,7 @@ export function Home({ onNavigate }: HomeProps) {
                                                                 <ActionIcon
                                                                     variant="light"
                                                                     color="gray"
-                                                                    onClick={() => openResultDir(item.dirName)}
+                                                                    onClick={() => openRecent(openResultDir, item.dirName)}
                                                                 >
                                                                     <IconFolderOpen size={16} />
                                                                 </ActionIcon>
@@ -195,7 +210,7 @@ export function Home({ onNavigate }: HomeProps) {
                                                                     variant="light"
                                                                     color="blue"
                                                                     disabled={!item.bestFile}
-                                                                    onClick={() => openResultFile(item.dirName)}
+                                                                    onClick={() => openRecent(openResultFile, item.dirName)}
                                                                 >
                                                                     <IconExternalLink size={16} />
                                                                 </ActionIcon>
@@ -55,6 +55,8 @@ interface RunJobProps {
     progress: number
     currentMessage: string
     etaSeconds: number | null
+    /** 処理中のファイルだけの残り秒数（etaSeconds はバッチ全体） */
+    fileEtaSeconds: number | null
     log: string[]
     error: string | null
     setError: (error: string | null) => void
@@ -70,6 +72,7 @@ export function RunJob({
     progress,
     currentMessage,
     etaSeconds,
+    fileEtaSeconds,
     log,
     error,
     setError,
@@ -147,7 +150,7 @@ export function RunJob({
         const selected = await open({
             multiple: true,
             filters: [
-                { name: 'Input', extensions: ['pdf', 'heic', 'heif', 'jpg', 'jpeg', 'png'] },
+                { name: 'Input', extensions: ['pdf', 'heic', 'heif', 'jpg', 'jpeg', 'png', 'tif', 'tiff'] },
             ],
         })
         if (!selected) return
@@ -166,6 +169,14 @@ export function RunJob({
         if (m <= 0) return `${r}秒`
         return `${m}分${r.toString().padStart(2, '0')}秒`
     }
+    // 複数ファイルのときは、今のファイルと全体の残り時間を分けて出す
+    const etaLabel = (() => {
+        if (etaSeconds == null) return ''
+        if (filePaths.length > 1 && fileEtaSeconds != null && fileEtaSeconds !== etaSeconds) {
+            return `（このファイル残り約 ${formatEta(fileEtaSeconds)}・全体残り約 ${formatEta(etaSeconds)}）`
+        }
+        return `（残り約 ${formatEta(etaSeconds)}）`
+    })()
 
     return (
         <Container size="lg" px={0}>
@@ -246,7 +257,7 @@ export function RunJob({
                                         ref={fileInputRef}
                                         type="file"
                                         multiple
-                                        accept=".pdf,.heic,.heif,.jpg,.jpeg,.png"
+                                        accept=".pdf,.heic,.heif,.jpg,.jpeg,.png,.tif,.tiff"
                                         style={{ display: 'none' }}
                                         onChange={(e) => {
                                             const files = Array.from(e.target.files || [])
@@ -417,7 +428,7 @@ export function RunJob({
 
                                     {status === 'running' && (currentMessage || etaSeconds != null) && (
                                         <Text size="sm" c="dimmed">
-                                            {currentMessage || '処理中'}{etaSeconds != null ? `（残り約 ${formatEta(etaSeconds)}）` : ''}
+                                            {currentMessage || '処理中'}{etaLabel}
                                         </Text>
                                     )}
 
@@ -2,13 +2,17 @@ from __future__ import annotations
 
 import argparse
 import base64
+import hashlib
 import io
 import json
 import os
+import shutil
 import sys
 import tempfile
 from pathlib import Path
 
+from crop_rect import CropRect, apply_crop, crop_box, parse_crop
+
 
 def resolve_poppler_path(base_dir: Path) -> Path:
     system = sys.platform
@@ -34,37 +38,44 @@ def resolve_poppler_path(base_dir: Path) -> Path:
     )
 
 
-CropRect = tuple[float, float, float, float]
-
+# --exact-crop の PDF 描画 DPI の既定（ocr_chanked.py の --dpi と同じ）
+DEFAULT_RUN_DPI = 300
 
-def parse_crop(value: str | None) -> CropRect | None:
-    if not value:
-        return None
-    parts = [p.strip() for p in value.split(",")]
-    if len(parts) != 4:
-        raise ValueError("crop は left,top,width,height の4要素が必要です")
-    left, top, width, height = (float(p) for p in parts)
-    left = max(0.0, min(1.0, left))
-    top = max(0.0, min(1.0, top))
-    width = max(0.0, min(1.0 - left, width))
-    height = max(0.0, min(1.0 - top, height))
-    if width <= 0 or height <= 0:
-        return None
-    return (left, top, width, height)
 
+def draw_crop_outline(img, crop: CropRect | None):
+    """トリミングせず、トリミング範囲を枠で描いた全体画像を返す（範囲確認用）。"""
 
-def apply_crop(img, crop: CropRect | None):
-    if not crop:
+    box = crop_box(img.size, crop)
+    if box is None:
         return img
-    left, top, width, height = crop
+    from PIL import ImageDraw
+
     w, h = img.size
-    lpx = int(round(left * w))
-    tpx = int(round(top * h))
-    rpx = int(round((left + width) * w))
-    bpx = int(round((top + height) * h))
-    if rpx <= lpx or bpx <= tpx:
-        return img
-    return img.crop((lpx, tpx, rpx, bpx))
+    outlined = img.convert("RGB")
+    line_width = max(2, round(max(w, h) / 300))
+    ImageDraw.Draw(outlined).rectangle(box, outline=(230, 40, 40), width=line_width)
+    return outlined
+
+
+def crop_for_preview(img, crop: CropRect | None, args):
+    """--outline なら枠を描き、それ以外は切り抜く。
+
+    --exact-crop のときは、切り出した範囲（回転・傾き補正後の画像上の px）も返す。
+    """
+
+    if not args.exact_crop:
+        return (draw_crop_outline(img, crop) if args.outline else apply_crop(img, crop)), None
+    w, h = img.size
+    left, top, right, bottom = crop_box(img.size, crop) or (0, 0, w, h)
+    effective = {
+        "left": left,
+        "top": top,
+        "right": right,
+        "bottom": bottom,
+        "imageWidth": w,
+        "imageHeight": h,
+    }
+    return apply_crop(img, crop), effective
 
 
 def resize_long_edge(img, max_long_edge: int):
@@ -79,32 +90,422 @@ def resize_long_edge(img, max_long_edge: int):
     return img.resize(new_size)
 
 
-def image_to_data_url(img) -> str:
+# タイル表示（--tile-size 指定時）: zoom=1 で通常プレビューと同じ 150dpi 相当
+TILE_BASE_DPI = 150
+MIN_TILE_ZOOM = 0.25
+MAX_TILE_ZOOM = 4.0
+
+
+def tile_box(width: int, height: int, tile_x: int, tile_y: int, tile_size: int) -> tuple[tuple[int, int, int, int], int, int]:
+    """(tile_x, tile_y) 番目のタイルの切り出し範囲と、列数・行数を返す。
+
+    右端・下端のタイルは画像の端で切り詰める。範囲外のタイルは ValueError。
+    """
+
+    if tile_size <= 0:
+        raise ValueError(f"tile size must be positive: {tile_size}")
+    columns = max(1, -(-width // tile_size))
+    rows = max(1, -(-height // tile_size))
+    if not (0 <= tile_x < columns and 0 <= tile_y < rows):
+        raise ValueError(f"tile out of range: ({tile_x}, {tile_y}) for {columns}x{rows} tiles")
+    left = tile_x * tile_size
+    top = tile_y * tile_size
+    return (left, top, min(width, left + tile_size), min(height, top + tile_size)), columns, rows
+
+
+# 描いたページを全タイルに切り分けて置く場所。同じページ・倍率の残りのタイルは描き直さずここから返す
+TILE_CACHE_ROOT = Path(tempfile.gettempdir()) / "ocr_to_doc_tiles"
+# 残すページ数（使われていないものから消す）
+TILE_CACHE_MAX_PAGES = 8
+
+
+def tile_cache_dir(input_path: Path, args, root: Path = TILE_CACHE_ROOT) -> Path | None:
+    """入力（更新時刻・サイズ込み）・ページ・倍率・回転・タイルの大きさごとのキャッシュ先。
+
+    パスワード付き PDF は復号したページをディスクに残さないよう、キャッシュしない。
+    --auto-orient は応答の autoRotation を描画時にしか出せないので、これもキャッシュしない。
+    """
+
+    if args.password or args.auto_orient:
+        return None
+    try:
+        stat = input_path.stat()
+    except OSError:
+        return None
+    key = json.dumps(
+        [
+            str(input_path.resolve()),
+            stat.st_mtime_ns,
+            stat.st_size,
+            args.page,
+            args.zoom,
+            args.tile_size,
+            args.rotate,
+            args.deskew,
+        ]
+    )
+    return root / hashlib.sha256(key.encode("utf-8")).hexdigest()[:32]
+
+
+def save_tile_cache(img, cache_dir: Path, tile_size: int, meta: dict) -> None:
+    """ページ全体を tile_size px 四方に切り分けて保存する。
+
+    同じページを並行して描いたときは先に置いた方を残す。キャッシュに書けなくてもプレビューは失敗させない。
+    """
+
+    try:
+        cache_dir.parent.mkdir(parents=True, exist_ok=True)
+        staging = Path(tempfile.mkdtemp(prefix=".staging_", dir=cache_dir.parent))
+        try:
+            for y in range(meta["rows"]):
+                for x in range(meta["columns"]):
+                    box, _, _ = tile_box(*img.size, x, y, tile_size)
+                    img.crop(box).save(staging / f"{x}_{y}.png", format="PNG", compress_level=1)
+            (staging / "meta.json").write_text(json.dumps(meta), encoding="utf-8")
+            try:
+                staging.rename(cache_dir)
+            except OSError:
+                pass
+        finally:
+            shutil.rmtree(staging, ignore_errors=True)
+        prune_tile_cache(cache_dir.parent)
+    except OSError:
+        pass
+
+
+def prune_tile_cache(root: Path, keep: int = TILE_CACHE_MAX_PAGES) -> None:
+    """最近使った keep ページ分だけ残す（描画中の .staging_* は触らない）。"""
+
+    entries = []
+    for path in root.iterdir():
+        if path.name.startswith(".") or not path.is_dir():
+            continue
+        try:
+            entries.append((path.stat().st_mtime, path))
+        except OSError:
+            continue
+    entries.sort(reverse=True)
+    for _, path in entries[keep:]:
+        shutil.rmtree(path, ignore_errors=True)
+
+
+def load_cached_tile(cache_dir: Path, args) -> dict | None:
+    """キャッシュ済みのページならタイル 1 枚分の応答を返す。無ければ None（描画する）。"""
+
+    try:
+        meta = json.loads((cache_dir / "meta.json").read_text(encoding="utf-8"))
+    except (OSError, ValueError):
+        return None
+    try:
+        tile_box(meta["imageWidth"], meta["imageHeight"], args.tile_x, args.tile_y, args.tile_size)
+    except ValueError as exc:
+        raise SystemExit(str(exc)) from exc
+    from PIL import Image
+
+    try:
+        with Image.open(cache_dir / f"{args.tile_x}_{args.tile_y}.png") as tile:
+            tile.load()
+            img = tile.copy()
+        # 最近使ったページとして残す
+        os.utime(cache_dir)
+    except OSError:
+        return None
+    return tile_response(img, args, meta)
+
+
+def tile_response(img, args, meta: dict) -> dict:
+    """切り出したタイル 1 枚の応答（meta はページ全体の pageCount / page / 列数・行数・大きさ）。"""
+
+    response: dict = {
+        "pageCount": meta["pageCount"],
+        "page": meta["page"],
+        "tile": {
+            "x": args.tile_x,
+            "y": args.tile_y,
+            "size": args.tile_size,
+            "zoom": args.zoom,
+            "columns": meta["columns"],
+            "rows": meta["rows"],
+            "imageWidth": meta["imageWidth"],
+            "imageHeight": meta["imageHeight"],
+        },
+    }
+    fmt = choose_format(img, args.format)
+    response["dataUrl"] = image_to_data_url(img, fmt=fmt, quality=args.quality)
+    response["mimeType"] = MIME_TYPES[fmt]
+    return response
+
+
+def scale_image(img, zoom: float):
+    """PDF 以外の入力をタイル表示の倍率に合わせる（PDF は描画 DPI で合わせる）。"""
+
+    if zoom == 1.0:
+        return img
+    w, h = img.size
+    return img.resize((max(1, int(round(w * zoom))), max(1, int(round(h * zoom)))))
+
+
+# --format auto のとき、長辺がこれ以上なら JPEG にして IPC で渡すデータ量を抑える
+AUTO_JPEG_MIN_LONG_EDGE = 1000
+DEFAULT_JPEG_QUALITY = 80
+MIME_TYPES = {"png": "image/png", "jpeg": "image/jpeg", "webp": "image/webp"}
+
+
+def choose_format(img, requested: str) -> str:
+    """--format の値を実際の保存形式にする（auto は大きい画像だけ JPEG）。"""
+
+    if requested != "auto":
+        return requested
+    return "jpeg" if max(img.size) >= AUTO_JPEG_MIN_LONG_EDGE else "png"
+
+
+def image_to_data_url(
+    img,
+    output: Path | None = None,
+    *,
+    fmt: str = "png",
+    quality: int = DEFAULT_JPEG_QUALITY,
+) -> str:
     buf = io.BytesIO()
-    img.save(buf, format="PNG", optimize=True)
+    if fmt == "png":
+        img.save(buf, format="PNG", optimize=True)
+    else:
+        # JPEG はアルファを持てないので RGB に落とす（WebP も同じ扱いで揃える）
+        if img.mode not in ("RGB", "L"):
+            img = img.convert("RGB")
+        img.save(buf, format=fmt.upper(), quality=quality)
+    if output is not None:
+        output.parent.mkdir(parents=True, exist_ok=True)
+        output.write_bytes(buf.getvalue())
     b64 = base64.b64encode(buf.getvalue()).decode("ascii")
-    return f"data:image/png;base64,{b64}"
+    return f"data:{MIME_TYPES[fmt]};base64,{b64}"
+
+
+def build_response(
+    img,
+    args,
+    output: Path | None,
+    *,
+    page_count: int | None,
+    page: int,
+    effective_crop: dict | None = None,
+    tile_cache: Path | None = None,
+) -> dict:
+    """仕上げた画像を data URL にして、stdout に出す JSON の中身を作る。
+
+    タイル指定時は 1 枚だけ切り出し、tile_cache があればページ全体のタイルをそこに残す。
+    """
+
+    if args.tile_size is not None:
+        try:
+            box, columns, rows = tile_box(*img.size, args.tile_x, args.tile_y, args.tile_size)
+        except ValueError as exc:
+            raise SystemExit(str(exc)) from exc
+        meta = {
+            "pageCount": page_count,
+            "page": page,
+            "columns": columns,
+            "rows": rows,
+            "imageWidth": img.size[0],
+            "imageHeight": img.size[1],
+        }
+        if tile_cache is not None:
+            save_tile_cache(img, tile_cache, args.tile_size, meta)
+        return tile_response(img.crop(box), args, meta)
+    response: dict = {"pageCount": page_count, "page": page}
+    if effective_crop is not None:
+        response["effectiveCrop"] = effective_crop
+    fmt = "png" if output else choose_format(img, args.format)
+    response["dataUrl"] = image_to_data_url(img, output, fmt=fmt, quality=args.quality)
+    response["mimeType"] = MIME_TYPES[fmt]
+    return response
+
+
+def encrypted_error() -> dict:
+    """パスワード保護された PDF は失敗ではなく errorKind で返し、UI にパスワードを求めさせる。"""
+
+    return {"errorKind": "encrypted", "error": "PDF にパスワードが設定されています"}
+
+
+def print_encrypted_error() -> None:
+    print(json.dumps(encrypted_error(), ensure_ascii=False))
+
+
+# --batch で 1 ファイルの描画に失敗したときの errorKind（Rust 側の PREVIEW_ERROR_* と同じ値）
+BATCH_ERROR_NOT_FOUND = "not_found"
+BATCH_ERROR_RENDER_FAILED = "render_failed"
+
+
+def render_batch(paths: list[str], render) -> list[dict]:
+    """各入力を `render(Path)` で描き、入力の順に `path` 付きの応答を返す。
+
+    1 ファイルの失敗で全体を止めず、そのファイルだけ errorKind / error を付けて返す。
+    """
+
+    responses = []
+    for raw in paths:
+        path = Path(raw)
+        if not path.exists():
+            response = {"errorKind": BATCH_ERROR_NOT_FOUND, "error": f"input not found: {path}"}
+        else:
+            try:
+                response = render(path)
+            except Exception as exc:  # noqa: BLE001 - 1 ファイルの失敗は応答に載せて続ける
+                response = {"errorKind": BATCH_ERROR_RENDER_FAILED, "error": f"{type(exc).__name__}: {exc}"}
+        response["path"] = raw
+        responses.append(response)
+    return responses
 
 
 def main() -> None:
     parser = argparse.ArgumentParser(description="UI 用の画像プレビュー生成")
-    parser.add_argument("--input", required=True, help="入力ファイルパス（PDF/画像）")
+    parser.add_argument("--input", help="入力ファイルパス（PDF/画像）")
+    parser.add_argument(
+        "--batch",
+        help="入力パスの JSON 配列を書いたファイル。各入力の --page ページ目を描き、応答の配列（path 付き）を返す",
+    )
     parser.add_argument("--page", type=int, default=1, help="PDF のページ番号（1起点）")
-    parser.add_argument("--crop", help="正規化トリミング（left,top,width,height / 0〜1）")
+    parser.add_argument(
+        "--crop",
+        help="正規化トリミング（left,top,width,height / 0〜1。回転・傾き補正後の画像に対する割合。crop_rect.py 参照）",
+    )
+    parser.add_argument(
+        "--exact-crop",
+        action="store_true",
+        help="実行時と同じ解像度（PDF は --dpi、画像は元のサイズ）で描いてから切り抜き、切り出した px 範囲も返す（--outline は無視）",
+    )
+    parser.add_argument(
+        "--dpi",
+        type=int,
+        default=DEFAULT_RUN_DPI,
+        help=f"--exact-crop で PDF を描く DPI（実行時の pdfDpi。既定 {DEFAULT_RUN_DPI}）",
+    )
+    parser.add_argument(
+        "--rotate",
+        type=int,
+        choices=[0, 90, 180, 270],
+        default=0,
+        help="時計回りの回転角度（処理時と同じ向きで表示する）",
+    )
+    parser.add_argument("--deskew", action="store_true", help="傾き補正を適用して表示する")
+    parser.add_argument(
+        "--auto-orient",
+        action="store_true",
+        help="横倒しのページを実行時と同じ判定で回して表示する（回した角度は autoRotation で返す）",
+    )
+    parser.add_argument(
+        "--password",
+        help="パスワード保護された PDF のパスワード（未指定なら環境変数 OCR_TO_DOC_PDF_PASSWORD）",
+    )
+    parser.add_argument(
+        "--outline",
+        action="store_true",
+        help="--crop で切り抜かず、全体画像にトリミング枠を描いて返す",
+    )
     parser.add_argument("--max-long-edge", type=int, default=1400, help="長辺の最大 px（プレビュー用）")
+    parser.add_argument("--output", help="生成した PNG をこのパスにも保存する（サムネイルのキャッシュ用）")
+    parser.add_argument(
+        "--format",
+        choices=["auto", "png", "jpeg", "webp"],
+        default="png",
+        help="返す画像の形式。auto は長辺が大きいときだけ JPEG（--output 指定時は常に PNG）",
+    )
+    parser.add_argument(
+        "--quality",
+        type=int,
+        default=DEFAULT_JPEG_QUALITY,
+        help="jpeg/webp の品質（1〜100）",
+    )
+    parser.add_argument(
+        "--tile-size",
+        type=int,
+        help="ページを一辺この px のタイルに分け、--tile-x/--tile-y の 1 枚だけを返す（--max-long-edge/--crop は無視）",
+    )
+    parser.add_argument("--tile-x", type=int, default=0, help="タイルの列（0 起点）")
+    parser.add_argument("--tile-y", type=int, default=0, help="タイルの行（0 起点）")
+    parser.add_argument(
+        "--zoom",
+        type=float,
+        default=1.0,
+        help=f"タイル表示の倍率（1.0 = {TILE_BASE_DPI}dpi 相当、{MIN_TILE_ZOOM}〜{MAX_TILE_ZOOM}）",
+    )
+    parser.add_argument(
+        "--page-count",
+        action="store_true",
+        help="画像を生成せずページ数だけを返す（PDF はページ数、TIFF はフレーム数、それ以外の画像は 1）",
+    )
     args = parser.parse_args()
+    if (args.input is None) == (args.batch is None):
+        parser.error("--input か --batch のどちらか一方を指定してください")
 
+    from image_normalizer import TIFF_EXTENSIONS, tiff_frame_count
+    from ingest import PDF_PASSWORD_ENV, is_pdf_password_error
+
+    args.password = args.password or os.environ.get(PDF_PASSWORD_ENV) or None
     base_dir = Path(__file__).resolve().parent
+    if args.batch is not None:
+        if args.page_count or args.tile_size is not None or args.output:
+            parser.error("--batch は --page-count / --tile-size / --output と併用できません")
+        paths = json.loads(Path(args.batch).read_text(encoding="utf-8"))
+        if not isinstance(paths, list) or not all(isinstance(p, str) for p in paths):
+            raise SystemExit("--batch には入力パスの JSON 配列を指定してください")
+        responses = render_batch(paths, lambda path: render_input(path, args, base_dir))
+        print(json.dumps(responses, ensure_ascii=False))
+        return
+
     input_path = Path(args.input)
     if not input_path.exists():
         raise SystemExit(f"input not found: {input_path}")
 
-    crop = parse_crop(args.crop)
+    if args.page_count:
+        page_count = 1
+        if input_path.suffix.lower() == ".pdf":
+            from pdf2image import pdfinfo_from_path
+
+            poppler_path = resolve_poppler_path(base_dir)
+            try:
+                info = pdfinfo_from_path(
+                    str(input_path), poppler_path=str(poppler_path), userpw=args.password
+                )
+            except Exception as exc:
+                if is_pdf_password_error(exc):
+                    print_encrypted_error()
+                    return
+                raise
+            page_count = int(info["Pages"])
+        elif input_path.suffix.lower() in TIFF_EXTENSIONS:
+            page_count = tiff_frame_count(input_path)
+        print(json.dumps({"pageCount": page_count}))
+        return
+
+    print(json.dumps(render_input(input_path, args, base_dir), ensure_ascii=False))
+
+
+def render_input(input_path: Path, args, base_dir: Path) -> dict:
+    """1 つの入力の --page ページ目を描いて応答の dict にする（パスワード付き PDF は errorKind）。"""
+
+    from image_normalizer import TIFF_EXTENSIONS
+    from ingest import is_pdf_password_error
+
+    tiling = args.tile_size is not None
+    if tiling and not (MIN_TILE_ZOOM <= args.zoom <= MAX_TILE_ZOOM):
+        raise SystemExit(f"zoom must be between {MIN_TILE_ZOOM} and {MAX_TILE_ZOOM}: {args.zoom}")
+    # タイルは描画した全体から切り出すので、トリミングや縮小はかけない
+    crop = None if tiling else parse_crop(args.crop)
+    max_long_edge = 0 if tiling else args.max_long_edge
+    output = None if tiling else (Path(args.output) if args.output else None)
+    # 同じページ・倍率のタイルはまとめて描いてあるので、残りのタイルは描き直さない
+    tile_cache = tile_cache_dir(input_path, args) if tiling else None
+    if tile_cache is not None:
+        cached = load_cached_tile(tile_cache, args)
+        if cached is not None:
+            return cached
 
     try:
         from PIL import Image, ImageOps
     except ImportError as exc:
         raise SystemExit(f"Pillow is required: {exc}") from exc
+    from image_preprocessor import orient_image
 
     page_count: int | None = None
     page = args.page
@@ -115,23 +516,73 @@ def main() -> None:
         poppler_path = resolve_poppler_path(base_dir)
         os.environ["PATH"] = str(poppler_path) + os.pathsep + os.environ.get("PATH", "")
 
-        info = pdfinfo_from_path(str(input_path), poppler_path=str(poppler_path))
+        try:
+            info = pdfinfo_from_path(
+                str(input_path), poppler_path=str(poppler_path), userpw=args.password
+            )
+        except Exception as exc:
+            if is_pdf_password_error(exc):
+                return encrypted_error()
+            raise
         page_count = int(info["Pages"])
         page = max(1, min(page, page_count))
 
+        if tiling:
+            dpi = int(round(TILE_BASE_DPI * args.zoom))
+        elif args.exact_crop:
+            # ocr_chanked.py と同じ DPI で描かないと、丸めで境界が 1px ずれうる
+            dpi = max(72, args.dpi)
+        else:
+            dpi = 150
         images = convert_from_path(
             str(input_path),
-            dpi=150,
+            dpi=dpi,
             first_page=page,
             last_page=page,
             fmt="png",
             poppler_path=str(poppler_path),
+            userpw=args.password,
         )
         img = images[0]
         img = ImageOps.exif_transpose(img)
-        img = apply_crop(img, crop)
-        img = resize_long_edge(img, args.max_long_edge)
-        data_url = image_to_data_url(img)
+        img, auto_rotation = orient_image(
+            img, rotate=args.rotate, deskew=args.deskew, auto_orient=args.auto_orient
+        )
+        img, effective_crop = crop_for_preview(img, crop, args)
+        img = resize_long_edge(img, max_long_edge)
+        response = build_response(
+            img,
+            args,
+            output,
+            page_count=page_count,
+            page=page,
+            effective_crop=effective_crop,
+            tile_cache=tile_cache,
+        )
+    elif input_path.suffix.lower() in TIFF_EXTENSIONS:
+        # マルチページ TIFF は PDF と同じく --page のフレームを表示する
+        with Image.open(input_path) as tiff:
+            page_count = max(1, int(getattr(tiff, "n_frames", 1)))
+            page = max(1, min(page, page_count))
+            tiff.seek(page - 1)
+            img = tiff.convert("RGB")
+        img = ImageOps.exif_transpose(img)
+        if tiling:
+            img = scale_image(img, args.zoom)
+        img, auto_rotation = orient_image(
+            img, rotate=args.rotate, deskew=args.deskew, auto_orient=args.auto_orient
+        )
+        img, effective_crop = crop_for_preview(img, crop, args)
+        img = resize_long_edge(img, max_long_edge)
+        response = build_response(
+            img,
+            args,
+            output,
+            page_count=page_count,
+            page=page,
+            effective_crop=effective_crop,
+            tile_cache=tile_cache,
+        )
     else:
         # HEIC/HEIF/SVG を含めて、まず PNG に正規化（tmp 配下に変換）
         from image_normalizer import ensure_png_image
@@ -139,20 +590,25 @@ def main() -> None:
             conversion = ensure_png_image(input_path, convert_dir=Path(tmp))
             with Image.open(conversion.converted) as img:
                 img = ImageOps.exif_transpose(img)
-                img = apply_crop(img, crop)
-                img = resize_long_edge(img, args.max_long_edge)
-                data_url = image_to_data_url(img)
-
-    print(
-        json.dumps(
-            {
-                "dataUrl": data_url,
-                "pageCount": page_count,
-                "page": page,
-            },
-            ensure_ascii=False,
-        )
-    )
+                if tiling:
+                    img = scale_image(img, args.zoom)
+                img, auto_rotation = orient_image(
+                    img, rotate=args.rotate, deskew=args.deskew, auto_orient=args.auto_orient
+                )
+                img, effective_crop = crop_for_preview(img, crop, args)
+                img = resize_long_edge(img, max_long_edge)
+                response = build_response(
+                    img,
+                    args,
+                    output,
+                    page_count=page_count,
+                    page=page,
+                    effective_crop=effective_crop,
+                    tile_cache=tile_cache,
+                )
+    if args.auto_orient:
+        response["autoRotation"] = auto_rotation
+    return response
 
 
 if __name__ == "__main__":